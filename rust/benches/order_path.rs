@@ -0,0 +1,107 @@
+// benches/order_path.rs
+// Signal-to-order hot path: whale fill -> signed order -> POST body -> signed L2 headers
+//
+// cargo bench --bench order_path                      (system allocator)
+// cargo bench --bench order_path --features mimalloc  (or --features jemalloc)
+//
+// Besides criterion's mean/median, prints p50/p99/p99.9 of the full path since tail
+// latency is what decides whether we land on the whale's fill
+
+use criterion::{black_box, criterion_group, Criterion};
+use pm_whale_follower::{ApiCreds, OrderArgs, PreparedCreds, RustClobClient, ALLOCATOR};
+use std::time::Instant;
+
+/// Hardhat/Anvil account #0 - public test key, never funded on Polygon
+const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const TEST_FUNDER: &str = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+const TEST_TOKEN: &str = "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+
+const TAIL_SAMPLES: usize = 20_000;
+
+fn setup() -> (RustClobClient, PreparedCreds) {
+    let client = RustClobClient::new("https://clob.polymarket.com", 137, TEST_KEY, TEST_FUNDER).unwrap();
+    let creds = PreparedCreds::from_api_creds(&ApiCreds {
+        api_key: "00000000-0000-0000-0000-000000000000".into(),
+        api_secret: "c2VjcmV0LXNlY3JldC1zZWNyZXQtc2VjcmV0LXNlY3I=".into(),
+        api_passphrase: "passphrase".into(),
+    }).unwrap();
+    (client, creds)
+}
+
+fn args(i: u64) -> OrderArgs {
+    OrderArgs {
+        token_id: TEST_TOKEN.to_string(),
+        price: 0.40 + (i % 50) as f64 / 100.0,
+        size: 25.0 + (i % 400) as f64 / 4.0,
+        side: if i.is_multiple_of(2) { "BUY".into() } else { "SELL".into() },
+        fee_rate_bps: None,
+        nonce: Some(0),
+        expiration: Some("0".into()),
+        taker: None,
+        order_type: Some("FAK".into()),
+    }
+}
+
+fn signal_to_order(client: &RustClobClient, creds: &PreparedCreds, i: u64) -> usize {
+    let signed = client.sign_order(args(i), "0.01", i.is_multiple_of(3), i as u128).unwrap();
+    let body = signed.post_body(&creds.api_key, "FAK");
+    let (url, headers) = client.prepare_order_post(&body, creds).unwrap();
+    url.len() + headers.len() + body.len()
+}
+
+fn bench_order_path(c: &mut Criterion) {
+    let (client, creds) = setup();
+    let mut group = c.benchmark_group(format!("order_path/{}", ALLOCATOR));
+
+    group.bench_function("sign_order", |b| {
+        let mut i = 0u64;
+        b.iter(|| {
+            i += 1;
+            black_box(client.sign_order(args(i), "0.01", false, i as u128).unwrap())
+        })
+    });
+
+    let signed = client.sign_order(args(1), "0.01", false, 1).unwrap();
+    group.bench_function("post_body", |b| b.iter(|| black_box(signed.post_body(&creds.api_key, "FAK"))));
+
+    let body = signed.post_body(&creds.api_key, "FAK");
+    group.bench_function("l2_headers", |b| b.iter(|| black_box(client.prepare_order_post(&body, &creds).unwrap())));
+
+    group.bench_function("signal_to_order", |b| {
+        let mut i = 0u64;
+        b.iter(|| {
+            i += 1;
+            black_box(signal_to_order(&client, &creds, i))
+        })
+    });
+    group.finish();
+}
+
+fn report_tail_latency() {
+    let (client, creds) = setup();
+    for i in 0..1_000 {
+        black_box(signal_to_order(&client, &creds, i));
+    }
+
+    let mut samples: Vec<u64> = (0..TAIL_SAMPLES as u64)
+        .map(|i| {
+            let start = Instant::now();
+            black_box(signal_to_order(&client, &creds, i));
+            start.elapsed().as_nanos() as u64
+        })
+        .collect();
+    samples.sort_unstable();
+    let pct = |p: f64| samples[((samples.len() as f64 * p) as usize).min(samples.len() - 1)] as f64 / 1000.0;
+    println!(
+        "order_path/{}/signal_to_order tail ({} samples): p50 {:.1}us  p99 {:.1}us  p99.9 {:.1}us",
+        ALLOCATOR, TAIL_SAMPLES, pct(0.50), pct(0.99), pct(0.999)
+    );
+}
+
+criterion_group!(benches, bench_order_path);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    report_tail_latency();
+}
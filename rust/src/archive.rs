@@ -0,0 +1,395 @@
+//! Daily journal archiving
+//! At each UTC day change the rotated logs (trades CSV, shadow orders, what-if, depth history)
+//! are cut: the live file and its rotated generations are concatenated, zstd-compressed into
+//! `<dir>/<day>/<file>.zst` and removed, and the cut is recorded in `<dir>/index.jsonl`.
+//! Archives are optionally copied to S3-compatible storage. Readers use `read_history`, which
+//! returns archived days followed by what is still on disk, fetching days no longer kept
+//! locally from the bucket
+
+use crate::retention::{ROTATED_FILES_KEPT, ROTATED_LOGS};
+use crate::timestamp;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// How often the archiver checks for a day change
+pub const ARCHIVE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// One line per archived file
+pub const ARCHIVE_INDEX_FILE: &str = "index.jsonl";
+
+const ZSTD_LEVEL: i32 = 9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3Target {
+    /// e.g. `https://s3.us-east-1.amazonaws.com` or an R2/MinIO endpoint; path-style addressing
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveConfig {
+    pub dir: PathBuf,
+    pub s3: Option<S3Target>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// UTC day the cut closed (YYYY-MM-DD)
+    pub day: String,
+    /// Journal name, e.g. `matches_optimized.csv`
+    pub file: String,
+    /// Relative to the archive dir (and the key in the bucket)
+    pub path: String,
+    pub raw_bytes: u64,
+    pub zst_bytes: u64,
+    pub uploaded: bool,
+}
+
+// ============================================================================
+// Archiving
+// ============================================================================
+
+fn journal_name(path: &str) -> String {
+    Path::new(path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| path.to_string())
+}
+
+fn append_index(dir: &Path, entry: &ArchiveEntry) -> std::io::Result<()> {
+    let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+    let mut f = OpenOptions::new().append(true).create(true).open(dir.join(ARCHIVE_INDEX_FILE))?;
+    writeln!(f, "{}", line)
+}
+
+/// Cuts of `path` a crash left before they were archived, oldest first
+fn leftover_cuts(path: &str) -> Vec<String> {
+    let name = journal_name(path);
+    let prefix = format!("{}.archiving", name);
+    let dir = Path::new(path).parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut found: Vec<String> = fs::read_dir(dir).into_iter().flatten().flatten()
+        .filter_map(|e| e.file_name().to_str().map(String::from))
+        .filter(|f| f.starts_with(&prefix))
+        .map(|f| format!("{}{}", path, &f[name.len()..]))
+        .collect();
+    found.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    found
+}
+
+/// Cut `path` (and its rotated generations, oldest first) into `<dir>/<day>/<name>.zst`.
+/// The live file restarts with `header` when given. None when there was nothing to archive
+pub fn archive_journal(cfg: &ArchiveConfig, day: NaiveDate, path: &str, header: Option<&str>) -> Result<Option<ArchiveEntry>> {
+    let mut sources: Vec<String> = (1..=ROTATED_FILES_KEPT).rev()
+        .map(|n| format!("{}.{}", path, n))
+        .filter(|p| Path::new(p).exists())
+        .collect();
+    sources.extend(leftover_cuts(path));
+    // Writers open-append per write, so the live file can be moved aside while they continue
+    let cutting = format!("{}.archiving.{}", path, timestamp::unix_millis());
+    if fs::rename(path, &cutting).is_ok() {
+        sources.push(cutting);
+    }
+    if sources.is_empty() {
+        return Ok(None);
+    }
+    // A writer may already have recreated the file since the rename; its rows stay
+    if let Some(header) = header {
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut f) => writeln!(f, "{}", header)?,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let mut raw = Vec::new();
+    for (i, src) in sources.iter().enumerate() {
+        let Ok(data) = fs::read(src) else { continue };
+        // Keep a CSV header only at the top of the day
+        let skip = match header {
+            Some(h) if i > 0 && data.starts_with(h.as_bytes()) => (h.len() + 1).min(data.len()),
+            _ => 0,
+        };
+        raw.extend_from_slice(&data[skip..]);
+    }
+    let data_lines = raw.split(|b| *b == b'\n').filter(|l| !l.is_empty()).count();
+    if data_lines <= usize::from(header.is_some()) {
+        let cut_prefix = format!("{}.archiving", path);
+        for cut in sources.iter().filter(|s| s.starts_with(&cut_prefix)) {
+            let _ = fs::remove_file(cut);
+        }
+        return Ok(None);
+    }
+
+    let name = journal_name(path);
+    let rel = format!("{}/{}.zst", day, name);
+    let dest = cfg.dir.join(&rel);
+    fs::create_dir_all(dest.parent().unwrap_or(&cfg.dir))?;
+    let zst = zstd::encode_all(raw.as_slice(), ZSTD_LEVEL).context("zstd compression failed")?;
+    // Never overwrite an earlier cut of the same day (e.g. after a restart)
+    let (rel, dest) = if dest.exists() {
+        let rel = format!("{}/{}.{}.zst", day, name, Utc::now().timestamp());
+        (rel.clone(), cfg.dir.join(rel))
+    } else {
+        (rel, dest)
+    };
+    fs::write(&dest, &zst)?;
+
+    for src in &sources {
+        let _ = fs::remove_file(src);
+    }
+
+    let uploaded = match &cfg.s3 {
+        Some(s3) => match s3.put(&rel, zst.clone()) {
+            Ok(()) => true,
+            Err(e) => {
+                crate::console_eprintln!("⚠️ Archive upload failed for {}: {}", rel, e);
+                false
+            }
+        },
+        None => false,
+    };
+    let entry = ArchiveEntry {
+        day: day.to_string(),
+        file: name,
+        path: rel,
+        raw_bytes: raw.len() as u64,
+        zst_bytes: zst.len() as u64,
+        uploaded,
+    };
+    append_index(&cfg.dir, &entry)?;
+    Ok(Some(entry))
+}
+
+/// Archive every rotated log for `day`
+pub fn archive_day(cfg: &ArchiveConfig, day: NaiveDate) -> Vec<ArchiveEntry> {
+    let mut done = Vec::new();
+    for (path, header) in ROTATED_LOGS {
+        match archive_journal(cfg, day, path, header) {
+            Ok(Some(entry)) => done.push(entry),
+            Ok(None) => {}
+            Err(e) => crate::console_eprintln!("⚠️ Archiving {} failed: {}", path, e),
+        }
+    }
+    done
+}
+
+/// Cut the journals whenever the UTC day changes
+pub fn spawn_archive_task(cfg: ArchiveConfig) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut open_day = Utc::now().date_naive();
+        loop {
+            tokio::time::sleep(ARCHIVE_CHECK_INTERVAL).await;
+            let today = Utc::now().date_naive();
+            if today == open_day {
+                continue;
+            }
+            let (cfg, day) = (cfg.clone(), open_day);
+            match tokio::task::spawn_blocking(move || archive_day(&cfg, day)).await {
+                Ok(entries) => {
+                    let (raw, zst) = entries.iter().fold((0, 0), |(r, z), e| (r + e.raw_bytes, z + e.zst_bytes));
+                    crate::console_println!(
+                        "🗄️ Archived {} journals for {} ({} KB -> {} KB)",
+                        entries.len(), day, raw / 1024, zst / 1024
+                    );
+                }
+                Err(e) => crate::console_eprintln!("⚠️ Archive task error: {}", e),
+            }
+            open_day = today;
+        }
+    })
+}
+
+// ============================================================================
+// Retrieval
+// ============================================================================
+
+/// Index entries, oldest first
+pub fn index(dir: &Path) -> Vec<ArchiveEntry> {
+    fs::read_to_string(dir.join(ARCHIVE_INDEX_FILE))
+        .map(|data| data.lines().filter_map(|l| serde_json::from_str(l).ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Decompressed contents of one archive, from disk or else from the bucket
+pub fn read_entry(cfg: &ArchiveConfig, entry: &ArchiveEntry) -> Result<String> {
+    let zst = match fs::read(cfg.dir.join(&entry.path)) {
+        Ok(data) => data,
+        Err(_) => match (&cfg.s3, entry.uploaded) {
+            (Some(s3), true) => s3.get(&entry.path)?,
+            _ => anyhow::bail!("{} is neither on disk nor uploaded", entry.path),
+        },
+    };
+    let raw = zstd::decode_all(zst.as_slice()).context("zstd decompression failed")?;
+    Ok(String::from_utf8_lossy(&raw).into_owned())
+}
+
+/// Everything a journal ever held: archived days oldest first, then its rotated generations
+/// and the live file. Without archiving configured this is just what is on disk
+pub fn read_history(path: &str) -> String {
+    let mut out = String::new();
+    if let Some(cfg) = archive_config() {
+        let name = journal_name(path);
+        for entry in index(&cfg.dir).iter().filter(|e| e.file == name) {
+            match read_entry(cfg, entry) {
+                Ok(text) => out.push_str(&text),
+                Err(e) => crate::console_eprintln!("⚠️ Archive {} unreadable: {}", entry.path, e),
+            }
+        }
+    }
+    for n in (1..=ROTATED_FILES_KEPT).rev() {
+        if let Ok(text) = fs::read_to_string(format!("{}.{}", path, n)) {
+            out.push_str(&text);
+        }
+    }
+    if let Ok(text) = fs::read_to_string(path) {
+        out.push_str(&text);
+    }
+    out
+}
+
+// ============================================================================
+// S3-Compatible Storage (SigV4, path-style)
+// ============================================================================
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k = hmac(format!("AWS4{}", secret).as_bytes(), date);
+    let k = hmac(&k, region);
+    let k = hmac(&k, service);
+    hmac(&k, "aws4_request")
+}
+
+impl S3Target {
+    fn request(&self, method: reqwest::Method, key: &str, body: Vec<u8>) -> Result<reqwest::blocking::Response> {
+        let url = reqwest::Url::parse(&format!("{}/{}/{}", self.endpoint.trim_end_matches('/'), self.bucket, key))?;
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let now = Utc::now();
+        let (amz_date, date) = (now.format("%Y%m%dT%H%M%SZ").to_string(), now.format("%Y%m%d").to_string());
+        let payload_hash = hex(&Sha256::digest(&body));
+        let canonical = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, url.path(), host, payload_hash, amz_date, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex(&Sha256::digest(canonical.as_bytes())));
+        let signature = hex(&hmac(&signing_key(&self.secret_key, &date, &self.region, "s3"), &to_sign));
+        let auth = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key, scope, signature
+        );
+        let resp = reqwest::blocking::Client::new()
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", auth)
+            .body(body)
+            .timeout(Duration::from_secs(120))
+            .send()?;
+        if !resp.status().is_success() {
+            anyhow::bail!("HTTP {} for {}", resp.status(), key);
+        }
+        Ok(resp)
+    }
+
+    pub fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        self.request(reqwest::Method::PUT, key, body).map(|_| ())
+    }
+
+    pub fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(self.request(reqwest::Method::GET, key, Vec::new())?.bytes()?.to_vec())
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_ARCHIVE: OnceLock<Option<ArchiveConfig>> = OnceLock::new();
+
+/// Set once at startup (None = no archiving)
+pub fn init_archive(cfg: Option<ArchiveConfig>) {
+    let _ = GLOBAL_ARCHIVE.set(cfg);
+}
+
+pub fn archive_config() -> Option<&'static ArchiveConfig> {
+    GLOBAL_ARCHIVE.get().and_then(Option::as_ref)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+
+    #[test]
+    fn test_archive_cuts_generations_and_reads_back() {
+        let dir = std::env::temp_dir().join(format!("pm_archive_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cfg = ArchiveConfig { dir: dir.join("archive"), s3: None };
+        let path = dir.join("trades.csv");
+        let path = path.to_str().unwrap();
+        fs::write(format!("{}.1", path), "h\nrow1\n").unwrap();
+        fs::write(path, "h\nrow2\n").unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let entry = archive_journal(&cfg, day, path, Some("h")).unwrap().unwrap();
+        assert_eq!(entry.path, "2026-03-01/trades.csv.zst");
+        assert_eq!(fs::read_to_string(path).unwrap(), "h\n");
+        assert!(!Path::new(&format!("{}.1", path)).exists());
+        assert_eq!(read_entry(&cfg, &entry).unwrap(), "h\nrow1\nrow2\n");
+        assert_eq!(index(&cfg.dir), vec![entry]);
+
+        // Only a header left: nothing to archive
+        assert!(archive_journal(&cfg, day, path, Some("h")).unwrap().is_none());
+        assert_eq!(fs::read_to_string(path).unwrap(), "h\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_archive_picks_up_cut_left_by_crash() {
+        let dir = std::env::temp_dir().join(format!("pm_archive_crash_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cfg = ArchiveConfig { dir: dir.join("archive"), s3: None };
+        let path = dir.join("trades.csv");
+        let path = path.to_str().unwrap();
+        fs::write(format!("{}.archiving", path), "h\nrow0\n").unwrap();
+        fs::write(path, "h\nrow1\n").unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let entry = archive_journal(&cfg, day, path, Some("h")).unwrap().unwrap();
+        assert_eq!(read_entry(&cfg, &entry).unwrap(), "h\nrow0\nrow1\n");
+        assert!(leftover_cuts(path).is_empty());
+        assert_eq!(fs::read_to_string(path).unwrap(), "h\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
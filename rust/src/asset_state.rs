@@ -0,0 +1,317 @@
+//! Per-token trade lifecycle state machine
+//! Idle → Entering → Holding → Exiting → Settling, gating which operations may start so an entry
+//! can never race a working exit (or a second exit) on the same token
+
+use rustc_hash::FxHashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// =============================================================================
+// Configuration
+// =============================================================================
+
+/// After an exit order is accepted, block new operations while balances settle on-chain
+pub const SETTLE_COOLDOWN: Duration = Duration::from_secs(5);
+
+/// Entering/Exiting older than this is assumed abandoned (worker died mid-order)
+pub const STUCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// After an exit order fails, hold off further exits so a stop isn't resent on every book update
+pub const EXIT_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+// =============================================================================
+// State
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetPhase {
+    Idle,
+    Entering,
+    Holding,
+    Exiting,
+    Settling,
+}
+
+impl AssetPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetPhase::Idle => "IDLE",
+            AssetPhase::Entering => "ENTERING",
+            AssetPhase::Holding => "HOLDING",
+            AssetPhase::Exiting => "EXITING",
+            AssetPhase::Settling => "SETTLING",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AssetState {
+    phase: AssetPhase,
+    since: Instant,
+    /// Whether we held shares before the current Entering/Exiting began
+    holding_before: bool,
+    /// Set by a failed exit; no new exit starts before this
+    retry_exit_at: Option<Instant>,
+}
+
+impl AssetState {
+    fn new(phase: AssetPhase, holding_before: bool) -> Self {
+        Self { phase, since: Instant::now(), holding_before, retry_exit_at: None }
+    }
+
+    /// Time left before another exit may start
+    fn exit_backoff(&self, now: Instant) -> Option<Duration> {
+        self.retry_exit_at.and_then(|at| at.checked_duration_since(now)).filter(|d| !d.is_zero())
+    }
+
+    /// Phase with time-based transitions applied (settled cooldown, stuck operations)
+    fn effective_phase(&self, now: Instant) -> AssetPhase {
+        let age = now.duration_since(self.since);
+        match self.phase {
+            AssetPhase::Settling if age >= SETTLE_COOLDOWN => AssetPhase::Idle,
+            AssetPhase::Entering | AssetPhase::Exiting if age >= STUCK_TIMEOUT => {
+                if self.holding_before { AssetPhase::Holding } else { AssetPhase::Idle }
+            }
+            phase => phase,
+        }
+    }
+}
+
+// =============================================================================
+// State Machine
+// =============================================================================
+
+#[derive(Default)]
+pub struct AssetStateMachine {
+    states: Mutex<FxHashMap<String, AssetState>>,
+}
+
+impl AssetStateMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current phase for a token (Idle if never seen)
+    pub fn phase(&self, token_id: &str) -> AssetPhase {
+        let now = Instant::now();
+        self.states.lock()
+            .ok()
+            .and_then(|s| s.get(token_id).map(|st| st.effective_phase(now)))
+            .unwrap_or(AssetPhase::Idle)
+    }
+
+    /// Start an entry. Allowed from Idle or Holding; returns the blocking phase otherwise
+    pub fn try_begin_entry(&self, token_id: &str) -> Result<(), AssetPhase> {
+        self.transition(token_id, |phase| match phase {
+            AssetPhase::Idle => Some(AssetState::new(AssetPhase::Entering, false)),
+            AssetPhase::Holding => Some(AssetState::new(AssetPhase::Entering, true)),
+            _ => None,
+        })
+    }
+
+    /// Entry finished: Holding if anything filled (or we already held), Idle otherwise
+    pub fn finish_entry(&self, token_id: &str, filled: bool) {
+        self.complete(token_id, AssetPhase::Entering, |st| {
+            if filled || st.holding_before { AssetPhase::Holding } else { AssetPhase::Idle }
+        });
+    }
+
+    /// Start an exit. Allowed from Idle or Holding; blocked while entering, exiting or settling,
+    /// and for EXIT_RETRY_BACKOFF after a failed exit
+    pub fn try_begin_exit(&self, token_id: &str) -> Result<(), AssetPhase> {
+        if self.exit_backoff(token_id).is_some() {
+            return Err(self.phase(token_id));
+        }
+        self.transition(token_id, |phase| match phase {
+            AssetPhase::Idle => Some(AssetState::new(AssetPhase::Exiting, false)),
+            AssetPhase::Holding => Some(AssetState::new(AssetPhase::Exiting, true)),
+            _ => None,
+        })
+    }
+
+    /// Exit finished: Settling if the exit order was accepted, back to the prior phase (backing
+    /// off further exits) otherwise
+    pub fn finish_exit(&self, token_id: &str, accepted: bool) {
+        let finished = self.complete(token_id, AssetPhase::Exiting, |st| {
+            if accepted {
+                AssetPhase::Settling
+            } else if st.holding_before {
+                AssetPhase::Holding
+            } else {
+                AssetPhase::Idle
+            }
+        });
+        if finished && !accepted
+            && let Ok(mut states) = self.states.lock()
+            && let Some(st) = states.get_mut(token_id) {
+                st.retry_exit_at = Some(Instant::now() + EXIT_RETRY_BACKOFF);
+            }
+    }
+
+    /// Time left before an exit may be retried after a failed one (None if it may start now)
+    pub fn exit_backoff(&self, token_id: &str) -> Option<Duration> {
+        let now = Instant::now();
+        self.states.lock().ok()?.get(token_id)?.exit_backoff(now)
+    }
+
+    /// Snapshot of all non-idle tokens (for diagnostics)
+    pub fn snapshot(&self) -> Vec<(String, AssetPhase, Duration)> {
+        let now = Instant::now();
+        let Ok(states) = self.states.lock() else { return Vec::new() };
+        states.iter()
+            .map(|(t, st)| (t.clone(), st.effective_phase(now), now.duration_since(st.since)))
+            .filter(|(_, phase, _)| *phase != AssetPhase::Idle)
+            .collect()
+    }
+
+    /// Number of tracked tokens (including idle ones not yet pruned)
+    pub fn len(&self) -> usize {
+        self.states.lock().map(|s| s.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop tokens that have returned to Idle (returns number removed)
+    pub fn prune_idle(&self) -> usize {
+        let now = Instant::now();
+        let Ok(mut states) = self.states.lock() else { return 0 };
+        let before = states.len();
+        states.retain(|_, st| st.effective_phase(now) != AssetPhase::Idle || st.exit_backoff(now).is_some());
+        before - states.len()
+    }
+
+    fn transition(
+        &self,
+        token_id: &str,
+        next: impl FnOnce(AssetPhase) -> Option<AssetState>,
+    ) -> Result<(), AssetPhase> {
+        let now = Instant::now();
+        let mut states = self.states.lock().map_err(|_| AssetPhase::Idle)?;
+        let current = states.get(token_id).map(|st| st.effective_phase(now)).unwrap_or(AssetPhase::Idle);
+        match next(current) {
+            Some(state) => {
+                states.insert(token_id.to_string(), state);
+                Ok(())
+            }
+            None => Err(current),
+        }
+    }
+
+    /// Leave `expected` for the phase chosen by `next`; false if the token wasn't in `expected`
+    fn complete(&self, token_id: &str, expected: AssetPhase, next: impl FnOnce(&AssetState) -> AssetPhase) -> bool {
+        let Ok(mut states) = self.states.lock() else { return false };
+        if let Some(st) = states.get_mut(token_id)
+            && st.phase == expected {
+                let phase = next(st);
+                *st = AssetState::new(phase, phase == AssetPhase::Holding);
+                return true;
+            }
+        false
+    }
+}
+
+static GLOBAL_ASSET_STATES: OnceLock<AssetStateMachine> = OnceLock::new();
+
+/// Get the global per-token state machine
+pub fn asset_states() -> &'static AssetStateMachine {
+    GLOBAL_ASSET_STATES.get_or_init(AssetStateMachine::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_lifecycle() {
+        let sm = AssetStateMachine::new();
+        assert_eq!(sm.phase("t"), AssetPhase::Idle);
+
+        assert!(sm.try_begin_entry("t").is_ok());
+        assert_eq!(sm.phase("t"), AssetPhase::Entering);
+        // Second entry while the first is working is blocked
+        assert_eq!(sm.try_begin_entry("t"), Err(AssetPhase::Entering));
+
+        sm.finish_entry("t", true);
+        assert_eq!(sm.phase("t"), AssetPhase::Holding);
+
+        // Unfilled entry with no prior position goes back to Idle
+        sm.finish_entry("u", false);
+        assert!(sm.try_begin_entry("u").is_ok());
+        sm.finish_entry("u", false);
+        assert_eq!(sm.phase("u"), AssetPhase::Idle);
+    }
+
+    #[test]
+    fn test_entry_blocked_while_exiting() {
+        let sm = AssetStateMachine::new();
+        sm.try_begin_entry("t").unwrap();
+        sm.finish_entry("t", true);
+
+        assert!(sm.try_begin_exit("t").is_ok());
+        assert_eq!(sm.try_begin_entry("t"), Err(AssetPhase::Exiting));
+        assert_eq!(sm.try_begin_exit("t"), Err(AssetPhase::Exiting));
+
+        sm.finish_exit("t", true);
+        assert_eq!(sm.phase("t"), AssetPhase::Settling);
+        assert_eq!(sm.try_begin_entry("t"), Err(AssetPhase::Settling));
+    }
+
+    #[test]
+    fn test_failed_exit_returns_to_holding() {
+        let sm = AssetStateMachine::new();
+        sm.try_begin_entry("t").unwrap();
+        sm.finish_entry("t", true);
+        sm.try_begin_exit("t").unwrap();
+        sm.finish_exit("t", false);
+        assert_eq!(sm.phase("t"), AssetPhase::Holding);
+    }
+
+    #[test]
+    fn test_failed_exit_backs_off_retries() {
+        let sm = AssetStateMachine::new();
+        sm.try_begin_entry("t").unwrap();
+        sm.finish_entry("t", true);
+        sm.try_begin_exit("t").unwrap();
+        sm.finish_exit("t", false);
+
+        // The next book update must not resend the exit
+        assert!(sm.exit_backoff("t").is_some());
+        assert_eq!(sm.try_begin_exit("t"), Err(AssetPhase::Holding));
+
+        // A failed exit from Idle keeps its backoff through pruning
+        sm.try_begin_exit("v").unwrap();
+        sm.finish_exit("v", false);
+        assert_eq!(sm.phase("v"), AssetPhase::Idle);
+        assert_eq!(sm.prune_idle(), 0);
+        assert_eq!(sm.try_begin_exit("v"), Err(AssetPhase::Idle));
+
+        // An accepted exit leaves no backoff
+        sm.try_begin_entry("u").unwrap();
+        sm.finish_entry("u", true);
+        sm.try_begin_exit("u").unwrap();
+        sm.finish_exit("u", true);
+        assert!(sm.exit_backoff("u").is_none());
+    }
+
+    #[test]
+    fn test_prune_idle_keeps_active() {
+        let sm = AssetStateMachine::new();
+        sm.try_begin_entry("held").unwrap();
+        sm.finish_entry("held", true);
+        sm.try_begin_entry("gone").unwrap();
+        sm.finish_entry("gone", false);
+
+        assert_eq!(sm.prune_idle(), 1);
+        assert_eq!(sm.len(), 1);
+        assert_eq!(sm.phase("held"), AssetPhase::Holding);
+    }
+
+    #[test]
+    fn test_exit_blocked_while_entering() {
+        let sm = AssetStateMachine::new();
+        sm.try_begin_entry("t").unwrap();
+        assert_eq!(sm.try_begin_exit("t"), Err(AssetPhase::Entering));
+    }
+}
@@ -0,0 +1,52 @@
+//! Mock CLOB exchange for local end-to-end runs
+//! Run with: cargo run --bin mock_clob <scenario.jsonl> [whale_address] [http_addr] [ws_addr]
+//!
+//! Prints the environment that points pm_bot at the mock, plays the scenario (one
+//! `ScenarioStep` per line: market, book, whale_fill, resolve, reject, rotate_key, wait) and
+//! keeps serving until Ctrl-C, then lists every order it received
+
+use anyhow::{Context, Result};
+use pm_whale_follower::mock_clob::{self, MockClob, ScenarioStep};
+
+/// USDC the bot's account starts with
+const START_BALANCE_USD: f64 = 1_000.0;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = args.first().context("usage: mock_clob <scenario.jsonl> [whale_address] [http_addr] [ws_addr]")?;
+    let whale = args.get(1).map_or("204f72f35326db932158cba6adff0b9a1da95e14", String::as_str);
+    let http_addr = args.get(2).map_or("127.0.0.1:8780", String::as_str);
+    let ws_addr = args.get(3).map_or("127.0.0.1:8781", String::as_str);
+
+    let steps: Vec<ScenarioStep> = std::fs::read_to_string(path)
+        .with_context(|| format!("reading {}", path))?
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .map(|(i, l)| serde_json::from_str(l).with_context(|| format!("{}:{}", path, i + 1)))
+        .collect::<Result<_>>()?;
+
+    let mock = MockClob::new(whale, START_BALANCE_USD);
+    let endpoints = mock_clob::start(mock.clone(), http_addr, ws_addr).await?;
+    println!("🧪 Mock CLOB on http://{} and ws://{}. Run pm_bot with:", endpoints.http, endpoints.ws);
+    for (key, value) in endpoints.bot_env() {
+        println!("  export {}={}", key, value);
+    }
+    println!("  export TARGET_WHALE_ADDRESS={}", whale);
+
+    for step in &steps {
+        step.run(&mock).await;
+    }
+    println!("▶️ Scenario played ({} steps), serving until Ctrl-C", steps.len());
+
+    tokio::signal::ctrl_c().await?;
+    for o in mock.orders() {
+        println!(
+            "{} {} {} {:.2} @ {:.2} {} filled {:.2}: {}",
+            o.order_id, o.token_id, if o.is_buy { "BUY" } else { "SELL" }, o.size, o.price, o.order_type, o.filled, o.status
+        );
+    }
+    println!("💵 Balance {:.2} USDC", mock.balance());
+    Ok(())
+}
@@ -0,0 +1,17 @@
+//! Re-record the API fixtures used by offline tests
+//! Run with: cargo run --release --bin update_fixtures [fixture_dir]
+//!
+//! Fetches every URL in <fixture_dir>/urls.txt (default: fixtures) and overwrites its
+//! recorded response. Add a URL to the list to record a new fixture
+
+use anyhow::Result;
+use pm_whale_follower::fixtures::{record_all, FIXTURE_DIR};
+use std::path::PathBuf;
+
+fn main() -> Result<()> {
+    let dir: PathBuf = std::env::args().nth(1).unwrap_or_else(|| FIXTURE_DIR.to_string()).into();
+    let client = reqwest::blocking::Client::builder().no_proxy().build()?;
+    let written = record_all(&dir, &client)?;
+    println!("✅ {} fixtures recorded in {}", written, dir.display());
+    Ok(())
+}
@@ -0,0 +1,221 @@
+//! Live blotter state for the terminal UI
+//! Collects recent fills, book prices and log lines; console output is routed here instead of
+//! stdout while the TUI owns the terminal (headless mode keeps printing as before)
+
+use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+// =============================================================================
+// Configuration
+// =============================================================================
+
+/// Fills kept for the tape panel
+pub const MAX_FILLS: usize = 200;
+
+/// Log lines kept for the signal log panel
+pub const MAX_LOG_LINES: usize = 500;
+
+// =============================================================================
+// Rows
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct FillRow {
+    pub at: SystemTime,
+    pub token_id: String,
+    pub side: &'static str,
+    pub shares: f64,
+    pub price: f64,
+    pub whale_shares: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PriceRow {
+    pub best_price: String,
+    pub best_size: String,
+    pub second_price: String,
+    pub updated_at: SystemTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub at: SystemTime,
+    pub is_error: bool,
+    /// Text with ANSI color codes stripped
+    pub text: String,
+}
+
+// =============================================================================
+// Blotter
+// =============================================================================
+
+#[derive(Default)]
+pub struct Blotter {
+    fills: Mutex<VecDeque<FillRow>>,
+    prices: Mutex<FxHashMap<String, PriceRow>>,
+    log: Mutex<VecDeque<LogLine>>,
+}
+
+impl Blotter {
+    pub fn record_fill(&self, fill: FillRow) {
+        if let Ok(mut fills) = self.fills.lock() {
+            if fills.len() >= MAX_FILLS {
+                fills.pop_front();
+            }
+            fills.push_back(fill);
+        }
+    }
+
+    pub fn record_price(&self, token_id: &str, row: PriceRow) {
+        if let Ok(mut prices) = self.prices.lock() {
+            prices.insert(token_id.to_string(), row);
+        }
+    }
+
+    pub fn push_log(&self, text: &str, is_error: bool) {
+        if let Ok(mut log) = self.log.lock() {
+            if log.len() >= MAX_LOG_LINES {
+                log.pop_front();
+            }
+            log.push_back(LogLine { at: SystemTime::now(), is_error, text: strip_ansi(text) });
+        }
+    }
+
+    /// Most recent fills, newest first
+    pub fn recent_fills(&self, n: usize) -> Vec<FillRow> {
+        self.fills.lock().map(|f| f.iter().rev().take(n).cloned().collect()).unwrap_or_default()
+    }
+
+    /// Latest book prices, most recently updated first
+    pub fn prices(&self, n: usize) -> Vec<(String, PriceRow)> {
+        let Ok(prices) = self.prices.lock() else { return Vec::new() };
+        let mut rows: Vec<_> = prices.iter().map(|(t, p)| (t.clone(), p.clone())).collect();
+        rows.sort_by_key(|(_, p)| std::cmp::Reverse(p.updated_at));
+        rows.truncate(n);
+        rows
+    }
+
+    /// Most recent log lines, oldest first (ready to render bottom-aligned)
+    pub fn recent_log(&self, n: usize) -> Vec<LogLine> {
+        let Ok(log) = self.log.lock() else { return Vec::new() };
+        let skip = log.len().saturating_sub(n);
+        log.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Remove ANSI escape sequences (the console output is colorized)
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() { break; }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// =============================================================================
+// Global Instance & Console Routing
+// =============================================================================
+
+static GLOBAL_BLOTTER: OnceLock<Blotter> = OnceLock::new();
+static TUI_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Get the global blotter
+pub fn blotter() -> &'static Blotter {
+    GLOBAL_BLOTTER.get_or_init(Blotter::default)
+}
+
+/// Route console output into the blotter instead of stdout/stderr
+pub fn set_tui_active(active: bool) {
+    TUI_ACTIVE.store(active, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn is_tui_active() -> bool {
+    TUI_ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Print a line to stdout (headless) or the blotter log (TUI)
+pub fn emit(line: String) {
+    if is_tui_active() {
+        blotter().push_log(&line, false);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Print a line to stderr (headless) or the blotter log flagged as error (TUI)
+pub fn emit_err(line: String) {
+    if is_tui_active() {
+        blotter().push_log(&line, true);
+    } else {
+        eprintln!("{}", line);
+    }
+}
+
+/// `println!` replacement that respects the active UI mode
+#[macro_export]
+macro_rules! console_println {
+    ($($arg:tt)*) => {
+        $crate::blotter::emit(format!($($arg)*))
+    };
+}
+
+/// `eprintln!` replacement that respects the active UI mode
+#[macro_export]
+macro_rules! console_eprintln {
+    ($($arg:tt)*) => {
+        $crate::blotter::emit_err(format!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[32m200 OK\x1b[0m | x"), "200 OK | x");
+        assert_eq!(strip_ansi("\x1b[38;5;199m0.55\x1b[0m"), "0.55");
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+
+    #[test]
+    fn test_fill_tape_is_bounded_newest_first() {
+        let b = Blotter::default();
+        for i in 0..(MAX_FILLS + 5) {
+            b.record_fill(FillRow {
+                at: SystemTime::now(),
+                token_id: i.to_string(),
+                side: "BUY",
+                shares: 1.0,
+                price: 0.5,
+                whale_shares: 100.0,
+            });
+        }
+        let fills = b.recent_fills(MAX_FILLS * 2);
+        assert_eq!(fills.len(), MAX_FILLS);
+        assert_eq!(fills[0].token_id, (MAX_FILLS + 4).to_string());
+    }
+
+    #[test]
+    fn test_log_tail() {
+        let b = Blotter::default();
+        b.push_log("one", false);
+        b.push_log("two", true);
+        b.push_log("three", false);
+        let tail = b.recent_log(2);
+        assert_eq!(tail.len(), 2);
+        assert_eq!(tail[0].text, "two");
+        assert!(tail[0].is_error);
+    }
+}
@@ -0,0 +1,251 @@
+//! Live book feed for held tokens
+//! Subscribes to the CLOB market channel for every token the position tracker holds and keeps
+//! each token's bid ladder from the book snapshot plus level updates. Every change of a best
+//! bid is forwarded, so the stop-loss is evaluated on the update itself instead of on the next
+//! poll. The subscription follows the held set: a new or closed position resubscribes
+
+use crate::diagnostics::diagnostics;
+use crate::position_tracker::PositionTracker;
+use crate::supervisor::{report_failure, RestartPolicy};
+use anyhow::{anyhow, Result};
+use futures::{SinkExt, StreamExt};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+pub const MARKET_WS_URL: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
+
+/// Market channel, `MARKET_WS_URL` when set (e.g. the mock exchange)
+pub fn market_ws_url() -> &'static str {
+    static URL: OnceLock<String> = OnceLock::new();
+    URL.get_or_init(|| std::env::var("MARKET_WS_URL").ok().filter(|u| !u.trim().is_empty()).unwrap_or_else(|| MARKET_WS_URL.to_string()))
+}
+
+/// How often the held set is compared with the subscription (and the channel pinged)
+const HELD_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// No message for this long means the connection is dead
+const FEED_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Wait before reconnecting after an error
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+// ============================================================================
+// Bid Ladders
+// ============================================================================
+
+/// Price key in 1/10000 (finer than the smallest tick)
+#[inline]
+fn key(price: f64) -> u32 {
+    (price * 10_000.0).round() as u32
+}
+
+fn num(v: &Value) -> Option<f64> {
+    v.as_str().and_then(|s| s.parse().ok()).or_else(|| v.as_f64())
+}
+
+/// token_id -> price key -> size, bids only (exits sell into the bid)
+#[derive(Default)]
+pub struct BidLadders {
+    books: FxHashMap<String, BTreeMap<u32, f64>>,
+    /// Last message that touched each token's book
+    updated: FxHashMap<String, Instant>,
+}
+
+impl BidLadders {
+    pub fn best_bid(&self, token_id: &str) -> Option<f64> {
+        self.books.get(token_id)?.keys().next_back().map(|&k| k as f64 / 10_000.0)
+    }
+
+    fn set_level(&mut self, token_id: &str, price: f64, size: f64) {
+        let ladder = self.books.entry(token_id.to_string()).or_default();
+        if size > 0.0 {
+            ladder.insert(key(price), size);
+        } else {
+            ladder.remove(&key(price));
+        }
+    }
+
+    /// Apply one market-channel message (a single event or an array of them) and return the
+    /// tokens whose best bid changed, with the new bid
+    pub fn apply(&mut self, msg: &Value) -> Vec<(String, f64)> {
+        let events = msg.as_array().map(Vec::as_slice).unwrap_or(std::slice::from_ref(msg));
+        let mut touched: Vec<(String, Option<f64>)> = Vec::new();
+        let mut touch = |ladders: &Self, token: &str| {
+            if !touched.iter().any(|(t, _)| t == token) {
+                touched.push((token.to_string(), ladders.best_bid(token)));
+            }
+        };
+
+        for e in events {
+            match e["event_type"].as_str() {
+                Some("book") => {
+                    let Some(token) = e["asset_id"].as_str() else { continue };
+                    touch(self, token);
+                    let levels = e["bids"].as_array().or_else(|| e["buys"].as_array());
+                    let ladder = levels.into_iter().flatten()
+                        .filter_map(|l| Some((key(num(&l["price"])?), num(&l["size"])?)))
+                        .filter(|(_, size)| *size > 0.0)
+                        .collect();
+                    self.books.insert(token.to_string(), ladder);
+                }
+                Some("price_change") => {
+                    // Current format carries the asset per change, the older one per event
+                    let changes = e["price_changes"].as_array().or_else(|| e["changes"].as_array());
+                    for c in changes.into_iter().flatten() {
+                        let Some(token) = c["asset_id"].as_str().or_else(|| e["asset_id"].as_str()) else { continue };
+                        if !c["side"].as_str().is_some_and(|s| s.eq_ignore_ascii_case("BUY")) {
+                            continue;
+                        }
+                        let (Some(price), Some(size)) = (num(&c["price"]), num(&c["size"])) else { continue };
+                        touch(self, token);
+                        self.set_level(token, price, size);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        for (token, _) in &touched {
+            self.updated.insert(token.clone(), now);
+        }
+        touched.into_iter()
+            .filter_map(|(token, before)| {
+                let after = self.best_bid(&token)?;
+                before.is_none_or(|b| (b - after).abs() > 1e-9).then_some((token, after))
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// Book Ages
+// ============================================================================
+
+static BOOK_UPDATES: OnceLock<Mutex<FxHashMap<String, Instant>>> = OnceLock::new();
+
+fn book_updates() -> &'static Mutex<FxHashMap<String, Instant>> {
+    BOOK_UPDATES.get_or_init(Mutex::default)
+}
+
+fn publish_updates(updated: &FxHashMap<String, Instant>) {
+    if let Ok(mut ages) = book_updates().lock() {
+        ages.clone_from(updated);
+    }
+}
+
+/// Time since the feed last touched each subscribed token's book, oldest first
+pub fn book_ages() -> Vec<(String, Duration)> {
+    let Ok(updates) = book_updates().lock() else { return Vec::new() };
+    let mut ages: Vec<_> = updates.iter().map(|(t, at)| (t.clone(), at.elapsed())).collect();
+    ages.sort_by_key(|a| Reverse(a.1));
+    ages
+}
+
+// ============================================================================
+// Feed Task
+// ============================================================================
+
+async fn held_tokens(tracker: &PositionTracker) -> FxHashSet<String> {
+    tracker.get_all_positions().await.into_iter().map(|p| p.token_id).collect()
+}
+
+/// One subscription; returns Ok when the held set changed and a resubscribe is due
+async fn run_feed(held: &FxHashSet<String>, tracker: &PositionTracker, tx: &mpsc::UnboundedSender<(String, f64)>) -> Result<()> {
+    let (mut ws, _) = connect_async(market_ws_url()).await?;
+    let sub = serde_json::json!({ "assets_ids": held.iter().collect::<Vec<_>>(), "type": "market" }).to_string();
+    ws.send(Message::Text(sub)).await?;
+    diagnostics().heartbeat("book_feed", "subscribed");
+
+    let mut ladders = BidLadders::default();
+    publish_updates(&ladders.updated);
+    let mut check = tokio::time::interval(HELD_CHECK_INTERVAL);
+    check.tick().await;
+    loop {
+        tokio::select! {
+            msg = tokio::time::timeout(FEED_TIMEOUT, ws.next()) => {
+                let msg = msg.map_err(|_| anyhow!("book feed timeout"))?
+                    .ok_or_else(|| anyhow!("book feed closed"))??;
+                let text = match msg {
+                    Message::Text(text) => text,
+                    Message::Ping(d) => { ws.send(Message::Pong(d)).await?; continue; }
+                    Message::Close(f) => return Err(anyhow!("book feed closed: {:?}", f)),
+                    _ => continue,
+                };
+                crate::replay_buffer::record("book", &text);
+                // Keepalive replies ("PONG") are not JSON
+                let Ok(value) = serde_json::from_str::<Value>(&text) else { continue };
+                diagnostics().heartbeat("book_feed", "message");
+                let changes = ladders.apply(&value);
+                publish_updates(&ladders.updated);
+                for update in changes {
+                    if tx.send(update).is_err() {
+                        return Err(anyhow!("stop-loss receiver gone"));
+                    }
+                }
+            }
+            _ = check.tick() => {
+                ws.send(Message::Text("PING".into())).await?;
+                if held_tokens(tracker).await != *held {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Stream best-bid changes of held tokens into `tx` for the stop-loss worker
+pub fn spawn_book_feed_task(tracker: Arc<PositionTracker>, tx: mpsc::UnboundedSender<(String, f64)>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let held = held_tokens(&tracker).await;
+            if held.is_empty() {
+                diagnostics().heartbeat("book_feed", "idle");
+                tokio::time::sleep(HELD_CHECK_INTERVAL).await;
+                continue;
+            }
+            if let Err(e) = run_feed(&held, &tracker, &tx).await {
+                crate::console_eprintln!("⚠️ Book feed: {} (reconnecting, stop-loss polling continues)", e);
+                tokio::time::sleep(report_failure("book_feed", &RestartPolicy::default(), RECONNECT_DELAY)).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_snapshot_and_level_updates() {
+        let mut l = BidLadders::default();
+        let changed = l.apply(&json!([{ "event_type": "book", "asset_id": "t",
+            "bids": [{ "price": "0.48", "size": "100" }, { "price": "0.50", "size": "20" }],
+            "asks": [{ "price": "0.52", "size": "50" }] }]));
+        assert_eq!(changed, vec![("t".to_string(), 0.50)]);
+
+        // Ask-side changes and bids below the top leave the best bid alone
+        let changed = l.apply(&json!({ "event_type": "price_change", "price_changes": [
+            { "asset_id": "t", "price": "0.53", "size": "10", "side": "SELL" },
+            { "asset_id": "t", "price": "0.47", "size": "10", "side": "BUY" }
+        ]}));
+        assert!(changed.is_empty());
+
+        // The top level is pulled: the bid drops to the next level
+        let changed = l.apply(&json!({ "event_type": "price_change", "asset_id": "t",
+            "changes": [{ "price": "0.50", "size": "0", "side": "BUY" }] }));
+        assert_eq!(changed, vec![("t".to_string(), 0.48)]);
+        assert_eq!(l.best_bid("t"), Some(0.48));
+    }
+}
@@ -0,0 +1,182 @@
+//! Capital at risk
+//! A binary outcome token can resolve to zero, so the worst case of a position is losing what
+//! is left of its premium. Per held token (all tags sharing the wallet) this gives the premium
+//! paid since the position opened and the part still at risk after partial exits (shares held
+//! x average entry). The total is shown in the diagnostics dump, the TUI and
+//! `pm_bot at-risk`, and MAX_CAPITAL_AT_RISK_USD refuses entries that would take it past the
+//! limit
+
+use crate::display;
+use rustc_hash::FxHashMap;
+use std::fmt::Write as _;
+use std::sync::OnceLock;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Positions listed in the diagnostics dump (largest at risk first)
+const DUMP_TOP_POSITIONS: usize = 5;
+
+// ============================================================================
+// Positions
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionRisk {
+    pub token_id: String,
+    pub shares: f64,
+    pub avg_price: f64,
+    /// Every buy since the position opened
+    pub paid_usd: f64,
+    /// Loss if the token resolves to zero: shares still held x average entry
+    pub at_risk_usd: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CapitalAtRisk {
+    /// Largest at risk first
+    pub positions: Vec<PositionRisk>,
+    pub paid_usd: f64,
+    pub at_risk_usd: f64,
+}
+
+/// Combine `(token, shares, avg, paid)` rows (one per tag holding a token) into per-token
+/// positions and totals
+pub fn summarize(rows: &[(String, f64, f64, f64)]) -> CapitalAtRisk {
+    let mut by_token: FxHashMap<&str, (f64, f64, f64)> = FxHashMap::default();
+    for (token, shares, avg, paid) in rows {
+        let e = by_token.entry(token.as_str()).or_default();
+        e.0 += shares;
+        e.1 += shares * avg;
+        e.2 += paid;
+    }
+    let mut positions: Vec<PositionRisk> = by_token.into_iter()
+        .filter(|(_, (shares, _, _))| *shares > 1e-9)
+        .map(|(token, (shares, cost, paid))| PositionRisk {
+            token_id: token.to_string(),
+            shares,
+            avg_price: cost / shares,
+            // Older ledgers without the buys behind a manual set still have their cost
+            paid_usd: paid.max(cost),
+            at_risk_usd: cost,
+        })
+        .collect();
+    positions.sort_by(|a, b| b.at_risk_usd.total_cmp(&a.at_risk_usd).then_with(|| a.token_id.cmp(&b.token_id)));
+    CapitalAtRisk {
+        paid_usd: positions.iter().map(|p| p.paid_usd).sum(),
+        at_risk_usd: positions.iter().map(|p| p.at_risk_usd).sum(),
+        positions,
+    }
+}
+
+/// Held positions across every tag in the strategy ledger
+pub fn current() -> CapitalAtRisk {
+    let mut rows = Vec::new();
+    for (_, book) in crate::strategy::strategy_ledger().snapshot() {
+        for token in book.tokens() {
+            if let Some((shares, avg)) = book.held(&token) {
+                let paid = book.paid(&token);
+                rows.push((token, shares, avg, paid));
+            }
+        }
+    }
+    summarize(&rows)
+}
+
+/// Err(at risk now) when an entry costing `order_usd` would take the total past `limit`
+pub fn check_entry(limit: Option<f64>, at_risk_usd: f64, order_usd: f64) -> Result<(), f64> {
+    match limit {
+        Some(max) if at_risk_usd + order_usd > max + 1e-9 => Err(at_risk_usd),
+        _ => Ok(()),
+    }
+}
+
+impl CapitalAtRisk {
+    /// Diagnostics dump lines: the total and the largest positions (empty when flat)
+    pub fn report(&self, limit: Option<f64>) -> String {
+        let mut out = String::new();
+        if self.positions.is_empty() {
+            return out;
+        }
+        let _ = write!(
+            out, "  {:<14} at_risk={} paid={} positions={}",
+            "capital_risk", display::usd(self.at_risk_usd), display::usd(self.paid_usd), self.positions.len()
+        );
+        if let Some(max) = limit {
+            let _ = write!(out, " limit={} headroom={}", display::usd(max), display::usd((max - self.at_risk_usd).max(0.0)));
+        }
+        out.push('\n');
+        for p in self.positions.iter().take(DUMP_TOP_POSITIONS) {
+            let _ = writeln!(
+                out, "    {} {} sh @ {} at_risk={} paid={}",
+                p.token_id, display::shares(p.shares), display::avg_price(&p.token_id, p.avg_price),
+                display::usd(p.at_risk_usd), display::usd(p.paid_usd)
+            );
+        }
+        out
+    }
+}
+
+/// Table for `pm_bot at-risk`
+pub fn render_report(c: &CapitalAtRisk, limit: Option<f64>) -> String {
+    if c.positions.is_empty() {
+        return "No open positions in the strategy ledger\n".to_string();
+    }
+    let mut out = format!("{:<24} {:>10} {:>8} {:>12} {:>12}\n", "TOKEN", "SHARES", "AVG", "PAID", "AT RISK");
+    for p in &c.positions {
+        let _ = writeln!(
+            out, "{:<24} {:>10.2} {:>8.4} {:>12.2} {:>12.2}",
+            p.token_id, p.shares, p.avg_price, p.paid_usd, p.at_risk_usd
+        );
+    }
+    let _ = writeln!(out, "{:<24} {:>10} {:>8} {:>12.2} {:>12.2}", "TOTAL", "", "", c.paid_usd, c.at_risk_usd);
+    if let Some(max) = limit {
+        let _ = writeln!(out, "Limit ${:.2} (MAX_CAPITAL_AT_RISK_USD), ${:.2} left for entries", max, (max - c.at_risk_usd).max(0.0));
+    }
+    out
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_LIMIT: OnceLock<Option<f64>> = OnceLock::new();
+
+/// Set once at startup (MAX_CAPITAL_AT_RISK_USD)
+pub fn init_limit(limit: Option<f64>) {
+    let _ = GLOBAL_LIMIT.set(limit);
+}
+
+pub fn limit() -> Option<f64> {
+    GLOBAL_LIMIT.get().copied().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_combines_tags_after_partial_exit() {
+        let rows = vec![
+            // 100 bought at 0.40, 40 sold: $40 paid, 60 x 0.40 still at risk
+            ("a".to_string(), 60.0, 0.40, 40.0),
+            ("b".to_string(), 10.0, 0.50, 5.0),
+            ("a".to_string(), 20.0, 0.60, 12.0),
+        ];
+        let c = summarize(&rows);
+        assert_eq!(c.positions.len(), 2);
+        let a = &c.positions[0];
+        assert_eq!(a.token_id, "a");
+        assert!((a.at_risk_usd - 36.0).abs() < 1e-9 && (a.paid_usd - 52.0).abs() < 1e-9);
+        assert!((a.avg_price - 0.45).abs() < 1e-9);
+        assert!((c.at_risk_usd - 41.0).abs() < 1e-9 && (c.paid_usd - 57.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_check_entry_limit() {
+        assert!(check_entry(None, 1_000.0, 50.0).is_ok());
+        assert!(check_entry(Some(100.0), 60.0, 40.0).is_ok());
+        assert_eq!(check_entry(Some(100.0), 60.0, 40.01), Err(60.0));
+    }
+}
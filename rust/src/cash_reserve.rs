@@ -0,0 +1,164 @@
+//! Cash reserve
+//! Keeps a fraction of account equity (USDC balance plus the cost of open positions) out of new
+//! entries, so exits, re-entries after a stop and fees never meet a fully deployed wallet. The
+//! balance is polled from the CLOB; entries since the last poll are deducted locally until the
+//! next one confirms them
+
+use crate::{PreparedCreds, RustClobClient};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// How often the USDC balance is re-read
+pub const BALANCE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// ============================================================================
+// Reserve
+// ============================================================================
+
+#[derive(Default)]
+struct Cash {
+    /// Last polled USDC balance, None until the first poll
+    polled: Option<f64>,
+    /// Entry cost committed since that poll
+    committed: f64,
+}
+
+pub struct CashReserve {
+    /// Share of equity kept uncommitted (0-1)
+    pub fraction: f64,
+    cash: Mutex<Cash>,
+    blocked: AtomicU64,
+}
+
+impl CashReserve {
+    pub fn new(fraction: f64) -> Self {
+        Self { fraction: fraction.clamp(0.0, 1.0), cash: Mutex::default(), blocked: AtomicU64::new(0) }
+    }
+
+    pub fn set_balance(&self, usd: f64) {
+        if let Ok(mut c) = self.cash.lock() {
+            *c = Cash { polled: Some(usd), committed: 0.0 };
+        }
+    }
+
+    /// Cash still free for entries, None before the first poll
+    pub fn cash(&self) -> Option<f64> {
+        let c = self.cash.lock().ok()?;
+        c.polled.map(|b| b - c.committed)
+    }
+
+    /// (cash, equity, reserve) given the open positions' cost
+    pub fn status(&self, open_cost: f64) -> Option<(f64, f64, f64)> {
+        let cash = self.cash()?;
+        let equity = cash + open_cost;
+        Some((cash, equity, equity * self.fraction))
+    }
+
+    /// Err((cash, reserve)) when spending `order_usd` would dip into the reserve. An unknown
+    /// balance lets the entry through (the exchange still refuses what the wallet cannot pay)
+    pub fn check_entry(&self, order_usd: f64, open_cost: f64) -> Result<(), (f64, f64)> {
+        let Some((cash, _, reserve)) = self.status(open_cost) else { return Ok(()) };
+        if cash - order_usd < reserve {
+            self.blocked.fetch_add(1, Ordering::Relaxed);
+            return Err((cash, reserve));
+        }
+        Ok(())
+    }
+
+    /// An entry was accepted for `usd`
+    pub fn commit(&self, usd: f64) {
+        if let Ok(mut c) = self.cash.lock() {
+            c.committed += usd;
+        }
+    }
+
+    /// Dump line, empty before the first poll
+    pub fn report(&self, open_cost: f64) -> String {
+        let mut out = String::new();
+        if let Some((cash, equity, reserve)) = self.status(open_cost) {
+            let _ = writeln!(
+                out, "  {:<14} cash=${:.2} equity=${:.2} reserve=${:.2} ({:.0}%) free=${:.2} blocked={}",
+                "cash_reserve", cash, equity, reserve, self.fraction * 100.0, (cash - reserve).max(0.0),
+                self.blocked.load(Ordering::Relaxed)
+            );
+        }
+        out
+    }
+}
+
+/// Cost of open positions across every tag sharing the wallet
+pub fn wallet_open_cost() -> f64 {
+    crate::strategy::strategy_ledger().snapshot().iter().map(|(_, b)| b.open_cost()).sum()
+}
+
+/// USDC balance from the CLOB (blocking), None when the request failed
+pub fn fetch_balance(client: &RustClobClient, creds: &PreparedCreds) -> Option<f64> {
+    client.get_l2("/balance-allowance", "asset_type=COLLATERAL&signature_type=1", creds)
+        .ok()
+        .filter(|r| r.status().is_success())
+        .and_then(|r| r.json::<serde_json::Value>().ok())
+        .and_then(|v| crate::doctor::parse_balance_allowance(&v))
+        .map(|(usd, _)| usd)
+}
+
+/// Poll the USDC balance every `BALANCE_POLL_INTERVAL`
+pub fn spawn_balance_task(client: Arc<RustClobClient>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(BALANCE_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(reserve) = cash_reserve() else { return };
+            let (c, pc) = (Arc::clone(&client), crate::credentials::current());
+            let balance = tokio::task::spawn_blocking(move || fetch_balance(&c, &pc)).await.ok().flatten();
+            match balance {
+                Some(usd) => reserve.set_balance(usd),
+                None => crate::console_eprintln!("⚠️ USDC balance poll failed, cash reserve uses the last balance"),
+            }
+        }
+    })
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_RESERVE: OnceLock<Option<CashReserve>> = OnceLock::new();
+
+/// Set once at startup (None = no reserve)
+pub fn init_cash_reserve(fraction: Option<f64>) {
+    let _ = GLOBAL_RESERVE.set(fraction.map(CashReserve::new));
+}
+
+pub fn cash_reserve() -> Option<&'static CashReserve> {
+    GLOBAL_RESERVE.get().and_then(Option::as_ref)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_blocks_entries_into_it() {
+        let r = CashReserve::new(0.20);
+        // No balance yet: nothing to enforce
+        assert!(r.check_entry(1_000.0, 0.0).is_ok());
+        assert!(r.report(0.0).is_empty());
+
+        // $600 cash + $400 in positions: $200 stays free of entries
+        r.set_balance(600.0);
+        assert!(r.check_entry(400.0, 400.0).is_ok());
+        r.commit(350.0);
+        assert_eq!(r.check_entry(100.0, 750.0), Err((250.0, 200.0)));
+        assert!(r.report(750.0).contains("cash=$250.00 equity=$1000.00 reserve=$200.00 (20%) free=$50.00 blocked=1"));
+
+        // The next poll replaces the local estimate
+        r.set_balance(250.0);
+        assert_eq!(r.cash(), Some(250.0));
+    }
+}
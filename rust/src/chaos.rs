@@ -0,0 +1,318 @@
+//! Fault injection for chaos testing (feature `chaos`)
+//! A seeded fault plan is consulted at fault points in the real code (WS message loop, order
+//! POST) once installed process-wide, and wrappers around the engine's Feed and Executor
+//! inject the same faults into embedded engines. None of this is compiled into normal builds
+
+use crate::engine::{CopyOrder, Executor, Feed};
+use crate::models::ParsedEvent;
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustc_hash::FxHashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Plan
+// ============================================================================
+
+/// Probabilities are per event (0 = never, 1 = always)
+#[derive(Debug, Clone)]
+pub struct ChaosPlan {
+    pub seed: u64,
+    /// A WS message is replaced by a dropped connection
+    pub ws_drop_rate: f64,
+    /// An order POST fails with `http_error_status`
+    pub http_error_rate: f64,
+    pub http_error_status: u16,
+    /// Added to every order POST
+    pub latency: Duration,
+    /// An accepted order fills only `partial_fill_ratio` of its size
+    pub partial_fill_rate: f64,
+    pub partial_fill_ratio: f64,
+    /// A feed event is delivered twice, as logs are replayed after a reconnect
+    pub duplicate_rate: f64,
+}
+
+impl Default for ChaosPlan {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            ws_drop_rate: 0.0,
+            http_error_rate: 0.0,
+            http_error_status: 429,
+            latency: Duration::ZERO,
+            partial_fill_rate: 0.0,
+            partial_fill_ratio: 0.5,
+            duplicate_rate: 0.0,
+        }
+    }
+}
+
+impl ChaosPlan {
+    /// Plan from CHAOS_* env vars, for running the real binary under faults
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        }
+        let d = Self::default();
+        Self {
+            seed: var("CHAOS_SEED", d.seed),
+            ws_drop_rate: var("CHAOS_WS_DROP_RATE", d.ws_drop_rate),
+            http_error_rate: var("CHAOS_HTTP_ERROR_RATE", d.http_error_rate),
+            http_error_status: var("CHAOS_HTTP_STATUS", d.http_error_status),
+            latency: Duration::from_millis(var("CHAOS_LATENCY_MS", 0)),
+            partial_fill_rate: var("CHAOS_PARTIAL_FILL_RATE", d.partial_fill_rate),
+            partial_fill_ratio: var("CHAOS_PARTIAL_FILL_RATIO", d.partial_fill_ratio),
+            duplicate_rate: var("CHAOS_DUPLICATE_RATE", d.duplicate_rate),
+        }
+    }
+}
+
+// ============================================================================
+// Fault Source
+// ============================================================================
+
+pub struct Chaos {
+    plan: ChaosPlan,
+    rng: Mutex<StdRng>,
+    injected: Mutex<FxHashMap<&'static str, u64>>,
+}
+
+impl Chaos {
+    pub fn new(plan: ChaosPlan) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(plan.seed)),
+            plan,
+            injected: Mutex::new(FxHashMap::default()),
+        }
+    }
+
+    fn roll(&self, fault: &'static str, p: f64) -> bool {
+        if p <= 0.0 {
+            return false;
+        }
+        let hit = self.rng.lock().map(|mut r| r.gen_bool(p.min(1.0))).unwrap_or(false);
+        if hit && let Ok(mut n) = self.injected.lock() {
+            *n.entry(fault).or_default() += 1;
+        }
+        hit
+    }
+
+    pub fn ws_drop(&self) -> bool {
+        self.roll("ws_drop", self.plan.ws_drop_rate)
+    }
+
+    /// Status code to fail an order POST with
+    pub fn http_fault(&self) -> Option<u16> {
+        self.roll("http_error", self.plan.http_error_rate).then_some(self.plan.http_error_status)
+    }
+
+    pub fn latency(&self) -> Duration {
+        self.plan.latency
+    }
+
+    /// Shares that actually fill out of `shares`
+    pub fn partial_fill(&self, shares: f64) -> f64 {
+        if self.roll("partial_fill", self.plan.partial_fill_rate) {
+            (shares * self.plan.partial_fill_ratio * 100.0).floor() / 100.0
+        } else {
+            shares
+        }
+    }
+
+    pub fn duplicate(&self) -> bool {
+        self.roll("duplicate", self.plan.duplicate_rate)
+    }
+
+    /// How many times `fault` was injected
+    pub fn injected(&self, fault: &str) -> u64 {
+        self.injected.lock().ok().and_then(|n| n.get(fault).copied()).unwrap_or(0)
+    }
+}
+
+static GLOBAL_CHAOS: OnceLock<Chaos> = OnceLock::new();
+
+/// Enable the fault points in the real code for this process
+pub fn install(plan: ChaosPlan) {
+    let _ = GLOBAL_CHAOS.set(Chaos::new(plan));
+}
+
+/// Installed plan, if any
+pub fn chaos() -> Option<&'static Chaos> {
+    GLOBAL_CHAOS.get()
+}
+
+// ============================================================================
+// Engine Wrappers
+// ============================================================================
+
+/// Feed that sometimes delivers an event twice
+pub struct ChaosFeed<F> {
+    inner: F,
+    chaos: Arc<Chaos>,
+    replay: Option<ParsedEvent>,
+}
+
+impl<F> ChaosFeed<F> {
+    pub fn new(inner: F, chaos: Arc<Chaos>) -> Self {
+        Self { inner, chaos, replay: None }
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Feed> Feed for ChaosFeed<F> {
+    async fn next_event(&mut self) -> Option<ParsedEvent> {
+        if let Some(evt) = self.replay.take() {
+            return Some(evt);
+        }
+        let evt = self.inner.next_event().await?;
+        if self.chaos.duplicate() {
+            self.replay = Some(evt.clone());
+        }
+        Some(evt)
+    }
+}
+
+/// Executor behind a slow, rate-limited, partially filling exchange
+pub struct ChaosExecutor<E> {
+    inner: E,
+    chaos: Arc<Chaos>,
+}
+
+impl<E> ChaosExecutor<E> {
+    pub fn new(inner: E, chaos: Arc<Chaos>) -> Self {
+        Self { inner, chaos }
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: Executor> Executor for ChaosExecutor<E> {
+    async fn execute(&self, order: &CopyOrder) -> Result<String> {
+        tokio::time::sleep(self.chaos.latency()).await;
+        if let Some(status) = self.chaos.http_fault() {
+            anyhow::bail!("HTTP {} (chaos)", status);
+        }
+        let shares = self.chaos.partial_fill(order.shares);
+        self.inner.execute(&CopyOrder { shares, ..order.clone() }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{DedupCheck, Engine, Notifier, Outcome, TierCopyStrategy};
+    use crate::models::OrderInfo;
+
+    fn event(i: u64) -> ParsedEvent {
+        ParsedEvent {
+            received_at: Default::default(),
+            block_number: i,
+            tx_hash: format!("0x{:x}", i),
+            log_index: 0,
+            order: OrderInfo {
+                order_type: "BUY_FILL".into(),
+                clob_token_id: format!("t{}", i).into(),
+                usd_value: 1000.0,
+                shares: 2000.0,
+                price_per_share: 0.5,
+            },
+        }
+    }
+
+    struct VecFeed(Vec<ParsedEvent>);
+
+    #[async_trait::async_trait]
+    impl Feed for VecFeed {
+        async fn next_event(&mut self) -> Option<ParsedEvent> {
+            self.0.pop()
+        }
+    }
+
+    /// Exchange that books every order it receives per token
+    #[derive(Clone, Default)]
+    struct FakeExchange(Arc<Mutex<FxHashMap<String, (u32, f64)>>>);
+
+    #[async_trait::async_trait]
+    impl Executor for FakeExchange {
+        async fn execute(&self, order: &CopyOrder) -> Result<String> {
+            let mut book = self.0.lock().unwrap();
+            let entry = book.entry(order.token_id.to_string()).or_default();
+            entry.0 += 1;
+            entry.1 += order.shares;
+            Ok(format!("filled {:.2}", order.shares))
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Outcomes(Arc<Mutex<Vec<(String, Outcome)>>>);
+
+    impl Notifier for Outcomes {
+        fn notify(&self, evt: &ParsedEvent, outcome: &Outcome) {
+            self.0.lock().unwrap().push((evt.order.clob_token_id.to_string(), outcome.clone()));
+        }
+    }
+
+    #[test]
+    fn test_plan_is_deterministic() {
+        let plan = ChaosPlan { seed: 7, http_error_rate: 0.5, ..Default::default() };
+        let (a, b) = (Chaos::new(plan.clone()), Chaos::new(plan));
+        let run = |c: &Chaos| (0..50).map(|_| c.http_fault().is_some()).collect::<Vec<_>>();
+        assert_eq!(run(&a), run(&b));
+        assert!(a.injected("http_error") > 0);
+        assert!(!Chaos::new(ChaosPlan::default()).ws_drop());
+    }
+
+    #[tokio::test]
+    async fn test_engine_degrades_safely_under_faults() {
+        let chaos = Arc::new(Chaos::new(ChaosPlan {
+            seed: 42,
+            http_error_rate: 0.3,
+            http_error_status: 500,
+            partial_fill_rate: 0.3,
+            duplicate_rate: 0.5,
+            latency: Duration::from_millis(1),
+            ..Default::default()
+        }));
+        let exchange = FakeExchange::default();
+        let outcomes = Outcomes::default();
+        let engine = Engine::builder()
+            .feed(ChaosFeed::new(VecFeed((0..40).map(event).collect()), Arc::clone(&chaos)))
+            .strategy(TierCopyStrategy)
+            .risk(DedupCheck::default())
+            .executor(ChaosExecutor::new(exchange.clone(), Arc::clone(&chaos)))
+            .notifier(outcomes.clone())
+            .build()
+            .unwrap();
+        engine.run().await;
+
+        assert!(chaos.injected("duplicate") > 0 && chaos.injected("http_error") > 0 && chaos.injected("partial_fill") > 0);
+        let book = exchange.0.lock().unwrap();
+        // No duplicate orders: a replayed event is refused once its original went through, and
+        // only retried when that original failed
+        assert!(book.values().all(|(orders, _)| *orders == 1));
+        let outcomes = outcomes.0.lock().unwrap();
+        assert_eq!(outcomes.len() as u64, 40 + chaos.injected("duplicate"));
+        // No orphaned positions: every fill the exchange booked was reported with its real size,
+        // and every failed POST left nothing on the exchange
+        let mut executed = rustc_hash::FxHashSet::default();
+        for (token, outcome) in outcomes.iter() {
+            match outcome {
+                Outcome::Executed { status, .. } => {
+                    assert!(executed.insert(token.clone()));
+                    assert_eq!(status, &format!("filled {:.2}", book[token].1));
+                }
+                Outcome::Blocked { reason, .. } => {
+                    assert_eq!(reason, "DUPLICATE_INTENT");
+                    assert!(executed.contains(token));
+                }
+                Outcome::Failed { error, .. } => {
+                    assert!(error.contains("HTTP 500"));
+                    assert!(!executed.contains(token));
+                }
+                Outcome::Skipped { .. } => {}
+            }
+        }
+        assert_eq!(book.len(), executed.len());
+    }
+}
@@ -0,0 +1,256 @@
+//! Trade clustering
+//! The whale sometimes fires a burst of entries into correlated markets within a couple of
+//! minutes (four games of the same league, several strikes of one price ladder). Such bursts
+//! tend to resolve the same way, so copying all of them stacks one bet several times over.
+//! Markets are grouped by the leading segments of their slug ("nba-lal-bos-..." -> "nba").
+//! The guard caps how many entries one group takes within a sliding window, and
+//! `pm_bot clusters` finds past bursts in the strategy ledger with their settled P&L
+
+use crate::pnl_attribution::Settlement;
+use crate::strategy::{FillKind, StrategyFill};
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterLimit {
+    /// Entries one group may take within the window
+    pub max_entries: usize,
+    pub window_secs: u64,
+    /// Leading slug segments that make up the group key
+    pub slug_segments: usize,
+}
+
+/// Group key of a market: its first `segments` dash-separated slug segments. Tokens without
+/// a known slug form a group of their own
+pub fn cluster_key(token_id: &str, slug: Option<&str>, segments: usize) -> String {
+    match slug.filter(|s| !s.is_empty()) {
+        Some(slug) => slug.split('-').take(segments.max(1)).collect::<Vec<_>>().join("-"),
+        None => format!("token:{}", token_id),
+    }
+}
+
+fn slug_of(token_id: &str) -> Option<String> {
+    crate::token_metadata::get(token_id).map(|m| m.slug)
+}
+
+// ============================================================================
+// Guard
+// ============================================================================
+
+pub struct ClusterGuard {
+    limit: ClusterLimit,
+    /// Group key -> entry times (unix seconds), oldest first
+    entries: Mutex<FxHashMap<String, VecDeque<u64>>>,
+}
+
+impl ClusterGuard {
+    pub fn new(limit: ClusterLimit) -> Self {
+        Self { limit, entries: Mutex::new(FxHashMap::default()) }
+    }
+
+    pub fn limit(&self) -> ClusterLimit {
+        self.limit
+    }
+
+    /// Ok, or Err(entries already in the window) when the group is full
+    pub fn check(&self, key: &str, now: u64) -> Result<(), usize> {
+        let Ok(mut entries) = self.entries.lock() else { return Ok(()) };
+        let Some(times) = entries.get_mut(key) else { return Ok(()) };
+        let cutoff = now.saturating_sub(self.limit.window_secs);
+        while times.front().is_some_and(|&t| t <= cutoff) {
+            times.pop_front();
+        }
+        if times.len() >= self.limit.max_entries { Err(times.len()) } else { Ok(()) }
+    }
+
+    pub fn record(&self, key: &str, now: u64) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let cutoff = now.saturating_sub(self.limit.window_secs);
+            entries.retain(|_, times| times.back().is_some_and(|&t| t > cutoff));
+            entries.entry(key.to_string()).or_default().push_back(now);
+        }
+    }
+}
+
+// ============================================================================
+// Report
+// ============================================================================
+
+/// Entries into one group within one window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Burst {
+    pub key: String,
+    pub start: u64,
+    pub end: u64,
+    /// Distinct tokens entered
+    pub tokens: Vec<String>,
+    pub entries: usize,
+    pub cost_usd: f64,
+}
+
+/// Entries of the same group each within `window_secs` of the burst's first, at least
+/// `min_entries` long. A burst ends at the first entry outside the window
+pub fn find_bursts(fills: &[StrategyFill], window_secs: u64, min_entries: usize, key_of: impl Fn(&str) -> String) -> Vec<Burst> {
+    let mut by_key: BTreeMap<String, Vec<&StrategyFill>> = BTreeMap::new();
+    for f in fills.iter().filter(|f| f.is_buy && f.kind == FillKind::Trade) {
+        by_key.entry(key_of(&f.token_id)).or_default().push(f);
+    }
+    let mut out = Vec::new();
+    for (key, mut entries) in by_key {
+        entries.sort_by_key(|f| f.ts);
+        let mut i = 0;
+        while i < entries.len() {
+            let start = entries[i].ts;
+            let j = i + entries[i..].iter().take_while(|f| f.ts <= start + window_secs).count();
+            let run = &entries[i..j];
+            if run.len() >= min_entries.max(2) {
+                let mut tokens: Vec<String> = run.iter().map(|f| f.token_id.clone()).collect();
+                tokens.sort();
+                tokens.dedup();
+                out.push(Burst {
+                    key: key.clone(),
+                    start,
+                    end: run[run.len() - 1].ts,
+                    tokens,
+                    entries: run.len(),
+                    cost_usd: run.iter().map(|f| f.shares * f.price).sum(),
+                });
+            }
+            i = j;
+        }
+    }
+    out.sort_by_key(|b| b.start);
+    out
+}
+
+/// Settled P&L of a burst's tokens and whether they all resolved the same way
+/// (None until at least one has settled)
+pub fn burst_outcome(burst: &Burst, settlements: &[Settlement]) -> Option<(f64, bool)> {
+    let settled: Vec<&Settlement> = settlements.iter().filter(|s| burst.tokens.contains(&s.token_id)).collect();
+    let first = settled.first()?;
+    let pnl = settled.iter().map(|s| s.entry_edge + s.resolution_luck).sum();
+    let same = settled.iter().all(|s| (s.payout >= 0.5) == (first.payout >= 0.5));
+    Some((pnl, same))
+}
+
+/// Table for `pm_bot clusters`
+pub fn render_report(bursts: &[Burst], settlements: &[Settlement]) -> String {
+    let mut out = format!(
+        "{:<24} {:>12} {:>6} {:>7} {:>10} {:>10} {:>8}\n",
+        "GROUP", "START", "SECS", "ENTRIES", "COST", "PNL", "SAME"
+    );
+    let (mut settled, mut same_count, mut pnl_total) = (0, 0, 0.0);
+    for b in bursts {
+        let key: String = b.key.chars().take(24).collect();
+        let (pnl, same) = match burst_outcome(b, settlements) {
+            Some((pnl, same)) => {
+                settled += 1;
+                pnl_total += pnl;
+                if same {
+                    same_count += 1;
+                }
+                (format!("{:+.2}", pnl), if same { "yes" } else { "no" })
+            }
+            None => ("-".to_string(), "-"),
+        };
+        out.push_str(&format!(
+            "{:<24} {:>12} {:>6} {:>7} {:>10.2} {:>10} {:>8}\n",
+            key, b.start, b.end - b.start, b.entries, b.cost_usd, pnl, same
+        ));
+    }
+    out.push_str(&format!(
+        "{} bursts, {} settled, {} resolved the same way, settled P&L {:+.2}\n",
+        bursts.len(), settled, same_count, pnl_total
+    ));
+    out
+}
+
+/// Bursts in the ledger at `path`, grouped with the slugs in the token metadata cache
+pub fn bursts_from_ledger(path: &str, window_secs: u64, min_entries: usize, slug_segments: usize) -> Vec<Burst> {
+    let fills = crate::session::load_fills(path);
+    find_bursts(&fills, window_secs, min_entries, |t| cluster_key(t, slug_of(t).as_deref(), slug_segments))
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_CLUSTER_GUARD: OnceLock<Option<ClusterGuard>> = OnceLock::new();
+
+/// Set once at startup (None = entries are not capped per group)
+pub fn init_cluster_guard(limit: Option<ClusterLimit>) {
+    let _ = GLOBAL_CLUSTER_GUARD.set(limit.map(ClusterGuard::new));
+}
+
+pub fn cluster_guard() -> Option<&'static ClusterGuard> {
+    GLOBAL_CLUSTER_GUARD.get().and_then(Option::as_ref)
+}
+
+/// Group key and entries in the window when an entry into `token_id` would overfill its group
+pub fn blocked_entry(token_id: &str) -> Option<(String, usize)> {
+    let guard = cluster_guard()?;
+    let key = cluster_key(token_id, slug_of(token_id).as_deref(), guard.limit().slug_segments);
+    guard.check(&key, timestamp::unix_secs()).err().map(|n| (key, n))
+}
+
+/// Count a filled entry against its group
+pub fn record_entry(token_id: &str) {
+    if let Some(guard) = cluster_guard() {
+        let key = cluster_key(token_id, slug_of(token_id).as_deref(), guard.limit().slug_segments);
+        guard.record(&key, timestamp::unix_secs());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy(ts: u64, token: &str) -> StrategyFill {
+        StrategyFill {
+            ts,
+            tag: "default".into(),
+            token_id: token.into(),
+            is_buy: true,
+            shares: 10.0,
+            price: 0.5,
+            kind: FillKind::Trade,
+            session: None,
+        }
+    }
+
+    #[test]
+    fn test_guard_sliding_window() {
+        let guard = ClusterGuard::new(ClusterLimit { max_entries: 2, window_secs: 120, slug_segments: 1 });
+        guard.record("nba", 1_000);
+        guard.record("nba", 1_060);
+        assert_eq!(guard.check("nba", 1_100), Err(2));
+        assert_eq!(guard.check("epl", 1_100), Ok(()));
+        // The first entry leaves the window
+        assert_eq!(guard.check("nba", 1_120), Ok(()));
+        assert_eq!(cluster_key("1", Some("nba-lal-bos-2026-01-02"), 1), "nba");
+        assert_eq!(cluster_key("1", Some("nba-lal-bos-2026-01-02"), 2), "nba-lal");
+        assert_eq!(cluster_key("1", None, 1), "token:1");
+    }
+
+    #[test]
+    fn test_find_bursts() {
+        let key = |t: &str| if t.starts_with('n') { "nba".to_string() } else { "epl".to_string() };
+        let fills = vec![
+            buy(0, "n1"), buy(30, "n2"), buy(60, "e1"), buy(90, "n3"), buy(100, "n3"),
+            buy(500, "n4"), buy(510, "n5"),
+        ];
+        let bursts = find_bursts(&fills, 120, 3, key);
+        assert_eq!(bursts.len(), 1);
+        assert_eq!(bursts[0].key, "nba");
+        assert_eq!(bursts[0].entries, 4);
+        assert_eq!(bursts[0].tokens, vec!["n1", "n2", "n3"]);
+        assert_eq!(bursts[0].end, 100);
+        assert_eq!(find_bursts(&fills, 120, 2, key).len(), 2);
+    }
+}
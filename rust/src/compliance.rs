@@ -0,0 +1,223 @@
+//! Pre-trade compliance checks
+//! Every order passes the registered checks before it is signed: copy entries and exits,
+//! resubmits, stop-loss sells, paired legs. A check sees the order with the market's metadata
+//! and refuses it with a reason. `ComplianceRules` is the built-in check, driven by the
+//! COMPLIANCE_* settings (banned slug prefixes and question keywords, per-order size limits);
+//! embedders add their own rules (jurisdiction lists, category bans, ...) with `register`.
+//! Checks may run more than once for one order, so they should not count or record anything
+
+use crate::token_metadata::TokenMetadata;
+use std::sync::{Arc, OnceLock, RwLock};
+
+// ============================================================================
+// Interface
+// ============================================================================
+
+/// An order about to be submitted
+#[derive(Debug, Clone)]
+pub struct PreTradeOrder {
+    pub token_id: String,
+    pub is_buy: bool,
+    pub price: f64,
+    pub shares: f64,
+    /// None when the market's metadata has not been fetched yet
+    pub market: Option<TokenMetadata>,
+}
+
+impl PreTradeOrder {
+    pub fn notional(&self) -> f64 {
+        self.price * self.shares
+    }
+}
+
+/// A pre-trade rule; Err(reason) refuses the order
+pub trait PreTradeCheck: Send + Sync {
+    fn name(&self) -> &str;
+    fn check(&self, order: &PreTradeOrder) -> Result<(), String>;
+}
+
+/// Checks run in the order registered; the first refusal wins
+#[derive(Default)]
+pub struct CheckSet {
+    checks: RwLock<Vec<Arc<dyn PreTradeCheck>>>,
+}
+
+impl CheckSet {
+    pub fn register(&self, check: Arc<dyn PreTradeCheck>) {
+        if let Ok(mut checks) = self.checks.write() {
+            checks.push(check);
+        }
+    }
+
+    /// Ok, or Err("<check>: <reason>") from the first check that refuses
+    pub fn check(&self, order: &PreTradeOrder) -> Result<(), String> {
+        let Ok(checks) = self.checks.read() else { return Ok(()) };
+        checks.iter().try_for_each(|c| c.check(order).map_err(|reason| format!("{}: {}", c.name(), reason)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checks.read().map(|c| c.is_empty()).unwrap_or(true)
+    }
+}
+
+// ============================================================================
+// Config Rules
+// ============================================================================
+
+/// COMPLIANCE_* settings. Market and size rules apply to entries only, so a held position can
+/// always be closed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComplianceRules {
+    /// Slug prefixes no entry may go into (COMPLIANCE_BLOCK_SLUGS)
+    pub block_slugs: Vec<String>,
+    /// Lowercase words no entry's market question or slug may contain (COMPLIANCE_BLOCK_KEYWORDS)
+    pub block_keywords: Vec<String>,
+    pub max_order_usd: Option<f64>,
+    pub max_order_shares: Option<f64>,
+    /// Refuse entries into markets whose metadata is unknown while market rules are set
+    pub block_unknown: bool,
+}
+
+/// Comma-separated list, trimmed, empty entries dropped
+pub fn parse_list(raw: &str) -> Vec<String> {
+    raw.split(',').map(str::trim).filter(|e| !e.is_empty()).map(String::from).collect()
+}
+
+impl ComplianceRules {
+    pub fn is_active(&self) -> bool {
+        !self.block_slugs.is_empty() || !self.block_keywords.is_empty() || self.max_order_usd.is_some() || self.max_order_shares.is_some()
+    }
+}
+
+impl PreTradeCheck for ComplianceRules {
+    fn name(&self) -> &str {
+        "config"
+    }
+
+    fn check(&self, order: &PreTradeOrder) -> Result<(), String> {
+        if !order.is_buy {
+            return Ok(());
+        }
+        if let Some(max) = self.max_order_usd && order.notional() > max + 1e-9 {
+            return Err(format!("${:.2} > COMPLIANCE_MAX_ORDER_USD ${:.2}", order.notional(), max));
+        }
+        if let Some(max) = self.max_order_shares && order.shares > max + 1e-9 {
+            return Err(format!("{:.2} shares > COMPLIANCE_MAX_ORDER_SHARES {:.2}", order.shares, max));
+        }
+        if self.block_slugs.is_empty() && self.block_keywords.is_empty() {
+            return Ok(());
+        }
+        let Some(market) = &order.market else {
+            return if self.block_unknown { Err("market metadata unknown".into()) } else { Ok(()) };
+        };
+        if let Some(prefix) = self.block_slugs.iter().find(|p| market.slug.starts_with(p.as_str())) {
+            return Err(format!("slug {} matches banned prefix {}", market.slug, prefix));
+        }
+        let text = format!("{} {}", market.question.to_lowercase(), market.slug);
+        if let Some(word) = self.block_keywords.iter().find(|w| text.contains(w.as_str())) {
+            return Err(format!("market mentions banned keyword '{}'", word));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_CHECKS: OnceLock<CheckSet> = OnceLock::new();
+
+pub fn checks() -> &'static CheckSet {
+    GLOBAL_CHECKS.get_or_init(CheckSet::default)
+}
+
+/// Add a check for every order from now on
+pub fn register(check: impl PreTradeCheck + 'static) {
+    checks().register(Arc::new(check));
+}
+
+/// Run the registered checks on an order (market metadata looked up here)
+pub fn check(token_id: &str, is_buy: bool, price: f64, shares: f64) -> Result<(), String> {
+    let checks = checks();
+    if checks.is_empty() {
+        return Ok(());
+    }
+    checks.check(&PreTradeOrder {
+        token_id: token_id.to_string(),
+        is_buy,
+        price,
+        shares,
+        market: crate::token_metadata::get(token_id),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(is_buy: bool, shares: f64, slug: Option<&str>) -> PreTradeOrder {
+        PreTradeOrder {
+            token_id: "t".into(),
+            is_buy,
+            price: 0.5,
+            shares,
+            market: slug.map(|s| TokenMetadata {
+                token_id: "t".into(),
+                outcome: "Yes".into(),
+                question: "Will Arsenal win on Saturday?".into(),
+                slug: s.into(),
+                condition_id: "c".into(),
+                tick_size: "0.01".into(),
+                min_order_size: 5.0,
+                neg_risk: false,
+                end_ts: 0,
+                fetched_at: 0,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_config_rules() {
+        let rules = ComplianceRules {
+            block_slugs: vec!["nba-".into()],
+            block_keywords: vec!["election".into()],
+            max_order_usd: Some(50.0),
+            max_order_shares: None,
+            block_unknown: true,
+        };
+        assert!(rules.check(&order(true, 10.0, Some("epl-ars-che"))).is_ok());
+        assert!(rules.check(&order(true, 10.0, Some("nba-lal-bos"))).unwrap_err().contains("nba-"));
+        assert!(rules.check(&order(true, 10.0, Some("us-election"))).is_err());
+        let mut by_question = order(true, 10.0, Some("epl-ars-che"));
+        if let Some(m) = by_question.market.as_mut() {
+            m.question = "Will the Election be called by Friday?".into();
+        }
+        assert!(rules.check(&by_question).unwrap_err().contains("election"));
+        assert!(rules.check(&order(true, 200.0, Some("epl-ars-che"))).unwrap_err().contains("MAX_ORDER_USD"));
+        assert!(rules.check(&order(true, 10.0, None)).is_err());
+        // Exits are never refused
+        assert!(rules.check(&order(false, 200.0, Some("nba-lal-bos"))).is_ok());
+    }
+
+    struct Never;
+
+    impl PreTradeCheck for Never {
+        fn name(&self) -> &str {
+            "never"
+        }
+
+        fn check(&self, _order: &PreTradeOrder) -> Result<(), String> {
+            Err("closed".into())
+        }
+    }
+
+    #[test]
+    fn test_check_set_first_refusal_wins() {
+        let set = CheckSet::default();
+        assert!(set.check(&order(true, 10.0, None)).is_ok());
+        set.register(Arc::new(ComplianceRules { max_order_shares: Some(5.0), ..Default::default() }));
+        set.register(Arc::new(Never));
+        assert!(set.check(&order(true, 10.0, None)).unwrap_err().starts_with("config:"));
+        assert_eq!(set.check(&order(true, 1.0, None)), Err("never: closed".to_string()));
+    }
+}
@@ -0,0 +1,200 @@
+//! Layered configuration files
+//! Settings can be split into a shared base and per-environment overlays so paper and live
+//! setups share most values without copying them between files. Layers, highest first:
+//! process environment, `.env`, `config/<CONFIG_PROFILE>.env`, `config/base.env`. All use
+//! the `.env` format and a key takes the value of the highest layer that sets it. The
+//! effective values with their layer are printed at startup, and `pm_bot config diff <a> <b>`
+//! compares two profiles
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Directory holding the base and profile files
+pub const CONFIG_DIR: &str = "config";
+
+/// Shared by every profile
+pub const BASE_PROFILE: &str = "base";
+
+/// Host-local file above the profiles (secrets, one-off tweaks)
+const DOTENV_FILE: &str = ".env";
+
+pub const USAGE: &str = "usage:\n  pm_bot config show\n  pm_bot config diff <profile_a> <profile_b>";
+
+/// Keys whose values are never printed
+const SECRET_MARKERS: [&str; 5] = ["KEY", "SECRET", "PASSPHRASE", "TOKEN", "PASSWORD"];
+
+// ============================================================================
+// Layers
+// ============================================================================
+
+pub fn profile_path(dir: &str, profile: &str) -> PathBuf {
+    Path::new(dir).join(format!("{}.env", profile))
+}
+
+/// KEY=VALUE pairs of a layer file, in file order; a missing file is an empty layer
+pub fn read_layer(path: &Path) -> Result<Vec<(String, String)>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    dotenvy::from_path_iter(path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("reading {}", path.display()))
+}
+
+/// Key -> (value, layer name); `layers` are given highest first and the first to set a key wins
+pub fn merge(layers: &[(String, Vec<(String, String)>)]) -> BTreeMap<String, (String, String)> {
+    let mut out = BTreeMap::new();
+    for (name, values) in layers {
+        for (key, value) in values {
+            out.entry(key.clone()).or_insert_with(|| (value.clone(), name.clone()));
+        }
+    }
+    out
+}
+
+/// Base plus `profile` from `dir`, as `merge` returns it
+pub fn resolve_profile(dir: &str, profile: &str) -> Result<BTreeMap<String, (String, String)>> {
+    let overlay = profile_path(dir, profile);
+    if profile != BASE_PROFILE && !overlay.exists() {
+        anyhow::bail!("profile '{}' has no {}", profile, overlay.display());
+    }
+    Ok(merge(&[
+        (profile.to_string(), read_layer(&overlay)?),
+        (BASE_PROFILE.to_string(), read_layer(&profile_path(dir, BASE_PROFILE))?),
+    ]))
+}
+
+/// Keys whose values differ between two profiles: (key, a, b), "" for unset
+pub fn diff_profiles(dir: &str, a: &str, b: &str) -> Result<Vec<(String, String, String)>> {
+    let flat = |m: BTreeMap<String, (String, String)>| m.into_iter().map(|(k, (v, _))| (k, v)).collect::<Vec<_>>();
+    Ok(crate::remote_config::diff(&flat(resolve_profile(dir, a)?), &flat(resolve_profile(dir, b)?)))
+}
+
+// ============================================================================
+// Display
+// ============================================================================
+
+/// Value as it may be printed: secrets hidden, URLs reduced to their host
+pub fn display_value(key: &str, value: &str) -> String {
+    if value.is_empty() {
+        return String::new();
+    }
+    if SECRET_MARKERS.iter().any(|m| key.contains(m)) {
+        return "***".to_string();
+    }
+    if value.contains("://") {
+        return value.split(',').map(|u| crate::latency_probe::redact_url(u.trim())).collect::<Vec<_>>().join(",");
+    }
+    value.to_string()
+}
+
+pub fn render_effective(entries: &BTreeMap<String, (String, String)>) -> String {
+    let mut out = format!("⚙️ Effective config ({} keys from files, highest layer wins)\n", entries.len());
+    for (key, (value, layer)) in entries {
+        let _ = writeln!(out, "  {:<32} {:<24} [{}]", key, display_value(key, value), layer);
+    }
+    out
+}
+
+pub fn render_diff(a: &str, b: &str, rows: &[(String, String, String)]) -> String {
+    if rows.is_empty() {
+        return format!("Profiles '{}' and '{}' resolve to the same values\n", a, b);
+    }
+    let mut out = format!("{:<32} {:<24} {:<24}\n", "KEY", a, b);
+    for (key, va, vb) in rows {
+        let shown = |v: &str| if v.is_empty() { "(unset)".to_string() } else { display_value(key, v) };
+        let _ = writeln!(out, "{:<32} {:<24} {:<24}", key, shown(va), shown(vb));
+    }
+    out
+}
+
+// ============================================================================
+// Startup
+// ============================================================================
+
+static GLOBAL_EFFECTIVE: OnceLock<BTreeMap<String, (String, String)>> = OnceLock::new();
+
+/// Load the layers into the process environment (replaces a plain `dotenv()`). Variables
+/// already set are never overwritten, so each layer only fills what the ones above left unset.
+/// Fails when a file does not parse or CONFIG_PROFILE names a profile without a file
+pub fn load() -> Result<()> {
+    let dotenv = read_layer(Path::new(DOTENV_FILE))?;
+    let profile = std::env::var("CONFIG_PROFILE").ok()
+        .or_else(|| dotenv.iter().rev().find(|(k, _)| k == "CONFIG_PROFILE").map(|(_, v)| v.clone()))
+        .filter(|p| !p.trim().is_empty());
+    let mut layers = vec![(DOTENV_FILE.to_string(), dotenv)];
+    if let Some(profile) = &profile {
+        let path = profile_path(CONFIG_DIR, profile.trim());
+        if !path.exists() {
+            anyhow::bail!("CONFIG_PROFILE={} but {} does not exist", profile, path.display());
+        }
+        layers.push((profile.trim().to_string(), read_layer(&path)?));
+    }
+    layers.push((BASE_PROFILE.to_string(), read_layer(&profile_path(CONFIG_DIR, BASE_PROFILE))?));
+
+    // Keys set in the process environment before any file is read sit above every layer
+    let keys: Vec<String> = layers.iter().flat_map(|(_, v)| v.iter().map(|(k, _)| k.clone())).collect();
+    let process: Vec<(String, String)> = keys.iter()
+        .filter_map(|k| std::env::var(k).ok().map(|v| (k.clone(), v)))
+        .collect();
+    layers.insert(0, ("env".to_string(), process));
+
+    for path in [PathBuf::from(DOTENV_FILE)].into_iter()
+        .chain(profile.map(|p| profile_path(CONFIG_DIR, p.trim())))
+        .chain([profile_path(CONFIG_DIR, BASE_PROFILE)])
+    {
+        if path.exists() {
+            dotenvy::from_path(&path).with_context(|| format!("loading {}", path.display()))?;
+        }
+    }
+    let _ = GLOBAL_EFFECTIVE.set(merge(&layers));
+    Ok(())
+}
+
+/// Effective file-backed values and their layer, as loaded at startup
+pub fn effective() -> &'static BTreeMap<String, (String, String)> {
+    GLOBAL_EFFECTIVE.get_or_init(BTreeMap::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_layer_and_diff() {
+        let dir = std::env::temp_dir().join(format!("config_layers_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("base.env"), "MIN_ORDER_USD=1\nSTRATEGY_TAG=copy\n# comment\nENABLE_TRADING=false\n").unwrap();
+        std::fs::write(dir.join("live.env"), "ENABLE_TRADING=true\nMIN_ORDER_USD=2\n").unwrap();
+        std::fs::write(dir.join("paper.env"), "MOCK_TRADING=true\n").unwrap();
+        let d = dir.to_str().unwrap();
+
+        let live = resolve_profile(d, "live").unwrap();
+        assert_eq!(live["ENABLE_TRADING"], ("true".to_string(), "live".to_string()));
+        assert_eq!(live["STRATEGY_TAG"], ("copy".to_string(), "base".to_string()));
+
+        let rows = diff_profiles(d, "paper", "live").unwrap();
+        assert_eq!(rows, vec![
+            ("ENABLE_TRADING".to_string(), "false".to_string(), "true".to_string()),
+            ("MIN_ORDER_USD".to_string(), "1".to_string(), "2".to_string()),
+            ("MOCK_TRADING".to_string(), "true".to_string(), String::new()),
+        ]);
+        assert!(resolve_profile(d, "staging").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_display_value_hides_secrets() {
+        assert_eq!(display_value("PRIVATE_KEY", "0xabc"), "***");
+        assert_eq!(display_value("REMOTE_CONFIG_TOKEN", "t"), "***");
+        assert_eq!(display_value("WSS_URLS", "wss://polygon-mainnet.g.alchemy.com/v2/SECRET"), "polygon-mainnet.g.alchemy.com");
+        assert_eq!(display_value("MIN_ORDER_USD", "1.5"), "1.5");
+    }
+}
@@ -0,0 +1,138 @@
+//! Opposite-outcome conflicts
+//! The whale can buy both outcomes of one market over a session (Yes, then No after the news
+//! turns). Copying both leaves us holding shares that pay out against each other. Before an
+//! entry, the held tokens of the same market (condition id) are looked up and the market's
+//! mode decides: hedge lets the entry through, block skips it. The mode is set globally and
+//! can be overridden per market by slug prefix
+
+use anyhow::Result;
+use std::sync::OnceLock;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictMode {
+    /// Hold both outcomes (the pair pays $1 whatever happens)
+    Hedge,
+    /// Skip an entry into the opposite outcome of a held market
+    Block,
+}
+
+impl ConflictMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hedge" => Some(Self::Hedge),
+            "block" => Some(Self::Block),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictPolicy {
+    pub default: ConflictMode,
+    /// (slug prefix, mode); the longest matching prefix wins
+    pub overrides: Vec<(String, ConflictMode)>,
+}
+
+impl ConflictPolicy {
+    /// CONFLICT_MODE and CONFLICT_MODE_BY_SLUG ("nba-:block,epl-:hedge")
+    pub fn parse(default: &str, by_slug: &str) -> Result<Self> {
+        let default = ConflictMode::parse(default)
+            .ok_or_else(|| anyhow::anyhow!("CONFLICT_MODE must be hedge or block (got '{}')", default))?;
+        let overrides = by_slug.split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .map(|e| {
+                let (prefix, mode) = e.rsplit_once(':')
+                    .ok_or_else(|| anyhow::anyhow!("CONFLICT_MODE_BY_SLUG entry '{}' is not prefix:mode", e))?;
+                let mode = ConflictMode::parse(mode)
+                    .ok_or_else(|| anyhow::anyhow!("CONFLICT_MODE_BY_SLUG entry '{}' must end in :hedge or :block", e))?;
+                Ok((prefix.trim().to_string(), mode))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { default, overrides })
+    }
+
+    pub fn mode_for(&self, slug: &str) -> ConflictMode {
+        self.overrides.iter()
+            .filter(|(prefix, _)| slug.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, mode)| *mode)
+    }
+
+    /// Whether any market can block (otherwise the check is skipped entirely)
+    pub fn is_active(&self) -> bool {
+        self.default == ConflictMode::Block || self.overrides.iter().any(|(_, m)| *m == ConflictMode::Block)
+    }
+}
+
+// ============================================================================
+// Check
+// ============================================================================
+
+/// A held token of the same market as `token_id`, other than `token_id` itself.
+/// `condition_of` maps a token to its market's condition id (None when unknown)
+pub fn opposite_holding(token_id: &str, held: &[String], condition_of: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let condition = condition_of(token_id).filter(|c| !c.is_empty())?;
+    held.iter()
+        .filter(|t| t.as_str() != token_id)
+        .find(|t| condition_of(t).is_some_and(|c| c == condition))
+        .cloned()
+}
+
+/// The held opposite token when the market's mode blocks an entry into `token_id`
+pub fn blocking_holding(policy: &ConflictPolicy, token_id: &str, held: &[String]) -> Option<String> {
+    let meta = crate::token_metadata::get(token_id)?;
+    if policy.mode_for(&meta.slug) != ConflictMode::Block {
+        return None;
+    }
+    opposite_holding(token_id, held, |t| crate::token_metadata::get(t).map(|m| m.condition_id))
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_CONFLICT: OnceLock<Option<ConflictPolicy>> = OnceLock::new();
+
+/// Set once at startup (None = every market hedges)
+pub fn init_conflict_policy(policy: Option<ConflictPolicy>) {
+    let _ = GLOBAL_CONFLICT.set(policy.filter(ConflictPolicy::is_active));
+}
+
+pub fn conflict_policy() -> Option<&'static ConflictPolicy> {
+    GLOBAL_CONFLICT.get().and_then(Option::as_ref)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_mode_for() {
+        let p = ConflictPolicy::parse("hedge", "nba-:block, nba-finals-:hedge").unwrap();
+        assert_eq!(p.mode_for("nba-lal-bos-2026-01-02"), ConflictMode::Block);
+        assert_eq!(p.mode_for("nba-finals-game-7"), ConflictMode::Hedge);
+        assert_eq!(p.mode_for("epl-ars-che"), ConflictMode::Hedge);
+        assert!(p.is_active());
+        assert!(!ConflictPolicy::parse("hedge", "").unwrap().is_active());
+        assert!(ConflictPolicy::parse("net", "").is_err());
+        assert!(ConflictPolicy::parse("block", "nba-").is_err());
+    }
+
+    #[test]
+    fn test_opposite_holding_same_condition_only() {
+        let condition = |t: &str| match t {
+            "yes" | "no" => Some("c1".to_string()),
+            "other" => Some("c2".to_string()),
+            _ => None,
+        };
+        let held = vec!["other".to_string(), "no".to_string()];
+        assert_eq!(opposite_holding("yes", &held, condition), Some("no".to_string()));
+        assert_eq!(opposite_holding("no", &held, condition), None);
+        assert_eq!(opposite_holding("unknown", &held, condition), None);
+    }
+}
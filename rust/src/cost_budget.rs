@@ -0,0 +1,228 @@
+//! Round-trip transaction-cost budget
+//! Each position gets one budget per share for crossing the spread on the way in and on the
+//! way out. The entry may use at most its share of it over the whale's price; whatever the
+//! fill leaves is what the stop-loss concedes below the bid on the way out, so a cheap entry
+//! buys a more certain exit and an expensive one a tighter exit. Realized entry and exit costs
+//! are journaled against the budget when the position is sold
+
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Realized entry + exit cost per closed position
+pub const COST_BUDGET_FILE: &str = "cost_budget.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostBudget {
+    /// Entry slippage + exit slippage allowed per share, in price units (0.04 = 4c)
+    pub per_share: f64,
+    /// Part of the budget the entry may use (0-1)
+    pub entry_share: f64,
+}
+
+impl CostBudget {
+    /// Highest entry limit: the whale's price plus the entry's part of the budget
+    pub fn entry_cap(&self, whale_price: f64) -> f64 {
+        whale_price + self.per_share * self.entry_share
+    }
+
+    /// What the exit may concede below its reference price after the entry cost `entry_cost`,
+    /// in whole ticks (0 = sell at the bid)
+    pub fn exit_offset(&self, entry_cost: f64, tick: f64) -> f64 {
+        let remaining = (self.per_share - entry_cost.max(0.0)).max(0.0);
+        floor_to_tick(remaining + 1e-9, tick)
+    }
+}
+
+pub fn floor_to_tick(price: f64, tick: f64) -> f64 {
+    if tick <= 0.0 {
+        return price;
+    }
+    (price / tick).floor() * tick
+}
+
+// ============================================================================
+// Tracking
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default)]
+struct OpenCost {
+    shares: f64,
+    /// Share-weighted (fill - whale price)
+    cost_sum: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CostOutcome {
+    pub ts: u64,
+    pub token_id: String,
+    pub shares: f64,
+    /// Per share, price units
+    pub entry_cost: f64,
+    pub exit_cost: f64,
+    pub total: f64,
+    pub budget: f64,
+}
+
+#[derive(Default)]
+struct Totals {
+    closed: u64,
+    over: u64,
+    entry_sum: f64,
+    exit_sum: f64,
+}
+
+#[derive(Default)]
+pub struct CostTracker {
+    open: Mutex<FxHashMap<String, OpenCost>>,
+    totals: Mutex<Totals>,
+    capped: Mutex<u64>,
+}
+
+impl CostTracker {
+    /// An entry limit was lowered to the budget cap
+    pub fn record_cap(&self) {
+        if let Ok(mut n) = self.capped.lock() {
+            *n += 1;
+        }
+    }
+
+    /// A buy filled `shares` at `fill` against the whale's `reference` price
+    pub fn record_entry(&self, token_id: &str, shares: f64, fill: f64, reference: f64) {
+        if let Ok(mut open) = self.open.lock() {
+            let c = open.entry(token_id.to_string()).or_default();
+            c.shares += shares;
+            c.cost_sum += shares * (fill - reference);
+        }
+    }
+
+    /// Average entry cost per share of the open position (0 when unknown)
+    pub fn entry_cost(&self, token_id: &str) -> f64 {
+        self.open.lock().ok()
+            .and_then(|open| open.get(token_id).copied())
+            .filter(|c| c.shares > 0.0)
+            .map_or(0.0, |c| c.cost_sum / c.shares)
+    }
+
+    /// A sell filled at `fill` against `reference` (the bid or the whale's price); closes the
+    /// position's entry cost
+    pub fn record_exit(&self, token_id: &str, fill: f64, reference: f64, budget: &CostBudget) -> Option<CostOutcome> {
+        let open = self.open.lock().ok()?.remove(token_id)?;
+        if open.shares <= 0.0 {
+            return None;
+        }
+        let entry_cost = open.cost_sum / open.shares;
+        let exit_cost = reference - fill;
+        let total = entry_cost + exit_cost;
+        if let Ok(mut t) = self.totals.lock() {
+            t.closed += 1;
+            t.entry_sum += entry_cost;
+            t.exit_sum += exit_cost;
+            if total > budget.per_share + 1e-9 {
+                t.over += 1;
+            }
+        }
+        let ts = timestamp::unix_secs();
+        Some(CostOutcome { ts, token_id: token_id.to_string(), shares: open.shares, entry_cost, exit_cost, total, budget: budget.per_share })
+    }
+
+    /// Dump line: capped entries, open positions and average realized costs
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let (Ok(t), Ok(open), Ok(capped)) = (self.totals.lock(), self.open.lock(), self.capped.lock()) else { return out };
+        if t.closed == 0 && open.is_empty() && *capped == 0 {
+            return out;
+        }
+        let _ = write!(out, "  {:<14} capped={} open={} closed={}", "cost_budget", capped, open.len(), t.closed);
+        if t.closed > 0 {
+            let n = t.closed as f64;
+            let _ = write!(
+                out, " avg_entry={} avg_exit={} over_budget={}",
+                crate::display::cents(t.entry_sum / n), crate::display::cents(t.exit_sum / n), t.over
+            );
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// Close the entry cost for a filled sell and journal the outcome
+pub fn record_exit(token_id: &str, fill: f64, reference: f64) {
+    let Some(budget) = cost_budget() else { return };
+    let Some(outcome) = cost_tracker().record_exit(token_id, fill, reference, &budget) else { return };
+    let Ok(line) = serde_json::to_string(&outcome) else { return };
+    match OpenOptions::new().append(true).create(true).open(COST_BUDGET_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Cost budget write failed: {}", e),
+    }
+}
+
+/// Track a filled buy against the budget (no-op without one)
+pub fn record_entry(token_id: &str, shares: f64, fill: f64, reference: f64) {
+    if cost_budget().is_some() {
+        cost_tracker().record_entry(token_id, shares, fill, reference);
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_COST_BUDGET: OnceLock<Option<CostBudget>> = OnceLock::new();
+static GLOBAL_COST_TRACKER: OnceLock<CostTracker> = OnceLock::new();
+
+/// Set once at startup (None = no budget)
+pub fn init_cost_budget(budget: Option<CostBudget>) {
+    let _ = GLOBAL_COST_BUDGET.set(budget);
+}
+
+pub fn cost_budget() -> Option<CostBudget> {
+    GLOBAL_COST_BUDGET.get().copied().flatten()
+}
+
+pub fn cost_tracker() -> &'static CostTracker {
+    GLOBAL_COST_TRACKER.get_or_init(CostTracker::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_use_shrinks_exit_offset() {
+        let b = CostBudget { per_share: 0.04, entry_share: 0.5 };
+        assert!((b.entry_cap(0.50) - 0.52).abs() < 1e-9);
+        assert!((b.exit_offset(0.0, 0.01) - 0.04).abs() < 1e-9);
+        assert!((b.exit_offset(0.01, 0.01) - 0.03).abs() < 1e-9);
+        assert!((b.exit_offset(0.015, 0.01) - 0.02).abs() < 1e-9);
+        assert_eq!(b.exit_offset(0.05, 0.01), 0.0);
+        // Price improvement at entry does not widen the exit past the budget
+        assert!((b.exit_offset(-0.02, 0.01) - 0.04).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_realized_cost_against_budget() {
+        let b = CostBudget { per_share: 0.04, entry_share: 0.5 };
+        let t = CostTracker::default();
+        t.record_entry("tok", 10.0, 0.51, 0.50);
+        t.record_entry("tok", 10.0, 0.53, 0.50);
+        assert!((t.entry_cost("tok") - 0.02).abs() < 1e-9);
+
+        let o = t.record_exit("tok", 0.38, 0.40, &b).unwrap();
+        assert_eq!(o.shares, 20.0);
+        assert!((o.total - 0.04).abs() < 1e-9);
+        assert!(t.record_exit("tok", 0.38, 0.40, &b).is_none());
+
+        t.record_entry("tok2", 5.0, 0.53, 0.50);
+        t.record_exit("tok2", 0.35, 0.40, &b);
+        assert!(t.report().contains("closed=2 avg_entry=2.50c avg_exit=3.50c over_budget=1"));
+    }
+}
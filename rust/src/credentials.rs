@@ -0,0 +1,185 @@
+//! Live API credential refresh
+//! The L2 API key in `.clob_creds.json` can expire or be revoked while the bot runs, after
+//! which every order fails with 401 until someone intervenes. Authenticated requests report
+//! auth failures here; the refresh task then re-derives the key from the private key (creating
+//! a new one when none can be derived), saves it and swaps it in. Workers read `current()` for
+//! each request, so the next order after the swap is signed with the new key
+
+use crate::{ApiCreds, PreparedCreds, RustClobClient};
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Derived L2 credentials, reused across restarts
+pub const CREDS_FILE: &str = ".clob_creds.json";
+
+/// How often the refresh task looks for reported auth failures
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Least time between two refresh attempts
+const REFRESH_COOLDOWN: Duration = Duration::from_secs(60);
+
+// ============================================================================
+// Store
+// ============================================================================
+
+pub struct CredentialStore {
+    current: RwLock<Arc<PreparedCreds>>,
+    /// Auth failures reported since the last refresh
+    failures: AtomicU64,
+    refreshes: AtomicU64,
+    last_attempt: Mutex<Option<Instant>>,
+}
+
+impl CredentialStore {
+    pub fn new(creds: PreparedCreds) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(creds)),
+            failures: AtomicU64::new(0),
+            refreshes: AtomicU64::new(0),
+            last_attempt: Mutex::new(None),
+        }
+    }
+
+    pub fn current(&self) -> Arc<PreparedCreds> {
+        match self.current.read() {
+            Ok(c) => Arc::clone(&c),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
+    }
+
+    pub fn replace(&self, creds: PreparedCreds) {
+        if let Ok(mut c) = self.current.write() {
+            *c = Arc::new(creds);
+        }
+        self.failures.store(0, Ordering::Relaxed);
+        self.refreshes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn report_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn refreshes(&self) -> u64 {
+        self.refreshes.load(Ordering::Relaxed)
+    }
+
+    /// Whether failures are pending and the cooldown has passed; starts an attempt when true
+    pub fn begin_refresh(&self, now: Instant) -> bool {
+        if self.failures.load(Ordering::Relaxed) == 0 {
+            return false;
+        }
+        let Ok(mut last) = self.last_attempt.lock() else { return false };
+        if last.is_some_and(|at| now.duration_since(at) < REFRESH_COOLDOWN) {
+            return false;
+        }
+        *last = Some(now);
+        true
+    }
+}
+
+/// Derive the account's API key (create one when none can be derived) and save it to `path`
+pub fn rederive(client: &RustClobClient, path: &str) -> Result<ApiCreds> {
+    let creds = client.derive_api_key(0)
+        .or_else(|derive_err| client.create_api_key(0).with_context(|| format!("after {}", derive_err)))?;
+    let tmp = format!("{}.tmp", path);
+    std::fs::write(&tmp, serde_json::to_string_pretty(&creds)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(creds)
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_CREDS: OnceLock<CredentialStore> = OnceLock::new();
+
+/// Set once at startup with the loaded credentials
+pub fn init_credentials(creds: PreparedCreds) {
+    let _ = GLOBAL_CREDS.set(CredentialStore::new(creds));
+}
+
+pub fn credentials() -> Option<&'static CredentialStore> {
+    GLOBAL_CREDS.get()
+}
+
+/// Credentials to sign the next request with
+pub fn current() -> Arc<PreparedCreds> {
+    credentials().expect("credentials are initialized at startup").current()
+}
+
+/// An authenticated request came back 401 (no-op before startup finished)
+pub fn report_auth_failure() {
+    if let Some(store) = credentials() {
+        store.report_failure();
+    }
+}
+
+/// Re-derive and swap in the API key after reported auth failures, at most once a minute
+pub fn spawn_refresh_task(client: Arc<RustClobClient>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(store) = credentials() else { return };
+            if !store.begin_refresh(Instant::now()) {
+                continue;
+            }
+            let old_key = store.current().api_key.clone();
+            crate::console_eprintln!("🔑 API credentials rejected, re-deriving from the private key...");
+            let c = Arc::clone(&client);
+            let derived = tokio::task::spawn_blocking(move || rederive(&c, CREDS_FILE))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|r| r)
+                .and_then(|creds| PreparedCreds::from_api_creds(&creds));
+            match derived {
+                Ok(creds) => {
+                    let same = creds.api_key == old_key;
+                    store.replace(creds);
+                    if same {
+                        crate::console_eprintln!("⚠️ Re-derived the same API key; if 401s continue check the clock and FUNDER_ADDRESS");
+                    } else {
+                        crate::console_println!("🔑 New API key saved to {} and in use", CREDS_FILE);
+                    }
+                }
+                Err(e) => crate::console_eprintln!("⚠️ Credential refresh failed: {:#}. Retrying in {}s", e, REFRESH_COOLDOWN.as_secs()),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creds(key: &str) -> PreparedCreds {
+        PreparedCreds::from_api_creds(&ApiCreds {
+            api_key: key.into(),
+            api_secret: "c2VjcmV0".into(),
+            api_passphrase: "pass".into(),
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_refresh_after_failure_with_cooldown() {
+        let store = CredentialStore::new(creds("old"));
+        let now = Instant::now();
+        assert!(!store.begin_refresh(now));
+
+        store.report_failure();
+        assert!(store.begin_refresh(now));
+        assert!(!store.begin_refresh(now + Duration::from_secs(30)));
+        assert!(store.begin_refresh(now + REFRESH_COOLDOWN));
+
+        store.replace(creds("new"));
+        assert_eq!(store.current().api_key, "new");
+        assert_eq!(store.refreshes(), 1);
+        assert!(!store.begin_refresh(now + 2 * REFRESH_COOLDOWN));
+    }
+}
@@ -0,0 +1,290 @@
+//! Order book depth history
+//! Periodic snapshots of the top of the book for every token we hold, appended to a journal,
+//! and an export that turns them into a liquidity heatmap dataset (time × price level × size)
+//! for choosing entry limits and sizing rules
+
+use crate::position_tracker::PositionTracker;
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Snapshots, one JSON object per line
+pub const DEPTH_HISTORY_FILE: &str = "depth_history.jsonl";
+
+/// Levels kept per side in a snapshot
+pub const DEPTH_SNAPSHOT_LEVELS: usize = 10;
+
+/// Tokens per `/books` request
+pub const BOOKS_BATCH_SIZE: usize = 100;
+
+/// Heatmap price rows are rounded to this tick
+pub const HEATMAP_TICK: f64 = 0.01;
+
+pub const USAGE: &str = "usage:\n  pm_bot depth-export <out.csv> [token_id] [bucket_secs]";
+
+// ============================================================================
+// Snapshots
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepthSnapshot {
+    /// Unix seconds
+    pub ts: u64,
+    pub token_id: String,
+    /// (price, size), best first
+    pub bids: Levels,
+    pub asks: Levels,
+}
+
+impl DepthSnapshot {
+    /// Sort both sides best first and keep the top `DEPTH_SNAPSHOT_LEVELS`
+    pub fn new(ts: u64, token_id: &str, mut bids: Levels, mut asks: Levels) -> Self {
+        bids.sort_by(|a, b| b.0.total_cmp(&a.0));
+        asks.sort_by(|a, b| a.0.total_cmp(&b.0));
+        bids.truncate(DEPTH_SNAPSHOT_LEVELS);
+        asks.truncate(DEPTH_SNAPSHOT_LEVELS);
+        Self { ts, token_id: token_id.to_string(), bids, asks }
+    }
+}
+
+/// One side of a book as (price, size)
+pub type Levels = Vec<(f64, f64)>;
+
+/// Full book for a token: (bids, asks) as (price, size) in any order
+pub type Book = (Levels, Levels);
+
+#[async_trait::async_trait]
+pub trait BookFetcher: Send + Sync {
+    async fn fetch_book(&self, token_id: &str) -> Option<Book>;
+
+    /// Books of several tokens; tokens without a book are left out. The default asks for
+    /// each token in turn
+    async fn fetch_books(&self, token_ids: &[String]) -> Vec<(String, Book)> {
+        let mut books = Vec::with_capacity(token_ids.len());
+        for token_id in token_ids {
+            if let Some(book) = self.fetch_book(token_id).await {
+                books.push((token_id.clone(), book));
+            }
+        }
+        books
+    }
+}
+
+/// Best (highest) bid of a book
+pub fn best_bid(book: &Book) -> Option<f64> {
+    book.0.iter().map(|l| l.0).reduce(f64::max)
+}
+
+/// Best (lowest) ask of a book
+pub fn best_ask(book: &Book) -> Option<f64> {
+    book.1.iter().map(|l| l.0).reduce(f64::min)
+}
+
+/// Midpoint of the best bid and ask, None when either side is empty
+pub fn mid(book: &Book) -> Option<f64> {
+    Some((best_bid(book)? + best_ask(book)?) / 2.0)
+}
+
+/// (bids, asks) of a CLOB `/book` response; levels that do not parse are skipped
+pub fn parse_book(book: &serde_json::Value) -> (Levels, Levels) {
+    let levels = |key: &str| -> Levels {
+        book[key].as_array()
+            .map(|arr| arr.iter().filter_map(|lvl| Some((
+                lvl["price"].as_str()?.parse().ok()?,
+                lvl["size"].as_str()?.parse().ok()?,
+            ))).collect())
+            .unwrap_or_default()
+    };
+    (levels("bids"), levels("asks"))
+}
+
+/// Books of a CLOB `/books` response, each under its `asset_id`
+pub fn parse_books(books: &serde_json::Value) -> Vec<(String, Book)> {
+    books.as_array()
+        .map(|arr| arr.iter()
+            .filter_map(|b| Some((b["asset_id"].as_str()?.to_string(), parse_book(b))))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Books of `token_ids` through the batched `/books` endpoint, `BOOKS_BATCH_SIZE` per request.
+/// None if any request fails, so the caller can fall back to `/book` per token
+pub fn fetch_books_batched(http: &reqwest::blocking::Client, token_ids: &[String]) -> Option<Vec<(String, Book)>> {
+    let url = format!("{}/books", crate::settings::clob_api_base());
+    let mut books = Vec::with_capacity(token_ids.len());
+    for chunk in token_ids.chunks(BOOKS_BATCH_SIZE) {
+        let body: Vec<serde_json::Value> = chunk.iter().map(|t| serde_json::json!({ "token_id": t })).collect();
+        let resp = http.post(&url).json(&body).timeout(Duration::from_secs(2)).send().ok()?;
+        if !resp.status().is_success() {
+            return None;
+        }
+        books.extend(parse_books(&resp.json().ok()?));
+    }
+    Some(books)
+}
+
+fn append(snapshots: &[DepthSnapshot], path: &str) -> std::io::Result<()> {
+    let mut lines = String::new();
+    for s in snapshots {
+        if let Ok(line) = serde_json::to_string(s) {
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+    }
+    OpenOptions::new().append(true).create(true).open(path)?.write_all(lines.as_bytes())
+}
+
+/// Snapshot every held token each `interval`
+pub fn spawn_depth_history_task(
+    tracker: Arc<PositionTracker>,
+    fetcher: Arc<dyn BookFetcher>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let positions = tracker.get_all_positions().await;
+            if positions.is_empty() {
+                continue;
+            }
+            let ts = timestamp::unix_secs();
+            let token_ids: Vec<String> = positions.into_iter().map(|p| p.token_id).collect();
+            let snapshots: Vec<DepthSnapshot> = fetcher.fetch_books(&token_ids).await
+                .into_iter()
+                .map(|(token_id, (bids, asks))| DepthSnapshot::new(ts, &token_id, bids, asks))
+                .collect();
+            if let Err(e) = append(&snapshots, DEPTH_HISTORY_FILE) {
+                crate::console_eprintln!("⚠️ Depth history write failed: {}", e);
+            }
+        }
+    })
+}
+
+// ============================================================================
+// Heatmap Export
+// ============================================================================
+
+/// Snapshots from `path`'s archived days, rotated generations and live file, oldest first
+pub fn load_snapshots(path: &str, token_id: Option<&str>) -> Vec<DepthSnapshot> {
+    crate::archive::read_history(path)
+        .lines()
+        .filter_map(|l| serde_json::from_str::<DepthSnapshot>(l).ok())
+        .filter(|s| token_id.is_none_or(|t| s.token_id == t))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatmapCell {
+    /// Start of the time bucket (unix seconds)
+    pub time: u64,
+    pub token_id: String,
+    pub side: &'static str,
+    pub price: f64,
+    /// Average size resting at this level across the bucket's snapshots (0 when absent)
+    pub size: f64,
+}
+
+/// One cell per (bucket, token, side, price tick) seen in the snapshots
+pub fn heatmap(snapshots: &[DepthSnapshot], bucket_secs: u64) -> Vec<HeatmapCell> {
+    let bucket_secs = bucket_secs.max(1);
+    // (time, token, side, tick) -> summed size; (time, token) -> snapshots in bucket
+    let mut sums: BTreeMap<(u64, String, &'static str, i64), f64> = BTreeMap::new();
+    let mut counts: FxHashMap<(u64, String), u32> = FxHashMap::default();
+    for s in snapshots {
+        let time = s.ts / bucket_secs * bucket_secs;
+        *counts.entry((time, s.token_id.clone())).or_default() += 1;
+        for (side, levels) in [("bid", &s.bids), ("ask", &s.asks)] {
+            for &(price, size) in levels {
+                let tick = (price / HEATMAP_TICK).round() as i64;
+                *sums.entry((time, s.token_id.clone(), side, tick)).or_default() += size;
+            }
+        }
+    }
+    sums.into_iter()
+        .map(|((time, token_id, side, tick), total)| {
+            let n = counts.get(&(time, token_id.clone())).copied().unwrap_or(1).max(1);
+            HeatmapCell { time, token_id, side, price: tick as f64 * HEATMAP_TICK, size: total / n as f64 }
+        })
+        .collect()
+}
+
+/// Long-format CSV, one row per cell, ready for a pivot or a plotting library
+pub fn render_csv(cells: &[HeatmapCell]) -> String {
+    let mut out = String::from("time,token_id,side,price,size\n");
+    for c in cells {
+        out.push_str(&format!("{},{},{},{:.2},{:.2}\n", c.time, c.token_id, c.side, c.price, c.size));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_snapshot_keeps_best_levels() {
+        let asks: Vec<(f64, f64)> = (0..15).map(|i| (0.99 - i as f64 * 0.01, 10.0)).collect();
+        let s = DepthSnapshot::new(0, "t", vec![(0.40, 5.0), (0.45, 7.0)], asks);
+        assert_eq!(s.bids[0], (0.45, 7.0));
+        assert_eq!(s.asks.len(), DEPTH_SNAPSHOT_LEVELS);
+        assert!((s.asks[0].0 - 0.85).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_books_by_asset() {
+        let v = serde_json::json!([
+            { "asset_id": "a", "bids": [{ "price": "0.40", "size": "5" }, { "price": "0.45", "size": "7" }], "asks": [] },
+            { "asset_id": "b", "bids": [], "asks": [{ "price": "0.60", "size": "1" }] },
+            { "bids": [] }
+        ]);
+        let books = parse_books(&v);
+        assert_eq!(books.len(), 2);
+        assert_eq!(books[0].0, "a");
+        assert_eq!(best_bid(&books[0].1), Some(0.45));
+        assert_eq!(best_bid(&books[1].1), None);
+        assert!(parse_books(&serde_json::json!({ "error": "x" })).is_empty());
+    }
+
+    #[test]
+    fn test_heatmap_averages_within_bucket() {
+        let snaps = vec![
+            DepthSnapshot::new(120, "t", vec![(0.40, 100.0)], vec![(0.42, 50.0)]),
+            DepthSnapshot::new(150, "t", vec![(0.40, 300.0)], vec![(0.43, 20.0)]),
+            DepthSnapshot::new(185, "t", vec![(0.41, 10.0)], vec![]),
+        ];
+        let cells = heatmap(&snaps, 60);
+        let find = |time: u64, side: &str, price: f64| {
+            cells.iter().find(|c| c.time == time && c.side == side && (c.price - price).abs() < 1e-9).map(|c| c.size)
+        };
+        assert_eq!(find(120, "bid", 0.40), Some(200.0));
+        assert_eq!(find(120, "ask", 0.42), Some(25.0));
+        assert_eq!(find(180, "bid", 0.41), Some(10.0));
+        assert_eq!(cells.len(), 4);
+        assert!(render_csv(&cells).starts_with("time,token_id,side,price,size\n120,t,ask,0.42,25.00\n"));
+    }
+
+    #[test]
+    fn test_load_reads_rotated_generations() {
+        let path = std::env::temp_dir().join(format!("pm_depth_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let rotated = format!("{}.1", path);
+        append(&[DepthSnapshot::new(1, "a", vec![], vec![])], &rotated).unwrap();
+        append(&[DepthSnapshot::new(2, "b", vec![], vec![]), DepthSnapshot::new(3, "a", vec![], vec![])], path).unwrap();
+        let all = load_snapshots(path, None);
+        assert_eq!(all.iter().map(|s| s.ts).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(load_snapshots(path, Some("a")).len(), 2);
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(rotated);
+    }
+}
@@ -0,0 +1,189 @@
+//! On-demand runtime diagnostics
+//! Components report heartbeats and in-flight operations here; `kill -USR2 <pid>` dumps
+//! every component's last event, what it is blocked on, queue depths and per-token states
+
+use rustc_hash::FxHashMap;
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// =============================================================================
+// Configuration
+// =============================================================================
+
+/// A component silent for longer than this is flagged STALE in the dump
+pub const STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// An operation in flight for longer than this is flagged SLOW in the dump
+pub const SLOW_OP_AFTER: Duration = Duration::from_secs(5);
+
+/// Tokens listed in the FAK fill/improvement section
+const DUMP_TOP_TOKENS: usize = 10;
+
+// =============================================================================
+// Registry
+// =============================================================================
+
+#[derive(Debug, Clone)]
+struct ComponentState {
+    last_event: String,
+    last_at: Instant,
+    events: u64,
+    /// Operation currently running (e.g. a blocking order POST) and when it started
+    in_flight: Option<(String, Instant)>,
+}
+
+type Gauge = Box<dyn Fn() -> usize + Send + Sync>;
+
+#[derive(Default)]
+pub struct Diagnostics {
+    components: Mutex<FxHashMap<&'static str, ComponentState>>,
+    gauges: Mutex<Vec<(&'static str, Gauge)>>,
+}
+
+impl Diagnostics {
+    /// Record that a component is alive and what it last did
+    pub fn heartbeat(&self, component: &'static str, event: &str) {
+        let Ok(mut comps) = self.components.lock() else { return };
+        let now = Instant::now();
+        let st = comps.entry(component).or_insert_with(|| ComponentState {
+            last_event: String::new(),
+            last_at: now,
+            events: 0,
+            in_flight: None,
+        });
+        st.last_event.clear();
+        st.last_event.push_str(event);
+        st.last_at = now;
+        st.events += 1;
+    }
+
+    /// Mark the start of a potentially blocking operation
+    pub fn begin_op(&self, component: &'static str, op: &str) {
+        self.heartbeat(component, op);
+        if let Ok(mut comps) = self.components.lock()
+            && let Some(st) = comps.get_mut(component) {
+                st.in_flight = Some((op.to_string(), Instant::now()));
+            }
+    }
+
+    /// Mark the end of the current operation
+    pub fn end_op(&self, component: &'static str) {
+        if let Ok(mut comps) = self.components.lock()
+            && let Some(st) = comps.get_mut(component) {
+                st.in_flight = None;
+                st.last_at = Instant::now();
+            }
+    }
+
+    /// Time since each component's last event
+    pub fn ages(&self) -> Vec<(&'static str, Duration)> {
+        let now = Instant::now();
+        self.components.lock()
+            .map(|comps| comps.iter().map(|(name, st)| (*name, now.duration_since(st.last_at))).collect())
+            .unwrap_or_default()
+    }
+
+    /// Register a depth gauge (queue length, open positions...) sampled at dump time
+    pub fn register_gauge(&self, name: &'static str, gauge: impl Fn() -> usize + Send + Sync + 'static) {
+        if let Ok(mut gauges) = self.gauges.lock() {
+            gauges.push((name, Box::new(gauge)));
+        }
+    }
+
+    /// Human-readable snapshot of every component and gauge
+    pub fn dump(&self) -> String {
+        let now = Instant::now();
+        let mut out = String::from("🩺 Diagnostics dump\n");
+
+        if let Ok(comps) = self.components.lock() {
+            let mut names: Vec<_> = comps.keys().copied().collect();
+            names.sort_unstable();
+            for name in names {
+                let st = &comps[name];
+                let idle = now.duration_since(st.last_at);
+                let flag = if st.in_flight.is_none() && idle >= STALE_AFTER { " STALE" } else { "" };
+                let _ = write!(out, "  {:<14} events={:<6} last='{}' {:.1}s ago{}",
+                    name, st.events, st.last_event, idle.as_secs_f64(), flag);
+                if let Some((op, since)) = &st.in_flight {
+                    let held = now.duration_since(*since);
+                    let slow = if held >= SLOW_OP_AFTER { " SLOW" } else { "" };
+                    let _ = write!(out, " | in-flight '{}' for {:.1}s{}", op, held.as_secs_f64(), slow);
+                }
+                out.push('\n');
+            }
+        }
+
+        if let Ok(gauges) = self.gauges.lock() {
+            for (name, gauge) in gauges.iter() {
+                let _ = writeln!(out, "  {:<14} {}", name, gauge());
+            }
+        }
+
+        for (token, phase, age) in crate::asset_state::asset_states().snapshot() {
+            let _ = writeln!(out, "  token {} {} for {:.1}s", token, phase.as_str(), age.as_secs_f64());
+        }
+
+        out.push_str(&crate::execution_stats::execution_stats().report(DUMP_TOP_TOKENS));
+        out.push_str(&crate::what_if::what_if().report());
+        out.push_str(&crate::feed_gaps::feed_gaps().report());
+        out.push_str(&crate::reward_risk::rr_calibration().report());
+        out.push_str(&crate::probe::probe_stats().report());
+        out.push_str(&crate::markout::markouts().report());
+        out.push_str(&crate::cost_budget::cost_tracker().report());
+        out.push_str(&crate::latency_budget::report());
+        out.push_str(&crate::supervisor::health().report());
+        if let Some(reserve) = crate::cash_reserve::cash_reserve() {
+            out.push_str(&reserve.report(crate::cash_reserve::wallet_open_cost()));
+        }
+        out.push_str(&crate::capital_at_risk::current().report(crate::capital_at_risk::limit()));
+        out.push_str(&crate::lockup::current_forecast().report(crate::timestamp::unix_secs()));
+        out
+    }
+}
+
+static GLOBAL_DIAGNOSTICS: OnceLock<Diagnostics> = OnceLock::new();
+
+/// Get the global diagnostics registry
+pub fn diagnostics() -> &'static Diagnostics {
+    GLOBAL_DIAGNOSTICS.get_or_init(Diagnostics::default)
+}
+
+/// Dump diagnostics to the log whenever the process receives SIGUSR2
+#[cfg(unix)]
+pub fn spawn_dump_on_signal() -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut sig = match signal(SignalKind::user_defined2()) {
+            Ok(s) => s,
+            Err(e) => {
+                crate::console_eprintln!("⚠️ Could not install SIGUSR2 handler: {}", e);
+                return;
+            }
+        };
+        while sig.recv().await.is_some() {
+            crate::console_println!("{}", diagnostics().dump());
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump_reports_in_flight_and_gauges() {
+        let d = Diagnostics::default();
+        d.heartbeat("ws", "message");
+        d.begin_op("order_worker", "POST 123");
+        d.register_gauge("order_queue", || 7);
+
+        let dump = d.dump();
+        assert!(dump.contains("ws"));
+        assert!(dump.contains("in-flight 'POST 123'"));
+        assert!(dump.contains("order_queue    7"));
+
+        d.end_op("order_worker");
+        assert!(!d.dump().contains("in-flight"));
+    }
+}
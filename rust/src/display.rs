@@ -0,0 +1,71 @@
+//! Display formatting for logs, the TUI and journals
+//! Prices are shown at the token's tick precision (a 0.001-tick market needs three decimals,
+//! a fixed {:.2} rounds its prices together) and average fill prices two decimals finer.
+//! Shares, dollars, percentages and cents each have one format everywhere
+
+use crate::token_metadata;
+
+/// Decimal places of a tick size string ("0.01" -> 2, "0.001" -> 3)
+pub fn tick_decimals(tick: &str) -> usize {
+    tick.split_once('.').map_or(0, |(_, frac)| frac.len())
+}
+
+/// Order price at `tick` precision
+pub fn price_at(p: f64, tick: &str) -> String {
+    format!("{:.*}", tick_decimals(tick), p)
+}
+
+/// Order price at the token's tick precision (default tick when metadata is unknown)
+pub fn price(token_id: &str, p: f64) -> String {
+    price_at(p, &token_metadata::tick_size(token_id))
+}
+
+/// Average fill price: two decimals finer than the tick, since averages fall between ticks
+pub fn avg_price(token_id: &str, p: f64) -> String {
+    format!("{:.*}", tick_decimals(&token_metadata::tick_size(token_id)) + 2, p)
+}
+
+/// Share counts (the exchange accepts 0.01)
+pub fn shares(n: f64) -> String {
+    format!("{:.2}", n)
+}
+
+/// Dollar amount, sign before the symbol
+pub fn usd(x: f64) -> String {
+    if x < 0.0 { format!("-${:.2}", -x) } else { format!("${:.2}", x) }
+}
+
+/// Signed dollar amount for P&L
+pub fn signed_usd(x: f64) -> String {
+    if x < 0.0 { format!("-${:.2}", -x) } else { format!("+${:.2}", x) }
+}
+
+/// A fraction as a percentage (0.05 -> "5.00%")
+pub fn pct(fraction: f64) -> String {
+    format!("{:.2}%", fraction * 100.0)
+}
+
+/// A price difference in cents (0.015 -> "1.50c")
+pub fn cents(delta: f64) -> String {
+    format!("{:.2}c", delta * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(tick_decimals("0.01"), 2);
+        assert_eq!(tick_decimals("0.0001"), 4);
+        assert_eq!(tick_decimals("1"), 0);
+        assert_eq!(price_at(0.4567, "0.001"), "0.457");
+        assert_eq!(price_at(0.4567, "0.01"), "0.46");
+        assert_eq!(price("unknown-token", 0.5), "0.50");
+        assert_eq!(avg_price("unknown-token", 0.51234), "0.5123");
+        assert_eq!(usd(-3.5), "-$3.50");
+        assert_eq!(signed_usd(3.5), "+$3.50");
+        assert_eq!(pct(-0.05), "-5.00%");
+        assert_eq!(cents(0.015), "1.50c");
+    }
+}
@@ -0,0 +1,247 @@
+//! `pm_bot doctor` - credential and connectivity self-test
+//! Verifies wallet derivation, API key validity (signed request), USDC balance and allowances,
+//! clock sync against the CLOB, and endpoint reachability/latency, then prints a pass/fail table
+
+use crate::latency_probe::{self, EndpointKind, ProbeTarget, POOR_LOCATION_RTT_MS, PROBE_SAMPLES};
+use crate::{ApiCreds, PreparedCreds, RustClobClient};
+use crate::timestamp;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::path::Path;
+
+// =============================================================================
+// Configuration
+// =============================================================================
+
+/// Clock skew tolerated without comment (L2 headers carry a timestamp)
+const CLOCK_SKEW_WARN_SECS: i64 = 2;
+
+/// Clock skew at which signed requests start getting rejected
+const CLOCK_SKEW_FAIL_SECS: i64 = 10;
+
+/// USDC uses 6 decimals on Polygon
+const USDC_DECIMALS: f64 = 1_000_000.0;
+
+// =============================================================================
+// Results
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "✅ PASS",
+            CheckStatus::Warn => "⚠️ WARN",
+            CheckStatus::Fail => "❌ FAIL",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), status, detail: detail.into() }
+    }
+}
+
+/// Render results as an aligned table
+pub fn render_table(results: &[CheckResult]) -> String {
+    let width = results.iter().map(|r| r.name.len()).max().unwrap_or(5).max(5);
+    let mut out = format!("{:<width$}  {:<8}  DETAIL\n", "CHECK", "STATUS", width = width);
+    for r in results {
+        out.push_str(&format!("{:<width$}  {:<8}  {}\n", r.name, r.status.as_str(), r.detail, width = width));
+    }
+    out
+}
+
+pub fn has_failures(results: &[CheckResult]) -> bool {
+    results.iter().any(|r| r.status == CheckStatus::Fail)
+}
+
+// =============================================================================
+// Individual checks
+// =============================================================================
+
+/// Classify local-minus-server clock skew
+pub fn clock_status(skew_secs: i64) -> CheckStatus {
+    match skew_secs.abs() {
+        s if s <= CLOCK_SKEW_WARN_SECS => CheckStatus::Pass,
+        s if s <= CLOCK_SKEW_FAIL_SECS => CheckStatus::Warn,
+        _ => CheckStatus::Fail,
+    }
+}
+
+/// Parse a `/balance-allowance` response into (USDC balance, spenders with zero allowance)
+pub fn parse_balance_allowance(val: &Value) -> Option<(f64, Vec<String>)> {
+    let raw = match &val["balance"] {
+        Value::String(s) => s.parse::<f64>().ok()?,
+        Value::Number(n) => n.as_f64()?,
+        _ => return None,
+    };
+    let zero_allowances = val["allowances"].as_object()
+        .map(|m| m.iter()
+            .filter(|(_, v)| v.as_str().map(|s| s.trim_start_matches('0').is_empty()).unwrap_or(true))
+            .map(|(spender, _)| spender.clone())
+            .collect())
+        .unwrap_or_default();
+    Some((raw / USDC_DECIMALS, zero_allowances))
+}
+
+fn check_clock(client: &RustClobClient) -> CheckResult {
+    let server = client.get_time().ok().and_then(|t| t.trim().parse::<i64>().ok());
+    let Some(server) = server else {
+        return CheckResult::new("Clock sync", CheckStatus::Fail, "could not read CLOB /time");
+    };
+    let local = timestamp::unix_secs() as i64;
+    let skew = local - server;
+    CheckResult::new("Clock sync", clock_status(skew), format!("local - CLOB = {:+}s", skew))
+}
+
+fn load_creds(client: &RustClobClient, creds_path: &str) -> Result<(ApiCreds, &'static str)> {
+    if Path::new(creds_path).exists() {
+        let data = std::fs::read_to_string(creds_path)?;
+        return Ok((serde_json::from_str(&data)?, "cached"));
+    }
+    Ok((client.derive_api_key(0)?, "derived"))
+}
+
+fn check_api_key(client: &RustClobClient, creds: &PreparedCreds, source: &str) -> CheckResult {
+    match client.get_l2("/auth/api-keys", "", creds) {
+        Ok(resp) if resp.status().is_success() =>
+            CheckResult::new("API key", CheckStatus::Pass, format!("{} key accepted by signed request", source)),
+        Ok(resp) =>
+            CheckResult::new("API key", CheckStatus::Fail, format!("{} key rejected: HTTP {}", source, resp.status())),
+        Err(e) => CheckResult::new("API key", CheckStatus::Fail, format!("request failed: {}", e)),
+    }
+}
+
+fn check_balance(client: &RustClobClient, creds: &PreparedCreds) -> Vec<CheckResult> {
+    let val: Result<Value> = client
+        .get_l2("/balance-allowance", "asset_type=COLLATERAL&signature_type=1", creds)
+        .and_then(|r| {
+            if !r.status().is_success() {
+                return Err(anyhow!("HTTP {}", r.status()));
+            }
+            Ok(r.json()?)
+        });
+    let parsed = match val {
+        Ok(v) => parse_balance_allowance(&v).ok_or_else(|| anyhow!("unexpected response {}", v)),
+        Err(e) => Err(e),
+    };
+    match parsed {
+        Ok((balance, zero)) => {
+            let balance_check = if balance > 0.0 {
+                CheckResult::new("USDC balance", CheckStatus::Pass, format!("${:.2}", balance))
+            } else {
+                CheckResult::new("USDC balance", CheckStatus::Fail, "$0.00 - fund the funder wallet")
+            };
+            let allowance_check = if zero.is_empty() {
+                CheckResult::new("USDC allowance", CheckStatus::Pass, "set for all exchange contracts")
+            } else {
+                CheckResult::new("USDC allowance", CheckStatus::Warn, format!("zero for {}", zero.join(", ")))
+            };
+            vec![balance_check, allowance_check]
+        }
+        Err(e) => vec![CheckResult::new("USDC balance", CheckStatus::Fail, e.to_string())],
+    }
+}
+
+fn check_endpoints(targets: &[ProbeTarget]) -> Vec<CheckResult> {
+    targets.iter()
+        .map(|t| {
+            let r = latency_probe::probe_blocking(t, PROBE_SAMPLES);
+            let name = format!("{} reachability", t.kind.as_str());
+            match r.median_ms() {
+                None => CheckResult::new(name, CheckStatus::Fail, format!("{} unreachable", latency_probe::redact_url(&t.url))),
+                Some(ms) if t.kind == EndpointKind::Clob && ms > POOR_LOCATION_RTT_MS =>
+                    CheckResult::new(name, CheckStatus::Warn, format!("{} {:.1}ms (poor location for copy trading)", latency_probe::redact_url(&t.url), ms)),
+                Some(ms) => CheckResult::new(name, CheckStatus::Pass, format!("{} {:.1}ms", latency_probe::redact_url(&t.url), ms)),
+            }
+        })
+        .collect()
+}
+
+// =============================================================================
+// Runner
+// =============================================================================
+
+/// Run every check (blocking - call from spawn_blocking)
+pub fn run_checks(private_key: &str, funder: &str, clob_host: &str, creds_path: &str, targets: &[ProbeTarget]) -> Vec<CheckResult> {
+    let mut results = check_endpoints(targets);
+
+    let client = match RustClobClient::new(clob_host, 137, private_key, funder) {
+        Ok(c) => c,
+        Err(e) => {
+            results.push(CheckResult::new("Wallet", CheckStatus::Fail, e.to_string()));
+            return results;
+        }
+    };
+    results.push(CheckResult::new("Wallet", CheckStatus::Pass,
+        format!("signer {} | funder {}", client.wallet_address(), funder)));
+
+    results.push(check_clock(&client));
+
+    let prepared = load_creds(&client, creds_path)
+        .and_then(|(creds, source)| Ok((PreparedCreds::from_api_creds(&creds)?, source)));
+    match prepared {
+        Ok((creds, source)) => {
+            results.push(check_api_key(&client, &creds, source));
+            results.extend(check_balance(&client, &creds));
+        }
+        Err(e) => results.push(CheckResult::new("API key", CheckStatus::Fail, format!("could not load or derive: {}", e))),
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_status() {
+        assert_eq!(clock_status(0), CheckStatus::Pass);
+        assert_eq!(clock_status(-2), CheckStatus::Pass);
+        assert_eq!(clock_status(5), CheckStatus::Warn);
+        assert_eq!(clock_status(-30), CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_parse_balance_allowance() {
+        let v = serde_json::json!({
+            "balance": "12345678",
+            "allowances": {
+                "0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E": "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+                "0xC5d563A36AE78145C45a50134d48A1215220f80a": "0"
+            }
+        });
+        let (balance, zero) = parse_balance_allowance(&v).unwrap();
+        assert!((balance - 12.345678).abs() < 1e-9);
+        assert_eq!(zero, vec!["0xC5d563A36AE78145C45a50134d48A1215220f80a".to_string()]);
+        assert!(parse_balance_allowance(&serde_json::json!({"error": "x"})).is_none());
+    }
+
+    #[test]
+    fn test_table_and_failures() {
+        let results = vec![
+            CheckResult::new("Wallet", CheckStatus::Pass, "ok"),
+            CheckResult::new("USDC balance", CheckStatus::Fail, "$0.00"),
+        ];
+        let table = render_table(&results);
+        assert!(table.starts_with("CHECK"));
+        assert!(table.contains("USDC balance  ❌ FAIL"));
+        assert!(has_failures(&results));
+        assert!(!has_failures(&results[..1]));
+    }
+}
@@ -0,0 +1,212 @@
+// src/eip712_tests.rs
+// EIP-712 order signing vectors
+//
+// Tests cover:
+// 1. Wallet derivation from a well-known key
+// 2. Digest from the typed-data JSON path matches an independent hand-rolled encoder
+//    (type strings copied from the CTF Exchange contract) on both chains, with and without neg-risk
+// 3. Signatures recover to the signing wallet
+// 4. Pinned regression vectors (RFC 6979 signatures are deterministic)
+// 5. Randomized orders across ticks, sides, chains and neg-risk
+
+use super::*;
+use alloy::primitives::{keccak256, Address, Signature};
+use rand::Rng;
+use std::str::FromStr;
+
+/// Hardhat/Anvil account #0 - public test key, never funded on Polygon
+const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const TEST_ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+const TEST_FUNDER: &str = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+const TEST_TOKEN: &str = "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const ORDER_TYPE: &str = "Order(uint256 salt,address maker,address signer,address taker,uint256 tokenId,uint256 makerAmount,uint256 takerAmount,uint256 expiration,uint256 nonce,uint256 feeRateBps,uint8 side,uint8 signatureType)";
+
+// =========================================================================
+// Independent EIP-712 encoder
+// =========================================================================
+
+fn word_uint(v: &str) -> [u8; 32] {
+    U256::from_str(v).unwrap().to_be_bytes()
+}
+
+fn word_addr(a: &str) -> [u8; 32] {
+    let addr = Address::from_str(a).unwrap();
+    let mut w = [0u8; 32];
+    w[12..].copy_from_slice(addr.as_slice());
+    w
+}
+
+fn domain_separator(chain_id: u64, exchange: &str) -> B256 {
+    let mut buf = Vec::with_capacity(5 * 32);
+    buf.extend_from_slice(keccak256(DOMAIN_TYPE).as_slice());
+    buf.extend_from_slice(keccak256("Polymarket CTF Exchange").as_slice());
+    buf.extend_from_slice(keccak256("1").as_slice());
+    buf.extend_from_slice(&word_uint(&chain_id.to_string()));
+    buf.extend_from_slice(&word_addr(exchange));
+    keccak256(buf)
+}
+
+fn struct_hash(o: &OrderStruct) -> B256 {
+    let mut buf = Vec::with_capacity(13 * 32);
+    buf.extend_from_slice(keccak256(ORDER_TYPE).as_slice());
+    buf.extend_from_slice(&word_uint(&o.salt.to_string()));
+    buf.extend_from_slice(&word_addr(&o.maker));
+    buf.extend_from_slice(&word_addr(&o.signer));
+    buf.extend_from_slice(&word_addr(&o.taker));
+    buf.extend_from_slice(&word_uint(&o.token_id));
+    buf.extend_from_slice(&word_uint(&o.maker_amount));
+    buf.extend_from_slice(&word_uint(&o.taker_amount));
+    buf.extend_from_slice(&word_uint(&o.expiration));
+    buf.extend_from_slice(&word_uint(&o.nonce));
+    buf.extend_from_slice(&word_uint(&o.fee_rate_bps));
+    buf.extend_from_slice(&word_uint(&o.side.to_string()));
+    buf.extend_from_slice(&word_uint(&o.signature_type.to_string()));
+    keccak256(buf)
+}
+
+fn reference_digest(chain_id: u64, neg_risk: bool, o: &OrderStruct) -> B256 {
+    let exchange = get_exchange_address(chain_id, neg_risk).unwrap();
+    let mut buf = Vec::with_capacity(2 + 64);
+    buf.extend_from_slice(&[0x19, 0x01]);
+    buf.extend_from_slice(domain_separator(chain_id, exchange).as_slice());
+    buf.extend_from_slice(struct_hash(o).as_slice());
+    keccak256(buf)
+}
+
+// =========================================================================
+// Helpers
+// =========================================================================
+
+fn client(chain_id: u64) -> RustClobClient {
+    RustClobClient::new("https://clob.polymarket.com", chain_id, TEST_KEY, TEST_FUNDER).unwrap()
+}
+
+fn args(side: &str, price: f64, size: f64, order_type: &str, expiration: &str) -> OrderArgs {
+    OrderArgs {
+        token_id: TEST_TOKEN.to_string(),
+        price,
+        size,
+        side: side.to_string(),
+        fee_rate_bps: None,
+        nonce: Some(0),
+        expiration: Some(expiration.to_string()),
+        taker: None,
+        order_type: Some(order_type.to_string()),
+    }
+}
+
+fn assert_signed_correctly(chain_id: u64, neg_risk: bool, signed: &SignedOrder) -> B256 {
+    let digest = reference_digest(chain_id, neg_risk, &signed.order);
+    let sig = Signature::from_str(&signed.signature).unwrap();
+    let recovered = sig.recover_address_from_prehash(&digest).unwrap();
+    assert_eq!(recovered, Address::from_str(TEST_ADDRESS).unwrap(), "chain {} neg_risk {}", chain_id, neg_risk);
+    digest
+}
+
+// =========================================================================
+// Tests
+// =========================================================================
+
+#[test]
+fn test_wallet_derivation() {
+    let c = client(137);
+    assert_eq!(c.wallet_address_str, TEST_ADDRESS);
+}
+
+#[test]
+fn test_order_fields_match_inputs() {
+    let signed = client(137).sign_order(args("BUY", 0.14, 108.68, "FAK", "0"), "0.01", false, 12345).unwrap();
+    let o = &signed.order;
+    assert_eq!(o.salt, 12345);
+    assert_eq!(o.maker, TEST_FUNDER);
+    assert_eq!(o.signer, TEST_ADDRESS);
+    assert_eq!(o.taker, ZERO_ADDRESS);
+    assert_eq!(o.token_id, TEST_TOKEN);
+    assert_eq!(o.side, 0);
+    assert_eq!(o.signature_type, 1);
+    assert_eq!(o.fee_rate_bps, "0");
+}
+
+#[test]
+fn test_post_only_body_and_cross_check() {
+    let signed = client(137).sign_order(args("BUY", 0.45, 50.0, "GTD", "1767225600"), "0.01", false, 1).unwrap();
+    let plain: serde_json::Value = serde_json::from_str(&signed.post_body("key", "GTD")).unwrap();
+    let post_only: serde_json::Value = serde_json::from_str(&signed.post_body_with("key", "GTD", true)).unwrap();
+    assert!(plain.get("postOnly").is_none());
+    assert_eq!(post_only["postOnly"], true);
+    assert_eq!(post_only["order"], plain["order"]);
+
+    assert!(crate::post_only_would_cross(true, 0.45, Some(0.45)));
+    assert!(!crate::post_only_would_cross(true, 0.44, Some(0.45)));
+    assert!(crate::post_only_would_cross(false, 0.40, Some(0.41)));
+    assert!(!crate::post_only_would_cross(false, 0.42, Some(0.41)));
+    assert!(!crate::post_only_would_cross(true, 0.99, None));
+}
+
+#[test]
+fn test_digest_matches_reference_encoder_all_exchanges() {
+    for chain_id in [137, 80002] {
+        for neg_risk in [false, true] {
+            let c = client(chain_id);
+            let buy = c.sign_order(args("BUY", 0.45, 50.0, "GTD", "1767225600"), "0.01", neg_risk, 987_654_321).unwrap();
+            let sell = c.sign_order(args("SELL", 0.455, 116.88, "FAK", "0"), "0.001", neg_risk, 42).unwrap();
+            assert_signed_correctly(chain_id, neg_risk, &buy);
+            assert_signed_correctly(chain_id, neg_risk, &sell);
+        }
+    }
+}
+
+#[test]
+fn test_neg_risk_changes_domain() {
+    let c = client(137);
+    let plain = c.sign_order(args("BUY", 0.5, 10.0, "FAK", "0"), "0.01", false, 7).unwrap();
+    let neg = c.sign_order(args("BUY", 0.5, 10.0, "FAK", "0"), "0.01", true, 7).unwrap();
+    assert_eq!(plain.order.maker_amount, neg.order.maker_amount);
+    assert_ne!(plain.signature, neg.signature);
+}
+
+#[test]
+fn test_pinned_vectors() {
+    // Pinned from this implementation after the reference-encoder cross-check above.
+    // Change only when the order format intentionally changes.
+    let c = client(137);
+    let signed = c.sign_order(args("BUY", 0.5, 10.0, "FAK", "0"), "0.01", false, 1).unwrap();
+    let digest = assert_signed_correctly(137, false, &signed);
+    assert_eq!(signed.order.maker_amount, "5000000");
+    assert_eq!(signed.order.taker_amount, "10000000");
+    assert_eq!(format!("{}", digest), PINNED_DIGEST);
+    assert_eq!(signed.signature, PINNED_SIGNATURE);
+}
+
+const PINNED_DIGEST: &str = "0xe5011d258b07a36ebeaf0cf84cd369c738900a4ef11239e464e6395bc8881496";
+const PINNED_SIGNATURE: &str = concat!(
+    "0xa66362360f44edeab6747c2e559779c6879048d55d2e611e90de6e7ee246cc09",
+    "1848e5d828e51438d39f2e063b8328a441ebfce2c7d322894340d5a59c1550141c",
+);
+
+#[test]
+fn test_randomized_orders() {
+    let mut rng = rand::thread_rng();
+    let ticks = ["0.1", "0.01", "0.001", "0.0001"];
+    let clients = [client(137), client(80002)];
+
+    for _ in 0..200 {
+        let tick = ticks[rng.gen_range(0..ticks.len())];
+        let t: f64 = tick.parse().unwrap();
+        let steps = (1.0 / t).round() as u64;
+        let price = (rng.gen_range(1..steps) as f64 * t * 1e4).round() / 1e4;
+        let size = rng.gen_range(1..100_000) as f64 / 100.0;
+        let side = if rng.gen_bool(0.5) { "BUY" } else { "SELL" };
+        let order_type = if rng.gen_bool(0.5) { "FAK" } else { "GTD" };
+        let neg_risk = rng.gen_bool(0.5);
+        let idx = rng.gen_range(0..clients.len());
+        let chain_id = if idx == 0 { 137 } else { 80002 };
+
+        let signed = clients[idx]
+            .sign_order(args(side, price, size, order_type, "0"), tick, neg_risk, rng.r#gen::<u32>() as u128)
+            .unwrap();
+        assert_signed_correctly(chain_id, neg_risk, &signed);
+    }
+}
@@ -0,0 +1,545 @@
+//! Embeddable copy-trading engine
+//! The pieces pm_bot wires together by hand (event feed, copy sizing, risk checks, order
+//! execution, notifications) behind plugin traits, so another binary can run the same loop
+//! with its own parts. Items re-exported from the crate root are the supported surface;
+//! other modules are internals and may change between releases
+
+use crate::capital_at_risk;
+use crate::compliance;
+use crate::credentials;
+use crate::models::ParsedEvent;
+use crate::settings::{get_tier_params, should_skip_trade, clob_api_base, SCALING_RATIO};
+use crate::signal_math;
+use crate::strategy::strategy_ledger;
+use crate::{post_only_would_cross, OrderArgs, PreparedCreds, RustClobClient};
+use crate::timestamp;
+use anyhow::{Result, anyhow};
+use rustc_hash::FxHashSet;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+// ============================================================================
+// Plugin Types
+// ============================================================================
+
+/// Order a strategy wants placed in response to one whale trade
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyOrder {
+    pub token_id: Arc<str>,
+    pub is_buy: bool,
+    /// Limit price (0.01 - 0.99)
+    pub price: f64,
+    pub shares: f64,
+    /// "FAK" or "GTD"
+    pub order_type: &'static str,
+    /// Rest on the book or be rejected, never take (GTD only)
+    pub post_only: bool,
+}
+
+/// What happened to one whale trade for one strategy
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// Strategy chose not to copy
+    Skipped { strategy: String },
+    /// A risk check refused the order
+    Blocked { strategy: String, reason: String },
+    /// Executor accepted the order; `status` is its report
+    Executed { strategy: String, status: String },
+    Failed { strategy: String, error: String },
+}
+
+/// Source of whale trades (chain logs, mempool, a replay file, ...)
+#[async_trait::async_trait]
+pub trait Feed: Send {
+    /// Next trade, None once the feed is finished
+    async fn next_event(&mut self) -> Option<ParsedEvent>;
+}
+
+/// Decides whether and how to copy a trade
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &str;
+    fn on_event(&self, evt: &ParsedEvent) -> Option<CopyOrder>;
+}
+
+/// Pre-trade check; Err(reason) blocks the order
+pub trait RiskCheck: Send + Sync {
+    fn check(&self, evt: &ParsedEvent, order: &CopyOrder) -> Result<(), String>;
+
+    /// Called once the executor accepted an order every check passed (for checks with memory)
+    fn on_executed(&self, _evt: &ParsedEvent, _order: &CopyOrder) {}
+}
+
+/// Places orders
+#[async_trait::async_trait]
+pub trait Executor: Send + Sync {
+    async fn execute(&self, order: &CopyOrder) -> Result<String>;
+}
+
+/// Receives every outcome (logging, alerts, metrics)
+pub trait Notifier: Send + Sync {
+    fn notify(&self, evt: &ParsedEvent, outcome: &Outcome);
+}
+
+// ============================================================================
+// Built-in Plugins
+// ============================================================================
+
+/// pm_bot's tiered sizing: SCALING_RATIO of the whale, tier buffer and multiplier, exchange
+/// minimum notional. Deterministic (no probabilistic sizing below the minimum)
+pub struct TierCopyStrategy;
+
+impl Strategy for TierCopyStrategy {
+    fn name(&self) -> &str {
+        "tier_copy"
+    }
+
+    fn on_event(&self, evt: &ParsedEvent) -> Option<CopyOrder> {
+        let info = &evt.order;
+        if should_skip_trade(info.shares) {
+            return None;
+        }
+        let is_buy = info.order_type.starts_with("BUY");
+        let (buffer, order_type, multiplier) = get_tier_params(info.shares, is_buy, &info.clob_token_id);
+        let price = signal_math::limit_price(info.price_per_share, buffer, is_buy);
+        let shares = (info.shares * SCALING_RATIO * multiplier).max(signal_math::size_floor(price));
+        Some(CopyOrder {
+            token_id: Arc::clone(&info.clob_token_id),
+            is_buy,
+            price,
+            shares: signal_math::floor_cents(shares),
+            order_type,
+            post_only: false,
+        })
+    }
+}
+
+/// Per-tag exposure cap from the strategy ledger (STRATEGY_MAX_OPEN_USD)
+pub struct StrategyCapCheck;
+
+impl RiskCheck for StrategyCapCheck {
+    fn check(&self, _evt: &ParsedEvent, order: &CopyOrder) -> Result<(), String> {
+        if !order.is_buy {
+            return Ok(());
+        }
+        strategy_ledger()
+            .check_entry(order.shares * order.price)
+            .map_err(|open| format!("STRATEGY_CAP (open ${:.2})", open))
+    }
+}
+
+/// Wallet-wide premium at risk across open positions (MAX_CAPITAL_AT_RISK_USD)
+pub struct CapitalAtRiskCheck;
+
+impl RiskCheck for CapitalAtRiskCheck {
+    fn check(&self, _evt: &ParsedEvent, order: &CopyOrder) -> Result<(), String> {
+        if !order.is_buy {
+            return Ok(());
+        }
+        capital_at_risk::check_entry(capital_at_risk::limit(), capital_at_risk::current().at_risk_usd, order.shares * order.price)
+            .map_err(|at_risk| format!("CAPITAL_AT_RISK (at risk ${:.2})", at_risk))
+    }
+}
+
+/// Registered pre-trade compliance checks (COMPLIANCE_* rules and `compliance::register`)
+pub struct ComplianceCheck;
+
+impl RiskCheck for ComplianceCheck {
+    fn check(&self, _evt: &ParsedEvent, order: &CopyOrder) -> Result<(), String> {
+        compliance::check(&order.token_id, order.is_buy, order.price, order.shares)
+            .map_err(|reason| format!("COMPLIANCE_BLOCKED ({})", reason))
+    }
+}
+
+/// Refuses a second order for the same whale trade and side/price/size, e.g. when logs are
+/// replayed after a feed reconnect. Only executed orders count, so a trade that was blocked or
+/// failed may be retried. Remembers the last `DEDUP_WINDOW` orders
+#[derive(Default)]
+pub struct DedupCheck {
+    seen: Mutex<(FxHashSet<String>, VecDeque<String>)>,
+}
+
+const DEDUP_WINDOW: usize = 10_000;
+
+impl DedupCheck {
+    fn key(evt: &ParsedEvent, order: &CopyOrder) -> String {
+        format!("{}:{}:{:.2}:{:.2}", evt.intent_id(), order.is_buy, order.price, order.shares)
+    }
+}
+
+impl RiskCheck for DedupCheck {
+    fn check(&self, evt: &ParsedEvent, order: &CopyOrder) -> Result<(), String> {
+        // Without its memory a duplicate can't be ruled out
+        let guard = self.seen.lock().map_err(|_| "DEDUP_UNAVAILABLE".to_string())?;
+        if guard.0.contains(&Self::key(evt, order)) {
+            return Err("DUPLICATE_INTENT".into());
+        }
+        Ok(())
+    }
+
+    fn on_executed(&self, evt: &ParsedEvent, order: &CopyOrder) {
+        let Ok(mut guard) = self.seen.lock() else { return };
+        let (set, order_of) = &mut *guard;
+        let key = Self::key(evt, order);
+        if !set.insert(key.clone()) {
+            return;
+        }
+        order_of.push_back(key);
+        if order_of.len() > DEDUP_WINDOW && let Some(old) = order_of.pop_front() {
+            set.remove(&old);
+        }
+    }
+}
+
+/// Signs and posts to the CLOB with the same client pm_bot uses. Once `credentials` is
+/// initialized its current (refreshed) key signs; `creds` is used until then
+pub struct ClobExecutor {
+    pub client: Arc<RustClobClient>,
+    pub creds: Arc<PreparedCreds>,
+}
+
+#[async_trait::async_trait]
+impl Executor for ClobExecutor {
+    async fn execute(&self, order: &CopyOrder) -> Result<String> {
+        let expiration = if order.order_type == "GTD" {
+            let now = timestamp::unix_secs();
+            (now + crate::settings::get_gtd_expiry_secs(false)).to_string()
+        } else {
+            "0".to_string()
+        };
+        let args = OrderArgs {
+            token_id: order.token_id.to_string(),
+            price: order.price,
+            size: order.shares,
+            side: if order.is_buy { "BUY".into() } else { "SELL".into() },
+            fee_rate_bps: None,
+            nonce: Some(0),
+            expiration: Some(expiration),
+            taker: None,
+            order_type: Some(order.order_type.to_string()),
+        };
+        if order.post_only && order.order_type != "GTD" {
+            anyhow::bail!("post-only requires a resting order type, got {}", order.order_type);
+        }
+        let creds = credentials::credentials().map_or_else(|| Arc::clone(&self.creds), |store| store.current());
+        let (client, order_type) = (Arc::clone(&self.client), order.order_type);
+        let (is_buy, price, post_only) = (order.is_buy, order.price, order.post_only);
+        tokio::task::spawn_blocking(move || {
+            // The exchange rejects crossing post-only orders too; checking first saves the signature
+            if post_only && post_only_would_cross(is_buy, price, fetch_best_opposite(&client, &args.token_id, is_buy)) {
+                anyhow::bail!("POST_ONLY_WOULD_CROSS @ {:.2}", price);
+            }
+            let mut client = (*client).clone();
+            let signed = client.create_order(args)?;
+            let reply = client.post_order(signed.post_body_with(&creds.api_key, order_type, post_only), &creds)?;
+            match reply.result {
+                Err(rejection) => Err(anyhow!("{} {}", reply.status, rejection)),
+                Ok(r) if r.is_resting() => Ok(format!("{} resting (maker)", reply.status)),
+                Ok(r) if r.taking_amount.is_empty() => Ok(reply.status.to_string()),
+                Ok(r) => Ok(format!("{} filled {} for {} (taker)", reply.status, r.taking_amount, r.making_amount)),
+            }
+        }).await?
+    }
+}
+
+/// Best ask (for buys) or best bid (for sells) from the CLOB book
+fn fetch_best_opposite(client: &RustClobClient, token_id: &str, is_buy: bool) -> Option<f64> {
+    let url = format!("{}/book?token_id={}", clob_api_base(), token_id);
+    let book: serde_json::Value = client.http_client()
+        .get(&url)
+        .timeout(std::time::Duration::from_millis(500))
+        .send().ok()?
+        .json().ok()?;
+    let levels = book[if is_buy { "asks" } else { "bids" }].as_array()?;
+    let prices = levels.iter().filter_map(|l| l["price"].as_str()?.parse::<f64>().ok());
+    if is_buy { prices.reduce(f64::min) } else { prices.reduce(f64::max) }
+}
+
+/// Never posts; reports what would have been sent
+pub struct DryRunExecutor;
+
+#[async_trait::async_trait]
+impl Executor for DryRunExecutor {
+    async fn execute(&self, order: &CopyOrder) -> Result<String> {
+        Ok(format!(
+            "DRY_RUN {} {:.2} @ {:.2} {}",
+            if order.is_buy { "BUY" } else { "SELL" }, order.shares, order.price, order.order_type
+        ))
+    }
+}
+
+/// One console line per outcome
+pub struct ConsoleNotifier;
+
+impl Notifier for ConsoleNotifier {
+    fn notify(&self, evt: &ParsedEvent, outcome: &Outcome) {
+        crate::console_println!("⚡ [B:{}] {} {} | {:?}", evt.block_number, evt.order.order_type, evt.order.clob_token_id, outcome);
+    }
+}
+
+// ============================================================================
+// Engine
+// ============================================================================
+
+#[derive(Default)]
+pub struct EngineBuilder {
+    feeds: Vec<Box<dyn Feed>>,
+    strategies: Vec<Box<dyn Strategy>>,
+    risk: Vec<Box<dyn RiskCheck>>,
+    executor: Option<Box<dyn Executor>>,
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl EngineBuilder {
+    pub fn feed(mut self, feed: impl Feed + 'static) -> Self {
+        self.feeds.push(Box::new(feed));
+        self
+    }
+
+    pub fn strategy(mut self, strategy: impl Strategy + 'static) -> Self {
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
+    /// Checks run in the order added; the first refusal wins
+    pub fn risk(mut self, check: impl RiskCheck + 'static) -> Self {
+        self.risk.push(Box::new(check));
+        self
+    }
+
+    pub fn executor(mut self, executor: impl Executor + 'static) -> Self {
+        self.executor = Some(Box::new(executor));
+        self
+    }
+
+    pub fn notifier(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.notifiers.push(Box::new(notifier));
+        self
+    }
+
+    pub fn build(self) -> Result<Engine> {
+        if self.strategies.is_empty() {
+            anyhow::bail!("Engine needs at least one strategy");
+        }
+        let executor = self.executor.ok_or_else(|| anyhow!("Engine needs an executor"))?;
+        Ok(Engine {
+            feeds: self.feeds,
+            strategies: self.strategies,
+            risk: self.risk,
+            executor,
+            notifiers: self.notifiers,
+        })
+    }
+}
+
+pub struct Engine {
+    feeds: Vec<Box<dyn Feed>>,
+    strategies: Vec<Box<dyn Strategy>>,
+    risk: Vec<Box<dyn RiskCheck>>,
+    executor: Box<dyn Executor>,
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl Engine {
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
+    }
+
+    /// Run one trade through every strategy, its risk checks and the executor
+    pub async fn process(&self, evt: &ParsedEvent) -> Vec<Outcome> {
+        let mut outcomes = Vec::with_capacity(self.strategies.len());
+        for strategy in &self.strategies {
+            let name = strategy.name().to_string();
+            let outcome = match strategy.on_event(evt) {
+                None => Outcome::Skipped { strategy: name },
+                Some(order) => match self.risk.iter().find_map(|r| r.check(evt, &order).err()) {
+                    Some(reason) => Outcome::Blocked { strategy: name, reason },
+                    None => match self.executor.execute(&order).await {
+                        Ok(status) => {
+                            for r in &self.risk {
+                                r.on_executed(evt, &order);
+                            }
+                            Outcome::Executed { strategy: name, status }
+                        }
+                        Err(e) => Outcome::Failed { strategy: name, error: e.to_string() },
+                    },
+                },
+            };
+            for n in &self.notifiers {
+                n.notify(evt, &outcome);
+            }
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+
+    /// Drain all feeds (merged in arrival order) until every one of them has finished
+    pub async fn run(mut self) {
+        let (tx, mut rx) = mpsc::channel::<ParsedEvent>(1024);
+        for mut feed in self.feeds.drain(..) {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                while let Some(evt) = feed.next_event().await {
+                    if tx.send(evt).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        while let Some(evt) = rx.recv().await {
+            self.process(&evt).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OrderInfo;
+    use crate::settings::MIN_CASH_VALUE;
+
+    fn event(token: &str, order_type: &str, shares: f64, price: f64) -> ParsedEvent {
+        ParsedEvent {
+            received_at: Default::default(),
+            block_number: 1,
+            tx_hash: "0xabc".into(),
+            log_index: 0,
+            order: OrderInfo {
+                order_type: order_type.into(),
+                clob_token_id: token.into(),
+                usd_value: shares * price,
+                shares,
+                price_per_share: price,
+            },
+        }
+    }
+
+    struct VecFeed(Vec<ParsedEvent>);
+
+    #[async_trait::async_trait]
+    impl Feed for VecFeed {
+        async fn next_event(&mut self) -> Option<ParsedEvent> {
+            self.0.pop()
+        }
+    }
+
+    struct BlockToken(&'static str);
+
+    impl RiskCheck for BlockToken {
+        fn check(&self, _evt: &ParsedEvent, order: &CopyOrder) -> Result<(), String> {
+            if &*order.token_id == self.0 { Err("BLOCKED".into()) } else { Ok(()) }
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl Executor for Recorder {
+        async fn execute(&self, order: &CopyOrder) -> Result<String> {
+            self.0.lock().unwrap().push(order.token_id.to_string());
+            Ok("OK".into())
+        }
+    }
+
+    impl Notifier for Recorder {
+        fn notify(&self, _evt: &ParsedEvent, outcome: &Outcome) {
+            self.0.lock().unwrap().push(format!("{:?}", outcome));
+        }
+    }
+
+    /// Fails its first `n` orders
+    struct FailFirst(Mutex<u32>);
+
+    #[async_trait::async_trait]
+    impl Executor for FailFirst {
+        async fn execute(&self, _order: &CopyOrder) -> Result<String> {
+            let mut left = self.0.lock().unwrap();
+            if *left > 0 {
+                *left -= 1;
+                anyhow::bail!("HTTP 500");
+            }
+            Ok("OK".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dedup_counts_only_executed_orders() {
+        let engine = Engine::builder()
+            .strategy(TierCopyStrategy)
+            .risk(DedupCheck::default())
+            .executor(FailFirst(Mutex::new(1)))
+            .build()
+            .unwrap();
+        let evt = event("t1", "BUY_FILL", 2000.0, 0.4);
+
+        // A failed order may be retried; once one went through, the replay is refused
+        assert!(matches!(engine.process(&evt).await[0], Outcome::Failed { .. }));
+        assert!(matches!(engine.process(&evt).await[0], Outcome::Executed { .. }));
+        assert!(matches!(&engine.process(&evt).await[0], Outcome::Blocked { reason, .. } if reason == "DUPLICATE_INTENT"));
+
+        // Blocked by a later check: not remembered either
+        let dedup = DedupCheck::default();
+        let order = TierCopyStrategy.on_event(&evt).unwrap();
+        assert!(dedup.check(&evt, &order).is_ok());
+        assert!(dedup.check(&evt, &order).is_ok());
+    }
+
+    #[test]
+    fn test_dedup_fails_closed_when_poisoned() {
+        let dedup = DedupCheck::default();
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = dedup.seen.lock().unwrap();
+            panic!("poison");
+        }));
+        let evt = event("t1", "BUY_FILL", 2000.0, 0.4);
+        let order = TierCopyStrategy.on_event(&evt).unwrap();
+        assert_eq!(dedup.check(&evt, &order), Err("DEDUP_UNAVAILABLE".to_string()));
+    }
+
+    #[test]
+    fn test_tier_copy_sizing() {
+        let order = TierCopyStrategy.on_event(&event("t1", "BUY_FILL", 5000.0, 0.50)).unwrap();
+        assert!(order.is_buy);
+        assert!((order.price - 0.51).abs() < 1e-9);
+        assert!((order.shares - 125.0).abs() < 1e-9);
+        assert_eq!(order.order_type, "FAK");
+
+        // Tiny copy is lifted to the exchange minimum notional
+        let order = TierCopyStrategy.on_event(&event("t1", "SELL_FILL", 20.0, 0.50)).unwrap();
+        assert_eq!(order.order_type, "GTD");
+        assert!(order.shares * order.price >= MIN_CASH_VALUE - 0.01);
+
+        assert!(TierCopyStrategy.on_event(&event("t1", "BUY_FILL", 5.0, 0.50)).is_none());
+    }
+
+    #[test]
+    fn test_builder_requires_strategy_and_executor() {
+        assert!(Engine::builder().executor(DryRunExecutor).build().is_err());
+        assert!(Engine::builder().strategy(TierCopyStrategy).build().is_err());
+        assert!(Engine::builder().strategy(TierCopyStrategy).executor(DryRunExecutor).build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_routes_through_risk_executor_and_notifiers() {
+        let executed = Recorder::default();
+        let notified = Recorder::default();
+        let engine = Engine::builder()
+            .feed(VecFeed(vec![event("ok", "BUY_FILL", 2000.0, 0.4), event("bad", "BUY_FILL", 2000.0, 0.4)]))
+            .feed(VecFeed(vec![event("small", "BUY_FILL", 1.0, 0.4)]))
+            .strategy(TierCopyStrategy)
+            .risk(BlockToken("bad"))
+            .executor(executed.clone())
+            .notifier(notified.clone())
+            .build()
+            .unwrap();
+        engine.run().await;
+
+        assert_eq!(*executed.0.lock().unwrap(), vec!["ok".to_string()]);
+        let notes = notified.0.lock().unwrap();
+        assert_eq!(notes.len(), 3);
+        assert!(notes.iter().any(|n| n.starts_with("Blocked") && n.contains("BLOCKED")));
+        assert!(notes.iter().any(|n| n.starts_with("Skipped")));
+    }
+}
@@ -0,0 +1,454 @@
+//! Price improvement and fill-failure statistics for FAK entries
+//! Each first submission is recorded as a fill (with how much better than our limit it
+//! executed) or a miss (no liquidity at the limit). Stats are kept per token and per UTC hour,
+//! and the recent miss rate can widen or tighten the submission buffer per token. Accepted
+//! orders of any type are also split into taker fills and maker (resting) posts
+
+use chrono::{Timelike, Utc};
+use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Recent outcomes per token used for the adaptive offset
+pub const OUTCOME_WINDOW: usize = 20;
+
+/// Outcomes needed before the offset moves
+pub const MIN_OUTCOMES: usize = 5;
+
+/// Offset step (one cent tick)
+pub const OFFSET_STEP: f64 = 0.01;
+
+/// Never chase more than this on top of the tier buffer
+pub const MAX_EXTRA_OFFSET: f64 = 0.02;
+
+/// Recent signal-to-submit and submit-to-ack latencies kept for the percentiles
+pub const LATENCY_WINDOW: usize = 1000;
+
+// ============================================================================
+// Stats
+// ============================================================================
+
+#[derive(Debug, Clone, Default)]
+pub struct FillStats {
+    pub fills: u64,
+    pub misses: u64,
+    /// Fills that executed strictly better than the limit
+    pub improved: u64,
+    /// Sum of (limit - fill) for buys, (fill - limit) for sells
+    pub improvement_sum: f64,
+}
+
+impl FillStats {
+    fn record(&mut self, improvement: Option<f64>) {
+        match improvement {
+            Some(imp) => {
+                self.fills += 1;
+                if imp > 1e-9 {
+                    self.improved += 1;
+                }
+                self.improvement_sum += imp;
+            }
+            None => self.misses += 1,
+        }
+    }
+
+    pub fn attempts(&self) -> u64 {
+        self.fills + self.misses
+    }
+
+    pub fn miss_rate(&self) -> f64 {
+        if self.attempts() == 0 { 0.0 } else { self.misses as f64 / self.attempts() as f64 }
+    }
+
+    /// Average improvement per fill, in price units
+    pub fn avg_improvement(&self) -> f64 {
+        if self.fills == 0 { 0.0 } else { self.improvement_sum / self.fills as f64 }
+    }
+}
+
+/// Maker/taker split of accepted orders
+#[derive(Debug, Clone, Default)]
+pub struct LiquidityStats {
+    /// Orders matched on arrival
+    pub taker_fills: u64,
+    pub taker_usd: f64,
+    /// Orders accepted onto the book (maker if and when they fill)
+    pub maker_posts: u64,
+    pub maker_usd: f64,
+}
+
+impl LiquidityStats {
+    /// Rebate the maker notional would earn at `rebate_bps` if it all filled
+    pub fn potential_rebate(&self, rebate_bps: f64) -> f64 {
+        self.maker_usd * rebate_bps / 10_000.0
+    }
+}
+
+#[derive(Debug, Default)]
+struct TokenStats {
+    total: FillStats,
+    /// Recent outcomes: Some(improvement) = filled, None = missed
+    recent: VecDeque<Option<f64>>,
+}
+
+#[derive(Default)]
+pub struct ExecutionStats {
+    adaptive: AtomicBool,
+    tokens: Mutex<FxHashMap<String, TokenStats>>,
+    by_hour: Mutex<[FillStats; 24]>,
+    liquidity: Mutex<LiquidityStats>,
+    /// f64 bits of MAKER_REBATE_BPS
+    maker_rebate_bps: AtomicU64,
+    /// Entries sized against visible depth, and how many of them the depth cap shrank
+    depth_checks: AtomicU64,
+    depth_capped: AtomicU64,
+    /// Whale signals dropped for waiting too long in the order queue
+    stale_signals: AtomicU64,
+    /// Entries whose ask was re-checked at submit, and how many were re-priced or aborted
+    ask_checks: AtomicU64,
+    ask_requoted: AtomicU64,
+    ask_aborted: AtomicU64,
+    /// Signal-to-POST latency of recent entries and exits (µs), and entries past the deadline
+    submit_latency_us: Mutex<VecDeque<u64>>,
+    deadline_misses: AtomicU64,
+    /// POST-to-response latency of recent orders (µs)
+    ack_latency_us: Mutex<VecDeque<u64>>,
+}
+
+fn push_latency(window: &Mutex<VecDeque<u64>>, latency: std::time::Duration) {
+    let Ok(mut window) = window.lock() else { return };
+    if window.len() >= LATENCY_WINDOW {
+        window.pop_front();
+    }
+    window.push_back(latency.as_micros() as u64);
+}
+
+/// (p50, p99, max) of a latency window in µs
+fn percentiles(window: &Mutex<VecDeque<u64>>) -> Option<(u64, u64, u64)> {
+    let mut sorted: Vec<u64> = window.lock().ok()?.iter().copied().collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_unstable();
+    let at = |q: f64| sorted[((sorted.len() - 1) as f64 * q).round() as usize];
+    Some((at(0.50), at(0.99), sorted[sorted.len() - 1]))
+}
+
+impl ExecutionStats {
+    /// Let `extra_offset` move submission prices (ADAPTIVE_OFFSET)
+    pub fn set_adaptive(&self, enabled: bool) {
+        self.adaptive.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Rebate rate used to value maker posts in the report (MAKER_REBATE_BPS)
+    pub fn set_maker_rebate_bps(&self, bps: f64) {
+        self.maker_rebate_bps.store(bps.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn maker_rebate_bps(&self) -> f64 {
+        f64::from_bits(self.maker_rebate_bps.load(Ordering::Relaxed))
+    }
+
+    /// An entry was sized against the visible book; `capped` if depth, not bankroll, set its size
+    pub fn record_depth_check(&self, capped: bool) {
+        self.depth_checks.fetch_add(1, Ordering::Relaxed);
+        if capped {
+            self.depth_capped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// (checks, capped)
+    pub fn depth_cap_counts(&self) -> (u64, u64) {
+        (self.depth_checks.load(Ordering::Relaxed), self.depth_capped.load(Ordering::Relaxed))
+    }
+
+    pub fn record_stale_signal(&self) {
+        self.stale_signals.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The ask was re-checked before submitting an entry
+    pub fn record_ask_check(&self, requoted: bool, aborted: bool) {
+        self.ask_checks.fetch_add(1, Ordering::Relaxed);
+        if requoted {
+            self.ask_requoted.fetch_add(1, Ordering::Relaxed);
+        }
+        if aborted {
+            self.ask_aborted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// (stale signals, ask checks, requoted, aborted)
+    pub fn signal_guard_counts(&self) -> (u64, u64, u64, u64) {
+        (
+            self.stale_signals.load(Ordering::Relaxed),
+            self.ask_checks.load(Ordering::Relaxed),
+            self.ask_requoted.load(Ordering::Relaxed),
+            self.ask_aborted.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Time from the whale signal to our POST
+    pub fn record_submit_latency(&self, latency: std::time::Duration) {
+        push_latency(&self.submit_latency_us, latency);
+    }
+
+    /// Time from our POST to the exchange's response
+    pub fn record_ack_latency(&self, latency: std::time::Duration) {
+        push_latency(&self.ack_latency_us, latency);
+    }
+
+    /// An order was abandoned because the submit deadline passed before the POST
+    pub fn record_deadline_miss(&self) {
+        self.deadline_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// (p50, p99, max) signal-to-submit latency in µs over the recent window
+    pub fn submit_latency(&self) -> Option<(u64, u64, u64)> {
+        percentiles(&self.submit_latency_us)
+    }
+
+    /// (p50, p99, max) submit-to-ack latency in µs over the recent window
+    pub fn ack_latency(&self) -> Option<(u64, u64, u64)> {
+        percentiles(&self.ack_latency_us)
+    }
+
+    /// Order matched on arrival for `usd` notional
+    pub fn record_taker(&self, usd: f64) {
+        if let Ok(mut l) = self.liquidity.lock() {
+            l.taker_fills += 1;
+            l.taker_usd += usd;
+        }
+    }
+
+    /// Order rested on the book with `usd` notional
+    pub fn record_maker(&self, usd: f64) {
+        if let Ok(mut l) = self.liquidity.lock() {
+            l.maker_posts += 1;
+            l.maker_usd += usd;
+        }
+    }
+
+    pub fn liquidity(&self) -> LiquidityStats {
+        self.liquidity.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+
+    /// FAK filled at `fill_price` against our `limit`
+    pub fn record_fill(&self, token_id: &str, is_buy: bool, limit: f64, fill_price: f64) {
+        let improvement = if is_buy { limit - fill_price } else { fill_price - limit };
+        self.record(token_id, Some(improvement));
+    }
+
+    /// FAK found nothing to match at our limit
+    pub fn record_miss(&self, token_id: &str) {
+        self.record(token_id, None);
+    }
+
+    fn record(&self, token_id: &str, outcome: Option<f64>) {
+        if let Ok(mut tokens) = self.tokens.lock() {
+            let st = tokens.entry(token_id.to_string()).or_default();
+            st.total.record(outcome);
+            if st.recent.len() == OUTCOME_WINDOW {
+                st.recent.pop_front();
+            }
+            st.recent.push_back(outcome);
+        }
+        if let Ok(mut hours) = self.by_hour.lock() {
+            hours[Utc::now().hour() as usize].record(outcome);
+        }
+    }
+
+    /// Extra buffer to add on top of the tier buffer for this token (0 unless adaptive)
+    pub fn extra_offset(&self, token_id: &str) -> f64 {
+        if !self.adaptive.load(Ordering::Relaxed) {
+            return 0.0;
+        }
+        let Ok(tokens) = self.tokens.lock() else { return 0.0 };
+        tokens.get(token_id).map_or(0.0, |st| offset_for(&st.recent))
+    }
+
+    pub fn token_stats(&self, token_id: &str) -> Option<FillStats> {
+        self.tokens.lock().ok()?.get(token_id).map(|st| st.total.clone())
+    }
+
+    pub fn tracked_tokens(&self) -> usize {
+        self.tokens.lock().map(|t| t.len()).unwrap_or(0)
+    }
+
+    /// Forget tokens no longer of interest (called from the retention sweep)
+    pub fn retain_tokens(&self, keep: impl Fn(&str) -> bool) -> usize {
+        let Ok(mut tokens) = self.tokens.lock() else { return 0 };
+        let before = tokens.len();
+        tokens.retain(|t, _| keep(t));
+        before - tokens.len()
+    }
+
+    /// Dump lines: maker/taker split, overall, busiest tokens and active UTC hours
+    pub fn report(&self, max_tokens: usize) -> String {
+        let mut out = String::new();
+        let l = self.liquidity();
+        if l.taker_fills + l.maker_posts > 0 {
+            let _ = writeln!(
+                out, "  {:<14} taker={} (${:.2}) maker={} (${:.2}) potential_rebate=${:.4}",
+                "liquidity", l.taker_fills, l.taker_usd, l.maker_posts, l.maker_usd, l.potential_rebate(self.maker_rebate_bps())
+            );
+        }
+        let (checks, capped) = self.depth_cap_counts();
+        if checks > 0 {
+            let _ = writeln!(out, "  {:<14} capped={}/{} ({:.0}%)", "depth_cap", capped, checks, capped as f64 * 100.0 / checks as f64);
+        }
+        let (stale, ask_checks, requoted, aborted) = self.signal_guard_counts();
+        if stale + ask_checks > 0 {
+            let _ = writeln!(out, "  {:<14} stale={} ask_checks={} requoted={} aborted={}", "signal_guard", stale, ask_checks, requoted, aborted);
+        }
+        let misses = self.deadline_misses.load(Ordering::Relaxed);
+        if let Some((p50, p99, max)) = self.submit_latency() {
+            let _ = writeln!(
+                out, "  {:<14} p50={:.1}ms p99={:.1}ms max={:.1}ms deadline_missed={}",
+                "submit_latency", p50 as f64 / 1000.0, p99 as f64 / 1000.0, max as f64 / 1000.0, misses
+            );
+        } else if misses > 0 {
+            let _ = writeln!(out, "  {:<14} deadline_missed={}", "submit_latency", misses);
+        }
+        let Ok(tokens) = self.tokens.lock() else { return out };
+        let mut rows: Vec<_> = tokens.iter().map(|(t, st)| (t, &st.total)).collect();
+        rows.sort_by_key(|(_, s)| std::cmp::Reverse(s.attempts()));
+
+        let mut all = FillStats::default();
+        for (_, s) in &rows {
+            all.fills += s.fills;
+            all.misses += s.misses;
+            all.improved += s.improved;
+            all.improvement_sum += s.improvement_sum;
+        }
+        if all.attempts() == 0 {
+            return out;
+        }
+        let _ = writeln!(out, "  {:<14} {}", "fak_fills", format_stats(&all));
+        for (token, s) in rows.iter().take(max_tokens) {
+            let extra = tokens.get(*token).map_or(0.0, |st| offset_for(&st.recent));
+            let _ = writeln!(out, "    token {} {} | offset +{:.2}", token, format_stats(s), extra);
+        }
+        if let Ok(hours) = self.by_hour.lock() {
+            for (h, s) in hours.iter().enumerate().filter(|(_, s)| s.attempts() > 0) {
+                let _ = writeln!(out, "    {:02}:00 UTC {}", h, format_stats(s));
+            }
+        }
+        out
+    }
+}
+
+fn format_stats(s: &FillStats) -> String {
+    format!(
+        "attempts={} miss={:.0}% improved={}/{} avg_improvement={}",
+        s.attempts(), s.miss_rate() * 100.0, s.improved, s.fills, crate::display::cents(s.avg_improvement())
+    )
+}
+
+/// Adaptive offset from recent outcomes: chase by a tick or two when FAKs keep missing,
+/// give a tick back when fills reliably come in a full tick better than the limit
+pub fn offset_for(recent: &VecDeque<Option<f64>>) -> f64 {
+    if recent.len() < MIN_OUTCOMES {
+        return 0.0;
+    }
+    let misses = recent.iter().filter(|o| o.is_none()).count();
+    let miss_rate = misses as f64 / recent.len() as f64;
+    let fills: Vec<f64> = recent.iter().flatten().copied().collect();
+    let avg_improvement = if fills.is_empty() { 0.0 } else { fills.iter().sum::<f64>() / fills.len() as f64 };
+
+    let offset = if miss_rate >= 0.5 {
+        2.0 * OFFSET_STEP
+    } else if miss_rate >= 0.25 {
+        OFFSET_STEP
+    } else if miss_rate < 0.1 && avg_improvement >= OFFSET_STEP - 1e-9 {
+        -OFFSET_STEP
+    } else {
+        0.0
+    };
+    offset.clamp(-OFFSET_STEP, MAX_EXTRA_OFFSET)
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_EXECUTION_STATS: OnceLock<ExecutionStats> = OnceLock::new();
+
+/// Get the global execution stats
+pub fn execution_stats() -> &'static ExecutionStats {
+    GLOBAL_EXECUTION_STATS.get_or_init(ExecutionStats::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_improvement_and_miss_rate() {
+        let s = ExecutionStats::default();
+        s.record_fill("t", true, 0.50, 0.48);
+        s.record_fill("t", false, 0.50, 0.50);
+        s.record_miss("t");
+        let st = s.token_stats("t").unwrap();
+        assert_eq!((st.fills, st.misses, st.improved), (2, 1, 1));
+        assert!((st.avg_improvement() - 0.01).abs() < 1e-9);
+        assert!((st.miss_rate() - 1.0 / 3.0).abs() < 1e-9);
+        assert!(s.report(5).contains("token t attempts=3"));
+    }
+
+    #[test]
+    fn test_adaptive_offset() {
+        let s = ExecutionStats::default();
+        for _ in 0..4 {
+            s.record_miss("t");
+        }
+        s.set_adaptive(true);
+        assert_eq!(s.extra_offset("t"), 0.0); // not enough samples yet
+        s.record_fill("t", true, 0.5, 0.5);
+        assert!((s.extra_offset("t") - 2.0 * OFFSET_STEP).abs() < 1e-9);
+
+        let mut generous: VecDeque<Option<f64>> = VecDeque::new();
+        generous.extend(std::iter::repeat_n(Some(0.02), 10));
+        assert!((offset_for(&generous) + OFFSET_STEP).abs() < 1e-9);
+
+        s.set_adaptive(false);
+        assert_eq!(s.extra_offset("t"), 0.0);
+    }
+
+    #[test]
+    fn test_maker_taker_split() {
+        let s = ExecutionStats::default();
+        assert!(s.report(5).is_empty());
+        s.record_taker(10.0);
+        s.record_maker(200.0);
+        s.record_maker(50.0);
+        s.set_maker_rebate_bps(20.0);
+        let l = s.liquidity();
+        assert_eq!((l.taker_fills, l.maker_posts), (1, 2));
+        assert!((l.potential_rebate(s.maker_rebate_bps()) - 0.5).abs() < 1e-9);
+        assert!(s.report(5).contains("maker=2 ($250.00) potential_rebate=$0.5000"));
+
+        s.record_depth_check(true);
+        s.record_depth_check(false);
+        assert_eq!(s.depth_cap_counts(), (2, 1));
+        assert!(s.report(5).contains("depth_cap      capped=1/2 (50%)"));
+
+        s.record_stale_signal();
+        s.record_ask_check(true, false);
+        s.record_ask_check(false, true);
+        s.record_ask_check(false, false);
+        assert!(s.report(5).contains("signal_guard   stale=1 ask_checks=3 requoted=1 aborted=1"));
+
+        for ms in 1..=100 {
+            s.record_submit_latency(std::time::Duration::from_millis(ms));
+        }
+        s.record_deadline_miss();
+        assert_eq!(s.submit_latency(), Some((51_000, 99_000, 100_000)));
+        assert!(s.report(5).contains("submit_latency p50=51.0ms p99=99.0ms max=100.0ms deadline_missed=1"));
+    }
+}
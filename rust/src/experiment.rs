@@ -0,0 +1,350 @@
+//! Time-boxed experiments
+//! `pm_bot experiment start <hours> <name> KEY=VALUE...` records a temporary override of the
+//! risk and sizing tunables. While it runs, fills are attributed to the `exp-<name>` strategy
+//! tag so its P&L stays apart from the base config's; at the deadline the running bot reverts
+//! to the base config on its own, journals the experiment's results and prints them
+
+use crate::risk_guard::RiskGuardConfig;
+use crate::settings::Config;
+use crate::strategy::{is_valid_tag, strategy_ledger, StrategyLedger};
+use crate::timestamp;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// The experiment in force (absent when running on the base config)
+pub const EXPERIMENT_FILE: &str = "experiment.json";
+
+/// One line per finished experiment
+pub const EXPERIMENT_RESULTS_FILE: &str = "experiment_results.jsonl";
+
+/// How often the running bot checks the deadline (and picks up `experiment stop`)
+pub const EXPERIMENT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Longest an override may stay in force
+pub const MAX_EXPERIMENT_HOURS: f64 = 72.0;
+
+pub const USAGE: &str = "usage:\n  \
+    pm_bot experiment start <hours> <name> KEY=VALUE [KEY=VALUE...]\n  \
+    pm_bot experiment status\n  \
+    pm_bot experiment stop";
+
+// ============================================================================
+// Experiment
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Experiment {
+    pub name: String,
+    pub started_ts: u64,
+    pub ends_ts: u64,
+    /// Env var name -> value, applied on top of the base config
+    pub overrides: BTreeMap<String, String>,
+}
+
+impl Experiment {
+    /// Strategy tag the experiment's fills are attributed to
+    pub fn tag(&self) -> String {
+        format!("exp-{}", self.name)
+    }
+
+    pub fn is_over(&self, now: u64) -> bool {
+        now >= self.ends_ts
+    }
+
+    /// `base` with this experiment's overrides applied
+    pub fn apply(&self, base: &Config) -> Result<Config> {
+        let mut cfg = base.clone();
+        for (key, value) in &self.overrides {
+            cfg.apply_override(key, value)?;
+        }
+        Ok(cfg)
+    }
+}
+
+/// Parse the arguments after `pm_bot experiment start`; overrides are validated against `base`
+pub fn parse_start(args: &[String], base: &Config, now: u64) -> Result<Experiment> {
+    let (Some(hours), Some(name)) = (args.first(), args.get(1)) else {
+        anyhow::bail!("{}", USAGE);
+    };
+    let hours: f64 = hours.parse().with_context(|| format!("'{}' is not a number of hours", hours))?;
+    if !(hours > 0.0 && hours <= MAX_EXPERIMENT_HOURS) {
+        anyhow::bail!("experiment length must be between 0 and {} hours", MAX_EXPERIMENT_HOURS);
+    }
+    // "exp-" plus the name must still be a valid strategy tag
+    if name.len() > 28 || !is_valid_tag(name) {
+        anyhow::bail!("experiment name '{}' must be up to 28 letters, digits, '-' or '_'", name);
+    }
+    let mut overrides = BTreeMap::new();
+    for kv in &args[2..] {
+        let Some((key, value)) = kv.split_once('=') else {
+            anyhow::bail!("'{}' is not KEY=VALUE\n{}", kv, USAGE);
+        };
+        overrides.insert(key.trim().to_ascii_uppercase(), value.trim().to_string());
+    }
+    if overrides.is_empty() {
+        anyhow::bail!("an experiment needs at least one override\n{}", USAGE);
+    }
+    let exp = Experiment {
+        name: name.clone(),
+        started_ts: now,
+        ends_ts: now + (hours * 3600.0) as u64,
+        overrides,
+    };
+    exp.apply(base)?;
+    Ok(exp)
+}
+
+pub fn load(path: &str) -> Option<Experiment> {
+    let data = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&data) {
+        Ok(exp) => Some(exp),
+        Err(e) => {
+            crate::console_eprintln!("⚠️ Ignoring unreadable {}: {}", path, e);
+            None
+        }
+    }
+}
+
+pub fn save(exp: &Experiment, path: &str) -> Result<()> {
+    let tmp = format!("{}.tmp", path);
+    fs::write(&tmp, serde_json::to_string_pretty(exp)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+// ============================================================================
+// Results
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentResult {
+    pub name: String,
+    pub tag: String,
+    pub started_ts: u64,
+    pub ended_ts: u64,
+    pub overrides: BTreeMap<String, String>,
+    pub fills: usize,
+    pub volume_usd: f64,
+    pub realized_pnl: f64,
+    /// Positions opened under the experiment and still held when it ended
+    pub open_positions: usize,
+    pub open_cost: f64,
+}
+
+/// The experiment's book as of `now`
+pub fn results(exp: &Experiment, ledger: &StrategyLedger, now: u64) -> ExperimentResult {
+    let book = ledger.book(&exp.tag()).unwrap_or_default();
+    ExperimentResult {
+        name: exp.name.clone(),
+        tag: exp.tag(),
+        started_ts: exp.started_ts,
+        ended_ts: now.min(exp.ends_ts.max(exp.started_ts)),
+        overrides: exp.overrides.clone(),
+        fills: book.fills,
+        volume_usd: book.volume_usd,
+        realized_pnl: book.realized_pnl,
+        open_positions: book.open_positions(),
+        open_cost: book.open_cost(),
+    }
+}
+
+pub fn render_result(r: &ExperimentResult) -> String {
+    let overrides: Vec<String> = r.overrides.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    format!(
+        "experiment {} ({:.1}h, {})\n  fills={} volume=${:.2} realized={:+.2} open={} (${:.2} cost)\n",
+        r.name, r.ended_ts.saturating_sub(r.started_ts) as f64 / 3600.0, overrides.join(" "),
+        r.fills, r.volume_usd, r.realized_pnl, r.open_positions, r.open_cost
+    )
+}
+
+/// Journal the experiment's results and remove the experiment file
+pub fn finish(exp: &Experiment, ledger: &StrategyLedger, path: &str, results_path: &str, now: u64) -> ExperimentResult {
+    let result = results(exp, ledger, now);
+    match serde_json::to_string(&result) {
+        Ok(line) => match OpenOptions::new().append(true).create(true).open(results_path) {
+            Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+            Err(e) => crate::console_eprintln!("⚠️ Experiment results write failed: {}", e),
+        },
+        Err(e) => crate::console_eprintln!("⚠️ Experiment results encode failed: {}", e),
+    }
+    let _ = fs::remove_file(path);
+    result
+}
+
+// ============================================================================
+// Automatic Revert
+// ============================================================================
+
+/// Risk config waiting for the order worker to swap it in (experiment revert, session switch)
+static PENDING_RISK_CONFIG: Mutex<Option<RiskGuardConfig>> = Mutex::new(None);
+
+/// Called by the order worker before each order
+pub fn take_pending_risk_config() -> Option<RiskGuardConfig> {
+    PENDING_RISK_CONFIG.lock().ok()?.take()
+}
+
+/// Have the order worker swap in `config` before its next order
+pub fn queue_risk_config(config: RiskGuardConfig) {
+    if let Ok(mut pending) = PENDING_RISK_CONFIG.lock() {
+        *pending = Some(config);
+    }
+}
+
+/// Revert to `base` once the experiment is over: the ledger goes back to the base tag and cap,
+/// the order worker picks up the base risk config before its next order. With a session
+/// schedule, the session in force now applies on top of `base`
+fn revert(exp: &Experiment, base: &Config) {
+    let result = finish(exp, strategy_ledger(), EXPERIMENT_FILE, EXPERIMENT_RESULTS_FILE, timestamp::unix_secs());
+    let base = crate::session::config_now(base);
+    strategy_ledger().retag(&base.strategy_tag, base.strategy_cap());
+    queue_risk_config(base.risk_guard_config());
+    crate::console_println!("🧪 Experiment over, reverted to base config\n{}", render_result(&result));
+}
+
+/// Wait out the experiment, then revert. The file is re-read on every check so
+/// `experiment stop` (which moves the deadline to now) takes effect without a restart
+pub fn spawn_expiry_task(exp: Experiment, base: Config) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPERIMENT_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let current = load(EXPERIMENT_FILE).filter(|e| e.name == exp.name);
+            let ends_ts = current.as_ref().map(|e| e.ends_ts).unwrap_or(0);
+            if timestamp::unix_secs() >= ends_ts {
+                revert(&Experiment { ends_ts: ends_ts.max(exp.started_ts), ..exp.clone() }, &base);
+                return;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    fn base() -> Config {
+        Config {
+            private_key: String::new(),
+            funder_address: String::new(),
+            wss_url: String::new(),
+            wss_urls: Vec::new(),
+            enable_trading: false,
+            mock_trading: true,
+            shadow_trading: false,
+            adaptive_offset: false,
+            maker_rebate_bps: 0.0,
+            strategy_tag: "base".into(),
+            strategy_max_open_usd: 0.0,
+            tui: false,
+            log_max_mb: 100,
+            metadata_retention_hours: 24,
+            cb_large_trade_shares: 1500.0,
+            cb_consecutive_trigger: 2,
+            cb_sequence_window_secs: 30,
+            cb_min_depth_usd: 200.0,
+            cb_trip_duration_secs: 120,
+            impact_max_depth_fraction: 0.0,
+            impact_depth_levels: 5,
+            slippage_budget: 0.0,
+            signal_max_age_ms: 0,
+            submit_deadline_ms: 0,
+            latency_mode: false,
+            ack_budget_ms: 0,
+            book_age_budget_ms: 0,
+            depth_snapshot_secs: 0,
+            flatten: None,
+            rr_min_ratio: 0.0,
+            rr_take_profit_pct: 0.10,
+            probe_shares: 0.0,
+            archive: None,
+            trade_sync_secs: 0,
+            exit_book_feed: false,
+            conflict: None,
+            cluster: None,
+            cash_reserve_pct: 0.0,
+            cost_budget_per_share: 0.0,
+            cost_budget_entry_share: 0.5,
+            min_order_usd: 0.0,
+            min_order_shares: 0.0,
+            lift_to_minimum: true,
+            size_jitter_pct: 0.0,
+            price_jitter_ticks: 0,
+            remediation: Default::default(),
+            health_file: None,
+            health_max_silence_secs: 600,
+            sessions: None,
+            process_role: Default::default(),
+            feed_socket: "pm_bot_feed.sock".into(),
+            settlement_check_secs: 1800,
+            settlement_lag_secs: 7200,
+            replay_buffer_mins: 0,
+            remote_config: None,
+            quote_surge: None,
+            risk_loss_limit_usd: None,
+            market_quality: None,
+            compliance: None,
+            max_capital_at_risk_usd: None,
+            taker_flow: None,
+            live_warmup: None,
+            size_ramp: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_and_apply() {
+        let exp = parse_start(&args("6 looser cb_min_depth_usd=50 STRATEGY_MAX_OPEN_USD=25"), &base(), 1_000).unwrap();
+        assert_eq!(exp.ends_ts, 1_000 + 6 * 3600);
+        assert_eq!(exp.tag(), "exp-looser");
+        let cfg = exp.apply(&base()).unwrap();
+        assert_eq!(cfg.cb_min_depth_usd, 50.0);
+        assert_eq!(cfg.strategy_cap(), Some(25.0));
+
+        assert!(parse_start(&args("6 looser"), &base(), 0).is_err());
+        assert!(parse_start(&args("100 looser CB_MIN_DEPTH_USD=50"), &base(), 0).is_err());
+        assert!(parse_start(&args("6 looser ENABLE_TRADING=true"), &base(), 0).is_err());
+        assert!(parse_start(&args("6 looser CB_MIN_DEPTH_USD=lots"), &base(), 0).is_err());
+        assert!(parse_start(&args("6 bad/name CB_MIN_DEPTH_USD=50"), &base(), 0).is_err());
+    }
+
+    #[test]
+    fn test_finish_reports_only_experiment_fills() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("pm_experiment_{}.json", std::process::id()));
+        let results_path = dir.join(format!("pm_experiment_results_{}.jsonl", std::process::id()));
+        let (path, results_path) = (path.to_str().unwrap(), results_path.to_str().unwrap());
+        let _ = fs::remove_file(results_path);
+
+        let exp = parse_start(&args("1 tight CB_CONSECUTIVE_TRIGGER=1"), &base(), 1_000).unwrap();
+        save(&exp, path).unwrap();
+        assert_eq!(load(path), Some(exp.clone()));
+
+        let ledger = StrategyLedger::new("base", None, None);
+        ledger.record("t0", true, 10.0, 0.5);
+        ledger.retag(&exp.tag(), None);
+        ledger.record("t1", true, 100.0, 0.40);
+        ledger.record("t1", false, 50.0, 0.50);
+
+        let r = finish(&exp, &ledger, path, results_path, 5_000);
+        assert_eq!((r.fills, r.open_positions, r.ended_ts), (2, 1, 4_600));
+        assert!((r.realized_pnl - 5.0).abs() < 1e-9);
+        assert!((r.open_cost - 20.0).abs() < 1e-9);
+        assert!(load(path).is_none());
+        let journaled: ExperimentResult = serde_json::from_str(fs::read_to_string(results_path).unwrap().trim()).unwrap();
+        assert_eq!(journaled, r);
+        assert!(render_result(&r).contains("fills=2 volume=$65.00 realized=+5.00 open=1 ($20.00 cost)"));
+        let _ = fs::remove_file(results_path);
+    }
+}
@@ -0,0 +1,226 @@
+//! Feed gap detector
+//! Inter-message gaps per WebSocket provider: a histogram since startup plus the largest gaps
+//! of the last 24h with when they ended, reported through the diagnostics dump. The log
+//! subscription only carries the whale's fills, so long gaps are either a quiet whale or a
+//! dead feed; gaps that spanned a reconnect are marked so the two can be told apart
+
+use crate::timestamp::Timestamp;
+use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Upper bounds of the histogram buckets (the last bucket is everything above)
+pub const GAP_BUCKETS: [Duration; 5] = [
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+    Duration::from_secs(60),
+    Duration::from_secs(300),
+];
+
+/// Gaps shorter than this are only counted in the histogram
+pub const NOTABLE_GAP: Duration = Duration::from_secs(30);
+
+/// Rolling window the largest gaps are reported over
+pub const GAP_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Largest gaps listed per feed
+const TOP_GAPS: usize = 5;
+
+/// Bound on notable gaps kept per feed
+const MAX_NOTABLE: usize = 1_000;
+
+// ============================================================================
+// Records
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gap {
+    /// Unix seconds when the gap ended (next message arrived)
+    pub ended_ts: u64,
+    pub length: Duration,
+    /// The connection dropped and was re-established inside the gap
+    pub reconnect: bool,
+}
+
+#[derive(Debug, Default)]
+struct FeedState {
+    last_at: Option<Instant>,
+    reconnect_pending: bool,
+    messages: u64,
+    reconnects: u64,
+    histogram: [u64; GAP_BUCKETS.len() + 1],
+    notable: VecDeque<(Instant, Gap)>,
+}
+
+/// Histogram bucket for a gap
+pub fn bucket_for(gap: Duration) -> usize {
+    GAP_BUCKETS.iter().position(|b| gap < *b).unwrap_or(GAP_BUCKETS.len())
+}
+
+fn bucket_label(i: usize) -> String {
+    match GAP_BUCKETS.get(i) {
+        Some(b) => format!("<{}s", b.as_secs()),
+        None => format!(">={}s", GAP_BUCKETS[GAP_BUCKETS.len() - 1].as_secs()),
+    }
+}
+
+// ============================================================================
+// Detector
+// ============================================================================
+
+#[derive(Default)]
+pub struct FeedGaps {
+    feeds: Mutex<FxHashMap<String, FeedState>>,
+}
+
+impl FeedGaps {
+    /// A message arrived on `feed`
+    pub fn record_message(&self, feed: &str) {
+        self.record_message_at(feed, Instant::now(), Timestamp::now());
+    }
+
+    fn record_message_at(&self, feed: &str, now: Instant, wall: Timestamp) {
+        let Ok(mut feeds) = self.feeds.lock() else { return };
+        let st = feeds.entry(feed.to_string()).or_default();
+        st.messages += 1;
+        if let Some(last) = st.last_at {
+            let length = now.duration_since(last);
+            st.histogram[bucket_for(length)] += 1;
+            if length >= NOTABLE_GAP {
+                let ended_ts = wall.as_secs();
+                if st.notable.len() >= MAX_NOTABLE {
+                    st.notable.pop_front();
+                }
+                st.notable.push_back((now, Gap { ended_ts, length, reconnect: st.reconnect_pending }));
+            }
+        }
+        while st.notable.front().is_some_and(|(at, _)| now.duration_since(*at) > GAP_WINDOW) {
+            st.notable.pop_front();
+        }
+        st.last_at = Some(now);
+        st.reconnect_pending = false;
+    }
+
+    /// The connection to `feed` dropped; the gap until its next message spans a reconnect
+    pub fn record_disconnect(&self, feed: &str) {
+        if let Ok(mut feeds) = self.feeds.lock() {
+            let st = feeds.entry(feed.to_string()).or_default();
+            st.reconnects += 1;
+            st.reconnect_pending = true;
+        }
+    }
+
+    /// Time since the last message of each feed
+    pub fn message_ages(&self) -> Vec<(String, Duration)> {
+        let Ok(feeds) = self.feeds.lock() else { return Vec::new() };
+        let mut ages: Vec<_> = feeds.iter()
+            .filter_map(|(name, st)| Some((name.clone(), st.last_at?.elapsed())))
+            .collect();
+        ages.sort();
+        ages
+    }
+
+    /// Largest gaps on `feed` within the rolling window, longest first
+    pub fn largest_gaps(&self, feed: &str, n: usize) -> Vec<Gap> {
+        let Ok(feeds) = self.feeds.lock() else { return Vec::new() };
+        let Some(st) = feeds.get(feed) else { return Vec::new() };
+        let mut gaps: Vec<Gap> = st.notable.iter().map(|(_, g)| *g).collect();
+        gaps.sort_by_key(|g| std::cmp::Reverse(g.length));
+        gaps.truncate(n);
+        gaps
+    }
+
+    /// Dump lines: per feed message count, histogram and largest recent gaps
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let names: Vec<String> = match self.feeds.lock() {
+            Ok(feeds) => feeds.keys().cloned().collect(),
+            Err(_) => return out,
+        };
+        for name in names {
+            let Ok(feeds) = self.feeds.lock() else { return out };
+            let Some(st) = feeds.get(&name) else { continue };
+            let _ = write!(out, "  feed {} messages={} reconnects={} gaps", name, st.messages, st.reconnects);
+            for (i, n) in st.histogram.iter().enumerate().filter(|(_, n)| **n > 0) {
+                let _ = write!(out, " {}:{}", bucket_label(i), n);
+            }
+            out.push('\n');
+            drop(feeds);
+            for g in self.largest_gaps(&name, TOP_GAPS) {
+                let ended = chrono::DateTime::from_timestamp(g.ended_ts as i64, 0)
+                    .map(|t| t.format("%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    out, "    gap {:.0}s ended {} UTC{}",
+                    g.length.as_secs_f64(), ended, if g.reconnect { " (reconnect)" } else { "" }
+                );
+            }
+        }
+        out
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_FEED_GAPS: OnceLock<FeedGaps> = OnceLock::new();
+
+/// Get the global feed gap detector
+pub fn feed_gaps() -> &'static FeedGaps {
+    GLOBAL_FEED_GAPS.get_or_init(FeedGaps::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_and_largest_gaps() {
+        let g = FeedGaps::default();
+        let t0 = Instant::now();
+        let wall = Timestamp::from_secs(1_700_000_000);
+        let at = |s: u64| (t0 + Duration::from_secs(s), wall + Duration::from_secs(s));
+
+        let (i, w) = at(0);
+        g.record_message_at("alchemy", i, w);
+        let (i, w) = at(2);
+        g.record_message_at("alchemy", i, w);
+        let (i, w) = at(47);
+        g.record_message_at("alchemy", i, w);
+        g.record_disconnect("alchemy");
+        let (i, w) = at(447);
+        g.record_message_at("alchemy", i, w);
+
+        let gaps = g.largest_gaps("alchemy", 5);
+        assert_eq!(gaps.len(), 2);
+        assert_eq!(gaps[0].length, Duration::from_secs(400));
+        assert!(gaps[0].reconnect && !gaps[1].reconnect);
+        assert_eq!(gaps[1].ended_ts, 1_700_000_047);
+
+        let report = g.report();
+        assert!(report.contains("feed alchemy messages=4 reconnects=1 gaps <5s:1 <60s:1 >=300s:1"));
+        assert!(report.contains("gap 400s ended 11-14 22:20:47 UTC (reconnect)"));
+    }
+
+    #[test]
+    fn test_window_evicts_old_gaps() {
+        let g = FeedGaps::default();
+        let t0 = Instant::now();
+        g.record_message_at("f", t0, Timestamp::now());
+        g.record_message_at("f", t0 + Duration::from_secs(60), Timestamp::now());
+        assert_eq!(g.largest_gaps("f", 5).len(), 1);
+        g.record_message_at("f", t0 + GAP_WINDOW + Duration::from_secs(120), Timestamp::now());
+        let gaps = g.largest_gaps("f", 5);
+        assert_eq!(gaps.len(), 1);
+        assert!(gaps[0].length > GAP_WINDOW);
+        assert_eq!(bucket_for(Duration::from_millis(999)), 0);
+    }
+}
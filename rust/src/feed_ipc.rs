@@ -0,0 +1,347 @@
+//! Feed / executor process split
+//! With `PROCESS_ROLE=feed` a process only holds the whale WebSocket and republishes every
+//! parsed fill on a local unix socket (`FEED_SOCKET`); `PROCESS_ROLE=executor` runs everything
+//! else and takes its signals from that socket instead of the WebSocket. Either side can crash
+//! or be redeployed while the other keeps running: the executor reconnects, and the feed serves
+//! whichever executor connected last so an overlapping redeploy never copies a fill twice.
+//! The wire format is JSON lines (`FeedMessage`), opened by a `hello` carrying `FEED_IPC_VERSION`
+
+use crate::models::{OrderInfo, ParsedEvent};
+use crate::timestamp::Timestamp;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Bumped on any incompatible change to `FeedMessage`
+pub const FEED_IPC_VERSION: u32 = 1;
+
+/// Sent by the feed while no fill arrives, so the executor can tell quiet from dead
+pub const FEED_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The executor drops the connection after this long without a line
+pub const FEED_READ_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Fills queued for a slow executor before the oldest are skipped
+const PUBLISH_BUFFER: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProcessRole {
+    /// Feed and executor in one process (no socket)
+    #[default]
+    All,
+    /// Whale WebSocket only, fills published on FEED_SOCKET
+    Feed,
+    /// Everything but the WebSocket, fills read from FEED_SOCKET
+    Executor,
+}
+
+impl ProcessRole {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "all" => Some(Self::All),
+            "feed" => Some(Self::Feed),
+            "executor" => Some(Self::Executor),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Feed => "feed",
+            Self::Executor => "executor",
+        }
+    }
+}
+
+// ============================================================================
+// Wire Format
+// ============================================================================
+
+/// One whale fill as parsed from the chain log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhaleFill {
+    pub block_number: u64,
+    pub tx_hash: String,
+    pub log_index: u64,
+    /// "BUY_FILL" / "SELL_FILL"
+    pub order_type: String,
+    pub token_id: String,
+    pub usd_value: f64,
+    pub shares: f64,
+    pub price: f64,
+    /// Arrival at the feed process; feeds that predate the field send none (taken as now)
+    #[serde(default, skip_serializing_if = "Timestamp::is_zero")]
+    pub received_at: Timestamp,
+}
+
+impl From<&ParsedEvent> for WhaleFill {
+    fn from(evt: &ParsedEvent) -> Self {
+        Self {
+            block_number: evt.block_number,
+            tx_hash: evt.tx_hash.clone(),
+            log_index: evt.log_index,
+            order_type: evt.order.order_type.clone(),
+            token_id: evt.order.clob_token_id.to_string(),
+            usd_value: evt.order.usd_value,
+            shares: evt.order.shares,
+            price: evt.order.price_per_share,
+            received_at: evt.received_at,
+        }
+    }
+}
+
+impl WhaleFill {
+    pub fn into_event(self) -> ParsedEvent {
+        ParsedEvent {
+            received_at: self.received_at.or_now(),
+            block_number: self.block_number,
+            tx_hash: self.tx_hash,
+            log_index: self.log_index,
+            order: OrderInfo {
+                order_type: self.order_type,
+                clob_token_id: self.token_id.into(),
+                usd_value: self.usd_value,
+                shares: self.shares,
+                price_per_share: self.price,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeedMessage {
+    /// First line on every connection
+    Hello { version: u32, pid: u32 },
+    Fill(WhaleFill),
+    Heartbeat,
+}
+
+impl FeedMessage {
+    /// One line, newline included
+    pub fn encode(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+        line
+    }
+
+    pub fn decode(line: &str) -> Result<Self> {
+        serde_json::from_str(line.trim_end()).with_context(|| format!("bad feed line: {}", line.trim_end()))
+    }
+}
+
+// ============================================================================
+// Feed Side
+// ============================================================================
+
+/// Hands parsed fills to the connected executor
+#[derive(Clone)]
+pub struct FeedPublisher {
+    tx: broadcast::Sender<Arc<str>>,
+    /// Bumped on each new executor connection; older ones are closed
+    takeover: Arc<watch::Sender<u64>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Default for FeedPublisher {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(PUBLISH_BUFFER).0,
+            takeover: Arc::new(watch::channel(0).0),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl FeedPublisher {
+    /// Queue a fill for the executor; false (and counted) when none is connected
+    pub fn publish(&self, evt: &ParsedEvent) -> bool {
+        let line: Arc<str> = FeedMessage::Fill(WhaleFill::from(evt)).encode().into();
+        if self.tx.send(line).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    /// Fills published while no executor was connected
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Listen on `path` and stream fills to the most recently connected executor. A stale socket
+/// file from a previous run is replaced
+#[cfg(unix)]
+pub fn spawn_feed_server(path: String, publisher: FeedPublisher) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let _ = std::fs::remove_file(&path);
+        let listener = match tokio::net::UnixListener::bind(&path) {
+            Ok(l) => l,
+            Err(e) => {
+                crate::console_eprintln!("⚠️ Feed socket {} bind failed: {}", path, e);
+                return;
+            }
+        };
+        loop {
+            let Ok((stream, _)) = listener.accept().await else { continue };
+            publisher.takeover.send_modify(|generation| *generation += 1);
+            // Subscribe before the hello so nothing published after the executor sees it is lost
+            let rx = publisher.tx.subscribe();
+            let takeover = publisher.takeover.subscribe();
+            tokio::spawn(serve_executor(stream, rx, takeover));
+            crate::console_println!("🔗 Executor connected to {}", path);
+        }
+    })
+}
+
+#[cfg(unix)]
+async fn serve_executor(mut stream: tokio::net::UnixStream, mut rx: broadcast::Receiver<Arc<str>>, mut takeover: watch::Receiver<u64>) {
+    use tokio::io::AsyncWriteExt;
+    let hello = FeedMessage::Hello { version: FEED_IPC_VERSION, pid: std::process::id() }.encode();
+    if stream.write_all(hello.as_bytes()).await.is_err() {
+        return;
+    }
+    let heartbeat = FeedMessage::Heartbeat.encode();
+    let start = tokio::time::Instant::now() + FEED_HEARTBEAT_INTERVAL;
+    let mut interval = tokio::time::interval_at(start, FEED_HEARTBEAT_INTERVAL);
+    loop {
+        let line: Arc<str> = tokio::select! {
+            biased;
+            // A newer executor took over
+            _ = takeover.changed() => return,
+            msg = rx.recv() => match msg {
+                Ok(line) => line,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    crate::console_eprintln!("⚠️ Executor too slow, {} whale fills skipped", n);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            _ = interval.tick() => heartbeat.as_str().into(),
+        };
+        if stream.write_all(line.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+// ============================================================================
+// Executor Side
+// ============================================================================
+
+#[cfg(unix)]
+pub struct FeedClient {
+    lines: tokio::io::Lines<tokio::io::BufReader<tokio::net::UnixStream>>,
+    /// Process id of the feed
+    pub feed_pid: u32,
+}
+
+#[cfg(unix)]
+impl FeedClient {
+    /// Connect and check the feed speaks this `FEED_IPC_VERSION`
+    pub async fn connect(path: &str) -> Result<Self> {
+        use tokio::io::AsyncBufReadExt;
+        let stream = tokio::net::UnixStream::connect(path).await
+            .with_context(|| format!("no feed process on {}", path))?;
+        let mut client = Self { lines: tokio::io::BufReader::new(stream).lines(), feed_pid: 0 };
+        match client.read().await? {
+            FeedMessage::Hello { version, pid } if version == FEED_IPC_VERSION => {
+                client.feed_pid = pid;
+                Ok(client)
+            }
+            FeedMessage::Hello { version, .. } => {
+                anyhow::bail!("feed speaks IPC version {}, this executor {}", version, FEED_IPC_VERSION)
+            }
+            other => anyhow::bail!("feed opened with {:?} instead of hello", other),
+        }
+    }
+
+    async fn read(&mut self) -> Result<FeedMessage> {
+        let line = tokio::time::timeout(FEED_READ_TIMEOUT, self.lines.next_line()).await
+            .map_err(|_| anyhow::anyhow!("feed silent for {}s", FEED_READ_TIMEOUT.as_secs()))??
+            .ok_or_else(|| anyhow::anyhow!("feed closed the connection"))?;
+        FeedMessage::decode(&line)
+    }
+
+    /// Next line from the feed: Some(fill), or None for a heartbeat. Err once the feed closed,
+    /// went silent or handed the stream to a newer executor
+    pub async fn next(&mut self) -> Result<Option<ParsedEvent>> {
+        match self.read().await? {
+            FeedMessage::Fill(fill) => Ok(Some(fill.into_event())),
+            FeedMessage::Heartbeat => Ok(None),
+            FeedMessage::Hello { .. } => anyhow::bail!("unexpected hello mid-stream"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(log_index: u64) -> ParsedEvent {
+        WhaleFill {
+            block_number: 70_000_000,
+            tx_hash: "0xabc".into(),
+            log_index,
+            order_type: "BUY_FILL".into(),
+            token_id: "12345".into(),
+            usd_value: 520.0,
+            shares: 1_000.0,
+            price: 0.52,
+            received_at: Timestamp::from_secs(1_767_384_000),
+        }.into_event()
+    }
+
+    #[test]
+    fn test_wire_roundtrip() {
+        let line = FeedMessage::Fill(WhaleFill::from(&event(7))).encode();
+        assert!(line.starts_with(r#"{"type":"fill","block_number":70000000,"tx_hash":"0xabc","log_index":7"#), "{}", line);
+        let FeedMessage::Fill(fill) = FeedMessage::decode(&line).unwrap() else { panic!("not a fill") };
+        let evt = fill.into_event();
+        assert_eq!(evt.intent_id(), "0xabc:7");
+        assert_eq!(evt.received_at, Timestamp::from_secs(1_767_384_000));
+        assert_eq!(&*evt.order.clob_token_id, "12345");
+        assert_eq!(FeedMessage::decode(r#"{"type":"heartbeat"}"#).unwrap(), FeedMessage::Heartbeat);
+        assert!(FeedMessage::decode(r#"{"type":"fill"}"#).is_err());
+        assert_eq!(ProcessRole::parse(" Executor"), Some(ProcessRole::Executor));
+        assert_eq!(ProcessRole::parse("both"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_latest_executor_takes_over() {
+        let path = std::env::temp_dir().join(format!("pm_feed_test_{}.sock", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        let publisher = FeedPublisher::default();
+        assert!(!publisher.publish(&event(0)));
+        let server = spawn_feed_server(path.clone(), publisher.clone());
+        // Let the server bind
+        let mut first = loop {
+            match FeedClient::connect(&path).await {
+                Ok(c) => break c,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        assert_eq!(first.feed_pid, std::process::id());
+        assert!(publisher.publish(&event(1)));
+        assert_eq!(first.next().await.unwrap().unwrap().log_index, 1);
+
+        let mut second = FeedClient::connect(&path).await.unwrap();
+        assert!(first.next().await.is_err());
+        assert!(publisher.publish(&event(2)));
+        assert_eq!(second.next().await.unwrap().unwrap().log_index, 2);
+        assert_eq!(publisher.dropped(), 1);
+
+        server.abort();
+        let _ = std::fs::remove_file(&path);
+    }
+}
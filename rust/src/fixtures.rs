@@ -0,0 +1,179 @@
+//! Recorded API responses for offline tests
+//! Gamma and CLOB reads go through `JsonSource`: `LiveSource` in the bot, `FixtureSource` in
+//! tests, which answers each URL from a file under `fixtures/` recorded from the real API.
+//! `update_fixtures` re-records every URL listed in `fixtures/urls.txt`
+
+use crate::depth_history::{parse_book, BookFetcher};
+use crate::settings::CLOB_API_BASE;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Fixture directory relative to the crate root
+pub const FIXTURE_DIR: &str = "fixtures";
+
+/// One URL per line; blank lines and # comments are skipped
+pub const FIXTURE_URLS_FILE: &str = "urls.txt";
+
+/// Per-request timeout when recording
+const RECORD_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ============================================================================
+// Sources
+// ============================================================================
+
+#[async_trait::async_trait]
+pub trait JsonSource: Send + Sync {
+    /// Parsed body of a successful GET, None on any failure
+    async fn get_json(&self, url: &str) -> Option<Value>;
+}
+
+pub struct LiveSource {
+    pub client: reqwest::Client,
+    pub timeout: Duration,
+}
+
+#[async_trait::async_trait]
+impl JsonSource for LiveSource {
+    async fn get_json(&self, url: &str) -> Option<Value> {
+        let resp = self.client.get(url).timeout(self.timeout).send().await.ok()?;
+        if !resp.status().is_success() {
+            return None;
+        }
+        resp.json().await.ok()
+    }
+}
+
+/// Answers from recorded files; a URL without a fixture behaves like a failed request
+pub struct FixtureSource {
+    pub dir: PathBuf,
+}
+
+impl FixtureSource {
+    /// The crate's own `fixtures/` directory
+    pub fn crate_fixtures() -> Self {
+        Self { dir: Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE_DIR) }
+    }
+
+    pub fn load(&self, url: &str) -> Option<Value> {
+        let data = fs::read_to_string(self.dir.join(fixture_name(url))).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+}
+
+#[async_trait::async_trait]
+impl JsonSource for FixtureSource {
+    async fn get_json(&self, url: &str) -> Option<Value> {
+        self.load(url)
+    }
+}
+
+#[async_trait::async_trait]
+impl BookFetcher for FixtureSource {
+    async fn fetch_book(&self, token_id: &str) -> Option<(Vec<(f64, f64)>, Vec<(f64, f64)>)> {
+        Some(parse_book(&self.load(&book_url(token_id))?))
+    }
+}
+
+pub fn book_url(token_id: &str) -> String {
+    format!("{}/book?token_id={}", CLOB_API_BASE, token_id)
+}
+
+/// File name for a URL: scheme dropped, anything outside [A-Za-z0-9._=-] replaced by '_'
+/// ("https://clob.polymarket.com/book?token_id=1" -> "clob.polymarket.com_book_token_id=1.json")
+pub fn fixture_name(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, r)| r);
+    let mut name: String = rest.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '=' | '-') { c } else { '_' })
+        .collect();
+    name.push_str(".json");
+    name
+}
+
+// ============================================================================
+// Recording
+// ============================================================================
+
+pub fn fixture_urls(dir: &Path) -> Result<Vec<String>> {
+    let path = dir.join(FIXTURE_URLS_FILE);
+    let data = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    Ok(data.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Re-record every listed URL (blocking). Returns the number of fixtures written
+pub fn record_all(dir: &Path, client: &reqwest::blocking::Client) -> Result<usize> {
+    let mut written = 0;
+    for url in fixture_urls(dir)? {
+        let value: Value = client.get(&url).timeout(RECORD_TIMEOUT).send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.json())
+            .with_context(|| format!("fetching {}", url))?;
+        let path = dir.join(fixture_name(&url));
+        fs::write(&path, serde_json::to_string_pretty(&value)? + "\n")
+            .with_context(|| format!("writing {}", path.display()))?;
+        crate::console_println!("📼 {} -> {}", url, path.display());
+        written += 1;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_metadata;
+
+    /// "Yes" and "No" of the resolved market in fixtures/, and a token of an open market with a book
+    const YES_TOKEN: &str = "21742633143463906290569050155826241533067272736897614950488156847949938836455";
+    const NO_TOKEN: &str = "48331043336612883890938759509493159234755048973500640148014422747788308965732";
+    const BOOK_TOKEN: &str = "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+
+    #[test]
+    fn test_fixture_name() {
+        assert_eq!(fixture_name("https://clob.polymarket.com/book?token_id=1"), "clob.polymarket.com_book_token_id=1.json");
+        assert_eq!(fixture_name("https://gamma-api.polymarket.com/markets?clob_token_ids=7&x=y"), "gamma-api.polymarket.com_markets_clob_token_ids=7_x=y.json");
+    }
+
+    #[test]
+    fn test_every_listed_url_is_recorded() {
+        let source = FixtureSource::crate_fixtures();
+        for url in fixture_urls(&source.dir).unwrap() {
+            assert!(source.load(&url).is_some(), "no fixture for {}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gamma_market_from_fixture() {
+        let source = FixtureSource::crate_fixtures();
+        let market = source.get_json(&token_metadata::gamma_market_url(YES_TOKEN)).await.unwrap();
+        let tokens = token_metadata::parse_gamma_market(&market[0], 0);
+        assert_eq!(tokens.len(), 2);
+        assert_eq!((tokens[0].token_id.as_str(), tokens[0].outcome.as_str()), (YES_TOKEN, "Yes"));
+        assert_eq!(tokens[1].token_id, NO_TOKEN);
+        assert_eq!(tokens[0].tick_size, "0.01");
+        assert!(!tokens[0].condition_id.is_empty() && tokens[0].condition_id == tokens[1].condition_id);
+
+        assert_eq!(token_metadata::settlement_price(&market[0], NO_TOKEN), Some(0.0));
+        assert_eq!(token_metadata::fetch_settlement_from(YES_TOKEN, &source).await, Some(1.0));
+        // Not recorded: same as a failed request
+        assert_eq!(token_metadata::fetch_settlement_from(NO_TOKEN, &source).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_book_from_fixture() {
+        let source = FixtureSource::crate_fixtures();
+        let (bids, asks) = source.fetch_book(BOOK_TOKEN).await.unwrap();
+        assert_eq!(bids.iter().map(|l| l.0).reduce(f64::max), Some(0.52));
+        assert_eq!(asks.iter().map(|l| l.0).reduce(f64::min), Some(0.54));
+        assert!(source.fetch_book("unrecorded").await.is_none());
+    }
+}
@@ -0,0 +1,140 @@
+//! Flat-at-time-of-day policy
+//! From the entry cutoff no new positions are opened and held ones are sold with a concession
+//! below the best bid that grows linearly until the flat-by deadline. Entries resume at the
+//! resume time. All times are local wall clock (HH:MM) and may wrap past midnight
+
+use anyhow::{Context, Result};
+use chrono::{NaiveTime, Timelike};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// How often the exit worker re-prices remaining positions
+pub const FLATTEN_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+const SECS_PER_DAY: u32 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlattenPolicy {
+    /// No new entries from here on; exits start
+    pub cutoff: NaiveTime,
+    /// Everything should be sold by now
+    pub deadline: NaiveTime,
+    /// Entries allowed again
+    pub resume: NaiveTime,
+    /// Price below the best bid accepted at the deadline, on top of the usual 1 tick
+    pub max_concession: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlattenPhase {
+    Open,
+    /// Between cutoff and deadline; `progress` runs 0 -> 1
+    Exiting { progress: f64 },
+    /// Past the deadline until entries resume
+    Flat,
+}
+
+fn parse_hhmm(key: &str, value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").with_context(|| format!("{}='{}' is not HH:MM", key, value))
+}
+
+/// Seconds from `from` forward to `to`, wrapping at midnight
+fn secs_after(from: NaiveTime, to: NaiveTime) -> u32 {
+    (to.num_seconds_from_midnight() + SECS_PER_DAY - from.num_seconds_from_midnight()) % SECS_PER_DAY
+}
+
+impl FlattenPolicy {
+    pub fn parse(cutoff: &str, deadline: &str, resume: &str, max_concession: f64) -> Result<Self> {
+        let policy = Self {
+            cutoff: parse_hhmm("FLATTEN_ENTRY_CUTOFF", cutoff)?,
+            deadline: parse_hhmm("FLATTEN_BY", deadline)?,
+            resume: parse_hhmm("FLATTEN_RESUME", resume)?,
+            max_concession: max_concession.clamp(0.0, 0.5),
+        };
+        let (exit_len, quiet_len) = (secs_after(policy.cutoff, policy.deadline), secs_after(policy.cutoff, policy.resume));
+        if quiet_len == 0 || exit_len >= quiet_len {
+            anyhow::bail!("FLATTEN_BY must fall between FLATTEN_ENTRY_CUTOFF and FLATTEN_RESUME");
+        }
+        Ok(policy)
+    }
+
+    pub fn phase(&self, now: NaiveTime) -> FlattenPhase {
+        let since = secs_after(self.cutoff, now);
+        let exit_len = secs_after(self.cutoff, self.deadline);
+        if since >= secs_after(self.cutoff, self.resume) {
+            FlattenPhase::Open
+        } else if since < exit_len {
+            FlattenPhase::Exiting { progress: since as f64 / exit_len as f64 }
+        } else {
+            FlattenPhase::Flat
+        }
+    }
+
+    pub fn blocks_entries(&self, now: NaiveTime) -> bool {
+        self.phase(now) != FlattenPhase::Open
+    }
+
+    /// Sell limit `progress` of the way through the exit window, in whole cents
+    pub fn exit_price(&self, best_bid: f64, progress: f64) -> f64 {
+        let concession = 0.01 + progress.clamp(0.0, 1.0) * self.max_concession;
+        (((best_bid - concession) * 100.0 + 1e-9).floor() / 100.0).max(0.01)
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_FLATTEN: OnceLock<Option<FlattenPolicy>> = OnceLock::new();
+
+/// Set once at startup (None = no flatten schedule)
+pub fn init_flatten_policy(policy: Option<FlattenPolicy>) {
+    let _ = GLOBAL_FLATTEN.set(policy);
+}
+
+pub fn flatten_policy() -> Option<FlattenPolicy> {
+    GLOBAL_FLATTEN.get().copied().flatten()
+}
+
+/// True between today's cutoff and resume time
+pub fn entries_blocked_now() -> bool {
+    flatten_policy().is_some_and(|p| p.blocks_entries(chrono::Local::now().time()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_phases_wrap_midnight() {
+        let p = FlattenPolicy::parse("23:00", "00:00", "07:00", 0.05).unwrap();
+        assert_eq!(p.phase(t("22:59")), FlattenPhase::Open);
+        assert_eq!(p.phase(t("23:00")), FlattenPhase::Exiting { progress: 0.0 });
+        assert_eq!(p.phase(t("23:30")), FlattenPhase::Exiting { progress: 0.5 });
+        assert_eq!(p.phase(t("00:00")), FlattenPhase::Flat);
+        assert_eq!(p.phase(t("06:59")), FlattenPhase::Flat);
+        assert_eq!(p.phase(t("07:00")), FlattenPhase::Open);
+        assert!(p.blocks_entries(t("03:00")) && !p.blocks_entries(t("12:00")));
+    }
+
+    #[test]
+    fn test_exit_price_and_validation() {
+        let p = FlattenPolicy::parse("22:00", "22:30", "06:00", 0.05).unwrap();
+        assert_eq!(p.exit_price(0.50, 0.0), 0.49);
+        assert_eq!(p.exit_price(0.50, 0.5), 0.46);
+        assert_eq!(p.exit_price(0.50, 2.0), 0.44);
+        assert_eq!(p.exit_price(0.03, 1.0), 0.01);
+
+        assert!(FlattenPolicy::parse("22:00", "06:30", "06:00", 0.05).is_err());
+        assert!(FlattenPolicy::parse("22:00", "22:30", "22:00", 0.05).is_err());
+        assert!(FlattenPolicy::parse("10pm", "22:30", "06:00", 0.05).is_err());
+    }
+}
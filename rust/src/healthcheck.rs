@@ -0,0 +1,180 @@
+//! Health file and systemd notification
+//! A wedged bot keeps its PID but stops doing work. Every `HEALTH_INTERVAL` the components that
+//! always report (whale feed, stop-loss) are checked for freshness and the result is written
+//! to the health file for a container healthcheck. Under systemd (`Type=notify`) READY=1 is
+//! sent once the bot is trading and WATCHDOG=1 only while healthy, so `WatchdogSec=` restarts
+//! a stuck process; positions come back from the strategy ledger on the next start
+
+use crate::timestamp;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// How often health is evaluated, the file rewritten and the watchdog pinged
+pub const HEALTH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Components expected to report regularly. One that never reported (e.g. the stop-loss
+/// outside live trading) is not checked
+pub const WATCHED_COMPONENTS: &[&str] = &["ws", "stop_loss"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthPolicy {
+    /// Heartbeat file rewritten every interval (None = not written)
+    pub file: Option<String>,
+    /// A watched component silent for longer makes the bot unhealthy
+    pub max_silence: Duration,
+}
+
+// ============================================================================
+// Status
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthStatus {
+    /// Unix seconds of this evaluation; a container check compares it with the clock
+    pub ts: u64,
+    pub pid: u32,
+    pub healthy: bool,
+    /// Seconds since each watched component's last event
+    pub ages: BTreeMap<String, f64>,
+    /// Watched components silent for longer than the limit
+    pub stale: Vec<String>,
+    /// Components the supervisor has marked degraded
+    pub degraded: Vec<String>,
+}
+
+impl HealthStatus {
+    /// Judge component ages (as reported to diagnostics) against `max_silence`
+    pub fn evaluate(ages: &[(&'static str, Duration)], degraded: Vec<String>, max_silence: Duration, ts: u64) -> Self {
+        let watched: Vec<_> = ages.iter().filter(|(name, _)| WATCHED_COMPONENTS.contains(name)).collect();
+        let stale: Vec<String> = watched.iter()
+            .filter(|(_, age)| *age > max_silence)
+            .map(|(name, _)| name.to_string())
+            .collect();
+        Self {
+            ts,
+            pid: std::process::id(),
+            healthy: stale.is_empty(),
+            ages: watched.iter().map(|(name, age)| (name.to_string(), (age.as_secs_f64() * 10.0).round() / 10.0)).collect(),
+            stale,
+            degraded,
+        }
+    }
+
+    pub fn capture(max_silence: Duration) -> Self {
+        let ts = timestamp::unix_secs();
+        let degraded = crate::supervisor::health().degraded().into_iter().map(|(name, _, _)| name.to_string()).collect();
+        Self::evaluate(&crate::diagnostics::diagnostics().ages(), degraded, max_silence, ts)
+    }
+}
+
+/// Replace the health file in one step so a reader never sees half a status
+pub fn write_status(path: &str, status: &HealthStatus) -> std::io::Result<()> {
+    let tmp = format!("{}.tmp", path);
+    fs::write(&tmp, serde_json::to_string(status)? + "\n")?;
+    fs::rename(&tmp, path)
+}
+
+// ============================================================================
+// systemd
+// ============================================================================
+
+/// Whether systemd expects notifications (unit with `Type=notify` or `NotifyAccess=`)
+pub fn under_systemd() -> bool {
+    std::env::var_os("NOTIFY_SOCKET").is_some()
+}
+
+/// Send a state line ("READY=1", "WATCHDOG=1", "STATUS=...") to systemd; false when not
+/// running under it or the send failed
+#[cfg(unix)]
+pub fn sd_notify(state: &str) -> bool {
+    use std::os::unix::net::UnixDatagram;
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else { return false };
+    let Ok(sock) = UnixDatagram::unbound() else { return false };
+    match path.as_encoded_bytes().strip_prefix(b"@") {
+        // Abstract namespace socket
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            std::os::unix::net::SocketAddr::from_abstract_name(name)
+                .and_then(|addr| sock.send_to_addr(state.as_bytes(), &addr))
+                .is_ok()
+        }
+        #[cfg(not(target_os = "linux"))]
+        Some(_) => false,
+        None => sock.send_to(state.as_bytes(), &path).is_ok(),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn sd_notify(_state: &str) -> bool {
+    false
+}
+
+// ============================================================================
+// Task
+// ============================================================================
+
+/// Evaluate health every `HEALTH_INTERVAL`: rewrite the file, ping the watchdog while healthy
+/// and log each change between healthy and unhealthy
+pub fn spawn_health_task(policy: HealthPolicy) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEALTH_INTERVAL);
+        let mut was_healthy = true;
+        loop {
+            interval.tick().await;
+            let status = HealthStatus::capture(policy.max_silence);
+            if let Some(path) = &policy.file
+                && let Err(e) = write_status(path, &status) {
+                    crate::console_eprintln!("⚠️ Health file write failed: {}", e);
+                }
+            if status.healthy {
+                sd_notify("WATCHDOG=1");
+            }
+            if was_healthy && !status.healthy {
+                crate::console_eprintln!(
+                    "🚨 Unhealthy: {} silent for over {}s, watchdog pings withheld",
+                    status.stale.join(", "), policy.max_silence.as_secs()
+                );
+                sd_notify(&format!("STATUS=unhealthy: {} silent", status.stale.join(", ")));
+            } else if !was_healthy && status.healthy {
+                crate::console_println!("✅ Healthy again");
+                sd_notify("STATUS=trading");
+            }
+            was_healthy = status.healthy;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_watched_components_count() {
+        let max = Duration::from_secs(600);
+        let ages = [
+            ("ws", Duration::from_secs(12)),
+            ("stop_loss", Duration::from_secs(3)),
+            // Event-driven: may be quiet for hours
+            ("order_worker", Duration::from_secs(7_200)),
+        ];
+        let ok = HealthStatus::evaluate(&ages, Vec::new(), max, 1_000);
+        assert!(ok.healthy);
+        assert_eq!(ok.ages.len(), 2);
+
+        let wedged = HealthStatus::evaluate(&[("ws", Duration::from_secs(900))], vec!["book_feed".into()], max, 1_000);
+        assert!(!wedged.healthy);
+        assert_eq!(wedged.stale, vec!["ws".to_string()]);
+        let json = serde_json::to_string(&wedged).unwrap();
+        assert!(json.contains(r#""healthy":false,"ages":{"ws":900.0},"stale":["ws"],"degraded":["book_feed"]"#));
+
+        // Nothing reported yet (still starting): not judged
+        assert!(HealthStatus::evaluate(&[], Vec::new(), max, 1_000).healthy);
+    }
+}
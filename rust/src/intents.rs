@@ -0,0 +1,318 @@
+//! Persisted order intents
+//! Every live order is journaled before it is posted and resolved once the exchange answers.
+//! After a crash, intents without a resolution are reconciled against exchange trade history
+//! at startup by the hashes of the orders signed for them, and a duplicate submission for an
+//! unresolved intent is refused
+
+use crate::timestamp;
+use crate::{PreparedCreds, RustClobClient};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Append-only journal (one JSON record per line)
+pub const INTENT_JOURNAL_PATH: &str = ".order_intents.jsonl";
+
+/// Look back this far before an intent's timestamp when searching exchange history
+const RECONCILE_SLACK_SECS: u64 = 60;
+
+// ============================================================================
+// Records
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderIntent {
+    /// Stable id of the whale event being copied ("<tx_hash>:<log_index>")
+    pub id: String,
+    pub token_id: String,
+    pub side: String,
+    pub price: f64,
+    pub size: f64,
+    /// Unix seconds when the intent was journaled
+    pub ts: u64,
+    /// Strategy tag of the instance that sent it (empty in journals written before tagging)
+    #[serde(default)]
+    pub tag: String,
+    /// Hashes of the orders signed for it (the exchange's order ids), one per submission
+    #[serde(default)]
+    pub order_hashes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JournalRecord {
+    Open(OrderIntent),
+    Signed { id: String, order_hash: String },
+    Resolved { id: String, outcome: String, ts: u64 },
+}
+
+// ============================================================================
+// Journal
+// ============================================================================
+
+pub struct IntentJournal {
+    path: Option<String>,
+    open: Mutex<FxHashMap<String, OrderIntent>>,
+}
+
+impl IntentJournal {
+    /// Journal backed by `path` (None keeps it in memory only)
+    pub fn new(path: Option<&str>) -> Self {
+        Self { path: path.map(String::from), open: Mutex::new(FxHashMap::default()) }
+    }
+
+    /// Replay the journal file; returns number of unresolved intents
+    pub fn load(&self) -> usize {
+        let Some(path) = &self.path else { return 0 };
+        let Ok(data) = std::fs::read_to_string(path) else { return 0 };
+        let Ok(mut open) = self.open.lock() else { return 0 };
+        for line in data.lines() {
+            match serde_json::from_str::<JournalRecord>(line) {
+                Ok(JournalRecord::Open(intent)) => { open.insert(intent.id.clone(), intent); }
+                Ok(JournalRecord::Signed { id, order_hash }) => {
+                    if let Some(intent) = open.get_mut(&id) {
+                        intent.order_hashes.push(order_hash);
+                    }
+                }
+                Ok(JournalRecord::Resolved { id, .. }) => { open.remove(&id); }
+                Err(_) => {} // torn final line after a crash
+            }
+        }
+        open.len()
+    }
+
+    /// Journal an intent before posting. Err(existing) if the same intent is still unresolved
+    pub fn begin(&self, intent: OrderIntent) -> Result<(), Box<OrderIntent>> {
+        let mut open = self.open.lock().map_err(|_| Box::new(intent.clone()))?;
+        if let Some(existing) = open.get(&intent.id) {
+            return Err(Box::new(existing.clone()));
+        }
+        self.append(&JournalRecord::Open(intent.clone()));
+        open.insert(intent.id.clone(), intent);
+        Ok(())
+    }
+
+    /// Record the hash of an order signed for an open intent, before it is posted
+    pub fn signed(&self, id: &str, order_hash: &str) {
+        let Ok(mut open) = self.open.lock() else { return };
+        if let Some(intent) = open.get_mut(id) {
+            intent.order_hashes.push(order_hash.to_lowercase());
+            self.append(&JournalRecord::Signed { id: id.to_string(), order_hash: order_hash.to_lowercase() });
+        }
+    }
+
+    /// Record the exchange's answer for an intent
+    pub fn resolve(&self, id: &str, outcome: &str) {
+        let Ok(mut open) = self.open.lock() else { return };
+        if open.remove(id).is_some() {
+            self.append(&JournalRecord::Resolved { id: id.to_string(), outcome: outcome.to_string(), ts: timestamp::unix_secs() });
+        }
+    }
+
+    pub fn unresolved(&self) -> Vec<OrderIntent> {
+        self.open.lock().map(|o| o.values().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Rewrite the journal with only unresolved intents
+    pub fn compact(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let Ok(open) = self.open.lock() else { return Ok(()) };
+        let mut data = String::new();
+        for intent in open.values() {
+            if let Ok(line) = serde_json::to_string(&JournalRecord::Open(intent.clone())) {
+                data.push_str(&line);
+                data.push('\n');
+            }
+        }
+        let tmp = format!("{}.tmp", path);
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(tmp, path)
+    }
+
+    fn append(&self, record: &JournalRecord) {
+        let Some(path) = &self.path else { return };
+        let Ok(line) = serde_json::to_string(record) else { return };
+        match OpenOptions::new().append(true).create(true).open(path) {
+            Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+            Err(e) => crate::console_eprintln!("⚠️ Intent journal write failed: {}", e),
+        }
+    }
+}
+
+/// Build an intent stamped with the current time and this instance's strategy tag
+pub fn new_intent(id: &str, token_id: &str, side: &str, price: f64, size: f64) -> OrderIntent {
+    OrderIntent {
+        id: id.to_string(),
+        token_id: token_id.to_string(),
+        side: side.to_string(),
+        price,
+        size,
+        ts: timestamp::unix_secs(),
+        tag: crate::strategy::strategy_ledger().tag(),
+        order_hashes: Vec::new(),
+    }
+}
+
+// ============================================================================
+// Reconciliation
+// ============================================================================
+
+/// Exchange order ids a trade filled: the taker order and every maker order
+fn trade_order_ids(trade: &Value) -> impl Iterator<Item = &str> {
+    let makers = trade["maker_orders"].as_array().map(Vec::as_slice).unwrap_or_default();
+    trade["taker_order_id"].as_str().into_iter().chain(makers.iter().filter_map(|m| m["order_id"].as_str()))
+}
+
+/// True if the trades response contains a fill of one of the intent's signed orders. Intents
+/// journaled without order hashes fall back to any fill on their side at or after their timestamp
+pub fn trades_contain_fill(trades: &Value, intent: &OrderIntent) -> bool {
+    let list = trades["data"].as_array().or_else(|| trades.as_array());
+    list.is_some_and(|arr| arr.iter().any(|t| {
+        if !intent.order_hashes.is_empty() {
+            return trade_order_ids(t).any(|id| intent.order_hashes.iter().any(|h| h.eq_ignore_ascii_case(id)));
+        }
+        let side_ok = t["side"].as_str().is_none_or(|s| s.eq_ignore_ascii_case(&intent.side));
+        let ts = t["match_time"].as_str().and_then(|s| s.parse::<u64>().ok())
+            .or_else(|| t["match_time"].as_u64())
+            .unwrap_or(u64::MAX);
+        side_ok && ts.saturating_add(RECONCILE_SLACK_SECS) >= intent.ts
+    }))
+}
+
+/// Resolve unresolved intents against exchange trade history (blocking).
+/// Intents whose lookup fails stay open so a duplicate cannot be sent
+pub fn reconcile(journal: &IntentJournal, client: &RustClobClient, creds: &PreparedCreds, funder: &str) -> usize {
+    let mut resolved = 0;
+    for intent in journal.unresolved() {
+        let query = format!(
+            "maker_address={}&asset_id={}&after={}",
+            funder, intent.token_id, intent.ts.saturating_sub(RECONCILE_SLACK_SECS)
+        );
+        let trades = client.get_l2("/data/trades", &query, creds)
+            .ok()
+            .filter(|r| r.status().is_success())
+            .and_then(|r| r.json::<Value>().ok());
+        let Some(trades) = trades else {
+            crate::console_eprintln!("⚠️ Intent {} ({}) could not be reconciled, keeping it open", intent.id, intent.token_id);
+            continue;
+        };
+        let outcome = if trades_contain_fill(&trades, &intent) { "FILLED_BEFORE_RESTART" } else { "NOT_FILLED" };
+        crate::console_println!("🧾 Intent {} [{}] {} {:.2} @ {:.2} -> {}", intent.id, intent.tag, intent.side, intent.size, intent.price, outcome);
+        // Order size is an upper bound on the fill; over-counting exposure is the safe side for the tag cap
+        if outcome == "FILLED_BEFORE_RESTART" && intent.tag == crate::strategy::strategy_ledger().tag() {
+            crate::strategy::strategy_ledger().record(&intent.token_id, intent.side.eq_ignore_ascii_case("BUY"), intent.size, intent.price);
+        }
+        journal.resolve(&intent.id, outcome);
+        resolved += 1;
+    }
+    resolved
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_INTENTS: OnceLock<IntentJournal> = OnceLock::new();
+
+/// Get the global intent journal
+pub fn intent_journal() -> &'static IntentJournal {
+    GLOBAL_INTENTS.get_or_init(|| IntentJournal::new(Some(INTENT_JOURNAL_PATH)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(id: &str) -> OrderIntent {
+        OrderIntent {
+            id: id.into(), token_id: "111".into(), side: "BUY".into(), price: 0.5, size: 10.0, ts: 1_000, tag: "default".into(),
+            order_hashes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_refused_until_resolved() {
+        let j = IntentJournal::new(None);
+        assert!(j.begin(intent("a:1")).is_ok());
+        assert_eq!(j.begin(intent("a:1")).unwrap_err().id, "a:1");
+        j.resolve("a:1", "200 OK");
+        assert!(j.begin(intent("a:1")).is_ok());
+    }
+
+    #[test]
+    fn test_replayed_event_refused_after_probe_filled() {
+        let j = IntentJournal::new(None);
+        // Main intent opened before its probe, which fills and resolves on its own
+        j.begin(intent("a:1")).unwrap();
+        j.begin(intent("a:1:probe")).unwrap();
+        j.signed("a:1:probe", "0xAAA");
+        j.resolve("a:1:probe", "200");
+
+        // A replay of the same event must not probe (or enter) again while the main order works
+        assert_eq!(j.begin(intent("a:1")).unwrap_err().id, "a:1");
+        assert!(j.unresolved().iter().all(|i| i.id == "a:1"));
+    }
+
+    #[test]
+    fn test_replay_and_compact() {
+        let path = std::env::temp_dir().join(format!("pm_intents_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let j = IntentJournal::new(Some(path));
+        j.begin(intent("a:1")).unwrap();
+        j.begin(intent("b:2")).unwrap();
+        j.resolve("a:1", "200 OK");
+
+        // Simulated restart
+        let j2 = IntentJournal::new(Some(path));
+        assert_eq!(j2.load(), 1);
+        assert!(j2.begin(intent("b:2")).is_err());
+
+        j2.compact().unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap().lines().count(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_trades_contain_fill() {
+        let i = intent("a:1");
+        let hit = serde_json::json!({"data": [{"side": "BUY", "match_time": "1010"}]});
+        let wrong_side = serde_json::json!({"data": [{"side": "SELL", "match_time": "1010"}]});
+        let too_old = serde_json::json!([{"side": "BUY", "match_time": "100"}]);
+        assert!(trades_contain_fill(&hit, &i));
+        assert!(!trades_contain_fill(&wrong_side, &i));
+        assert!(!trades_contain_fill(&too_old, &i));
+        // No match time at all is treated as recent, without overflowing
+        assert!(trades_contain_fill(&serde_json::json!([{"side": "BUY"}]), &i));
+    }
+
+    #[test]
+    fn test_reconcile_by_order_hash() {
+        let path = std::env::temp_dir().join(format!("pm_intents_signed_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let j = IntentJournal::new(Some(path));
+        j.begin(intent("a:1")).unwrap();
+        j.signed("a:1", "0xAAA");
+        let i = IntentJournal::new(Some(path));
+        i.load();
+        let i = i.unresolved().remove(0);
+        assert_eq!(i.order_hashes, vec!["0xaaa".to_string()]);
+
+        // Another order's fill on the same side and time no longer counts
+        let other = serde_json::json!({"data": [{"side": "BUY", "match_time": "1010", "taker_order_id": "0xBBB", "maker_orders": []}]});
+        let as_maker = serde_json::json!({"data": [{"side": "SELL", "match_time": "1010", "taker_order_id": "0xBBB", "maker_orders": [{"order_id": "0xaaa"}]}]});
+        assert!(!trades_contain_fill(&other, &i));
+        assert!(trades_contain_fill(&as_maker, &i));
+        let _ = std::fs::remove_file(path);
+    }
+}
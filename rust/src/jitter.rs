@@ -0,0 +1,116 @@
+//! Entry size and price jitter
+//! Copies sized by a fixed ratio, or lifted to the same minimum, repeat the same sizes and
+//! limits and are easy to spot in the trade feed. With `SIZE_JITTER_PCT` each entry's size is
+//! scaled by a random factor within ±that share, and with `PRICE_JITTER_TICKS` its limit moves
+//! 0..N ticks toward the passive side, so jitter never pays more than the unjittered order
+//! would. Sizes never drop below the order minimum. Each jittered order is journaled with its
+//! intent id, and fills are accounted at the size and price actually sent
+
+use crate::timestamp;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Before and after of every jittered order (one JSON object per line)
+pub const JITTER_FILE: &str = "order_jitter.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Jitter {
+    /// Size scaled by a factor in [1 - size_pct, 1 + size_pct] (0 = off)
+    pub size_pct: f64,
+    /// Limit moved 0..=price_ticks ticks toward the passive side (0 = off)
+    pub price_ticks: u32,
+}
+
+impl Jitter {
+    pub fn is_off(&self) -> bool {
+        self.size_pct <= 0.0 && self.price_ticks == 0
+    }
+
+    /// Jittered size, to the cent and not below `floor`. `roll` is uniform in [0, 1)
+    pub fn size(&self, shares: f64, floor: f64, roll: f64) -> f64 {
+        if self.size_pct <= 0.0 {
+            return shares;
+        }
+        let factor = 1.0 + self.size_pct.min(0.5) * (2.0 * roll - 1.0);
+        let jittered = crate::signal_math::floor_cents(shares * factor);
+        if jittered < floor { shares.max(floor) } else { jittered }
+    }
+
+    /// Jittered limit on the `tick` grid: lower for buys, higher for sells, kept in (0, 1).
+    /// `roll` is uniform in [0, 1)
+    pub fn price(&self, price: f64, tick: f64, is_buy: bool, roll: f64) -> f64 {
+        if self.price_ticks == 0 || tick <= 0.0 {
+            return price;
+        }
+        let ticks = (roll * (self.price_ticks + 1) as f64).floor().min(self.price_ticks as f64);
+        let moved = if is_buy { price - ticks * tick } else { price + ticks * tick };
+        let moved = (moved * 1e6).round() / 1e6;
+        if moved >= tick && moved <= 1.0 - tick { moved } else { price }
+    }
+}
+
+// ============================================================================
+// Journal
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JitterRecord {
+    pub ts: u64,
+    /// Intent id of the copy order ("<tx_hash>:<log_index>")
+    pub intent_id: String,
+    pub token_id: String,
+    pub size_before: f64,
+    pub size_after: f64,
+    pub price_before: f64,
+    pub price_after: f64,
+}
+
+impl JitterRecord {
+    pub fn new(intent_id: &str, token_id: &str, (size_before, price_before): (f64, f64), (size_after, price_after): (f64, f64)) -> Self {
+        Self {
+            ts: timestamp::unix_secs(),
+            intent_id: intent_id.to_string(),
+            token_id: token_id.to_string(),
+            size_before,
+            size_after,
+            price_before,
+            price_after,
+        }
+    }
+}
+
+/// Append a jittered order to `JITTER_FILE`
+pub fn record(rec: &JitterRecord) {
+    let Ok(line) = serde_json::to_string(rec) else { return };
+    match OpenOptions::new().append(true).create(true).open(JITTER_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Jitter journal write failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jitter_bounds() {
+        let j = Jitter { size_pct: 0.10, price_ticks: 2 };
+        assert_eq!(j.size(100.0, 2.0, 0.0), 90.0);
+        assert_eq!(j.size(100.0, 2.0, 0.5), 100.0);
+        assert!(j.size(100.0, 2.0, 0.999) <= 110.0);
+        // A minimum-sized order only ever jitters up
+        assert_eq!(j.size(10.0, 10.0, 0.0), 10.0);
+
+        assert_eq!(j.price(0.52, 0.01, true, 0.0), 0.52);
+        assert_eq!(j.price(0.52, 0.01, true, 0.99), 0.50);
+        assert_eq!(j.price(0.52, 0.01, false, 0.5), 0.53);
+        // Never off the price range
+        assert_eq!(j.price(0.01, 0.01, true, 0.99), 0.01);
+        assert!(Jitter::default().is_off());
+    }
+}
@@ -0,0 +1,146 @@
+//! Latency budget breakdown
+//! One place that answers "are we slow, and where": age of the last message per feed, age of
+//! each held token's book on the book feed, signal-to-submit and submit-to-ack percentiles.
+//! Each stage with a configured budget is checked against it and the dump lists the stages
+//! over budget, so a run without fills shows whether latency is the reason
+
+use std::fmt::Write as _;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Budgets per stage; None = measured but not checked
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyBudget {
+    /// Median signal-to-submit (SUBMIT_DEADLINE_MS)
+    pub signal_to_submit: Option<Duration>,
+    /// Median POST-to-response (LATENCY_BUDGET_ACK_MS)
+    pub submit_to_ack: Option<Duration>,
+    /// Oldest held token's book on the book feed (LATENCY_BUDGET_BOOK_AGE_MS)
+    pub book_age: Option<Duration>,
+}
+
+// ============================================================================
+// Breakdown
+// ============================================================================
+
+/// Current measurements of every stage
+#[derive(Debug, Clone, Default)]
+pub struct LatencySnapshot {
+    /// (feed, time since its last message)
+    pub feed_ages: Vec<(String, Duration)>,
+    /// (token, time since the book feed last touched it), oldest first
+    pub book_ages: Vec<(String, Duration)>,
+    /// Median signal-to-submit
+    pub signal_to_submit: Option<Duration>,
+    /// Median submit-to-ack
+    pub submit_to_ack: Option<Duration>,
+}
+
+impl LatencySnapshot {
+    pub fn capture() -> Self {
+        let stats = crate::execution_stats::execution_stats();
+        Self {
+            feed_ages: crate::feed_gaps::feed_gaps().message_ages(),
+            book_ages: crate::book_feed::book_ages(),
+            signal_to_submit: stats.submit_latency().map(|(p50, _, _)| Duration::from_micros(p50)),
+            submit_to_ack: stats.ack_latency().map(|(p50, _, _)| Duration::from_micros(p50)),
+        }
+    }
+
+    /// Stages over their budget: (stage, measured, budget)
+    pub fn over_budget(&self, budget: &LatencyBudget) -> Vec<(&'static str, Duration, Duration)> {
+        let oldest_book = self.book_ages.first().map(|(_, age)| *age);
+        [
+            ("signal_to_submit", self.signal_to_submit, budget.signal_to_submit),
+            ("submit_to_ack", self.submit_to_ack, budget.submit_to_ack),
+            ("book_age", oldest_book, budget.book_age),
+        ]
+        .into_iter()
+        .filter_map(|(stage, measured, limit)| {
+            let (measured, limit) = (measured?, limit?);
+            (measured > limit).then_some((stage, measured, limit))
+        })
+        .collect()
+    }
+
+    /// Dump lines: one per stage measured, then the stages over budget
+    pub fn report(&self, budget: &LatencyBudget) -> String {
+        let ms = |d: Duration| format!("{:.1}ms", d.as_secs_f64() * 1000.0);
+        let mut out = String::new();
+        for (feed, age) in &self.feed_ages {
+            let _ = writeln!(out, "  {:<14} feed {} last message {:.1}s ago", "latency", feed, age.as_secs_f64());
+        }
+        if let Some((token, oldest)) = self.book_ages.first() {
+            let _ = writeln!(
+                out, "  {:<14} book oldest {:.1}s ({}) over {} held tokens",
+                "latency", oldest.as_secs_f64(), token, self.book_ages.len()
+            );
+        }
+        if let Some(d) = self.signal_to_submit {
+            let _ = writeln!(out, "  {:<14} signal_to_submit p50={}", "latency", ms(d));
+        }
+        if let Some(d) = self.submit_to_ack {
+            let _ = writeln!(out, "  {:<14} submit_to_ack p50={}", "latency", ms(d));
+        }
+        let over = self.over_budget(budget);
+        if !over.is_empty() {
+            let list: Vec<String> = over.iter()
+                .map(|(stage, measured, limit)| format!("{} {} > {}", stage, ms(*measured), ms(*limit)))
+                .collect();
+            let _ = writeln!(out, "  {:<14} {}", "OVER_BUDGET", list.join(", "));
+        }
+        out
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_BUDGET: OnceLock<LatencyBudget> = OnceLock::new();
+
+/// Set once at startup
+pub fn init_latency_budget(budget: LatencyBudget) {
+    let _ = GLOBAL_BUDGET.set(budget);
+}
+
+/// Configured budgets (none checked before `init_latency_budget`)
+pub fn latency_budget() -> LatencyBudget {
+    GLOBAL_BUDGET.get().copied().unwrap_or_default()
+}
+
+/// Diagnostics dump section
+pub fn report() -> String {
+    LatencySnapshot::capture().report(&latency_budget())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_over_budget_lists_slow_stages_only() {
+        let snap = LatencySnapshot {
+            feed_ages: vec![("whale".to_string(), Duration::from_secs(4))],
+            book_ages: vec![("t1".to_string(), Duration::from_secs(45)), ("t2".to_string(), Duration::from_secs(2))],
+            signal_to_submit: Some(Duration::from_millis(12)),
+            submit_to_ack: Some(Duration::from_millis(620)),
+        };
+        let budget = LatencyBudget {
+            signal_to_submit: Some(Duration::from_millis(50)),
+            submit_to_ack: Some(Duration::from_millis(500)),
+            book_age: None,
+        };
+        let over = snap.over_budget(&budget);
+        assert_eq!(over, vec![("submit_to_ack", Duration::from_millis(620), Duration::from_millis(500))]);
+
+        let report = snap.report(&budget);
+        assert!(report.contains("book oldest 45.0s (t1) over 2 held tokens"));
+        assert!(report.contains("OVER_BUDGET    submit_to_ack 620.0ms > 500.0ms"));
+        assert!(!snap.report(&LatencyBudget::default()).contains("OVER_BUDGET"));
+    }
+}
@@ -0,0 +1,264 @@
+//! Endpoint latency probing and selection
+//! Measures TCP connect round-trip time from this host to the CLOB, Gamma and WebSocket
+//! providers, picks the fastest WebSocket provider and warns when the host is badly placed
+
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// How often the background task re-probes all endpoints
+pub const LATENCY_PROBE_INTERVAL_SECS: u64 = 5 * 60;
+
+/// Connect attempts per endpoint (median is reported)
+pub const PROBE_SAMPLES: usize = 5;
+
+/// Per-attempt connect timeout
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Median CLOB RTT above this means the host is a poor location for copy trading
+pub const POOR_LOCATION_RTT_MS: f64 = 80.0;
+
+// ============================================================================
+// Probe Types
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointKind {
+    Clob,
+    Gamma,
+    WebSocket,
+}
+
+impl EndpointKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndpointKind::Clob => "CLOB",
+            EndpointKind::Gamma => "GAMMA",
+            EndpointKind::WebSocket => "WS",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeTarget {
+    pub kind: EndpointKind,
+    pub url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub kind: EndpointKind,
+    pub url: String,
+    pub samples_ms: Vec<f64>,
+    pub failures: usize,
+}
+
+impl ProbeResult {
+    /// Median connect time, None if every attempt failed
+    pub fn median_ms(&self) -> Option<f64> {
+        if self.samples_ms.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples_ms.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Some(sorted[sorted.len() / 2])
+    }
+}
+
+impl std::fmt::Display for ProbeResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.median_ms() {
+            Some(ms) => write!(f, "{} {} median {:.1}ms ({} ok, {} failed)",
+                self.kind.as_str(), redact_url(&self.url), ms, self.samples_ms.len(), self.failures),
+            None => write!(f, "{} {} UNREACHABLE ({} failed)",
+                self.kind.as_str(), redact_url(&self.url), self.failures),
+        }
+    }
+}
+
+/// Extract (host, port) from an http(s)/ws(s) URL
+pub fn host_port(url: &str) -> Option<(String, u16)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let default_port = match scheme {
+        "https" | "wss" => 443,
+        "http" | "ws" => 80,
+        _ => return None,
+    };
+    let authority = rest.split(['/', '?']).next()?;
+    match authority.rsplit_once(':') {
+        Some((host, port)) => Some((host.to_string(), port.parse().ok()?)),
+        None => Some((authority.to_string(), default_port)),
+    }
+}
+
+/// Strip the path (API keys live there for WS providers) before logging
+pub fn redact_url(url: &str) -> String {
+    match host_port(url) {
+        Some((host, _)) => host,
+        None => "<invalid url>".to_string(),
+    }
+}
+
+/// Probe one endpoint (blocking - run inside spawn_blocking)
+pub fn probe_blocking(target: &ProbeTarget, samples: usize) -> ProbeResult {
+    let mut result = ProbeResult {
+        kind: target.kind,
+        url: target.url.clone(),
+        samples_ms: Vec::with_capacity(samples),
+        failures: 0,
+    };
+
+    let addr = host_port(&target.url)
+        .and_then(|(host, port)| (host.as_str(), port).to_socket_addrs().ok())
+        .and_then(|mut addrs| addrs.next());
+    let Some(addr) = addr else {
+        result.failures = samples;
+        return result;
+    };
+
+    for _ in 0..samples {
+        let start = Instant::now();
+        match TcpStream::connect_timeout(&addr, PROBE_TIMEOUT) {
+            Ok(_) => result.samples_ms.push(start.elapsed().as_secs_f64() * 1000.0),
+            Err(_) => result.failures += 1,
+        }
+    }
+    result
+}
+
+/// Fastest reachable endpoint of the given kind
+pub fn select_fastest(results: &[ProbeResult], kind: EndpointKind) -> Option<&ProbeResult> {
+    results.iter()
+        .filter(|r| r.kind == kind)
+        .filter_map(|r| r.median_ms().map(|ms| (r, ms)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(r, _)| r)
+}
+
+/// True if the CLOB is too far away from this host for latency-sensitive copying
+pub fn is_poor_location(results: &[ProbeResult]) -> bool {
+    match select_fastest(results, EndpointKind::Clob) {
+        Some(r) => r.median_ms().is_some_and(|ms| ms > POOR_LOCATION_RTT_MS),
+        None => true,
+    }
+}
+
+// ============================================================================
+// Latency Board (last results, shared with the WS reconnect loop)
+// ============================================================================
+
+#[derive(Default)]
+pub struct LatencyBoard {
+    results: RwLock<Vec<ProbeResult>>,
+}
+
+impl LatencyBoard {
+    pub fn set_results(&self, results: Vec<ProbeResult>) {
+        if let Ok(mut r) = self.results.write() {
+            *r = results;
+        }
+    }
+
+    pub fn results(&self) -> Vec<ProbeResult> {
+        self.results.read().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    /// WebSocket URL to use on the next (re)connect: fastest measured, else the configured primary
+    pub fn preferred_wss_url(&self, configured: &[String]) -> Option<String> {
+        let results = self.results();
+        select_fastest(&results, EndpointKind::WebSocket)
+            .filter(|r| configured.contains(&r.url))
+            .map(|r| r.url.clone())
+            .or_else(|| configured.first().cloned())
+    }
+}
+
+static GLOBAL_LATENCY_BOARD: OnceLock<LatencyBoard> = OnceLock::new();
+
+/// Get the global latency board
+pub fn global_latency_board() -> &'static LatencyBoard {
+    GLOBAL_LATENCY_BOARD.get_or_init(LatencyBoard::default)
+}
+
+/// Probe all targets, log the results and store them on the global board
+pub async fn run_probe(targets: Vec<ProbeTarget>) -> Vec<ProbeResult> {
+    let results = tokio::task::spawn_blocking(move || {
+        targets.iter().map(|t| probe_blocking(t, PROBE_SAMPLES)).collect::<Vec<_>>()
+    }).await.unwrap_or_default();
+
+    for r in &results {
+        crate::console_println!("📡 Latency: {}", r);
+    }
+    if let Some(ws) = select_fastest(&results, EndpointKind::WebSocket) {
+        crate::console_println!("📡 Selected WS provider: {}", redact_url(&ws.url));
+    }
+    if is_poor_location(&results) {
+        crate::console_eprintln!(
+            "⚠️ Host looks like a poor location for copy trading: CLOB RTT above {:.0}ms or unreachable. \
+            Consider running closer to Polymarket's servers (eu-west-2 / London).",
+            POOR_LOCATION_RTT_MS
+        );
+    }
+
+    global_latency_board().set_results(results.clone());
+    results
+}
+
+/// Spawn a background task that re-probes endpoints periodically
+pub fn spawn_latency_probe_task(targets: Vec<ProbeTarget>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let interval = Duration::from_secs(LATENCY_PROBE_INTERVAL_SECS);
+        loop {
+            tokio::time::sleep(interval).await;
+            run_probe(targets.clone()).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(kind: EndpointKind, url: &str, samples: &[f64]) -> ProbeResult {
+        ProbeResult { kind, url: url.to_string(), samples_ms: samples.to_vec(), failures: 0 }
+    }
+
+    #[test]
+    fn test_host_port_parsing() {
+        assert_eq!(host_port("https://clob.polymarket.com"), Some(("clob.polymarket.com".into(), 443)));
+        assert_eq!(host_port("wss://polygon-mainnet.g.alchemy.com/v2/KEY"), Some(("polygon-mainnet.g.alchemy.com".into(), 443)));
+        assert_eq!(host_port("ws://localhost:8546"), Some(("localhost".into(), 8546)));
+        assert_eq!(host_port("ftp://x"), None);
+        assert_eq!(redact_url("wss://polygon-mainnet.g.alchemy.com/v2/SECRET"), "polygon-mainnet.g.alchemy.com");
+    }
+
+    #[test]
+    fn test_select_fastest_and_median() {
+        let results = vec![
+            result(EndpointKind::WebSocket, "wss://a/1", &[30.0, 10.0, 20.0]),
+            result(EndpointKind::WebSocket, "wss://b/2", &[5.0, 6.0, 7.0]),
+            result(EndpointKind::WebSocket, "wss://c/3", &[]),
+            result(EndpointKind::Clob, "https://clob", &[120.0]),
+        ];
+        assert_eq!(results[0].median_ms(), Some(20.0));
+        assert_eq!(select_fastest(&results, EndpointKind::WebSocket).unwrap().url, "wss://b/2");
+        assert!(is_poor_location(&results));
+    }
+
+    #[test]
+    fn test_preferred_wss_falls_back_to_configured() {
+        let board = LatencyBoard::default();
+        let configured = vec!["wss://a/1".to_string(), "wss://b/2".to_string()];
+        assert_eq!(board.preferred_wss_url(&configured), Some("wss://a/1".to_string()));
+
+        board.set_results(vec![
+            result(EndpointKind::WebSocket, "wss://a/1", &[30.0]),
+            result(EndpointKind::WebSocket, "wss://b/2", &[5.0]),
+        ]);
+        assert_eq!(board.preferred_wss_url(&configured), Some("wss://b/2".to_string()));
+    }
+}
@@ -1,14 +1,15 @@
 /// PM Whale Follower - Polymarket trading bot library
 /// Core library for interacting with Polymarket CLOB API
-use std::time::{SystemTime, UNIX_EPOCH, Duration};
+use std::time::Duration;
 
 use anyhow::{Result, anyhow};
-use base64::Engine;
+use base64::Engine as _;
 use base64::engine::general_purpose::URL_SAFE;
 use alloy::signers::local::PrivateKeySigner;
 use alloy::signers::SignerSync;
-use alloy::primitives::{B256, U256};
+use alloy::primitives::{Address, B256, U256};
 use alloy::dyn_abi::eip712::TypedData;
+use alloy::sol_types::{Eip712Domain, SolStruct};
 use hmac::{Hmac, Mac};
 use reqwest::blocking::Client;
 use reqwest::header::{HeaderMap, HeaderValue};
@@ -18,6 +19,8 @@ use std::collections::HashMap;
 use std::fs;
 use itoa::Buffer as ItoaBuffer;
 use std::path::Path;
+use std::borrow::Cow;
+use std::str::FromStr;
 
 pub mod profiler;
 pub use profiler::{ops, PROFILER};
@@ -28,9 +31,80 @@ pub mod soccer_markets;
 pub mod settings;
 pub mod models;
 pub mod position_tracker;
+pub mod token_metadata;
+pub mod latency_probe;
+pub mod asset_state;
+pub mod blotter;
+pub mod diagnostics;
+pub mod shadow;
+pub mod retention;
+pub mod doctor;
+pub mod intents;
+pub mod supervisor;
+pub mod strategy;
+pub mod execution_stats;
+pub mod what_if;
+pub mod engine;
+pub mod feed_gaps;
+pub mod manual;
+pub mod experiment;
+pub mod depth_history;
+pub mod flatten;
+pub mod reward_risk;
+pub mod probe;
+pub mod archive;
+pub mod pnl_attribution;
+pub mod lockup;
+pub mod replay_buffer;
+pub mod remote_config;
+pub mod quote_activity;
+pub mod paired;
+pub mod risk_sim;
+pub mod signal_math;
+pub mod trade_sync;
+pub mod cash_reserve;
+pub mod display;
+pub mod book_feed;
+pub mod conflict;
+pub mod clustering;
+pub mod market_quality;
+pub mod config_layers;
+pub mod compliance;
+pub mod timestamp;
+pub mod fixtures;
+pub mod order_reply;
+pub mod cost_budget;
+pub mod latency_budget;
+pub mod healthcheck;
+pub mod session;
+pub mod markout;
+pub mod feed_ipc;
+pub mod remediation;
+pub mod jitter;
+pub mod credentials;
+pub mod capital_at_risk;
+pub mod taker_flow;
+pub mod live_interlock;
+pub mod size_ramp;
+pub mod mock_clob;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+
+// Stable embedding surface (see engine.rs); everything else is internal
+pub use engine::{
+    CapitalAtRiskCheck, ClobExecutor, ComplianceCheck, ConsoleNotifier, CopyOrder, DedupCheck, DryRunExecutor, Engine, EngineBuilder, Executor,
+    Feed, Notifier, Outcome, RiskCheck, Strategy, StrategyCapCheck, TierCopyStrategy,
+};
+pub use compliance::{PreTradeCheck, PreTradeOrder};
+pub use models::{OrderInfo, ParsedEvent};
+pub use order_reply::{OrderError, OrderReply};
 
 #[cfg(test)]
 mod resubmit_tests;
+#[cfg(test)]
+mod eip712_tests;
 
 const USER_AGENT: &str = "py_clob_client";
 const MSG_TO_SIGN: &str = "This message attests that I control the given wallet";
@@ -48,6 +122,30 @@ fn get_exchange_address(chain_id: u64, neg_risk: bool) -> Option<&'static str> {
     }
 }
 
+// ============================================================================
+// Global allocator (cargo feature `mimalloc` or `jemalloc`, system malloc otherwise)
+// ============================================================================
+
+#[cfg(all(feature = "mimalloc", feature = "jemalloc"))]
+compile_error!("features `mimalloc` and `jemalloc` are mutually exclusive");
+
+#[cfg(feature = "mimalloc")]
+#[global_allocator]
+static GLOBAL_ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[cfg(all(feature = "jemalloc", not(target_env = "msvc")))]
+#[global_allocator]
+static GLOBAL_ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// Allocator compiled into this build (shown in the startup banner)
+pub const ALLOCATOR: &str = if cfg!(feature = "mimalloc") {
+    "mimalloc"
+} else if cfg!(all(feature = "jemalloc", not(target_env = "msvc"))) {
+    "jemalloc"
+} else {
+    "system"
+};
+
 // Pre-allocated common strings to avoid allocations
 const ZERO_STR: &str = "0";
 const FEE_RATE_ZERO: &str = "0";
@@ -102,7 +200,7 @@ pub struct ApiCreds {
 // ORDER RESPONSE (for parsing FAK order results)
 // ============================================================================
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct OrderResponse {
     pub success: bool,
     #[serde(rename = "errorMsg", default)]
@@ -119,6 +217,32 @@ pub struct OrderResponse {
     pub making_amount: String,
 }
 
+impl OrderResponse {
+    /// Order was accepted onto the book rather than matched on arrival; any later fill
+    /// of it is a maker fill
+    pub fn is_resting(&self) -> bool {
+        self.status.eq_ignore_ascii_case("live")
+    }
+
+    /// (taking amount, making/taking price) of what matched on arrival, None when nothing did
+    pub fn filled(&self) -> Option<(f64, f64)> {
+        let taking: f64 = self.taking_amount.parse().ok()?;
+        let making: f64 = self.making_amount.parse().ok()?;
+        (taking > 0.0).then(|| (taking, making / taking))
+    }
+}
+
+/// True if a post-only order at `price` would take liquidity against the best opposite
+/// level (best ask for buys, best bid for sells). Post-only cannot be combined with FAK/FOK
+#[inline]
+pub fn post_only_would_cross(is_buy: bool, price: f64, best_opposite: Option<f64>) -> bool {
+    match best_opposite {
+        Some(p) if is_buy => price >= p - 1e-9,
+        Some(p) => price <= p + 1e-9,
+        None => false,
+    }
+}
+
 // ============================================================================
 // PREPARED CREDENTIALS 
 // ============================================================================
@@ -245,6 +369,26 @@ impl RustClobClient {
         Ok(())
     }
 
+    /// Address of the signing wallet (differs from the funder for proxy wallets)
+    pub fn wallet_address(&self) -> &str {
+        &self.wallet_address_str
+    }
+
+    /// Authenticated GET; only `path` is signed, `query` (without '?') is appended to the URL
+    pub fn get_l2(&self, path: &str, query: &str, creds: &PreparedCreds) -> Result<reqwest::blocking::Response> {
+        let mut url = build_url_1(&self.host, path);
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(query);
+        }
+        let headers = self.l2_headers_fast("GET", path, None, creds)?;
+        let resp = self.http.get(url).headers(headers).send()?;
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            credentials::report_auth_failure();
+        }
+        Ok(resp)
+    }
+
     pub fn get_time(&self) -> Result<String> {
         let url = build_url_1(&self.host, "/time");
         let resp = self.http.get(url).header("User-Agent", USER_AGENT).send()?;
@@ -260,6 +404,16 @@ impl RustClobClient {
         Ok(resp.json()?)
     }
 
+    /// New API key for the wallet, for when none can be derived
+    pub fn create_api_key(&self, nonce: u64) -> Result<ApiCreds> {
+        let url = build_url_1(&self.host, "/auth/api-key");
+        let resp = self.http.post(url).headers(self.l1_headers(nonce)?).send()?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("create-api-key failed: {} {}", resp.status(), resp.text().unwrap_or_default()));
+        }
+        Ok(resp.json()?)
+    }
+
     pub fn l1_headers(&self, nonce: u64) -> Result<HeaderMap> {
         let timestamp = current_unix_ts();
         let digest = clob_auth_digest(self.chain_id, &self.wallet_address_str, timestamp, nonce)?;
@@ -301,23 +455,57 @@ impl RustClobClient {
         Ok(headers)
     }
     
-    pub fn post_order_fast(&self, body: String, creds: &PreparedCreds) -> Result<reqwest::blocking::Response> {
-        profile!(ops::POST_ORDER);
+    /// URL and signed L2 headers for posting an order body (everything short of sending it)
+    pub fn prepare_order_post(&self, body: &str, creds: &PreparedCreds) -> Result<(String, HeaderMap)> {
         let path = "/order";
         let url = build_url_1(&self.host, path);
-        let headers = self.l2_headers_fast("POST", path, Some(&body), creds)?;
-        Ok(self.http.post(url).headers(headers).body(body).send()?)
+        let headers = self.l2_headers_fast("POST", path, Some(body), creds)?;
+        Ok((url, headers))
+    }
+
+    pub fn post_order_fast(&self, body: String, creds: &PreparedCreds) -> Result<reqwest::blocking::Response> {
+        profile!(ops::POST_ORDER);
+        #[cfg(feature = "chaos")]
+        if let Some(c) = chaos::chaos() {
+            std::thread::sleep(c.latency());
+            if let Some(status) = c.http_fault() {
+                anyhow::bail!("HTTP {} (chaos)", status);
+            }
+        }
+        let (url, headers) = self.prepare_order_post(&body, creds)?;
+        let resp = self.http.post(url).headers(headers).body(body).send()?;
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            credentials::report_auth_failure();
+        }
+        Ok(resp)
+    }
+
+    /// `post_order_fast` with the reply read and classified. Err only when no reply came back
+    pub fn post_order(&self, body: String, creds: &PreparedCreds) -> Result<OrderReply> {
+        let resp = self.post_order_fast(body, creds)?;
+        let status = resp.status().as_u16();
+        Ok(OrderReply::parse(status, &resp.text().unwrap_or_default()))
     }
 
     pub fn create_order(&mut self, args: OrderArgs) -> Result<SignedOrder> {
         profile!(ops::CREATE_ORDER);
 
-        let tick = "0.01";
+        // Registered pre-trade checks see every order before it is signed
+        compliance::check(&args.token_id, args.side.eq_ignore_ascii_case("BUY"), args.price, args.size)
+            .map_err(|reason| anyhow!("COMPLIANCE_BLOCKED ({})", reason))?;
+
+        // Token metadata supplies the market's real tick size (defaults to 0.01)
+        let metadata = token_metadata::get(&args.token_id);
+        let tick = metadata.as_ref().map_or(token_metadata::DEFAULT_TICK_SIZE, |m| m.tick_size.as_str());
 
         // Check global market cache first (periodically refreshed from disk)
         let neg_risk = if let Some(n) = market_cache::is_neg_risk(&args.token_id) {
             n
         }
+        // Then token metadata (fetched per market from Gamma)
+        else if let Some(ref m) = metadata {
+            m.neg_risk
+        }
         // Fallback: check client's internal cache (for previous API hits this session)
         else if let Some(&n) = self.neg_risk_cache.get(&args.token_id) {
             n
@@ -335,6 +523,12 @@ impl RustClobClient {
             nr
         };
 
+        self.sign_order(args, tick, neg_risk, generate_seed())
+    }
+
+    /// Build and sign an order fully offline (no cache or network lookups).
+    /// `create_order` resolves tick size and neg-risk, then delegates here with a fresh salt
+    pub fn sign_order(&self, args: OrderArgs, tick: &str, neg_risk: bool, salt: u128) -> Result<SignedOrder> {
         if !price_valid(args.price, tick) {
             return Err(anyhow!("price {} outside allowed range", args.price));
         }
@@ -350,8 +544,6 @@ impl RustClobClient {
             return Err(anyhow!("side must be BUY or SELL"));
         };
 
-        let salt = generate_seed();
-
         let maker_amount_u256 = U256::from(maker_amt);
         let taker_amount_u256 = U256::from(taker_amt);
         let nonce_u256 = U256::from(args.nonce.unwrap_or(0) as u64);
@@ -391,17 +583,19 @@ impl RustClobClient {
             .ok_or_else(|| anyhow!("unsupported chain"))?;
         
         profile!(ops::CREATE_ORDER_TYPED_DATA);
-        let typed = order_typed_data(self.chain_id, exchange, &data)?;
+        let digest = order_signing_hash(self.chain_id, exchange, &data)?;
 
         profile!(ops::CREATE_ORDER_SIGN);
-        let digest = typed.eip712_signing_hash()
-            .map_err(|e| anyhow!("EIP-712 encoding failed: {}", e))?;
         let sig = self.wallet.sign_hash_sync(&digest)
             .map_err(|e| anyhow!("Failed to sign order: {}", e))?;
+        // The order hash is the exchange order id: trade history sync uses it to tell our fills apart
+        let order_hash = format!("{:#x}", digest);
+        trade_sync::register_own_order(&order_hash);
 
         let order = SignedOrder {
             order: data.into_order_struct(),
             signature: sig.to_string(),
+            order_hash,
         };
 
         Ok(order)
@@ -444,7 +638,7 @@ fn add_default_headers(headers: &mut HeaderMap) {
 
 #[inline(always)]
 fn current_unix_ts() -> u64 {
-    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    timestamp::unix_secs()
 }
 
 fn clob_auth_digest(chain_id: u64, address_str: &str, timestamp: u64, nonce: u64) -> Result<B256> {
@@ -503,10 +697,22 @@ pub struct OrderStruct {
 }
 
 #[derive(Debug, Clone, Serialize)]
-pub struct SignedOrder { pub order: OrderStruct, pub signature: String }
+pub struct SignedOrder {
+    pub order: OrderStruct,
+    pub signature: String,
+    /// EIP-712 hash of the order, which the exchange uses as its order id
+    #[serde(skip)]
+    pub order_hash: String,
+}
 
 impl SignedOrder {
     pub fn post_body(&self, owner: &str, order_type: &str) -> String {
+        self.post_body_with(owner, order_type, false)
+    }
+
+    /// Post body with the exchange's `postOnly` flag: the order is rejected instead of
+    /// matched if it would cross the book (GTC/GTD only, see `post_only_would_cross`)
+    pub fn post_body_with(&self, owner: &str, order_type: &str, post_only: bool) -> String {
         JSON_BUF.with(|json_buf| {
             ITOA_BUF.with(|itoa_buf| {
                 let mut buf = json_buf.borrow_mut();
@@ -547,7 +753,11 @@ impl SignedOrder {
                 buf.push_str(owner);
                 buf.push_str(r#"","orderType":""#);
                 buf.push_str(order_type);
-                buf.push_str(r#""}"#);
+                if post_only {
+                    buf.push_str(r#"","postOnly":true}"#);
+                } else {
+                    buf.push_str(r#""}"#);
+                }
                 
                 buf.clone()
             })
@@ -625,7 +835,7 @@ fn to_token_decimals(x: f64) -> Result<u128> {
 }
 
 #[inline(always)]
-fn generate_seed() -> u128 { SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() % u128::from(u32::MAX) }
+fn generate_seed() -> u128 { u128::from(timestamp::Timestamp::now().as_nanos()) % u128::from(u32::MAX) }
 
 #[cfg(test)]
 mod tests {
@@ -685,45 +895,49 @@ mod tests {
     }
 }
 
-fn order_typed_data(chain_id: u64, exchange: &str, data: &OrderData) -> Result<TypedData> {
-    let json_str = format!(
-        concat!(
-            r#"{{"types":{{"EIP712Domain":["#,
-            r#"{{"name":"name","type":"string"}},"#,
-            r#"{{"name":"version","type":"string"}},"#,
-            r#"{{"name":"chainId","type":"uint256"}},"#,
-            r#"{{"name":"verifyingContract","type":"address"}}"#,
-            r#"],"Order":["#,
-            r#"{{"name":"salt","type":"uint256"}},"#,
-            r#"{{"name":"maker","type":"address"}},"#,
-            r#"{{"name":"signer","type":"address"}},"#,
-            r#"{{"name":"taker","type":"address"}},"#,
-            r#"{{"name":"tokenId","type":"uint256"}},"#,
-            r#"{{"name":"makerAmount","type":"uint256"}},"#,
-            r#"{{"name":"takerAmount","type":"uint256"}},"#,
-            r#"{{"name":"expiration","type":"uint256"}},"#,
-            r#"{{"name":"nonce","type":"uint256"}},"#,
-            r#"{{"name":"feeRateBps","type":"uint256"}},"#,
-            r#"{{"name":"side","type":"uint8"}},"#,
-            r#"{{"name":"signatureType","type":"uint8"}}"#,
-            r#"]}},"primaryType":"Order","#,
-            r#""domain":{{"name":"Polymarket CTF Exchange","version":"1","chainId":{},"verifyingContract":"{}"}},"#,
-            r#""message":{{"salt":"{}","maker":"{}","signer":"{}","taker":"{}","tokenId":"{}","makerAmount":"{}","takerAmount":"{}","expiration":"{}","nonce":"{}","feeRateBps":"0","side":{},"signatureType":{}}}}}"#
-        ),
-        chain_id,
-        exchange,
-        data.salt,
-        data.maker,
-        data.signer,
-        data.taker,
-        data.token_id_u256,
-        data.maker_amount_u256,
-        data.taker_amount_u256,
-        data.expiration_u256,
-        data.nonce_u256,
-        data.side,
-        data.signature_type,
+// CTF Exchange order type; field order must match the contract's ORDER_TYPEHASH
+mod exchange_order {
+    alloy::sol! {
+        struct Order {
+            uint256 salt;
+            address maker;
+            address signer;
+            address taker;
+            uint256 tokenId;
+            uint256 makerAmount;
+            uint256 takerAmount;
+            uint256 expiration;
+            uint256 nonce;
+            uint256 feeRateBps;
+            uint8 side;
+            uint8 signatureType;
+        }
+    }
+}
+
+/// EIP-712 digest of an order, encoded statically (no JSON round-trip through `TypedData`)
+fn order_signing_hash(chain_id: u64, exchange: &str, data: &OrderData) -> Result<B256> {
+    let domain = Eip712Domain::new(
+        Some(Cow::Borrowed("Polymarket CTF Exchange")),
+        Some(Cow::Borrowed("1")),
+        Some(U256::from(chain_id)),
+        Some(Address::from_str(exchange)?),
+        None,
     );
-    Ok(serde_json::from_str(&json_str)?)
+    let order = exchange_order::Order {
+        salt: U256::from(data.salt),
+        maker: Address::from_str(&data.maker)?,
+        signer: Address::from_str(&data.signer)?,
+        taker: Address::from_str(&data.taker)?,
+        tokenId: data.token_id_u256,
+        makerAmount: data.maker_amount_u256,
+        takerAmount: data.taker_amount_u256,
+        expiration: data.expiration_u256,
+        nonce: data.nonce_u256,
+        feeRateBps: U256::ZERO,
+        side: data.side as u8,
+        signatureType: data.signature_type as u8,
+    };
+    Ok(order.eip712_signing_hash(&domain))
 }
 
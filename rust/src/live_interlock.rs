@@ -0,0 +1,180 @@
+//! Live trading interlock
+//! Live mode (ENABLE_TRADING=true with mock and shadow off) signs and sends real orders, so a
+//! config edit alone must not start it. The bot prints the limits in force and refuses to
+//! start unless it was run with `--i-understand-live-risk` or LIVE_CONFIRM holds the same
+//! phrase (for service managers). After that, the first LIVE_WARMUP_TRADES filled entries are
+//! capped at LIVE_WARMUP_MAX_USD each; the count is kept in `live_interlock.json` so restarts
+//! do not reset it
+
+use crate::settings::Config;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Command-line flag that confirms live mode
+pub const LIVE_RISK_FLAG: &str = "--i-understand-live-risk";
+
+/// LIVE_CONFIRM value that confirms live mode
+pub const CONFIRM_PHRASE: &str = "i-understand-live-risk";
+
+/// Filled live entries so far
+pub const LIVE_INTERLOCK_FILE: &str = "live_interlock.json";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WarmupPolicy {
+    /// Entries held to the cap
+    pub trades: u32,
+    /// Most an entry may cost during warmup
+    pub max_usd: f64,
+}
+
+/// Confirmed by the flag in `args` or the phrase in LIVE_CONFIRM
+pub fn confirmed(args: &[String], live_confirm: Option<&str>) -> bool {
+    args.iter().any(|a| a == LIVE_RISK_FLAG)
+        || live_confirm.is_some_and(|v| v.trim().eq_ignore_ascii_case(CONFIRM_PHRASE))
+}
+
+fn usd_or(limit: Option<f64>, none: &str) -> String {
+    limit.map_or_else(|| none.to_string(), |v| format!("${:.2}", v))
+}
+
+/// The limits a live run trades under
+pub fn render_limits(cfg: &Config) -> String {
+    let mut out = String::from("⚠️ LIVE TRADING: real orders from the funder wallet\n");
+    let rows = [
+        ("wallet", cfg.funder_address.clone()),
+        ("copy ratio", format!("{:.1}% of whale size", crate::settings::SCALING_RATIO * 100.0)),
+        ("stop loss", format!("{:.0}%", crate::position_tracker::STOP_LOSS_PCT * 100.0)),
+        ("strategy cap", usd_or(cfg.strategy_cap(), "none (STRATEGY_MAX_OPEN_USD)")),
+        ("capital at risk", usd_or(cfg.max_capital_at_risk_usd, "none (MAX_CAPITAL_AT_RISK_USD)")),
+        ("cash reserve", cfg.cash_reserve().map_or_else(|| "none (CASH_RESERVE_PCT)".to_string(), |f| format!("{:.0}% of equity", f * 100.0))),
+        ("max order", usd_or(cfg.compliance.as_ref().and_then(|c| c.max_order_usd), "none (COMPLIANCE_MAX_ORDER_USD)")),
+        ("warmup", cfg.live_warmup.map_or_else(
+            || "off (LIVE_WARMUP_TRADES)".to_string(),
+            |w| format!("first {} entries capped at ${:.2}", w.trades, w.max_usd),
+        )),
+        ("size ramp", cfg.size_ramp.map_or_else(
+            || "off (SIZE_RAMP_START)".to_string(),
+            |r| format!("from {:.0}% of size, +{:.0}% per {} good closes", r.start * 100.0, r.step * 100.0, r.trades),
+        )),
+    ];
+    for (name, value) in rows {
+        let _ = writeln!(out, "  {:<16} {}", name, value);
+    }
+    out
+}
+
+// ============================================================================
+// Warmup
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct WarmupState {
+    live_entries: u32,
+}
+
+pub struct Warmup {
+    pub policy: WarmupPolicy,
+    path: Option<String>,
+    state: Mutex<WarmupState>,
+}
+
+impl Warmup {
+    /// Count read from `path` (None keeps it in memory only)
+    pub fn new(policy: WarmupPolicy, path: Option<&str>) -> Self {
+        let state = path
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        Self { policy, path: path.map(String::from), state: Mutex::new(state) }
+    }
+
+    pub fn live_entries(&self) -> u32 {
+        self.state.lock().map(|s| s.live_entries).unwrap_or(0)
+    }
+
+    /// Cap on the next entry's cost, None once warmup is over
+    pub fn entry_cap_usd(&self) -> Option<f64> {
+        (self.live_entries() < self.policy.trades).then_some(self.policy.max_usd)
+    }
+
+    /// A live entry filled
+    pub fn record_entry(&self) -> Result<()> {
+        let state = {
+            let Ok(mut s) = self.state.lock() else { return Ok(()) };
+            s.live_entries = s.live_entries.saturating_add(1);
+            *s
+        };
+        if let Some(path) = &self.path {
+            let tmp = format!("{}.tmp", path);
+            fs::write(&tmp, serde_json::to_string(&state)?)?;
+            fs::rename(&tmp, path)?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_WARMUP: OnceLock<Warmup> = OnceLock::new();
+
+/// Set once at startup, in live mode only
+pub fn init_warmup(policy: WarmupPolicy) {
+    let _ = GLOBAL_WARMUP.set(Warmup::new(policy, Some(LIVE_INTERLOCK_FILE)));
+}
+
+pub fn warmup() -> Option<&'static Warmup> {
+    GLOBAL_WARMUP.get()
+}
+
+/// A live entry filled: count it towards the warmup
+pub fn record_entry() {
+    let Some(w) = warmup() else { return };
+    let done_before = w.entry_cap_usd().is_none();
+    if let Err(e) = w.record_entry() {
+        crate::console_eprintln!("⚠️ Live interlock write failed: {}", e);
+    }
+    if !done_before && w.entry_cap_usd().is_none() {
+        crate::console_println!("🔓 Live warmup done after {} entries; configured sizes apply from now on", w.live_entries());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmation() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(!confirmed(&args(&["pm_bot"]), None));
+        assert!(confirmed(&args(&["pm_bot", LIVE_RISK_FLAG]), None));
+        assert!(confirmed(&args(&["pm_bot"]), Some(" I-Understand-Live-Risk ")));
+        assert!(!confirmed(&args(&["pm_bot"]), Some("yes")));
+    }
+
+    #[test]
+    fn test_warmup_cap_survives_restart() {
+        let path = std::env::temp_dir().join(format!("pm_live_interlock_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        let policy = WarmupPolicy { trades: 2, max_usd: 5.0 };
+
+        let w = Warmup::new(policy, Some(path));
+        assert_eq!(w.entry_cap_usd(), Some(5.0));
+        w.record_entry().unwrap();
+        let w = Warmup::new(policy, Some(path));
+        assert_eq!(w.live_entries(), 1);
+        assert_eq!(w.entry_cap_usd(), Some(5.0));
+        w.record_entry().unwrap();
+        assert_eq!(w.entry_cap_usd(), None);
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,153 @@
+//! Settlement-period capital lockup
+//! A position whose market has ended still ties up its cost until the market resolves and the
+//! payout is credited, typically hours later. The outcome is decided but the cash cannot be
+//! used for entries yet. For every held token the forecast takes the market's scheduled end
+//! from the token metadata, adds the settlement lag and buckets the cost by when it should
+//! come back. Shown in the diagnostics dump and the TUI
+
+use crate::display;
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use std::fmt::Write;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Typical time from a market's end to its payout (default of SETTLEMENT_LAG_SECS)
+pub const DEFAULT_SETTLEMENT_LAG: Duration = Duration::from_secs(2 * 60 * 60);
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+// ============================================================================
+// Forecast
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LockupForecast {
+    /// Cost of every held position
+    pub locked_usd: f64,
+    /// Cost of positions whose market has ended (outcome decided, payout pending)
+    pub decided_usd: f64,
+    /// Cost expected back within 24 hours, overdue included
+    pub within_day_usd: f64,
+    /// Cost expected back in 1 to 7 days
+    pub within_week_usd: f64,
+    /// Cost expected back later than a week
+    pub later_usd: f64,
+    /// Cost of positions without a known market end
+    pub unknown_usd: f64,
+    /// Earliest expected release still in the future (unix seconds)
+    pub next_release: Option<u64>,
+}
+
+/// Bucket `(cost, market end)` pairs by expected release (end + `lag_secs`). An end of 0 is
+/// unknown
+pub fn forecast(positions: &[(f64, u64)], now: u64, lag_secs: u64) -> LockupForecast {
+    let mut f = LockupForecast::default();
+    for &(cost, end_ts) in positions {
+        f.locked_usd += cost;
+        if end_ts == 0 {
+            f.unknown_usd += cost;
+            continue;
+        }
+        if end_ts <= now {
+            f.decided_usd += cost;
+        }
+        let release = end_ts + lag_secs;
+        match release.saturating_sub(now) {
+            left if left <= DAY_SECS => f.within_day_usd += cost,
+            left if left <= 7 * DAY_SECS => f.within_week_usd += cost,
+            _ => f.later_usd += cost,
+        }
+        if release > now && f.next_release.is_none_or(|r| release < r) {
+            f.next_release = Some(release);
+        }
+    }
+    f
+}
+
+impl LockupForecast {
+    /// One line for the diagnostics dump (empty when nothing is held)
+    pub fn report(&self, now: u64) -> String {
+        let mut out = String::new();
+        if self.locked_usd <= 0.0 {
+            return out;
+        }
+        let _ = write!(
+            out, "  {:<14} locked={} decided={} back<24h={} back<7d={} later={} unknown={}",
+            "lockup", display::usd(self.locked_usd), display::usd(self.decided_usd), display::usd(self.within_day_usd),
+            display::usd(self.within_week_usd), display::usd(self.later_usd), display::usd(self.unknown_usd)
+        );
+        if let Some(next) = self.next_release {
+            let _ = write!(out, " next in {:.1}h", next.saturating_sub(now) as f64 / 3600.0);
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// (cost, market end) of every token held across the tags sharing the wallet
+pub fn held_positions() -> Vec<(f64, u64)> {
+    let mut cost: FxHashMap<String, f64> = FxHashMap::default();
+    for (_, book) in crate::strategy::strategy_ledger().snapshot() {
+        for token in book.tokens() {
+            if let Some((shares, avg)) = book.held(&token) {
+                *cost.entry(token).or_default() += shares * avg;
+            }
+        }
+    }
+    cost.into_iter()
+        .map(|(token, cost)| (cost, crate::token_metadata::get(&token).map_or(0, |m| m.end_ts)))
+        .collect()
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_SETTLEMENT_LAG: OnceLock<Duration> = OnceLock::new();
+
+/// Set once at startup (SETTLEMENT_LAG_SECS)
+pub fn init_settlement_lag(lag: Duration) {
+    let _ = GLOBAL_SETTLEMENT_LAG.set(lag);
+}
+
+pub fn settlement_lag() -> Duration {
+    GLOBAL_SETTLEMENT_LAG.get().copied().unwrap_or(DEFAULT_SETTLEMENT_LAG)
+}
+
+/// Forecast for the current holdings
+pub fn current_forecast() -> LockupForecast {
+    forecast(&held_positions(), timestamp::unix_secs(), settlement_lag().as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forecast_buckets() {
+        let now = 1_000_000;
+        let lag = 7_200;
+        let positions = [
+            (10.0, now - 3_600),          // ended, payout due in an hour
+            (20.0, now - 10_000),         // ended, payout overdue
+            (30.0, now + 3 * DAY_SECS),   // still trading
+            (40.0, now + 30 * DAY_SECS),
+            (5.0, 0),
+        ];
+        let f = forecast(&positions, now, lag);
+        assert_eq!(f.locked_usd, 105.0);
+        assert_eq!(f.decided_usd, 30.0);
+        assert_eq!(f.within_day_usd, 30.0);
+        assert_eq!(f.within_week_usd, 30.0);
+        assert_eq!(f.later_usd, 40.0);
+        assert_eq!(f.unknown_usd, 5.0);
+        assert_eq!(f.next_release, Some(now - 3_600 + lag));
+        assert!(f.report(now).contains("next in 1.0h"));
+        assert_eq!(forecast(&[], now, lag).report(now), "");
+    }
+}
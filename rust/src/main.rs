@@ -3,11 +3,10 @@
 
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
-use dotenvy::dotenv;
 use alloy::primitives::U256;
 use futures::{SinkExt, StreamExt};
 use rand::Rng;
-use pm_whale_follower::{ApiCreds, OrderArgs, RustClobClient, PreparedCreds, OrderResponse};
+use pm_whale_follower::{ApiCreds, OrderArgs, RustClobClient, PreparedCreds, OrderResponse, OrderError, OrderReply};
 use serde_json::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -15,22 +14,75 @@ use std::fmt::Write as _;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::Path;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{mpsc, oneshot};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 
-mod models;
+use pm_whale_follower::models;
 
-use pm_whale_follower::risk_guard::{RiskGuard, RiskGuardConfig, SafetyDecision, TradeSide, calc_liquidity_depth};
+use pm_whale_follower::risk_guard::{RiskGuard, RiskGuardConfig, SafetyDecision, TradeSide, AskMove, calc_liquidity_depth, check_ask_move, visible_depth_shares};
 use pm_whale_follower::settings::*;
 use pm_whale_follower::market_cache;
+use pm_whale_follower::token_metadata;
+use pm_whale_follower::asset_state::asset_states;
+use pm_whale_follower::blotter::{blotter, FillRow, PriceRow};
+use pm_whale_follower::diagnostics::{self, diagnostics};
+use pm_whale_follower::retention;
+use pm_whale_follower::archive;
+use pm_whale_follower::doctor;
+use pm_whale_follower::intents;
+use pm_whale_follower::execution_stats::execution_stats;
+use pm_whale_follower::what_if::{self, what_if};
+use pm_whale_follower::feed_gaps::feed_gaps;
+use pm_whale_follower::manual;
+use pm_whale_follower::experiment::{self, Experiment};
+use pm_whale_follower::depth_history::{self, BookFetcher};
+use pm_whale_follower::flatten::{self, FlattenPhase, FlattenPolicy};
+use pm_whale_follower::reward_risk::{self, rr_calibration};
+use pm_whale_follower::probe::{self, probe_stats, ProbeVerdict};
+use pm_whale_follower::pnl_attribution::{self, fair_at_entry};
+use pm_whale_follower::signal_math::{self, OrderMinimums};
+use pm_whale_follower::feed_ipc::{self, FeedPublisher, ProcessRole};
+use pm_whale_follower::remediation::{self, Remediation};
+use pm_whale_follower::jitter;
+use pm_whale_follower::credentials;
+use pm_whale_follower::display;
+use pm_whale_follower::book_feed;
+use pm_whale_follower::conflict;
+use pm_whale_follower::clustering;
+use pm_whale_follower::lockup;
+use pm_whale_follower::replay_buffer;
+use pm_whale_follower::remote_config;
+use pm_whale_follower::quote_activity;
+use pm_whale_follower::risk_sim;
+use pm_whale_follower::market_quality;
+use pm_whale_follower::config_layers;
+use pm_whale_follower::compliance;
+use pm_whale_follower::capital_at_risk;
+use pm_whale_follower::taker_flow;
+use pm_whale_follower::live_interlock;
+use pm_whale_follower::size_ramp;
+use pm_whale_follower::timestamp::{self, Timestamp};
+use pm_whale_follower::latency_budget;
+use pm_whale_follower::healthcheck;
+use pm_whale_follower::session;
+use pm_whale_follower::markout::{self, markouts};
+use pm_whale_follower::cost_budget::{self, cost_tracker};
+use pm_whale_follower::cash_reserve::{self, cash_reserve};
+use pm_whale_follower::trade_sync;
+use pm_whale_follower::strategy::{self, strategy_ledger};
+use pm_whale_follower::supervisor::{self, supervise, RestartPolicy};
+use pm_whale_follower::{console_println, console_eprintln};
+use pm_whale_follower::latency_probe::{self, EndpointKind, ProbeTarget};
 use pm_whale_follower::tennis_markets;
 use pm_whale_follower::soccer_markets;
-use pm_whale_follower::position_tracker::{PositionTracker, PriceFetcher, STOP_LOSS_CHECK_INTERVAL_SECS};
+use pm_whale_follower::position_tracker::{stop_loss_cadence, Position, PositionTracker, PriceFetcher, STOP_LOSS_PCT};
 use models::*;
+use rustc_hash::FxHashMap;
 use std::sync::Arc;
 
-const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
+/// Feed name of the feed process socket in the gap histogram
+const FEED_IPC_NAME: &str = "feed_ipc";
 
 // ============================================================================
 // Thread-local buffers 
@@ -61,7 +113,7 @@ impl OrderEngine {
         }
 
         let (resp_tx, resp_rx) = oneshot::channel();
-        if let Err(e) = self.tx.try_send(WorkItem { event: evt, respond_to: resp_tx, is_live }) {
+        if let Err(e) = self.tx.try_send(WorkItem { event: evt, respond_to: resp_tx, is_live, queued_at: Instant::now() }) {
             return format!("QUEUE_ERR: {e}");
         }
 
@@ -79,25 +131,255 @@ impl OrderEngine {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    dotenv().ok();
+    // config/base.env, config/<CONFIG_PROFILE>.env and .env under the process environment
+    config_layers::load()?;
+
+    // `pm_bot config show|diff`: layered config files, then exit (works without valid credentials)
+    if std::env::args().nth(1).as_deref() == Some("config") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+            ["show"] => print!("{}", config_layers::render_effective(config_layers::effective())),
+            ["diff", a, b] => print!("{}", config_layers::render_diff(a, b, &config_layers::diff_profiles(config_layers::CONFIG_DIR, a, b)?)),
+            _ => anyhow::bail!("{}", config_layers::USAGE),
+        }
+        return Ok(());
+    }
     ensure_csv()?;
 
     // Initialize market data caches
     market_cache::init_caches();
+    token_metadata::init_token_metadata();
+
+    // Start background cache refresh task (background tasks are restarted if they panic)
+    let _cache_refresh_handle = supervise("cache_refresh", market_cache::spawn_cache_refresh_task);
+
+    let base_cfg = Config::from_env()?;
+
+    // Fleet-wide overrides from the remote store (REMOTE_CONFIG_URL), or its cached last good copy
+    if let Some(source) = &base_cfg.remote_config {
+        remote_config::load_startup(source, &base_cfg).await;
+    }
+
+    // A running time-boxed experiment overrides some tunables and gets its own strategy tag
+    let active_experiment = experiment::load(experiment::EXPERIMENT_FILE);
+    let running_experiment = active_experiment.as_ref().filter(|e| !e.is_over(timestamp::unix_secs()));
+    // Outside an experiment, the intraday session in force now (SESSIONS) overrides its tunables
+    session::init_session_schedule(base_cfg.sessions.clone());
+    let cfg = match running_experiment {
+        Some(exp) => exp.apply(&remote_config::with_remote(&base_cfg))?,
+        None => session::config_now(&base_cfg),
+    };
+
+    // Measure RTT to every endpoint, pick the fastest WS provider, keep re-probing in background
+    let mut probe_targets = vec![
+        ProbeTarget { kind: EndpointKind::Clob, url: clob_api_base().to_string() },
+        ProbeTarget { kind: EndpointKind::Gamma, url: gamma_api_base().to_string() },
+    ];
+    probe_targets.extend(cfg.wss_urls.iter().map(|u| ProbeTarget { kind: EndpointKind::WebSocket, url: u.clone() }));
+
+    // `pm_bot doctor`: self-test credentials and connectivity, then exit
+    if std::env::args().nth(1).as_deref() == Some("doctor") {
+        return run_doctor(&cfg, probe_targets).await;
+    }
+
+    // Fills are attributed to this instance's tag in the ledger shared with other instances
+    let ledger_tag = running_experiment.map(Experiment::tag).unwrap_or_else(|| cfg.strategy_tag.clone());
+    strategy::init_strategy_ledger(&ledger_tag, cfg.strategy_cap());
+    execution_stats().set_adaptive(cfg.adaptive_offset);
+    execution_stats().set_maker_rebate_bps(cfg.maker_rebate_bps);
+    flatten::init_flatten_policy(cfg.flatten);
+    reward_risk::init_rr_gate(cfg.rr_gate());
+    probe::init_probe_policy(cfg.probe_policy());
+    conflict::init_conflict_policy(cfg.conflict.clone());
+    clustering::init_cluster_guard(cfg.cluster);
+    market_quality::init_screener(cfg.market_quality.clone());
+    if let Some(rules) = cfg.compliance.clone() {
+        compliance::register(rules);
+    }
+    lockup::init_settlement_lag(Duration::from_secs(cfg.settlement_lag_secs));
+    quote_activity::init_surge_policy(cfg.quote_surge);
+    taker_flow::init_flow_policy(cfg.taker_flow);
+    replay_buffer::init_replay_buffer((cfg.replay_buffer_mins > 0).then_some(Duration::from_secs(cfg.replay_buffer_mins * 60)));
+    cash_reserve::init_cash_reserve(cfg.cash_reserve());
+    capital_at_risk::init_limit(cfg.max_capital_at_risk_usd);
+    latency_budget::init_latency_budget(cfg.latency_budget());
+    cost_budget::init_cost_budget(cfg.cost_budget());
+    remediation::init_remediation(cfg.remediation);
+    archive::init_archive(cfg.archive.clone());
+    #[cfg(feature = "chaos")]
+    pm_whale_follower::chaos::install(pm_whale_follower::chaos::ChaosPlan::from_env());
+
+    // `pm_bot strategies`: per-tag exposure and realized P&L from the shared ledger, then exit
+    if std::env::args().nth(1).as_deref() == Some("strategies") {
+        print!("{}", strategy::render_report(&strategy_ledger().snapshot()));
+        return Ok(());
+    }
+
+    // `pm_bot at-risk`: premium paid and still at risk per open position, then exit
+    if std::env::args().nth(1).as_deref() == Some("at-risk") {
+        print!("{}", capital_at_risk::render_report(&capital_at_risk::current(), cfg.max_capital_at_risk_usd));
+        return Ok(());
+    }
+
+    // `pm_bot sessions`: fills, volume and realized P&L per intraday session, then exit
+    if std::env::args().nth(1).as_deref() == Some("sessions") {
+        print!("{}", session::render_report(&session::totals(&session::load_fills(strategy::STRATEGY_LEDGER_FILE))));
+        return Ok(());
+    }
+
+    // `pm_bot attribution`: settled P&L split into entry edge and resolution luck, then exit
+    if std::env::args().nth(1).as_deref() == Some("attribution") {
+        print!("{}", pnl_attribution::render_report(&pnl_attribution::load_settlements(pnl_attribution::ATTRIBUTION_FILE)));
+        return Ok(());
+    }
 
-    // Start background cache refresh task
-    let _cache_refresh_handle = market_cache::spawn_cache_refresh_task();
+    // `pm_bot clusters [window_secs] [min_entries]`: bursts of correlated entries in the ledger, then exit
+    if std::env::args().nth(1).as_deref() == Some("clusters") {
+        let limit = cfg.cluster;
+        let window = std::env::args().nth(2).and_then(|a| a.parse().ok()).or(limit.map(|l| l.window_secs)).unwrap_or(120);
+        let min_entries = std::env::args().nth(3).and_then(|a| a.parse().ok()).unwrap_or(3);
+        let segments = limit.map_or(1, |l| l.slug_segments);
+        let bursts = clustering::bursts_from_ledger(strategy::STRATEGY_LEDGER_FILE, window, min_entries, segments);
+        print!("{}", clustering::render_report(&bursts, &pnl_attribution::load_settlements(pnl_attribution::ATTRIBUTION_FILE)));
+        return Ok(());
+    }
+
+    // `pm_bot risk`: Monte Carlo day/week P&L estimate from the ledger, then exit
+    if std::env::args().nth(1).as_deref() == Some("risk") {
+        match risk_sim::report(strategy::STRATEGY_LEDGER_FILE, cfg.risk_loss_limit_usd, timestamp::unix_secs()) {
+            Some(r) => print!("{}", risk_sim::render_report(&r)),
+            None => console_println!("🎲 Too few closing trades in {} for an estimate", strategy::STRATEGY_LEDGER_FILE),
+        }
+        return Ok(());
+    }
+
+    // `pm_bot replay-dump [note]`: ask the running bot to write its feed buffer to disk, then exit
+    if std::env::args().nth(1).as_deref() == Some("replay-dump") {
+        let note = std::env::args().skip(2).collect::<Vec<_>>().join(" ");
+        replay_buffer::request_dump(&note)?;
+        console_println!("📼 Dump requested; the running bot writes it to {}/ within {}s", replay_buffer::DUMP_DIR, replay_buffer::DUMP_POLL_INTERVAL.as_secs());
+        return Ok(());
+    }
+
+    // `pm_bot position close|set|note ...`: queue a manual adjustment for the running bot, then exit
+    if std::env::args().nth(1).as_deref() == Some("position") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        let cmd = manual::parse_args(&args)?;
+        manual::queue(&cmd, manual::POSITION_INBOX_FILE)?;
+        console_println!("✍️ Queued for {}; the running bot applies it within {}s", cmd.token_id(), manual::INBOX_POLL_INTERVAL.as_secs());
+        return Ok(());
+    }
+
+    // `pm_bot depth-export <out.csv> [token_id] [bucket_secs]`: liquidity heatmap dataset, then exit
+    if std::env::args().nth(1).as_deref() == Some("depth-export") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        let Some(out) = args.first() else { anyhow::bail!("{}", depth_history::USAGE) };
+        let bucket_secs = match args.get(2) {
+            Some(b) => b.parse().map_err(|_| anyhow!("'{}' is not a number of seconds\n{}", b, depth_history::USAGE))?,
+            None => cfg.depth_snapshot_secs.max(60),
+        };
+        let snapshots = depth_history::load_snapshots(depth_history::DEPTH_HISTORY_FILE, args.get(1).map(String::as_str));
+        let cells = depth_history::heatmap(&snapshots, bucket_secs);
+        std::fs::write(out, depth_history::render_csv(&cells))?;
+        console_println!("🗺️ {} snapshots -> {} heatmap cells in {}", snapshots.len(), cells.len(), out);
+        return Ok(());
+    }
+
+    // `pm_bot experiment start|status|stop`: manage the time-boxed override, then exit
+    if std::env::args().nth(1).as_deref() == Some("experiment") {
+        return run_experiment_command(&base_cfg, active_experiment.as_ref());
+    }
+
+    console_println!("{}", config_layers::render_effective(config_layers::effective()).trim_end());
+
+    // PROCESS_ROLE=feed: only the whale WebSocket, fills go to the executor process
+    if cfg.process_role == ProcessRole::Feed {
+        return run_feed_process(&cfg, probe_targets).await;
+    }
+
+    // Live mode needs an explicit confirmation on top of the config
+    if cfg.enable_trading && !cfg.mock_trading && !cfg.shadow_trading {
+        console_println!("{}", live_interlock::render_limits(&cfg).trim_end());
+        let args: Vec<String> = std::env::args().collect();
+        if !live_interlock::confirmed(&args, std::env::var("LIVE_CONFIRM").ok().as_deref()) {
+            anyhow::bail!(
+                "live trading not confirmed: run with {} or set LIVE_CONFIRM={} (or set MOCK_TRADING=true)",
+                live_interlock::LIVE_RISK_FLAG, live_interlock::CONFIRM_PHRASE
+            );
+        }
+        if let Some(policy) = cfg.live_warmup {
+            live_interlock::init_warmup(policy);
+            let done = live_interlock::warmup().map_or(0, |w| w.live_entries());
+            if done < policy.trades {
+                console_println!("🐢 Live warmup: {} of {} entries done, each capped at {}", done, policy.trades, display::usd(policy.max_usd));
+            }
+        }
+        if let Some(policy) = cfg.size_ramp {
+            size_ramp::init_size_ramp(policy);
+            console_println!(
+                "📶 Size ramp at {:.0}% of configured size (+{:.0}% per {} closes with win rate >= {:.0}%)",
+                size_ramp::multiplier() * 100.0, policy.step * 100.0, policy.trades, policy.min_win_rate * 100.0
+            );
+            supervise("size_ramp", size_ramp::spawn_ramp_task);
+        }
+    }
+
+    match active_experiment {
+        // Deadline passed while the bot was down: only the results are left to record
+        Some(exp) if exp.is_over(timestamp::unix_secs()) => {
+            let result = experiment::finish(&exp, strategy_ledger(), experiment::EXPERIMENT_FILE, experiment::EXPERIMENT_RESULTS_FILE, timestamp::unix_secs());
+            console_println!("🧪 Experiment ended while stopped\n{}", experiment::render_result(&result));
+        }
+        Some(exp) => {
+            console_println!(
+                "🧪 Experiment '{}' in force for {:.1}h more (tag {})",
+                exp.name, exp.ends_ts.saturating_sub(timestamp::unix_secs()) as f64 / 3600.0, exp.tag()
+            );
+            let base_for_revert = base_cfg.clone();
+            supervise("experiment", move || experiment::spawn_expiry_task(exp.clone(), base_for_revert.clone()));
+        }
+        None => {}
+    }
+    if session::session_schedule().is_some() {
+        let base_for_sessions = base_cfg.clone();
+        supervise("session", move || session::spawn_session_task(base_for_sessions.clone()));
+    }
+    if cfg.quote_surge.is_some() || cfg.taker_flow.is_some() {
+        supervise("quote_activity", quote_activity::spawn_activity_feed_task);
+    }
+    let risk_loss_limit = cfg.risk_loss_limit_usd;
+    supervise("risk_sim", move || risk_sim::spawn_daily_report_task(risk_loss_limit));
+    if let Some(source) = base_cfg.remote_config.clone() {
+        let base_for_remote = base_cfg.clone();
+        supervise("remote_config", move || remote_config::spawn_watch_task(source.clone(), base_for_remote.clone()));
+    }
 
-    let cfg = Config::from_env()?;
+    latency_probe::run_probe(probe_targets.clone()).await;
+    let reprobe_targets = probe_targets.clone();
+    let _latency_probe_handle = supervise("latency_probe", move || latency_probe::spawn_latency_probe_task(reprobe_targets.clone()));
     
     let (client, creds) = build_worker_state(
         cfg.private_key.clone(),
         cfg.funder_address.clone(),
         ".clob_market_cache.json",
-        ".clob_creds.json",
+        credentials::CREDS_FILE,
     ).await?;
     
     let prepared_creds = PreparedCreds::from_api_creds(&creds)?;
+    credentials::init_credentials(prepared_creds.clone());
+
+    // Orders journaled before a crash but never resolved: check exchange history first
+    let unresolved = intents::intent_journal().load();
+    if unresolved > 0 {
+        console_println!("🧾 Reconciling {} unresolved order intents...", unresolved);
+        let (c, pc, funder) = (client.clone(), prepared_creds.clone(), cfg.funder_address.clone());
+        tokio::task::spawn_blocking(move || {
+            intents::reconcile(intents::intent_journal(), &c, &pc, &funder);
+            if let Err(e) = intents::intent_journal().compact() {
+                console_eprintln!("⚠️ Intent journal compaction failed: {}", e);
+            }
+        }).await?;
+    }
     let risk_config = cfg.risk_guard_config();
 
     let (order_tx, order_rx) = mpsc::channel(1024);
@@ -105,45 +387,309 @@ async fn main() -> Result<()> {
     let (position_tx, position_rx) = mpsc::unbounded_channel::<PositionUpdate>();
 
     let client_arc = Arc::new(client);
-    let creds_arc = Arc::new(prepared_creds.clone());
+    // Workers read credentials::current() per request so a refreshed key applies at once
+    let client_for_creds = Arc::clone(&client_arc);
+    supervise("credentials", move || credentials::spawn_refresh_task(Arc::clone(&client_for_creds)));
 
     // Create position tracker for stop-loss monitoring
     let position_tracker = Arc::new(PositionTracker::new());
+    // Positions held before a restart come back from the strategy ledger so the stop-loss covers them
+    let restored = position_tracker.restore_from_ledger(strategy_ledger()).await;
+    if restored > 0 {
+        console_println!("♻️ Restored {} open positions from {}", restored, strategy::STRATEGY_LEDGER_FILE);
+    }
 
-    start_order_worker(order_rx, client_arc.clone(), prepared_creds.clone(), cfg.enable_trading, cfg.mock_trading, risk_config, resubmit_tx.clone(), position_tx);
+    start_order_worker(order_rx, client_arc.clone(), cfg.enable_trading, cfg.mock_trading, cfg.shadow_trading, risk_config, resubmit_tx.clone(), position_tx);
 
-    tokio::spawn(resubmit_worker(resubmit_rx, client_arc.clone(), creds_arc.clone()));
+    // Receivers are shared so a restarted worker picks up the same queue
+    let resubmit_rx = Arc::new(tokio::sync::Mutex::new(resubmit_rx));
+    let resubmit_client = client_arc.clone();
+    supervise("resubmitter", move || {
+        tokio::spawn(resubmit_worker(resubmit_rx.clone(), resubmit_client.clone()))
+    });
 
     // Start position update receiver
+    let position_rx = Arc::new(tokio::sync::Mutex::new(position_rx));
     let tracker_clone = Arc::clone(&position_tracker);
-    tokio::spawn(position_update_worker(position_rx, tracker_clone));
+    supervise("positions", move || tokio::spawn(position_update_worker(position_rx.clone(), tracker_clone.clone())));
+
+    // Manual adjustments queued by `pm_bot position ...` (position_adjustments.jsonl)
+    let tracker_for_manual = Arc::clone(&position_tracker);
+    supervise("manual", move || manual::spawn_inbox_task(tracker_for_manual.clone()));
+    supervise("replay_dump", replay_buffer::spawn_dump_task);
+
+    // Mark filter-rejected whale trades to market after a horizon (what_if.jsonl)
+    let what_if_fetcher: Arc<dyn PriceFetcher> = Arc::new(ClobPriceFetcher { client: Arc::clone(&client_arc) });
+    supervise("what_if", move || what_if::spawn_what_if_task(Arc::clone(&what_if_fetcher)));
+    diagnostics().register_gauge("what_if_pending", || what_if().pending_len());
+
+    // Mid 10/30/60s after each of our fills, per tag and token (markouts.jsonl)
+    let markout_fetcher: Arc<dyn BookFetcher> = Arc::new(ClobPriceFetcher { client: Arc::clone(&client_arc) });
+    supervise("markout", move || markout::spawn_markout_task(Arc::clone(&markout_fetcher)));
+    diagnostics().register_gauge("markout_pending", || markouts().pending_len());
+
+    // Resolved markets close held positions at their payout (pnl_attribution.jsonl, see `pm_bot attribution`)
+    let (tracker_for_settle, settle_client) = (Arc::clone(&position_tracker), reqwest::Client::builder().no_proxy().build()?);
+    let settle_every = Duration::from_secs(cfg.settlement_check_secs);
+    supervise("settlement", move || pnl_attribution::spawn_settlement_task(Arc::clone(&tracker_for_settle), settle_client.clone(), settle_every));
+
+    // Fills made outside the bot (UI trades) merged into positions (external_fills.jsonl)
+    if cfg.trade_sync_secs > 0 && cfg.enable_trading && !cfg.mock_trading && !cfg.shadow_trading {
+        let (tracker_for_sync, client_for_sync) = (Arc::clone(&position_tracker), Arc::clone(&client_arc));
+        let (funder, interval) = (cfg.funder_address.clone(), Duration::from_secs(cfg.trade_sync_secs));
+        supervise("trade_sync", move || {
+            trade_sync::spawn_trade_sync_task(Arc::clone(&tracker_for_sync), Arc::clone(&client_for_sync), funder.clone(), interval)
+        });
+    }
+
+    // USDC balance for the cash reserve (entries stop short of CASH_RESERVE_PCT of equity)
+    if cash_reserve().is_some() && cfg.enable_trading && !cfg.mock_trading && !cfg.shadow_trading {
+        let client_for_cash = Arc::clone(&client_arc);
+        supervise("cash_reserve", move || cash_reserve::spawn_balance_task(Arc::clone(&client_for_cash)));
+    }
+
+    // Periodic book snapshots of held tokens (depth_history.jsonl, see `pm_bot depth-export`)
+    if cfg.depth_snapshot_secs > 0 {
+        let (tracker_for_depth, interval) = (Arc::clone(&position_tracker), Duration::from_secs(cfg.depth_snapshot_secs));
+        let book_fetcher: Arc<dyn BookFetcher> = Arc::new(ClobPriceFetcher { client: Arc::clone(&client_arc) });
+        supervise("depth_history", move || {
+            depth_history::spawn_depth_history_task(Arc::clone(&tracker_for_depth), Arc::clone(&book_fetcher), interval)
+        });
+    }
+
+    // Rolling spread/depth/taker stats of the markets the whale enters (market_quality.jsonl)
+    if market_quality::screener().is_some() {
+        let quality_fetcher: Arc<dyn BookFetcher> = Arc::new(ClobPriceFetcher { client: Arc::clone(&client_arc) });
+        supervise("market_quality", move || market_quality::spawn_sample_task(Arc::clone(&quality_fetcher)));
+    }
 
     // Start stop-loss monitor
-    if cfg.enable_trading && !cfg.mock_trading {
+    if cfg.enable_trading && !cfg.mock_trading && !cfg.shadow_trading {
         let tracker_for_stoploss = Arc::clone(&position_tracker);
         let client_for_stoploss = Arc::clone(&client_arc);
-        let creds_for_stoploss = Arc::clone(&creds_arc);
-        tokio::spawn(stop_loss_worker(tracker_for_stoploss, client_for_stoploss, creds_for_stoploss));
-        println!("🛑 Stop-loss monitor started (5% threshold)");
+        // Live bids of held tokens trigger the stop on the update itself (EXIT_BOOK_FEED)
+        let book_rx = if cfg.exit_book_feed {
+            let (book_tx, book_rx) = mpsc::unbounded_channel();
+            let tracker_for_feed = Arc::clone(&position_tracker);
+            supervise("book_feed", move || book_feed::spawn_book_feed_task(Arc::clone(&tracker_for_feed), book_tx.clone()));
+            Some(Arc::new(tokio::sync::Mutex::new(book_rx)))
+        } else {
+            None
+        };
+        supervise("stop_loss", move || {
+            tokio::spawn(stop_loss_worker(tracker_for_stoploss.clone(), client_for_stoploss.clone(), book_rx.clone()))
+        });
+        console_println!("🛑 Stop-loss monitor started (5% threshold{})", if cfg.exit_book_feed { ", live book" } else { "" });
     }
 
+    // Nightly flat: sell down between the entry cutoff and the deadline
+    if let Some(policy) = cfg.flatten
+        && cfg.enable_trading && !cfg.mock_trading && !cfg.shadow_trading {
+            let (tracker_for_flatten, client_for_flatten) = (Arc::clone(&position_tracker), Arc::clone(&client_arc));
+            supervise("flatten", move || {
+                tokio::spawn(flatten_worker(tracker_for_flatten.clone(), client_for_flatten.clone(), policy))
+            });
+            console_println!(
+                "🌙 Flatten schedule: no entries from {}, flat by {}, resume at {}",
+                policy.cutoff.format("%H:%M"), policy.deadline.format("%H:%M"), policy.resume.format("%H:%M")
+            );
+        }
+
     let order_engine = OrderEngine {
         tx: order_tx,
         resubmit_tx,
         enable_trading: cfg.enable_trading,
     };
 
-    println!(
-        "🚀 Starting trader. Trading: {}, Mock: {}",
-        cfg.enable_trading, cfg.mock_trading
+    // `kill -USR2 <pid>` dumps component heartbeats, in-flight operations and queue depths
+    let order_queue = order_engine.tx.clone();
+    diagnostics().register_gauge("order_queue", move || order_queue.max_capacity() - order_queue.capacity());
+    let tracked = Arc::clone(&position_tracker);
+    diagnostics().register_gauge("positions", move || tracked.try_position_count().unwrap_or(0));
+    diagnostics().register_gauge("stop_loss_every_ms", || stop_loss_cadence().current().as_millis() as usize);
+    #[cfg(unix)]
+    let _diagnostics_handle = supervise("diagnostics", diagnostics::spawn_dump_on_signal);
+
+    // Rotate logs and evict stale per-token state so multi-week runs stay bounded
+    let retention_config = cfg.retention_config();
+    retention::register_usage_gauges();
+    let _retention_handle = supervise("retention", move || retention::spawn_retention_task(retention_config));
+    if let Some(archive_cfg) = cfg.archive.clone() {
+        supervise("archive", move || archive::spawn_archive_task(archive_cfg.clone()));
+    }
+
+    // Health file (HEALTH_FILE) and systemd watchdog pings while the feed and stop-loss keep reporting
+    let health_policy = cfg.health_policy();
+    if health_policy.file.is_some() || healthcheck::under_systemd() {
+        supervise("health", move || healthcheck::spawn_health_task(health_policy.clone()));
+    }
+
+    // Terminal blotter takes over stdout; console output is routed into its log panel
+    if cfg.tui {
+        #[cfg(feature = "tui")]
+        let _tui_handle = pm_whale_follower::tui::spawn_tui(Arc::clone(&position_tracker));
+        #[cfg(not(feature = "tui"))]
+        console_eprintln!("⚠️ UI_MODE=tui requested but this build lacks the `tui` feature; staying headless");
+    }
+
+    console_println!(
+        "🚀 Starting trader. Trading: {}, Mock: {}, Shadow: {}, Alloc: {}",
+        cfg.enable_trading, cfg.mock_trading, cfg.shadow_trading, pm_whale_follower::ALLOCATOR
     );
+    if cfg.shadow_trading && !cfg.mock_trading {
+        console_println!("👥 Shadow mode: orders are signed but not sent, see {}", pm_whale_follower::shadow::SHADOW_ORDERS_FILE);
+    }
+    healthcheck::sd_notify("READY=1\nSTATUS=trading");
+
+    let http_client = reqwest::Client::builder().no_proxy().build()?;
+    let dispatch = |evt: ParsedEvent| {
+        let engine = order_engine.clone();
+        let client = http_client.clone();
+        tokio::spawn(async move { handle_event(evt, &engine, &client).await });
+    };
+    if cfg.process_role == ProcessRole::Executor {
+        console_println!("🔗 Executor: whale fills come from the feed process on {}", cfg.feed_socket);
+        follow_feed_process(&cfg.feed_socket, &dispatch).await
+    } else {
+        follow_whale_feed(&cfg, &dispatch).await
+    }
+}
 
+/// Restart policy of the whale feed: it is the bot's input, so degraded means slower retries,
+/// never giving up
+fn whale_feed_policy() -> RestartPolicy {
+    RestartPolicy { degraded_retry: WS_DEGRADED_RETRY, ..RestartPolicy::default() }
+}
+
+/// Hold the whale WebSocket on the fastest provider, reconnecting forever
+async fn follow_whale_feed(cfg: &Config, dispatch: &impl Fn(ParsedEvent)) -> Result<()> {
     loop {
-        if let Err(e) = run_ws_loop(&cfg.wss_url, &order_engine).await {
-            eprintln!("⚠️ WS error: {e}. Reconnecting...");
-            tokio::time::sleep(WS_RECONNECT_DELAY).await;
+        let wss_url = latency_probe::global_latency_board()
+            .preferred_wss_url(&cfg.wss_urls)
+            .unwrap_or_else(|| cfg.wss_url.clone());
+        if let Err(e) = run_ws_loop(&wss_url, dispatch).await {
+            console_eprintln!("⚠️ WS error: {e}. Reconnecting...");
+            feed_gaps().record_disconnect(&latency_probe::redact_url(&wss_url));
+            tokio::time::sleep(supervisor::report_failure("whale_feed", &whale_feed_policy(), WS_RECONNECT_DELAY)).await;
+        }
+    }
+}
+
+/// PROCESS_ROLE=executor: take whale fills from the feed process, reconnecting forever
+#[cfg(unix)]
+async fn follow_feed_process(socket: &str, dispatch: &impl Fn(ParsedEvent)) -> Result<()> {
+    loop {
+        if let Err(e) = run_feed_client(socket, dispatch).await {
+            console_eprintln!("⚠️ Feed process: {e}. Reconnecting...");
+            feed_gaps().record_disconnect(FEED_IPC_NAME);
+            tokio::time::sleep(supervisor::report_failure("whale_feed", &whale_feed_policy(), WS_RECONNECT_DELAY)).await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn follow_feed_process(_socket: &str, _dispatch: &impl Fn(ParsedEvent)) -> Result<()> {
+    anyhow::bail!("PROCESS_ROLE=executor needs unix sockets")
+}
+
+#[cfg(unix)]
+async fn run_feed_client(socket: &str, dispatch: &impl Fn(ParsedEvent)) -> Result<()> {
+    let mut feed = feed_ipc::FeedClient::connect(socket).await?;
+    console_println!("🔌 Connected to feed process (pid {})", feed.feed_pid);
+    diagnostics().heartbeat("ws", "connected");
+    loop {
+        let fill = feed.next().await?;
+        diagnostics().heartbeat("ws", "message");
+        feed_gaps().record_message(FEED_IPC_NAME);
+        if let Some(evt) = fill {
+            dispatch(evt);
+        }
+    }
+}
+
+/// PROCESS_ROLE=feed: hold the whale WebSocket and publish parsed fills on FEED_SOCKET; no
+/// credentials, orders or positions
+#[cfg(unix)]
+async fn run_feed_process(cfg: &Config, probe_targets: Vec<ProbeTarget>) -> Result<()> {
+    latency_probe::run_probe(probe_targets.clone()).await;
+    let _latency_probe_handle = supervise("latency_probe", move || latency_probe::spawn_latency_probe_task(probe_targets.clone()));
+
+    let publisher = FeedPublisher::default();
+    let (socket, server_publisher) = (cfg.feed_socket.clone(), publisher.clone());
+    supervise("feed_server", move || feed_ipc::spawn_feed_server(socket.clone(), server_publisher.clone()));
+    let counted = publisher.clone();
+    diagnostics().register_gauge("feed_dropped", move || counted.dropped() as usize);
+    let _diagnostics_handle = supervise("diagnostics", diagnostics::spawn_dump_on_signal);
+    supervise("replay_dump", replay_buffer::spawn_dump_task);
+    let health_policy = cfg.health_policy();
+    if health_policy.file.is_some() || healthcheck::under_systemd() {
+        supervise("health", move || healthcheck::spawn_health_task(health_policy.clone()));
+    }
+
+    console_println!("📡 Feed process: publishing whale fills on {}", cfg.feed_socket);
+    healthcheck::sd_notify("READY=1\nSTATUS=feeding");
+    follow_whale_feed(cfg, &|evt: ParsedEvent| { publisher.publish(&evt); }).await
+}
+
+#[cfg(not(unix))]
+async fn run_feed_process(_cfg: &Config, _probe_targets: Vec<ProbeTarget>) -> Result<()> {
+    anyhow::bail!("PROCESS_ROLE=feed needs unix sockets")
+}
+
+// ============================================================================
+// Experiments
+// ============================================================================
+
+fn run_experiment_command(base: &Config, current: Option<&Experiment>) -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    let now = timestamp::unix_secs();
+    match args.first().map(String::as_str) {
+        Some("start") => {
+            if let Some(exp) = current.filter(|e| !e.is_over(now)) {
+                anyhow::bail!("Experiment '{}' is already running; `pm_bot experiment stop` it first", exp.name);
+            }
+            let exp = experiment::parse_start(&args[1..], base, now)?;
+            experiment::save(&exp, experiment::EXPERIMENT_FILE)?;
+            console_println!(
+                "🧪 Experiment '{}' saved for {:.1}h; restart the bot to apply it. Fills go to tag {}",
+                exp.name, (exp.ends_ts - exp.started_ts) as f64 / 3600.0, exp.tag()
+            );
+        }
+        Some("status") => match current {
+            Some(exp) => {
+                print!("{}", experiment::render_result(&experiment::results(exp, strategy_ledger(), now)));
+                console_println!("  ends in {:.1}h", exp.ends_ts.saturating_sub(now) as f64 / 3600.0);
+            }
+            None => console_println!("No experiment running"),
+        },
+        Some("stop") => {
+            let Some(exp) = current else { anyhow::bail!("No experiment running") };
+            experiment::save(&Experiment { ends_ts: now, ..exp.clone() }, experiment::EXPERIMENT_FILE)?;
+            console_println!("🧪 Experiment '{}' stopped; the running bot reverts within {}s", exp.name, experiment::EXPERIMENT_POLL_INTERVAL.as_secs());
         }
+        _ => anyhow::bail!("{}", experiment::USAGE),
     }
+    Ok(())
+}
+
+// ============================================================================
+// Doctor
+// ============================================================================
+
+async fn run_doctor(cfg: &Config, targets: Vec<ProbeTarget>) -> Result<()> {
+    console_println!("🩺 Running self-test...\n");
+    let private_key = cfg.private_key.clone();
+    let funder = cfg.funder_address.clone();
+    let results = tokio::task::spawn_blocking(move || {
+        doctor::run_checks(&private_key, &funder, clob_api_base(), credentials::CREDS_FILE, &targets)
+    }).await?;
+
+    console_println!("{}", doctor::render_table(&results));
+    if doctor::has_failures(&results) {
+        anyhow::bail!("Self-test found failures. See docs/02_SETUP_GUIDE.md");
+    }
+    console_println!("✅ All checks passed");
+    Ok(())
 }
 
 // ============================================================================
@@ -158,7 +704,7 @@ async fn build_worker_state(
 ) -> Result<(RustClobClient, ApiCreds)> {
     let cache_path = cache_path.to_string();
     let creds_path = creds_path.to_string();
-    let host = CLOB_API_BASE.to_string();
+    let host = clob_api_base().to_string();
 
     tokio::task::spawn_blocking(move || -> Result<(RustClobClient, ApiCreds)> {
         let mut client = RustClobClient::new(&host, 137, &private_key, &funder)?
@@ -171,9 +717,7 @@ async fn build_worker_state(
             let data = std::fs::read_to_string(&creds_path)?;
             serde_json::from_str(&data)?
         } else {
-            let derived = client.derive_api_key(0)?;
-            std::fs::write(&creds_path, serde_json::to_string_pretty(&derived)?)?;
-            derived
+            credentials::rederive(&client, &creds_path)?
         };
 
         Ok((client, creds))
@@ -183,32 +727,59 @@ async fn build_worker_state(
 fn start_order_worker(
     rx: mpsc::Receiver<WorkItem>,
     client: Arc<RustClobClient>,
-    creds: PreparedCreds,
     enable_trading: bool,
     mock_trading: bool,
+    shadow_trading: bool,
     risk_config: RiskGuardConfig,
     resubmit_tx: mpsc::UnboundedSender<ResubmitRequest>,
     position_tx: mpsc::UnboundedSender<PositionUpdate>,
 ) {
     std::thread::spawn(move || {
+        let mut rx = rx;
         let mut guard = RiskGuard::new(risk_config);
-        order_worker(rx, client, creds, enable_trading, mock_trading, &mut guard, resubmit_tx, position_tx);
+        // A panic drops the in-flight order's responder; the caller sees the failure and the
+        // worker resumes on the same queue with its risk state intact
+        supervisor::supervise_blocking("order_worker", RestartPolicy::default(), || {
+            order_worker(&mut rx, client.clone(), enable_trading, mock_trading, shadow_trading, &mut guard, resubmit_tx.clone(), position_tx.clone());
+        });
     });
 }
 
 fn order_worker(
-    mut rx: mpsc::Receiver<WorkItem>,
+    rx: &mut mpsc::Receiver<WorkItem>,
     client: Arc<RustClobClient>,
-    creds: PreparedCreds,
     enable_trading: bool,
     mock_trading: bool,
+    shadow_trading: bool,
     guard: &mut RiskGuard,
     resubmit_tx: mpsc::UnboundedSender<ResubmitRequest>,
     position_tx: mpsc::UnboundedSender<PositionUpdate>,
 ) {
     let mut client_mut = (*client).clone();
     while let Some(work) = rx.blocking_recv() {
-        let status = process_order(&work.event.order, &mut client_mut, &creds, enable_trading, mock_trading, guard, &resubmit_tx, &position_tx, work.is_live);
+        // An experiment that just ended or a session boundary hands over new thresholds
+        if let Some(config) = experiment::take_pending_risk_config() {
+            guard.set_config(config);
+        }
+        diagnostics().begin_op("order_worker", &work.event.order.clob_token_id);
+        // A signal that waited out a backlog is no longer the trade the whale made
+        let waited = work.queued_at.elapsed();
+        if let Some(max_age) = guard.max_signal_age()
+            && waited > max_age {
+                execution_stats().record_stale_signal();
+                let status = format!("SKIPPED_STALE_SIGNAL ({}ms queued)", waited.as_millis());
+                diagnostics().heartbeat("order_worker", &status);
+                diagnostics().end_op("order_worker");
+                let _ = work.respond_to.send(status);
+                continue;
+            }
+        let creds = credentials::current();
+        let status = process_order(&work.event.order, &work.event.intent_id(), &mut client_mut, &creds, enable_trading, mock_trading, shadow_trading, guard, &resubmit_tx, &position_tx, work.is_live, work.queued_at);
+        diagnostics().heartbeat("order_worker", &status);
+        // Filter rejections are re-priced later to measure what skipping them cost
+        let order = &work.event.order;
+        what_if().record_rejection(&status, &order.clob_token_id, order.order_type.starts_with("BUY"), order.price_per_share, order.shares * SCALING_RATIO);
+        diagnostics().end_op("order_worker");
         let _ = work.respond_to.send(status);
     }
 }
@@ -219,14 +790,17 @@ fn order_worker(
 
 fn process_order(
     info: &OrderInfo,
+    intent_id: &str,
     client: &mut RustClobClient,
     creds: &PreparedCreds,
     enable_trading: bool,
     mock_trading: bool,
+    shadow_trading: bool,
     guard: &mut RiskGuard,
     resubmit_tx: &mpsc::UnboundedSender<ResubmitRequest>,
     position_tx: &mpsc::UnboundedSender<PositionUpdate>,
     is_live: Option<bool>,
+    queued_at: Instant,
 ) -> String {
     if !enable_trading { return "SKIPPED_DISABLED".into(); }
     if mock_trading { return "MOCK_ONLY".into(); }
@@ -240,6 +814,48 @@ fn process_order(
         return format!("SKIPPED_SMALL (<{:.0} shares)", MIN_WHALE_SHARES_TO_COPY);
     }
 
+    // Market closed on an earlier order (AUTO_REMEDIATE=pause)
+    if let Some(left) = remediation::market_pauses().remaining(&info.clob_token_id, Instant::now()) {
+        return format!("SKIPPED_MARKET_PAUSED ({}s left)", left.as_secs());
+    }
+
+    // Flat-at-time-of-day: no new entries between the cutoff and the resume time
+    if side_is_buy && flatten::entries_blocked_now() {
+        return "SKIPPED_FLATTEN (after entry cutoff)".into();
+    }
+
+    // Opposite outcome of a market we already hold: the market's conflict mode decides
+    if side_is_buy
+        && let Some(policy) = conflict::conflict_policy()
+        && let Some(held) = conflict::blocking_holding(policy, &info.clob_token_id, &strategy_ledger().held_tokens()) {
+            return format!("SKIPPED_CONFLICT (holding {})", token_metadata::label(&held).unwrap_or(held));
+        }
+
+    // Burst of entries into correlated markets: cap the group within the window
+    if side_is_buy && let Some((group, n)) = clustering::blocked_entry(&info.clob_token_id) {
+        return format!("SKIPPED_CLUSTER ({} entries in {})", n, group);
+    }
+
+    // Market family with chronically wide spreads, thin books or no takers
+    if side_is_buy && let Some((family, reason)) = market_quality::blocked_entry(&info.clob_token_id) {
+        return format!("SKIPPED_QUALITY ({}: {})", family, reason);
+    }
+
+    // Book update surge on the token: journaled, and with QUOTE_SURGE_MODE boosts or skips the entry
+    let surge_boost = if side_is_buy {
+        match quote_activity::evaluate_entry(&info.clob_token_id) {
+            Ok(boost) => boost,
+            Err(ratio) => return format!("SKIPPED_QUOTE_SURGE ({:.1}x quote rate)", ratio),
+        }
+    } else {
+        1.0
+    };
+
+    // Taker flow from trade prints: journaled, and with TAKER_FLOW_MODE confirms or suppresses the entry
+    if side_is_buy && let Err(reason) = taker_flow::evaluate_entry(&info.clob_token_id) {
+        return format!("SKIPPED_TAKER_FLOW ({})", reason);
+    }
+
     // Risk guard safety check
     let eval = guard.check_fast(&info.clob_token_id, whale_shares);
     match eval.decision {
@@ -263,32 +879,212 @@ fn process_order(
     }
 
     let (buffer, order_action, size_multiplier) = get_tier_params(whale_shares, side_is_buy, &info.clob_token_id);
-
-    // Polymarket valid price range: 0.01 to 0.99 (tick size 0.01)
-    let limit_price = if side_is_buy {
-        (whale_price + buffer).min(0.99)
+    let size_multiplier = size_multiplier * surge_boost * if side_is_buy { size_ramp::multiplier() } else { 1.0 };
+    // FAK misses on this token widen the buffer a tick or two; consistent improvement narrows it
+    let buffer = if order_action == "FAK" {
+        (buffer + execution_stats().extra_offset(&info.clob_token_id)).max(0.0)
     } else {
-        (whale_price - buffer).max(0.01)
+        buffer
     };
 
-    let (my_shares, size_type) = calculate_safe_size(whale_shares, limit_price, size_multiplier);
+    // Polymarket valid price range: 0.01 to 0.99 (tick size 0.01)
+    let limit_price = signal_math::limit_price(whale_price, buffer, side_is_buy);
+
+    let mins = if side_is_buy { guard.order_minimums() } else { OrderMinimums::EXCHANGE };
+    let (my_shares, size_type) = calculate_safe_size(whale_shares, limit_price, size_multiplier, &mins);
     if my_shares == 0.0 {
         return format!("SKIPPED_PROBABILITY ({})", size_type);
     }
+
+    // One book fetch serves the ask re-check, the impact cap and the R/R gate
+    let rr_gate = reward_risk::rr_gate().filter(|_| side_is_buy);
+    let book = if side_is_buy && !guard.latency_mode() && (guard.slippage_budget().is_some() || guard.depth_cap().is_some() || rr_gate.is_some()) {
+        fetch_book_levels_blocking(client, &info.clob_token_id)
+    } else {
+        Err("NOT_NEEDED")
+    };
+
+    // Fair probability at entry for settlement attribution: the book mid, else the whale's print
+    let fair = match &book {
+        Ok((bids, asks)) => bids.iter().map(|l| l.0).reduce(f64::max)
+            .zip(asks.iter().map(|l| l.0).reduce(f64::min))
+            .map(|(bid, ask)| (bid + ask) / 2.0),
+        Err(_) => None,
+    }.unwrap_or(whale_price);
+
+    // Ask moved since the whale's fill: pay up to the slippage budget over the whale's price, else abort
+    let mut requote_msg: Option<String> = None;
+    let limit_price = match (guard.slippage_budget(), &book) {
+        (Some(budget), Ok((_, asks))) if side_is_buy => match asks.iter().map(|l| l.0).reduce(f64::min) {
+            Some(best_ask) => match check_ask_move(whale_price, limit_price, best_ask, budget) {
+                AskMove::Keep => {
+                    execution_stats().record_ask_check(false, false);
+                    limit_price
+                }
+                AskMove::Requote(price) => {
+                    execution_stats().record_ask_check(true, false);
+                    let tok = &info.clob_token_id;
+                    requote_msg = Some(format!(" | REQUOTED {}->{}", display::price(tok, limit_price), display::price(tok, price)));
+                    price
+                }
+                AskMove::Abort => {
+                    execution_stats().record_ask_check(false, true);
+                    let tok = &info.clob_token_id;
+                    return format!(
+                        "SKIPPED_ASK_MOVED (ask {} > whale {} + {})",
+                        display::price(tok, best_ask), display::price(tok, whale_price), display::price(tok, budget)
+                    );
+                }
+            },
+            None => limit_price,
+        },
+        _ => limit_price,
+    };
+
+    // Round-trip cost budget: the entry may only use its part of it over the whale's price
+    let limit_price = match cost_budget::cost_budget() {
+        Some(budget) if side_is_buy && limit_price > budget.entry_cap(whale_price) + 1e-9 => {
+            let tick = token_metadata::tick_size(&info.clob_token_id).parse().unwrap_or(0.01);
+            let capped = cost_budget::floor_to_tick(budget.entry_cap(whale_price) + 1e-9, tick).max(whale_price);
+            cost_tracker().record_cap();
+            let tok = &info.clob_token_id;
+            requote_msg = Some(format!(
+                "{} | COST_CAPPED {}->{}",
+                requote_msg.unwrap_or_default(), display::price(tok, limit_price), display::price(tok, capped)
+            ));
+            capped
+        }
+        _ => limit_price,
+    };
+
+    // Reward/risk gate: reward to the take-profit vs loss to the stop, both after the exit spread
+    let mut rr_estimate = None;
+    if let (Some(gate), Ok((bids, asks))) = (rr_gate, &book) {
+        let best_bid = bids.iter().map(|l| l.0).reduce(f64::max);
+        let best_ask = asks.iter().map(|l| l.0).reduce(f64::min);
+        if let (Some(bid), Some(ask)) = (best_bid, best_ask) {
+            let est = gate.estimate(limit_price, bid, ask);
+            if est.ratio < gate.min_ratio {
+                rr_calibration().record_skip();
+                return format!("SKIPPED_RR ({:.2} < {:.2}, spread {:.2})", est.ratio, gate.min_ratio, est.spread);
+            }
+            rr_estimate = Some(est);
+        }
+    }
+
+    // Size and passive price jitter on entries, journaled when the order is sent
+    let mut jitter_rec = None;
+    let (my_shares, limit_price) = match guard.jitter() {
+        j if side_is_buy && !j.is_off() => {
+            let mut rng = rand::thread_rng();
+            let tick = token_metadata::tick_size(&info.clob_token_id).parse().unwrap_or(0.01);
+            let price = j.price(limit_price, tick, true, rng.r#gen::<f64>());
+            let shares = j.size(my_shares, mins.floor(price), rng.r#gen::<f64>());
+            if (shares, price) != (my_shares, limit_price) {
+                jitter_rec = Some(jitter::JitterRecord::new(intent_id, &info.clob_token_id, (my_shares, limit_price), (shares, price)));
+            }
+            (shares, price)
+        }
+        _ => (my_shares, limit_price),
+    };
+
+    // Market impact guard: an entry never takes more than a fraction of the visible asks at our limit
+    let mut depth_msg: Option<String> = None;
+    let my_shares = match guard.depth_cap() {
+        Some((fraction, levels)) if side_is_buy => match &book {
+            Ok((_, asks)) => {
+                let visible = visible_depth_shares(TradeSide::Buy, asks, limit_price, levels);
+                let capped = signal_math::impact_capped(my_shares, visible, fraction);
+                execution_stats().record_depth_check(capped < my_shares);
+                if capped < my_shares {
+                    if capped * limit_price < MIN_CASH_VALUE {
+                        return format!("SKIPPED_DEPTH_CAP ({:.2} of {:.2} visible < ${:.2})", capped, visible, MIN_CASH_VALUE);
+                    }
+                    depth_msg = Some(format!(" | DEPTH_CAPPED {:.2}->{:.2} ({:.2} visible)", my_shares, capped, visible));
+                }
+                capped
+            }
+            // No book: size unchanged, the circuit breaker already guards thin books on large trades
+            Err(_) => my_shares,
+        },
+        _ => my_shares,
+    };
+
+    // First live entries are held to LIVE_WARMUP_MAX_USD
+    let mut warmup_msg: Option<String> = None;
+    let my_shares = match live_interlock::warmup().and_then(|w| w.entry_cap_usd()) {
+        Some(max_usd) if side_is_buy && my_shares * limit_price > max_usd => {
+            let capped = signal_math::floor_cents_for_usd(max_usd, limit_price);
+            warmup_msg = Some(format!(" | WARMUP_CAPPED {:.2}->{:.2}", my_shares, capped));
+            capped
+        }
+        _ => my_shares,
+    };
+
+    // Entries too small to be worth holding are rejected rather than left as dust
+    if side_is_buy && let Err(reason) = mins.check(my_shares, limit_price) {
+        return format!("SKIPPED_DUST ({})", reason);
+    }
+
+    // Reject orders the exchange would refuse anyway (min size known from token metadata)
+    if let Some(meta) = token_metadata::get(&info.clob_token_id)
+        && my_shares < meta.min_order_size {
+            return format!("SKIPPED_MIN_SIZE ({:.2} < {:.2} shares)", my_shares, meta.min_order_size);
+        }
     
+    // Pre-trade compliance checks (COMPLIANCE_* rules and any registered by an embedder)
+    if let Err(reason) = compliance::check(&info.clob_token_id, side_is_buy, limit_price, my_shares) {
+        return format!("COMPLIANCE_BLOCKED ({})", reason);
+    }
+
     // FAK orders need expiration "0", GTD orders need a future timestamp
     let expiration = if order_action == "GTD" {
         let expiry_secs = get_gtd_expiry_secs(is_live.unwrap_or(false));
-        let expiry_timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() + expiry_secs;
+        let expiry_timestamp = timestamp::unix_secs() + expiry_secs;
         Some(expiry_timestamp.to_string())
     } else {
         Some("0".into())
     };
 
-    let args = OrderArgs {
+    // Per-strategy exposure cap (the wallet may be shared with other tagged instances)
+    if side_is_buy
+        && let Err(open) = strategy_ledger().check_entry(my_shares * limit_price) {
+            return format!(
+                "SKIPPED_STRATEGY_CAP ({} open ${:.2} + ${:.2} > ${:.2})",
+                strategy_ledger().tag(), open, my_shares * limit_price, strategy_ledger().max_open_usd().unwrap_or(0.0)
+            );
+        }
+
+    // Account-level cash reserve, kept for exits and re-entries
+    if side_is_buy
+        && let Some(reserve) = cash_reserve()
+        && let Err((cash, kept)) = reserve.check_entry(my_shares * limit_price, cash_reserve::wallet_open_cost()) {
+            return format!(
+                "SKIPPED_CASH_RESERVE (cash {} - {} < reserve {})",
+                display::usd(cash), display::usd(my_shares * limit_price), display::usd(kept)
+            );
+        }
+
+    // Wallet-wide worst case: the premium still at risk if every held token resolves to zero
+    if side_is_buy
+        && let Err(at_risk) = capital_at_risk::check_entry(capital_at_risk::limit(), capital_at_risk::current().at_risk_usd, my_shares * limit_price) {
+            return format!(
+                "SKIPPED_CAPITAL_AT_RISK (at risk {} + {} > {})",
+                display::usd(at_risk), display::usd(my_shares * limit_price), display::usd(capital_at_risk::limit().unwrap_or(0.0))
+            );
+        }
+
+    // Lifecycle gate: no entry while an exit is working on this token (and vice versa)
+    let gate = if side_is_buy {
+        asset_states().try_begin_entry(&info.clob_token_id)
+    } else {
+        asset_states().try_begin_exit(&info.clob_token_id)
+    };
+    if let Err(phase) = gate {
+        return format!("SKIPPED_BUSY ({})", phase.as_str());
+    }
+
+    let mut args = OrderArgs {
         token_id: info.clob_token_id.to_string(),  
         price: limit_price,
         size: (my_shares * 100.0).floor() / 100.0,  
@@ -300,57 +1096,163 @@ fn process_order(
         order_type: Some(order_action.to_string()),
     };
 
-    match client.create_order(args).and_then(|signed| {
-        let body = signed.post_body(&creds.api_key, order_action);
-        client.post_order_fast(body, creds)
-    }) {
-        Ok(resp) => {
-            let status = resp.status();
-            let body_text = resp.text().unwrap_or_default();
-
-            let order_resp: Option<OrderResponse> = if status.is_success() {
-                serde_json::from_str(&body_text).ok()
+    // Shadow mode: identical build/sign path, recorded instead of posted
+    if shadow_trading {
+        let recorded = client.create_order(args).and_then(|signed| {
+            let body = signed.post_body(&creds.api_key, order_action);
+            let (url, headers) = client.prepare_order_post(&body, creds)?;
+            pm_whale_follower::shadow::record_order(&url, &headers, &body)
+        });
+        if side_is_buy {
+            asset_states().finish_entry(&info.clob_token_id, false);
+        } else {
+            asset_states().finish_exit(&info.clob_token_id, false);
+        }
+        return match recorded {
+            Ok(()) => format!(
+                "SHADOW_ONLY [{}] | {} @ {} {}",
+                size_type, display::shares(my_shares), display::price(&info.clob_token_id, limit_price), order_action
+            ),
+            Err(e) => format!("SHADOW_FAIL: {}", e),
+        };
+    }
+
+    // Hard deadline: an entry that could not go out in time would trade a book that has moved on
+    let since_signal = queued_at.elapsed();
+    if let Some(deadline) = guard.submit_deadline()
+        && since_signal > deadline {
+            execution_stats().record_deadline_miss();
+            if side_is_buy {
+                asset_states().finish_entry(&info.clob_token_id, false);
             } else {
-                None
-            };
+                asset_states().finish_exit(&info.clob_token_id, false);
+            }
+            return format!("SKIPPED_DEADLINE ({:.1}ms since signal)", since_signal.as_secs_f64() * 1000.0);
+        }
+    execution_stats().record_submit_latency(since_signal);
+
+    // Journal the intent before anything reaches the exchange (the probe included) so a replayed
+    // event or a restart cannot double-enter. Its size still counts a probe sent under it
+    let intent = intents::new_intent(intent_id, &info.clob_token_id, &args.side, limit_price, args.size);
+    if let Err(existing) = intents::intent_journal().begin(intent) {
+        if side_is_buy {
+            asset_states().finish_entry(&info.clob_token_id, false);
+        } else {
+            asset_states().finish_exit(&info.clob_token_id, false);
+        }
+        return format!("SKIPPED_DUPLICATE_INTENT ({} unresolved since {})", existing.id, existing.ts);
+    }
+
+    // Soft entry: a small FAK probe at the same limit must fill in full before the rest is sent
+    let mut probe_msg: Option<String> = None;
+    if side_is_buy && order_action == "FAK" && !guard.latency_mode()
+        && let Some(policy) = probe::probe_policy()
+        && let Some(probe_size) = policy.probe_size(args.size, limit_price, MIN_SHARE_COUNT, MIN_CASH_VALUE) {
+            let (filled, fill_price) = send_probe(client, creds, &args, probe_size, &format!("{}:probe", intent_id))
+                .unwrap_or((0.0, 0.0));
+            let verdict = policy.verdict(probe_size, filled, fill_price, limit_price);
+            probe_stats().record(verdict, fill_price, whale_price);
+            if filled > 0.0 {
+                execution_stats().record_fill(&info.clob_token_id, true, limit_price, fill_price);
+                execution_stats().record_taker(filled * fill_price);
+                blotter().record_fill(FillRow {
+                    at: SystemTime::now(),
+                    token_id: info.clob_token_id.to_string(),
+                    side: "BUY",
+                    shares: filled,
+                    price: fill_price,
+                    whale_shares,
+                });
+                strategy_ledger().record(&info.clob_token_id, true, filled, fill_price);
+                markout::record_fill(&info.clob_token_id, true, fill_price, filled);
+                if let Some(reserve) = cash_reserve() {
+                    reserve.commit(filled * fill_price);
+                }
+                fair_at_entry().record_entry(&info.clob_token_id, filled, fair);
+                cost_budget::record_entry(&info.clob_token_id, filled, fill_price, whale_price);
+                let _ = position_tx.send(PositionUpdate {
+                    token_id: info.clob_token_id.to_string(),
+                    entry_price: fill_price,
+                    shares: filled,
+                    is_buy: true,
+                });
+            }
+            if verdict != ProbeVerdict::Pass {
+                if let Some(est) = rr_estimate.filter(|_| filled > 0.0) {
+                    rr_calibration().record_entry(&info.clob_token_id, reward_risk::RrEstimate { entry: fill_price, ..est });
+                }
+                intents::intent_journal().resolve(intent_id, &format!("PROBE_{:?}", verdict).to_uppercase());
+                asset_states().finish_entry(&info.clob_token_id, filled > 0.0);
+                return format!(
+                    "SKIPPED_PROBE ({:?} {}/{} @ {})",
+                    verdict, display::shares(filled), display::shares(probe_size), display::price(&info.clob_token_id, limit_price)
+                );
+            }
+            args.size = ((args.size - filled) * 100.0).floor() / 100.0;
+            probe_msg = Some(format!(" | PROBE {} @ {}", display::shares(filled), display::avg_price(&info.clob_token_id, fill_price)));
+        }
+    if let Some(rec) = &jitter_rec {
+        jitter::record(rec);
+    }
+
+    let mut posted_at = None;
+    let mut sent = args.clone();
+    let mut result = post_copy_order(client, creds, args, order_action, intent_id, &mut posted_at);
+    // AUTO_REMEDIATE: a re-priced or resized order goes out once more under the same intent
+    let mut remedy_msg: Option<String> = None;
+    if let Ok(reply) = &result
+        && let Some((retry, note)) = remediate(reply, &sent, &mins, client, creds) {
+            sent = retry.clone();
+            result = post_copy_order(client, creds, retry, order_action, intent_id, &mut posted_at);
+            remedy_msg = Some(note);
+        }
+    // What the main order asked for (less a passed probe, after remediation)
+    let (my_shares, limit_price) = (sent.size, sent.price);
+    match result {
+        Ok(reply) => {
+            if let Some(at) = posted_at {
+                execution_stats().record_ack_latency(at.elapsed());
+            }
+            intents::intent_journal().resolve(intent_id, &reply.status.to_string());
+            let order_resp = reply.accepted();
+            let no_match = reply.error() == Some(OrderError::NoMatch);
 
             let mut underfill_msg: Option<String> = None;
-            if let Some(ref resp) = order_resp {
-                if side_is_buy && order_action == "FAK" {
-                    let filled_shares: f64 = resp.taking_amount.parse().unwrap_or(0.0);
-                    let requested_shares = (my_shares * 100.0).floor() / 100.0;
-
-                    if filled_shares < requested_shares && filled_shares > 0.0 {
-                        let remaining_shares = requested_shares - filled_shares;
-
-                        let min_threshold = MIN_SHARE_COUNT.max(MIN_CASH_VALUE / limit_price);
-                        if remaining_shares >= min_threshold {
-                            let resubmit_buffer = get_resubmit_max_buffer(whale_shares);
-                            let max_price = (limit_price + resubmit_buffer).min(0.99);
-                            let req = ResubmitRequest {
-                                token_id: info.clob_token_id.to_string(),
-                                whale_price,
-                                failed_price: limit_price,  // Start at same price (already filled some)
-                                size: (remaining_shares * 100.0).floor() / 100.0,
-                                whale_shares,
-                                side_is_buy: true,
-                                attempt: 1,
-                                max_price,
-                                cumulative_filled: filled_shares,
-                                original_size: requested_shares,
-                                is_live: is_live.unwrap_or(false),
-                            };
-                            let _ = resubmit_tx.send(req);
-                            underfill_msg = Some(format!(
-                                " | \x1b[33mUNDERFILL: {:.2}/{:.2} filled, resubmit {:.2}\x1b[0m",
-                                filled_shares, my_shares, remaining_shares
-                            ));
-                        }
+            if let Some(resp) = order_resp
+                && side_is_buy && order_action == "FAK" {
+                let filled_shares: f64 = resp.taking_amount.parse().unwrap_or(0.0);
+                let requested_shares = (my_shares * 100.0).floor() / 100.0;
+
+                if filled_shares < requested_shares && filled_shares > 0.0 {
+                    let remaining_shares = requested_shares - filled_shares;
+
+                    let min_threshold = mins.floor(limit_price);
+                    if remaining_shares >= min_threshold {
+                        let resubmit_buffer = get_resubmit_max_buffer(whale_shares);
+                        let max_price = (limit_price + resubmit_buffer).min(0.99);
+                        let req = ResubmitRequest {
+                            token_id: info.clob_token_id.to_string(),
+                            whale_price,
+                            failed_price: limit_price,  // Start at same price (already filled some)
+                            size: (remaining_shares * 100.0).floor() / 100.0,
+                            whale_shares,
+                            side_is_buy: true,
+                            attempt: 1,
+                            max_price,
+                            cumulative_filled: filled_shares,
+                            original_size: requested_shares,
+                            is_live: is_live.unwrap_or(false),
+                        };
+                        let _ = resubmit_tx.send(req);
+                        underfill_msg = Some(format!(
+                            " | \x1b[33mUNDERFILL: {:.2}/{:.2} filled, resubmit {:.2}\x1b[0m",
+                            filled_shares, my_shares, remaining_shares
+                        ));
                     }
                 }
             }
 
-            if status.as_u16() == 400 && body_text.contains("FAK") && side_is_buy {
+            if no_match && side_is_buy {
                 let resubmit_buffer = get_resubmit_max_buffer(whale_shares);
                 let max_price = (limit_price + resubmit_buffer).min(0.99);
                 let rounded_size = (my_shares * 100.0).floor() / 100.0;
@@ -371,18 +1273,69 @@ fn process_order(
             }
 
             // Extract filled shares and actual fill price for display (reuse parsed response)
-            let (filled_shares, actual_fill_price) = order_resp.as_ref()
-                .and_then(|r| {
-                    let taking: f64 = r.taking_amount.parse().ok()?;
-                    let making: f64 = r.making_amount.parse().ok()?;
-                    if taking > 0.0 { Some((taking, making / taking)) } else { None }
-                })
+            let (filled_shares, actual_fill_price) = order_resp
+                .and_then(OrderResponse::filled)
                 .unwrap_or_else(|| {
-                    if status.is_success() { (my_shares, limit_price) } else { (0.0, limit_price) }
+                    if reply.is_success() { (my_shares, limit_price) } else { (0.0, limit_price) }
                 });
 
+            // Maker/taker split: resting GTDs may earn the maker rebate, matched orders paid to take
+            match order_resp {
+                Some(r) if r.is_resting() => execution_stats().record_maker(my_shares * limit_price),
+                Some(_) if filled_shares > 0.0 => execution_stats().record_taker(filled_shares * actual_fill_price),
+                _ => {}
+            }
+
+            if order_action == "FAK" {
+                if reply.is_success() && filled_shares > 0.0 {
+                    execution_stats().record_fill(&info.clob_token_id, side_is_buy, limit_price, actual_fill_price);
+                } else if no_match {
+                    execution_stats().record_miss(&info.clob_token_id);
+                }
+            }
+
+            if side_is_buy {
+                asset_states().finish_entry(&info.clob_token_id, reply.is_success() && filled_shares > 0.0);
+            } else {
+                asset_states().finish_exit(&info.clob_token_id, reply.is_success());
+            }
+
+            if reply.is_success() && filled_shares > 0.0 {
+                blotter().record_fill(FillRow {
+                    at: SystemTime::now(),
+                    token_id: info.clob_token_id.to_string(),
+                    side: if side_is_buy { "BUY" } else { "SELL" },
+                    shares: filled_shares,
+                    price: actual_fill_price,
+                    whale_shares,
+                });
+                strategy_ledger().record(&info.clob_token_id, side_is_buy, filled_shares, actual_fill_price);
+                markout::record_fill(&info.clob_token_id, side_is_buy, actual_fill_price, filled_shares);
+                if side_is_buy && let Some(reserve) = cash_reserve() {
+                    reserve.commit(filled_shares * actual_fill_price);
+                }
+                if side_is_buy {
+                    clustering::record_entry(&info.clob_token_id);
+                    live_interlock::record_entry();
+                    if let Some(ramp) = size_ramp::size_ramp() {
+                        ramp.record_entry(actual_fill_price, whale_price);
+                    }
+                }
+                // Keep the entry's R/R estimate until a sell closes it
+                if !side_is_buy {
+                    reward_risk::record_exit(&info.clob_token_id, actual_fill_price);
+                    cost_budget::record_exit(&info.clob_token_id, actual_fill_price, whale_price);
+                } else {
+                    fair_at_entry().record_entry(&info.clob_token_id, filled_shares, fair);
+                    cost_budget::record_entry(&info.clob_token_id, filled_shares, actual_fill_price, whale_price);
+                    if let Some(est) = rr_estimate {
+                        rr_calibration().record_entry(&info.clob_token_id, reward_risk::RrEstimate { entry: actual_fill_price, ..est });
+                    }
+                }
+            }
+
             // Track position for stop-loss monitoring (only for successful buys)
-            if status.is_success() && side_is_buy && filled_shares > 0.0 {
+            if reply.is_success() && side_is_buy && filled_shares > 0.0 {
                 let _ = position_tx.send(PositionUpdate {
                     token_id: info.clob_token_id.to_string(),
                     entry_price: actual_fill_price,
@@ -396,46 +1349,136 @@ fn process_order(
             let reset = "\x1b[0m";
             let fill_color = get_fill_color(filled_shares, my_shares);
             let whale_color = get_whale_size_color(whale_shares);
-            let status_str = if status.is_success() { "200 OK" } else { "FAILED" };
+            let status_str = if reply.is_success() { "200 OK" } else { "FAILED" };
             let mut base = format!(
-                "{} [{}] | {}{:.2}/{:.2}{} filled @ {}{:.2}{} | {}whale {:.1}{} @ {:.2}",
-                status_str, size_type, fill_color, filled_shares, my_shares, reset, pink, actual_fill_price, reset, whale_color, whale_shares, reset, whale_price
+                "{} [{}] | {}{}/{}{} filled @ {}{}{} | {}whale {:.1}{} @ {}",
+                status_str, size_type, fill_color, display::shares(filled_shares), display::shares(my_shares), reset,
+                pink, display::avg_price(&info.clob_token_id, actual_fill_price), reset,
+                whale_color, whale_shares, reset, display::price(&info.clob_token_id, whale_price)
             );
             if let Some(msg) = underfill_msg {
                 base.push_str(&msg);
             }
-            if !status.is_success() {
-                base.push_str(&format!(" | {}", body_text));
+            if let Some(msg) = depth_msg {
+                base.push_str(&msg);
+            }
+            if let Some(msg) = warmup_msg {
+                base.push_str(&msg);
+            }
+            if let Some(msg) = requote_msg {
+                base.push_str(&msg);
+            }
+            if let Some(msg) = probe_msg {
+                base.push_str(&msg);
+            }
+            if let Some(msg) = remedy_msg {
+                base.push_str(&msg);
+            }
+            if let Some(rejection) = reply.rejection() {
+                let remedy = Remediation::for_error(rejection.kind);
+                let auto = if remediation::remediation_policy().applies(remedy) { "auto " } else { "" };
+                base.push_str(&format!(" | {} [{}{}: {}]", rejection, auto, remedy.as_str(), remediation::hint(rejection.kind)));
             }
             base
         }
         Err(e) => {
+            if side_is_buy {
+                asset_states().finish_entry(&info.clob_token_id, false);
+            } else {
+                asset_states().finish_exit(&info.clob_token_id, false);
+            }
+            // The POST may have reached the exchange (e.g. timeout), so the intent stays open
+            // until startup reconciliation checks trade history
             let chain: Vec<_> = e.chain().map(|c| c.to_string()).collect();
             format!("EXEC_FAIL: {} | chain: {}", e, chain.join(" -> "))
         }
     }
 }
 
-fn calculate_safe_size(whale_shares: f64, price: f64, size_multiplier: f64) -> (f64, SizeType) {
-    let target_scaled = whale_shares * SCALING_RATIO * size_multiplier;
-    let safe_price = price.max(0.0001);
-    let required_floor = (MIN_CASH_VALUE / safe_price).max(MIN_SHARE_COUNT);
+/// Sign and post a copy order, journaling its hash under `intent_id` and noting when it went out
+fn post_copy_order(
+    client: &mut RustClobClient,
+    creds: &PreparedCreds,
+    args: OrderArgs,
+    order_action: &str,
+    intent_id: &str,
+    posted_at: &mut Option<Instant>,
+) -> Result<OrderReply> {
+    client.create_order(args).and_then(|signed| {
+        intents::intent_journal().signed(intent_id, &signed.order_hash);
+        let body = signed.post_body(&creds.api_key, order_action);
+        *posted_at = Some(Instant::now());
+        client.post_order(body, creds)
+    })
+}
 
-    if target_scaled >= required_floor {
-        return (target_scaled, SizeType::Scaled);
+/// Apply the automatic remediation for a rejected copy order: pause a closed market, or return
+/// the order to send once more (re-priced onto the named tick, or resized to the balance) with
+/// a note for the status line
+fn remediate(reply: &OrderReply, args: &OrderArgs, mins: &OrderMinimums, client: &RustClobClient, creds: &PreparedCreds) -> Option<(OrderArgs, String)> {
+    let rejection = reply.rejection()?;
+    let remedy = Remediation::for_error(rejection.kind);
+    let policy = remediation::remediation_policy();
+    if !policy.applies(remedy) {
+        return None;
     }
-
-    if !USE_PROBABILISTIC_SIZING {
-        return (required_floor, SizeType::Scaled);
+    let is_buy = args.side == "BUY";
+    let tok = &args.token_id;
+    match remedy {
+        Remediation::PauseMarket => {
+            remediation::market_pauses().pause(tok, policy.pause?, Instant::now());
+            None
+        }
+        Remediation::Reprice => {
+            let tick = remediation::rejected_tick(rejection)?;
+            let price = remediation::repriced(args.price, tick, is_buy);
+            if (price - args.price).abs() < 1e-9 || !(0.0..1.0).contains(&price) || price <= 0.0 {
+                return None;
+            }
+            // Later orders on this token round to the tick the exchange named
+            if let Some(mut meta) = token_metadata::get(tok)
+                && let Some(tick_str) = token_metadata::tick_size_str(tick) {
+                    meta.tick_size = tick_str.to_string();
+                    token_metadata::global_token_metadata().insert_all(vec![meta]);
+                }
+            let note = format!(" | REPRICED {}->{} (tick {})", display::price(tok, args.price), display::price(tok, price), tick);
+            Some((OrderArgs { price, ..args.clone() }, note))
+        }
+        Remediation::Resize if is_buy => {
+            let balance = cash_reserve::fetch_balance(client, creds)?;
+            let shares = remediation::resized(balance, args.price);
+            if shares >= args.size || mins.check(shares, args.price).is_err() {
+                return None;
+            }
+            let note = format!(" | RESIZED {}->{} (balance {})", display::shares(args.size), display::shares(shares), display::usd(balance));
+            Some((OrderArgs { size: shares, ..args.clone() }, note))
+        }
+        _ => None,
     }
+}
 
-    let probability = target_scaled / required_floor;
-    let pct = (probability * 100.0) as u8;
-    if rand::thread_rng().r#gen::<f64>() < probability {
-        (required_floor, SizeType::ProbHit(pct))
-    } else {
-        (0.0, SizeType::ProbSkip(pct))
-    }
+/// Post a FAK probe of `size` with the entry's price; returns (filled shares, average price).
+/// Journaled under its own intent id so a restart cannot resend it
+fn send_probe(
+    client: &mut RustClobClient,
+    creds: &PreparedCreds,
+    args: &OrderArgs,
+    size: f64,
+    probe_id: &str,
+) -> Result<(f64, f64)> {
+    let probe = OrderArgs { size, ..args.clone() };
+    intents::intent_journal()
+        .begin(intents::new_intent(probe_id, &probe.token_id, &probe.side, probe.price, size))
+        .map_err(|existing| anyhow!("probe {} unresolved since {}", existing.id, existing.ts))?;
+    let signed = client.create_order(probe)?;
+    intents::intent_journal().signed(probe_id, &signed.order_hash);
+    let reply = client.post_order(signed.post_body(&creds.api_key, "FAK"), creds)?;
+    intents::intent_journal().resolve(probe_id, &reply.status.to_string());
+    Ok(reply.accepted().and_then(OrderResponse::filled).unwrap_or((0.0, 0.0)))
+}
+
+fn calculate_safe_size(whale_shares: f64, price: f64, size_multiplier: f64, mins: &OrderMinimums) -> (f64, SizeType) {
+    signal_math::safe_size(whale_shares, price, size_multiplier, rand::thread_rng().r#gen::<f64>(), mins)
 }
 
 /// Get ANSI color code based on fill percentage
@@ -465,7 +1508,7 @@ fn fetch_book_depth_blocking(
     side: TradeSide,
     threshold: f64,
 ) -> Result<f64, &'static str> {
-    let url = format!("{}/book?token_id={}", CLOB_API_BASE, token_id);
+    let url = format!("{}/book?token_id={}", clob_api_base(), token_id);
     let resp = client.http_client()
         .get(&url)
         .timeout(Duration::from_millis(500))
@@ -495,16 +1538,35 @@ fn fetch_book_depth_blocking(
     Ok(calc_liquidity_depth(side, &levels[..count], threshold))
 }
 
+/// Every (price, size) level as (bids, asks) (allocates; only used by the depth cap and R/R gate)
+fn fetch_book_levels_blocking(
+    client: &RustClobClient,
+    token_id: &str,
+) -> Result<depth_history::Book, &'static str> {
+    let url = format!("{}/book?token_id={}", clob_api_base(), token_id);
+    let resp = client.http_client()
+        .get(&url)
+        .timeout(Duration::from_millis(500))
+        .send()
+        .map_err(|_| "NETWORK")?;
+    if !resp.status().is_success() { return Err("HTTP_ERROR"); }
+
+    let book: Value = resp.json().map_err(|_| "PARSE")?;
+    Ok(depth_history::parse_book(&book))
+}
+
 // ============================================================================
 // Position Tracking & Stop-Loss
 // ============================================================================
 
 /// Receives position updates from order worker and updates the tracker
 async fn position_update_worker(
-    mut rx: mpsc::UnboundedReceiver<PositionUpdate>,
+    rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<PositionUpdate>>>,
     tracker: Arc<PositionTracker>,
 ) {
+    let mut rx = rx.lock().await;
     while let Some(update) = rx.recv().await {
+        diagnostics().heartbeat("positions", &update.token_id);
         if update.is_buy {
             tracker.add_position(update.token_id, update.entry_price, update.shares).await;
         } else {
@@ -513,78 +1575,233 @@ async fn position_update_worker(
     }
 }
 
+/// (token, best bid) updates from the book feed, shared across stop-loss worker restarts
+type BidUpdates = Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<(String, f64)>>>;
+
 /// Background worker that checks positions for stop-loss triggers
 async fn stop_loss_worker(
     tracker: Arc<PositionTracker>,
     client: Arc<RustClobClient>,
-    creds: Arc<PreparedCreds>,
+    book_rx: Option<BidUpdates>,
 ) {
     let price_fetcher = ClobPriceFetcher { client: client.clone() };
-    let mut interval = tokio::time::interval(Duration::from_secs(STOP_LOSS_CHECK_INTERVAL_SECS));
+    let cadence = stop_loss_cadence();
+    let mut last_prices: FxHashMap<String, f64> = FxHashMap::default();
+    let mut next_check = tokio::time::Instant::now() + cadence.current();
+    let mut book_rx = match &book_rx {
+        Some(rx) => Some(rx.lock().await),
+        None => None,
+    };
     
     loop {
-        interval.tick().await;
+        // Book updates of held tokens are checked as they arrive, the poll below covers the rest
+        if let Some(rx) = book_rx.as_mut() {
+            tokio::select! {
+                Some((token_id, best_bid)) = rx.recv() => {
+                    last_prices.insert(token_id.clone(), best_bid);
+                    if let Some(position) = tracker.get_position(&token_id).await
+                        && position.should_stop_loss(best_bid) {
+                            trigger_stop_loss(position, best_bid, "book", &client, &credentials::current(), &tracker);
+                        }
+                    continue;
+                }
+                _ = tokio::time::sleep_until(next_check) => {}
+            }
+        } else {
+            tokio::time::sleep_until(next_check).await;
+        }
+        diagnostics().heartbeat("stop_loss", "check");
         
         let positions = tracker.get_all_positions().await;
         if positions.is_empty() {
+            last_prices.clear();
+            next_check = tokio::time::Instant::now() + cadence.next(None, false);
             continue;
         }
         
+        // Check faster near a stop and while prices move, back off while they don't
+        let (mut nearest, mut moved) = (f64::MAX, false);
+        let token_ids: Vec<String> = positions.iter().map(|p| p.token_id.clone()).collect();
+        let bids: FxHashMap<String, f64> = price_fetcher.fetch_books(&token_ids).await
+            .into_iter()
+            .filter_map(|(token_id, book)| Some((token_id, depth_history::best_bid(&book)?)))
+            .collect();
         for position in positions {
-            // Fetch current price
-            if let Some(current_price) = price_fetcher.get_current_price(&position.token_id).await {
-                let pnl_pct = position.pnl_pct(current_price) * 100.0;
+            if let Some(&current_price) = bids.get(&position.token_id) {
+                moved |= last_prices.insert(position.token_id.clone(), current_price)
+                    .is_none_or(|prev| (prev - current_price).abs() > 1e-9);
+                nearest = nearest.min(position.pnl_pct(current_price) + STOP_LOSS_PCT);
                 
                 // Check if stop-loss should trigger
                 if position.should_stop_loss(current_price) {
-                    println!(
-                        "🛑 STOP-LOSS TRIGGERED: {} | entry: {:.4} | current: {:.4} | P&L: {:.2}% | shares: {:.2}",
-                        position.token_id, position.entry_price, current_price, pnl_pct, position.shares
+                    trigger_stop_loss(position, current_price, "poll", &client, &credentials::current(), &tracker);
+                }
+            }
+        }
+        next_check = tokio::time::Instant::now() + cadence.next(Some(nearest), moved);
+    }
+}
+
+/// Sell a position whose stop was hit at `current_price` (seen by `source`: poll or book)
+fn trigger_stop_loss(
+    position: Position,
+    current_price: f64,
+    source: &str,
+    client: &Arc<RustClobClient>,
+    creds: &Arc<PreparedCreds>,
+    tracker: &Arc<PositionTracker>,
+) {
+    // Dust can't be sold, and a failed exit waits out its backoff; both would otherwise be
+    // retried (and logged) on every book update
+    if sellable_shares(position.shares).is_none() || asset_states().exit_backoff(&position.token_id).is_some() {
+        return;
+    }
+    // Skip if an entry or another exit is already working on this token
+    if let Err(phase) = asset_states().try_begin_exit(&position.token_id) {
+        console_println!("🛑 STOP-LOSS DEFERRED: {} | token is {}", position.token_id, phase.as_str());
+        return;
+    }
+    console_println!(
+        "🛑 STOP-LOSS TRIGGERED ({}): {} | entry: {} | current: {} | P&L: {} | shares: {}",
+        source, position.token_id, display::avg_price(&position.token_id, position.entry_price),
+        display::price(&position.token_id, current_price), display::pct(position.pnl_pct(current_price)), display::shares(position.shares)
+    );
+    
+    // Execute stop-loss sell
+    let client_clone = client.clone();
+    let creds_clone = creds.clone();
+    let token_id = position.token_id.clone();
+    let shares = position.shares;
+    let tracker_clone = tracker.clone();
+    
+    tokio::spawn(async move {
+        let sell_price = stop_loss_sell_price(&token_id, current_price);
+        let result = execute_fak_sell(&client_clone, &creds_clone, &token_id, shares, sell_price).await;
+        asset_states().finish_exit(&token_id, result.is_ok());
+        match result {
+            Ok((filled, fill_price)) => {
+                console_println!(
+                    "🛑 STOP-LOSS EXECUTED: {} | sold {} shares @ {} (bid {}, limit {})",
+                    token_id, display::shares(filled), display::avg_price(&token_id, fill_price),
+                    display::price(&token_id, current_price), display::price(&token_id, sell_price)
+                );
+                strategy_ledger().record(&token_id, false, filled, fill_price);
+                markout::record_fill(&token_id, false, fill_price, filled);
+                reward_risk::record_exit(&token_id, fill_price);
+                cost_budget::record_exit(&token_id, fill_price, current_price);
+                // Remove position from tracker
+                tracker_clone.remove_position(&token_id).await;
+            }
+            Err(e) => {
+                console_eprintln!("🛑 STOP-LOSS FAILED: {} | error: {}", token_id, e);
+            }
+        }
+    });
+}
+
+/// Sell everything held between the entry cutoff and the resume time, conceding more below
+/// the best bid as the deadline nears. The final flat (or not flat) state is logged once a night
+async fn flatten_worker(
+    tracker: Arc<PositionTracker>,
+    client: Arc<RustClobClient>,
+    policy: FlattenPolicy,
+) {
+    let price_fetcher = ClobPriceFetcher { client: client.clone() };
+    let mut interval = tokio::time::interval(flatten::FLATTEN_CHECK_INTERVAL);
+    // Tonight's end state already reported
+    let (mut confirmed_flat, mut warned_open) = (false, false);
+
+    loop {
+        interval.tick().await;
+        let phase = policy.phase(chrono::Local::now().time());
+        diagnostics().heartbeat("flatten", &format!("{:?}", phase));
+        let progress = match phase {
+            FlattenPhase::Open => {
+                (confirmed_flat, warned_open) = (false, false);
+                continue;
+            }
+            FlattenPhase::Exiting { progress } => progress,
+            FlattenPhase::Flat => 1.0,
+        };
+
+        let positions = tracker.get_all_positions().await;
+        if phase == FlattenPhase::Flat {
+            if positions.is_empty() && !confirmed_flat {
+                console_println!("🌙 FLAT: no open positions; entries resume at {}", policy.resume.format("%H:%M"));
+                confirmed_flat = true;
+            } else if !positions.is_empty() && !warned_open {
+                console_eprintln!("🚨 NOT FLAT at {}: {} positions still open, still selling", policy.deadline.format("%H:%M"), positions.len());
+                warned_open = true;
+            }
+        }
+
+        let token_ids: Vec<String> = positions.iter().map(|p| p.token_id.clone()).collect();
+        let bids: FxHashMap<String, f64> = price_fetcher.fetch_books(&token_ids).await
+            .into_iter()
+            .filter_map(|(token_id, book)| Some((token_id, depth_history::best_bid(&book)?)))
+            .collect();
+        for position in positions {
+            let Some(&best_bid) = bids.get(&position.token_id) else { continue };
+            // Leave tokens an entry or another exit is working on for the next pass
+            if asset_states().try_begin_exit(&position.token_id).is_err() {
+                continue;
+            }
+            let sell_price = policy.exit_price(best_bid, progress);
+            let result = execute_fak_sell(&client, &credentials::current(), &position.token_id, position.shares, sell_price).await;
+            asset_states().finish_exit(&position.token_id, result.is_ok());
+            match result {
+                Ok((filled, fill_price)) => {
+                    console_println!(
+                        "🌙 FLATTEN SOLD: {} | {} shares @ {} (bid {}, {:.0}% through window)",
+                        position.token_id, display::shares(filled), display::avg_price(&position.token_id, fill_price),
+                        display::price(&position.token_id, best_bid), progress * 100.0
                     );
-                    
-                    // Execute stop-loss sell
-                    let client_clone = client.clone();
-                    let creds_clone = creds.clone();
-                    let token_id = position.token_id.clone();
-                    let shares = position.shares;
-                    let tracker_clone = tracker.clone();
-                    
-                    tokio::spawn(async move {
-                        match execute_stop_loss_sell(&client_clone, &creds_clone, &token_id, shares, current_price).await {
-                            Ok(filled) => {
-                                println!(
-                                    "🛑 STOP-LOSS EXECUTED: {} | sold {:.2} shares @ ~{:.4}",
-                                    token_id, filled, current_price
-                                );
-                                // Remove position from tracker
-                                tracker_clone.remove_position(&token_id).await;
-                            }
-                            Err(e) => {
-                                eprintln!("🛑 STOP-LOSS FAILED: {} | error: {}", token_id, e);
-                            }
-                        }
-                    });
+                    strategy_ledger().record(&position.token_id, false, filled, fill_price);
+                    markout::record_fill(&position.token_id, false, fill_price, filled);
+                    reward_risk::record_exit(&position.token_id, fill_price);
+                    cost_budget::record_exit(&position.token_id, fill_price, best_bid);
+                    tracker.remove_position(&position.token_id).await;
                 }
+                Err(e) => console_eprintln!(
+                    "🌙 FLATTEN SELL FAILED: {} @ {} | {} (retrying lower)",
+                    position.token_id, display::price(&position.token_id, sell_price), e
+                ),
             }
         }
     }
 }
 
-/// Execute a stop-loss sell order
-async fn execute_stop_loss_sell(
+/// Stop-loss limit: a tick under the bid to ensure the fill (market sell behavior), or with a
+/// cost budget whatever the entry left of it
+fn stop_loss_sell_price(token_id: &str, current_price: f64) -> f64 {
+    let offset = match cost_budget::cost_budget() {
+        Some(budget) => {
+            let tick = token_metadata::tick_size(token_id).parse().unwrap_or(0.01);
+            budget.exit_offset(cost_tracker().entry_cost(token_id), tick)
+        }
+        None => 0.01,
+    };
+    (current_price - offset).max(0.01)
+}
+
+/// Shares an exit can send (floored to the size step), None below the 1-share minimum
+fn sellable_shares(shares: f64) -> Option<f64> {
+    let rounded = (shares * 100.0).floor() / 100.0;
+    (rounded >= 1.0).then_some(rounded)
+}
+
+/// FAK sell of `shares` at `sell_price`; returns (shares sold, average price), the shares sent
+/// at the limit when the reply carries no amounts
+async fn execute_fak_sell(
     client: &Arc<RustClobClient>,
     creds: &Arc<PreparedCreds>,
     token_id: &str,
     shares: f64,
-    current_price: f64,
-) -> Result<f64> {
-    // Use a slightly lower price to ensure fill (market sell behavior)
-    let sell_price = (current_price - 0.01).max(0.01);
-    let rounded_shares = (shares * 100.0).floor() / 100.0;
-    
-    if rounded_shares < 1.0 {
+    sell_price: f64,
+) -> Result<(f64, f64)> {
+    let Some(rounded_shares) = sellable_shares(shares) else {
         return Err(anyhow!("Position too small to sell"));
-    }
+    };
     
     let args = OrderArgs {
         token_id: token_id.to_string(),
@@ -606,20 +1823,19 @@ async fn execute_stop_loss_sell(
         let mut client_mut = (*client_clone).clone();
         client_mut.create_order(args_clone).and_then(|signed| {
             let body = signed.post_body(&creds_clone.api_key, "FAK");
-            client_mut.post_order_fast(body, &creds_clone)
+            client_mut.post_order(body, &creds_clone)
         })
     }).await?;
     
-    match result {
-        Ok(resp) => {
-            if resp.status().is_success() {
-                Ok(rounded_shares)
-            } else {
-                let body = resp.text().unwrap_or_default();
-                Err(anyhow!("Sell failed: {}", body))
-            }
+    let reply = result.map_err(|e| anyhow!("Order error: {}", e))?;
+    match reply.accepted() {
+        // Sells make shares and take USDC
+        Some(resp) => {
+            let sold: f64 = resp.making_amount.parse().unwrap_or(0.0);
+            let usd: f64 = resp.taking_amount.parse().unwrap_or(0.0);
+            Ok(if sold > 0.0 && usd > 0.0 { (sold, usd / sold) } else { (rounded_shares, sell_price) })
         }
-        Err(e) => Err(anyhow!("Order error: {}", e)),
+        None => Err(anyhow!("Sell failed: {}", reply.rejection().map(ToString::to_string).unwrap_or_default())),
     }
 }
 
@@ -631,7 +1847,7 @@ struct ClobPriceFetcher {
 #[async_trait::async_trait]
 impl PriceFetcher for ClobPriceFetcher {
     async fn get_current_price(&self, token_id: &str) -> Option<f64> {
-        let url = format!("{}/book?token_id={}", CLOB_API_BASE, token_id);
+        let url = format!("{}/book?token_id={}", clob_api_base(), token_id);
         let client = self.client.clone();
         let url_clone = url.clone();
         
@@ -657,11 +1873,53 @@ impl PriceFetcher for ClobPriceFetcher {
     }
 }
 
+#[async_trait::async_trait]
+impl BookFetcher for ClobPriceFetcher {
+    /// One `/books` request for all tokens, `/book` per token if that fails
+    async fn fetch_books(&self, token_ids: &[String]) -> Vec<(String, depth_history::Book)> {
+        if token_ids.is_empty() {
+            return Vec::new();
+        }
+        let (client, ids) = (self.client.clone(), token_ids.to_vec());
+        let batched = tokio::task::spawn_blocking(move || depth_history::fetch_books_batched(client.http_client(), &ids))
+            .await.ok().flatten();
+        if let Some(books) = batched {
+            diagnostics().heartbeat("book_batch", &format!("{} books", books.len()));
+            return books;
+        }
+        diagnostics().heartbeat("book_batch", "batch failed, per-token fallback");
+        let mut books = Vec::with_capacity(token_ids.len());
+        for token_id in token_ids {
+            if let Some(book) = self.fetch_book(token_id).await {
+                books.push((token_id.clone(), book));
+            }
+        }
+        books
+    }
+
+    async fn fetch_book(&self, token_id: &str) -> Option<(Vec<(f64, f64)>, Vec<(f64, f64)>)> {
+        let url = format!("{}/book?token_id={}", clob_api_base(), token_id);
+        let client = self.client.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            client.http_client()
+                .get(&url)
+                .timeout(Duration::from_secs(2))
+                .send()
+        }).await.ok()?.ok()?;
+        if !result.status().is_success() {
+            return None;
+        }
+
+        let book: Value = result.json().ok()?;
+        Some(depth_history::parse_book(&book))
+    }
+}
+
 // ============================================================================
 // WebSocket Loop
 // ============================================================================
 
-async fn run_ws_loop(wss_url: &str, order_engine: &OrderEngine) -> Result<()> {
+async fn run_ws_loop(wss_url: &str, dispatch: &impl Fn(ParsedEvent)) -> Result<()> {
     let (mut ws, _) = connect_async(wss_url).await?;
 
     let sub = serde_json::json!({
@@ -672,30 +1930,36 @@ async fn run_ws_loop(wss_url: &str, order_engine: &OrderEngine) -> Result<()> {
         }]
     }).to_string();
 
-    println!("🔌 Connected. Subscribing...");
+    console_println!("🔌 Connected. Subscribing...");
+    diagnostics().heartbeat("ws", "connected");
     ws.send(Message::Text(sub)).await?;
 
-    let http_client = reqwest::Client::builder().no_proxy().build()?;
+    // Provider host only, the URL path carries the API key
+    let feed_name = latency_probe::redact_url(wss_url);
 
     loop {
         let msg = tokio::time::timeout(WS_PING_TIMEOUT, ws.next()).await
             .map_err(|_| anyhow!("WS timeout"))?
             .ok_or_else(|| anyhow!("WS closed"))??;
+        diagnostics().heartbeat("ws", "message");
+        feed_gaps().record_message(&feed_name);
+        #[cfg(feature = "chaos")]
+        if pm_whale_follower::chaos::chaos().is_some_and(|c| c.ws_drop()) {
+            return Err(anyhow!("WS dropped (chaos)"));
+        }
 
         match msg {
             Message::Text(text) => {
+                replay_buffer::record("whale", &text);
                 if let Some(evt) = parse_event(text) {
-                    let engine = order_engine.clone();
-                    let client = http_client.clone();
-                    tokio::spawn(async move { handle_event(evt, &engine, &client).await });
+                    dispatch(evt);
                 }
             }
             Message::Binary(bin) => {
                 if let Ok(text) = String::from_utf8(bin) {
+                    replay_buffer::record("whale", &text);
                     if let Some(evt) = parse_event(text) {
-                        let engine = order_engine.clone();
-                        let client = http_client.clone();
-                        tokio::spawn(async move { handle_event(evt, &engine, &client).await });
+                        dispatch(evt);
                     }
                 }
             }
@@ -713,6 +1977,10 @@ async fn handle_event(evt: ParsedEvent, order_engine: &OrderEngine, http_client:
         None => fetch_is_live(&evt.order.clob_token_id, http_client).await,
     };
 
+    // Warm token metadata (labels, tick size) without delaying the order; until it arrives the
+    // order is signed at the default tick and a tick rejection is repriced by AUTO_REMEDIATE
+    token_metadata::spawn_refresh_if_needed(&evt.order.clob_token_id, http_client);
+
     let status = order_engine.submit(evt.clone(), is_live).await;
 
     tokio::time::sleep(Duration::from_secs_f32(2.8)).await;
@@ -720,6 +1988,12 @@ async fn handle_event(evt: ParsedEvent, order_engine: &OrderEngine, http_client:
     // Fetch order book for post-trade logging
     let bests = fetch_best_book(&evt.order.clob_token_id, &evt.order.order_type, http_client).await;
     let ((bp, bs), (sp, ss)) = bests.unwrap_or_else(|| (("N/A".into(), "N/A".into()), ("N/A".into(), "N/A".into())));
+    blotter().record_price(&evt.order.clob_token_id, PriceRow {
+        best_price: bp.clone(),
+        best_size: bs.clone(),
+        second_price: sp.clone(),
+        updated_at: SystemTime::now(),
+    });
     let is_live = is_live.unwrap_or(false);
 
     // Highlight best price in bright pink
@@ -747,12 +2021,18 @@ async fn handle_event(evt: ParsedEvent, order_engine: &OrderEngine, http_client:
         ""
     };
 
-    println!(
-        "⚡ [B:{}] {}{}{} | ${:.0} | {} | best: {} @ {} | 2nd: {} @ {} | {}",
-        evt.block_number, tennis_display, soccer_display, evt.order.order_type, evt.order.usd_value, status, colored_bp, bs, sp, ss, live_display
+    // Outcome/market label from token metadata (empty if not fetched yet)
+    let label_display = token_metadata::label(&evt.order.clob_token_id)
+        .map(|l| format!(" | {}", l))
+        .unwrap_or_default();
+
+    console_println!(
+        "⚡ [B:{}] {}{}{} | ${:.0} | {} | best: {} @ {} | 2nd: {} @ {} | {}{}",
+        evt.block_number, tennis_display, soccer_display, evt.order.order_type, evt.order.usd_value, status, colored_bp, bs, sp, ss, live_display, label_display
     );
 
-    let ts: DateTime<Utc> = Utc::now();
+    // The row is written after the post-trade book read; its time is the fill's arrival
+    let ts: DateTime<Utc> = evt.received_at.or_now().to_datetime();
     let row = CSV_BUF.with(|buf| {
         SANITIZE_BUF.with(|sbuf| {
             let mut b = buf.borrow_mut();
@@ -777,13 +2057,14 @@ async fn handle_event(evt: ParsedEvent, order_engine: &OrderEngine, http_client:
 // ============================================================================
 
 async fn resubmit_worker(
-    mut rx: mpsc::UnboundedReceiver<ResubmitRequest>,
+    rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<ResubmitRequest>>>,
     client: Arc<RustClobClient>,
-    creds: Arc<PreparedCreds>,
 ) {
-    println!("🔄 Resubmitter worker started");
+    let mut rx = rx.lock().await;
+    console_println!("🔄 Resubmitter worker started");
 
     while let Some(req) = rx.recv().await {
+        diagnostics().heartbeat("resubmitter", &req.token_id);
         let max_attempts = get_max_resubmit_attempts(req.whale_shares);
         let is_last_attempt = req.attempt >= max_attempts;
 
@@ -802,7 +2083,7 @@ async fn resubmit_worker(
         // Check if we've exceeded max buffer (skip check for GTD - last attempt always goes through)
         if !is_last_attempt && req.side_is_buy && new_price > req.max_price {
             let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-            println!(
+            console_println!(
                 "🔄 Resubmit ABORT: attempt {} price {:.2} > max {:.2} | filled {:.2}/{:.2} ({:.0}%)",
                 req.attempt, new_price, req.max_price, req.cumulative_filled, req.original_size, fill_pct
             );
@@ -810,7 +2091,7 @@ async fn resubmit_worker(
         }
 
         let client_clone = Arc::clone(&client);
-        let creds_clone = Arc::clone(&creds);
+        let creds_clone = credentials::current();
         let token_id = req.token_id.clone();
         let size = req.size;
         let attempt = req.attempt;
@@ -823,11 +2104,24 @@ async fn resubmit_worker(
             submit_resubmit_order_sync(&client_clone, &creds_clone, &token_id, new_price, size, is_live, is_last_attempt)
         }).await;
 
+        if let Ok(Ok((_, filled))) = &result
+            && *filled > 0.0 {
+                strategy_ledger().record(&req.token_id, req.side_is_buy, *filled, new_price);
+                markout::record_fill(&req.token_id, req.side_is_buy, new_price, *filled);
+                if req.side_is_buy {
+                    fair_at_entry().record_entry(&req.token_id, *filled, req.whale_price);
+                    cost_budget::record_entry(&req.token_id, *filled, new_price, req.whale_price);
+                    if let Some(reserve) = cash_reserve() {
+                        reserve.commit(*filled * new_price);
+                    }
+                }
+            }
+
         match result {
-            Ok(Ok((true, _, filled_this_attempt))) => {
+            Ok(Ok((reply, filled_this_attempt))) if reply.is_success() => {
                 if is_last_attempt {
                     // GTD order placed on book - we don't know fill amount yet
-                    println!(
+                    console_println!(
                         "\x1b[32m🔄 Resubmit GTD SUBMITTED: attempt {} @ {:.2} | size {:.2} | prior filled {:.2}/{:.2}\x1b[0m",
                         attempt, new_price, size, req.cumulative_filled, req.original_size
                     );
@@ -839,7 +2133,7 @@ async fn resubmit_worker(
 
                     // If partial fill, continue with remaining size
                     if remaining > 1.0 && filled_this_attempt > 0.0 {
-                        println!(
+                        console_println!(
                             "\x1b[33m🔄 Resubmit PARTIAL: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%) | remaining {:.2}\x1b[0m",
                             attempt, new_price, total_filled, req.original_size, fill_pct, remaining
                         );
@@ -856,17 +2150,19 @@ async fn resubmit_worker(
                             original_size: req.original_size,
                             is_live: req.is_live,
                         };
-                        let _ = process_resubmit_chain(&client, &creds, next_req).await;
+                        let _ = process_resubmit_chain(&client, next_req).await;
                     } else {
-                        println!(
+                        console_println!(
                             "\x1b[32m🔄 Resubmit SUCCESS: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%)\x1b[0m",
                             attempt, new_price, total_filled, req.original_size, fill_pct
                         );
                     }
                 }
             }
-            Ok(Ok((false, body, filled_this_attempt))) => {
-                if attempt < max_attempts {
+            Ok(Ok((reply, filled_this_attempt))) => {
+                let body = reply.rejection().map(ToString::to_string).unwrap_or_default();
+                // Balance, closed market and invalid-order rejections fail the same way on every price
+                if attempt < max_attempts && reply.error().is_some_and(|e| e.is_retryable()) {
                     // Re-queue with updated price
                     let next_req = ResubmitRequest {
                         token_id: req.token_id,
@@ -886,7 +2182,7 @@ async fn resubmit_worker(
                     } else {
                         0.0
                     };
-                    println!(
+                    console_println!(
                         "🔄 Resubmit attempt {} failed (FAK), retrying @ {:.2} (max: {})",
                         attempt, new_price + next_increment, max_attempts
                     );
@@ -895,14 +2191,13 @@ async fn resubmit_worker(
                     }
                     let _ = process_resubmit_chain(
                         &client,
-                        &creds,
                         next_req,
                     ).await;
                 } else {
                     let total_filled = req.cumulative_filled + filled_this_attempt;
                     let fill_pct = if req.original_size > 0.0 { (total_filled / req.original_size) * 100.0 } else { 0.0 };
                     let error_msg = if DEBUG_FULL_ERRORS { body.clone() } else { body.chars().take(80).collect::<String>() };
-                    println!(
+                    console_println!(
                         "🔄 Resubmit FAILED: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%) | {}",
                         attempt, new_price, total_filled, req.original_size, fill_pct, error_msg
                     );
@@ -910,14 +2205,14 @@ async fn resubmit_worker(
             }
             Ok(Err(e)) => {
                 let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-                println!(
+                console_println!(
                     "🔄 Resubmit ERROR: attempt {} | filled {:.2}/{:.2} ({:.0}%) | {}",
                     attempt, req.cumulative_filled, req.original_size, fill_pct, e
                 );
             }
             Err(e) => {
                 let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-                println!(
+                console_println!(
                     "🔄 Resubmit TASK ERROR: filled {:.2}/{:.2} ({:.0}%) | {}",
                     req.cumulative_filled, req.original_size, fill_pct, e
                 );
@@ -928,7 +2223,6 @@ async fn resubmit_worker(
 
 async fn process_resubmit_chain(
     client: &Arc<RustClobClient>,
-    creds: &Arc<PreparedCreds>,
     mut req: ResubmitRequest,
 ) {
     let max_attempts = get_max_resubmit_attempts(req.whale_shares);
@@ -951,7 +2245,7 @@ async fn process_resubmit_chain(
         // Check if we've exceeded max buffer (skip check for GTD - last attempt always goes through)
         if !is_last_attempt && req.side_is_buy && new_price > req.max_price {
             let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-            println!(
+            console_println!(
                 "🔄 Resubmit chain ABORT: attempt {} price {:.2} > max {:.2} | filled {:.2}/{:.2} ({:.0}%)",
                 req.attempt, new_price, req.max_price, req.cumulative_filled, req.original_size, fill_pct
             );
@@ -959,7 +2253,7 @@ async fn process_resubmit_chain(
         }
 
         let client_clone = Arc::clone(&client);
-        let creds_clone = Arc::clone(&creds);
+        let creds_clone = credentials::current();
         let token_id = req.token_id.clone();
         let size = req.size;
         let attempt = req.attempt;
@@ -970,11 +2264,24 @@ async fn process_resubmit_chain(
             submit_resubmit_order_sync(&client_clone, &creds_clone, &token_id, new_price, size, is_live, is_last_attempt)
         }).await;
 
+        if let Ok(Ok((_, filled))) = &result
+            && *filled > 0.0 {
+                strategy_ledger().record(&req.token_id, req.side_is_buy, *filled, new_price);
+                markout::record_fill(&req.token_id, req.side_is_buy, new_price, *filled);
+                if req.side_is_buy {
+                    fair_at_entry().record_entry(&req.token_id, *filled, req.whale_price);
+                    cost_budget::record_entry(&req.token_id, *filled, new_price, req.whale_price);
+                    if let Some(reserve) = cash_reserve() {
+                        reserve.commit(*filled * new_price);
+                    }
+                }
+            }
+
         match result {
-            Ok(Ok((true, _, filled_this_attempt))) => {
+            Ok(Ok((reply, filled_this_attempt))) if reply.is_success() => {
                 if is_last_attempt {
                     // GTD order placed on book - we don't know fill amount yet
-                    println!(
+                    console_println!(
                         "\x1b[32m🔄 Resubmit chain GTD SUBMITTED: attempt {} @ {:.2} | size {:.2} | prior filled {:.2}/{:.2}\x1b[0m",
                         attempt, new_price, req.size, req.cumulative_filled, req.original_size
                     );
@@ -987,7 +2294,7 @@ async fn process_resubmit_chain(
 
                     // If partial fill, continue with remaining size
                     if remaining > 1.0 && filled_this_attempt > 0.0 {
-                        println!(
+                        console_println!(
                             "\x1b[33m🔄 Resubmit chain PARTIAL: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%) | remaining {:.2}\x1b[0m",
                             attempt, new_price, total_filled, req.original_size, fill_pct, remaining
                         );
@@ -997,7 +2304,7 @@ async fn process_resubmit_chain(
                         req.attempt += 1;
                         continue;
                     } else {
-                        println!(
+                        console_println!(
                             "\x1b[32m🔄 Resubmit chain SUCCESS: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%)\x1b[0m",
                             attempt, new_price, total_filled, req.original_size, fill_pct
                         );
@@ -1005,7 +2312,7 @@ async fn process_resubmit_chain(
                     }
                 }
             }
-            Ok(Ok((false, body, filled_this_attempt))) if body.contains("FAK") && attempt < max_attempts => {
+            Ok(Ok((reply, filled_this_attempt))) if reply.error() == Some(OrderError::NoMatch) && attempt < max_attempts => {
                 req.cumulative_filled += filled_this_attempt;
                 req.failed_price = new_price;
                 req.attempt += 1;
@@ -1015,13 +2322,14 @@ async fn process_resubmit_chain(
                 }
                 continue;
             }
-            Ok(Ok((false, body, filled_this_attempt))) => {
+            Ok(Ok((reply, filled_this_attempt))) => {
+                let body = reply.rejection().map(ToString::to_string).unwrap_or_default();
                 let total_filled = req.cumulative_filled + filled_this_attempt;
                 let fill_pct = if req.original_size > 0.0 { (total_filled / req.original_size) * 100.0 } else { 0.0 };
                 let fill_color = get_fill_color(total_filled, req.original_size);
                 let reset = "\x1b[0m";
                 let error_msg = if DEBUG_FULL_ERRORS { body.clone() } else { body.chars().take(80).collect::<String>() };
-                println!(
+                console_println!(
                     "🔄 Resubmit chain FAILED: attempt {}/{} @ {:.2} | {}filled {:.2}/{:.2} ({:.0}%){} | {}",
                     attempt, max_attempts, new_price, fill_color, total_filled, req.original_size, fill_pct, reset, error_msg
                 );
@@ -1031,7 +2339,7 @@ async fn process_resubmit_chain(
                 let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
                 let fill_color = get_fill_color(req.cumulative_filled, req.original_size);
                 let reset = "\x1b[0m";
-                println!(
+                console_println!(
                     "🔄 Resubmit chain ERROR: attempt {} | {}filled {:.2}/{:.2} ({:.0}%){} | {}",
                     attempt, fill_color, req.cumulative_filled, req.original_size, fill_pct, reset, e
                 );
@@ -1041,7 +2349,7 @@ async fn process_resubmit_chain(
                 let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
                 let fill_color = get_fill_color(req.cumulative_filled, req.original_size);
                 let reset = "\x1b[0m";
-                println!(
+                console_println!(
                     "🔄 Resubmit chain TASK ERROR: {}filled {:.2}/{:.2} ({:.0}%){} | {}",
                     fill_color, req.cumulative_filled, req.original_size, fill_pct, reset, e
                 );
@@ -1051,7 +2359,7 @@ async fn process_resubmit_chain(
     }
 }
 
-/// Returns (success, body_text, filled_shares)
+/// Returns (reply, filled_shares)
 fn submit_resubmit_order_sync(
     client: &RustClobClient,
     creds: &PreparedCreds,
@@ -1060,18 +2368,13 @@ fn submit_resubmit_order_sync(
     size: f64,
     is_live: bool,
     is_last_attempt: bool,
-) -> anyhow::Result<(bool, String, f64)> {
-    use std::time::{SystemTime, UNIX_EPOCH};
-
+) -> anyhow::Result<(OrderReply, f64)> {
     let mut client = client.clone();
 
     // Only use GTD with expiry on the LAST attempt; earlier attempts use FAK
     let (expiration, order_type) = if is_last_attempt {
         let expiry_secs = get_gtd_expiry_secs(is_live);
-        let expiry_timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() + expiry_secs;
+        let expiry_timestamp = timestamp::unix_secs() + expiry_secs;
         (Some(expiry_timestamp.to_string()), "GTD")
     } else {
         (None, "FAK")
@@ -1098,35 +2401,28 @@ fn submit_resubmit_order_sync(
 
     let signed = client.create_order(args)?;
     let body = signed.post_body(&creds.api_key, order_type);
-    let resp = client.post_order_fast(body, creds)?;
-
-    let status = resp.status();
-    let body_text = resp.text().unwrap_or_default();
+    let reply = client.post_order(body, creds)?;
 
     // Parse filled amount from successful responses
     // GTD orders return taking_amount=0 since they're placed on book, not immediately filled
     // For GTD, return 0 - caller handles GTD success messaging separately
-    let filled_shares = if status.is_success() && order_type == "FAK" {
-        serde_json::from_str::<OrderResponse>(&body_text)
-            .ok()
-            .and_then(|r| r.taking_amount.parse::<f64>().ok())
-            .unwrap_or(0.0)
-    } else {
-        0.0
+    let filled_shares = match reply.accepted() {
+        Some(r) if order_type == "FAK" => r.taking_amount.parse::<f64>().unwrap_or(0.0),
+        _ => 0.0,
     };
 
-    Ok((status.is_success(), body_text, filled_shares))
+    Ok((reply, filled_shares))
 }
 
 async fn fetch_is_live(token_id: &str, client: &reqwest::Client) -> Option<bool> {
     // Fetch market info to get slug
-    let market_url = format!("{}/markets?clob_token_ids={}", GAMMA_API_BASE, token_id);
+    let market_url = format!("{}/markets?clob_token_ids={}", gamma_api_base(), token_id);
     let resp = client.get(&market_url).timeout(Duration::from_secs(2)).send().await.ok()?;
     let val: Value = resp.json().await.ok()?;
     let slug = val.get(0)?.get("slug")?.as_str()?.to_string();
 
     // Fetch live status from events API
-    let event_url = format!("{}/events/slug/{}", GAMMA_API_BASE, slug);
+    let event_url = format!("{}/events/slug/{}", gamma_api_base(), slug);
     let resp = client.get(&event_url).timeout(Duration::from_secs(2)).send().await.ok()?;
     let val: Value = resp.json().await.ok()?;
 
@@ -1134,7 +2430,7 @@ async fn fetch_is_live(token_id: &str, client: &reqwest::Client) -> Option<bool>
 }
 
 async fn fetch_best_book(token_id: &str, order_type: &str, client: &reqwest::Client) -> Option<((String, String), (String, String))> {
-    let url = format!("{}/book?token_id={}", CLOB_API_BASE, token_id);
+    let url = format!("{}/book?token_id={}", clob_api_base(), token_id);
     let resp = client.get(&url).timeout(BOOK_REQ_TIMEOUT).send().await.ok()?;
     if !resp.status().is_success() { return None; }
     
@@ -1231,10 +2527,14 @@ fn parse_event(message: String) -> Option<ParsedEvent> {
     }
 
     Some(ParsedEvent {
+        received_at: Timestamp::now(),
         block_number: result.block_number.as_deref()
             .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
             .unwrap_or_default(),
         tx_hash: result.transaction_hash.unwrap_or_default(),
+        log_index: result.log_index.as_deref()
+            .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+            .unwrap_or_default(),
         order: OrderInfo {
             order_type,
             clob_token_id: u256_to_dec_cached(&token_bytes, &clob_id),
@@ -1321,7 +2621,7 @@ fn hex_nibble(b: u8) -> Option<u8> {
 fn ensure_csv() -> Result<()> {
     if !Path::new(CSV_FILE).exists() {
         let mut f = File::create(CSV_FILE)?;
-        writeln!(f, "timestamp,block,clob_asset_id,usd_value,shares,price_per_share,direction,order_status,best_price,best_size,second_price,second_size,tx_hash,is_live")?;
+        writeln!(f, "{}", CSV_HEADER)?;
     }
     Ok(())
 }
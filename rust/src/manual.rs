@@ -0,0 +1,270 @@
+//! Manual position adjustments
+//! For positions changed by hand on the Polymarket UI. `pm_bot position ...` queues a command
+//! in an inbox file; the running bot applies it to the position tracker and this tag's
+//! strategy ledger, and journals every applied command together with its note
+
+use crate::display;
+use crate::position_tracker::PositionTracker;
+use crate::strategy::{strategy_ledger, StrategyLedger};
+use crate::timestamp;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Commands queued by `pm_bot position` and not yet applied
+pub const POSITION_INBOX_FILE: &str = "position_commands.jsonl";
+
+/// Every applied command, with the instance tag and a timestamp
+pub const ADJUSTMENTS_FILE: &str = "position_adjustments.jsonl";
+
+/// How often the running bot picks up queued commands
+pub const INBOX_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub const USAGE: &str = "usage:\n  \
+    pm_bot position close <token_id> [exit_price] [note...]\n  \
+    pm_bot position set <token_id> <shares> <avg_price> [note...]\n  \
+    pm_bot position note <token_id> <note...>";
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ManualCommand {
+    /// Position was closed by hand; with a price the exit is booked as a sell at that price
+    Close { token_id: String, price: Option<f64>, note: Option<String> },
+    /// Replace the recorded size and average entry price
+    Set { token_id: String, shares: f64, price: f64, note: Option<String> },
+    Note { token_id: String, note: String },
+}
+
+impl ManualCommand {
+    pub fn token_id(&self) -> &str {
+        match self {
+            ManualCommand::Close { token_id, .. }
+            | ManualCommand::Set { token_id, .. }
+            | ManualCommand::Note { token_id, .. } => token_id,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AppliedCommand<'a> {
+    ts: u64,
+    tag: &'a str,
+    #[serde(flatten)]
+    command: &'a ManualCommand,
+    result: &'a str,
+}
+
+fn join_note(words: &[String]) -> Option<String> {
+    let note = words.join(" ");
+    (!note.trim().is_empty()).then_some(note)
+}
+
+fn parse_price(s: &str) -> Result<f64> {
+    let p: f64 = s.parse().with_context(|| format!("'{}' is not a price", s))?;
+    if !(0.0..=1.0).contains(&p) {
+        anyhow::bail!("price {} is outside 0-1", p);
+    }
+    Ok(p)
+}
+
+/// Parse the arguments after `pm_bot position`
+pub fn parse_args(args: &[String]) -> Result<ManualCommand> {
+    let (Some(action), Some(token_id)) = (args.first(), args.get(1)) else {
+        anyhow::bail!("{}", USAGE);
+    };
+    let token_id = token_id.clone();
+    let rest = &args[2..];
+    match action.as_str() {
+        "close" => {
+            // An optional leading number is the exit price, everything else is the note
+            let price = rest.first().filter(|s| s.parse::<f64>().is_ok()).map(|s| parse_price(s)).transpose()?;
+            let note_from = if price.is_some() { 1 } else { 0 };
+            Ok(ManualCommand::Close { token_id, price, note: join_note(&rest[note_from..]) })
+        }
+        "set" => {
+            let (Some(shares), Some(price)) = (rest.first(), rest.get(1)) else {
+                anyhow::bail!("{}", USAGE);
+            };
+            let shares: f64 = shares.parse().with_context(|| format!("'{}' is not a share count", shares))?;
+            if shares < 0.0 {
+                anyhow::bail!("shares cannot be negative");
+            }
+            Ok(ManualCommand::Set { token_id, shares, price: parse_price(price)?, note: join_note(&rest[2..]) })
+        }
+        "note" => match join_note(rest) {
+            Some(note) => Ok(ManualCommand::Note { token_id, note }),
+            None => anyhow::bail!("{}", USAGE),
+        },
+        _ => anyhow::bail!("{}", USAGE),
+    }
+}
+
+// ============================================================================
+// Inbox
+// ============================================================================
+
+/// Append a command for the running bot to pick up
+pub fn queue(cmd: &ManualCommand, path: &str) -> Result<()> {
+    let line = serde_json::to_string(cmd)?;
+    let mut f = OpenOptions::new().append(true).create(true).open(path)?;
+    writeln!(f, "{}", line)?;
+    Ok(())
+}
+
+/// Take every queued command. The inbox is renamed first so commands queued meanwhile
+/// land in a fresh file instead of being lost
+pub fn take_inbox(path: &str) -> Vec<ManualCommand> {
+    let taking = format!("{}.taking", path);
+    if fs::rename(path, &taking).is_err() {
+        return Vec::new();
+    }
+    let data = fs::read_to_string(&taking).unwrap_or_default();
+    let _ = fs::remove_file(&taking);
+    data.lines()
+        .filter_map(|l| match serde_json::from_str(l) {
+            Ok(cmd) => Some(cmd),
+            Err(e) => {
+                crate::console_eprintln!("⚠️ Ignoring malformed position command '{}': {}", l, e);
+                None
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// Apply
+// ============================================================================
+
+/// Apply one command to the tracker and ledger; returns what was done
+pub async fn apply(cmd: &ManualCommand, tracker: &PositionTracker, ledger: &StrategyLedger) -> String {
+    match cmd {
+        ManualCommand::Close { token_id, price, note } => {
+            let tracked = tracker.remove_position(token_id).await;
+            let held = ledger.held(token_id);
+            match (price, held) {
+                (Some(p), Some((shares, _))) => ledger.record(token_id, false, shares, *p),
+                _ => ledger.record_set(token_id, 0.0, 0.0),
+            }
+            let mut result = match (&tracked, price) {
+                (Some(pos), Some(p)) => format!("closed {} shares @ {}", display::shares(pos.shares), display::avg_price(token_id, *p)),
+                (Some(pos), None) => format!("closed {} shares (no exit price)", display::shares(pos.shares)),
+                (None, _) => "no tracked position, ledger cleared".to_string(),
+            };
+            if let Some(n) = note {
+                result.push_str(&format!(" | {}", n));
+            }
+            result
+        }
+        ManualCommand::Set { token_id, shares, price, note } => {
+            tracker.set_position(token_id.clone(), *price, *shares).await;
+            if let Some(n) = note {
+                tracker.set_note(token_id, n.clone()).await;
+            }
+            ledger.record_set(token_id, *shares, *price);
+            format!("set to {} shares @ {}", display::shares(*shares), display::avg_price(token_id, *price))
+        }
+        ManualCommand::Note { token_id, note } => {
+            if tracker.set_note(token_id, note.clone()).await {
+                format!("noted: {}", note)
+            } else {
+                format!("no tracked position, journaled only: {}", note)
+            }
+        }
+    }
+}
+
+fn journal(cmd: &ManualCommand, tag: &str, result: &str) {
+    let ts = timestamp::unix_secs();
+    let Ok(line) = serde_json::to_string(&AppliedCommand { ts, tag, command: cmd, result }) else { return };
+    match OpenOptions::new().append(true).create(true).open(ADJUSTMENTS_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Adjustment journal write failed: {}", e),
+    }
+}
+
+/// Poll the inbox and apply queued commands to the live tracker
+pub fn spawn_inbox_task(tracker: Arc<PositionTracker>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INBOX_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            for cmd in take_inbox(POSITION_INBOX_FILE) {
+                let result = apply(&cmd, &tracker, strategy_ledger()).await;
+                crate::console_println!("✍️ Manual adjustment {}: {}", cmd.token_id(), result);
+                journal(&cmd, &strategy_ledger().tag(), &result);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(&args("close t1 0.42 sold on UI")).unwrap(),
+            ManualCommand::Close { token_id: "t1".into(), price: Some(0.42), note: Some("sold on UI".into()) }
+        );
+        assert_eq!(
+            parse_args(&args("close t1")).unwrap(),
+            ManualCommand::Close { token_id: "t1".into(), price: None, note: None }
+        );
+        assert_eq!(
+            parse_args(&args("set t1 50 0.3")).unwrap(),
+            ManualCommand::Set { token_id: "t1".into(), shares: 50.0, price: 0.3, note: None }
+        );
+        assert!(parse_args(&args("set t1 50")).is_err());
+        assert!(parse_args(&args("close t1 1.5")).is_err());
+        assert!(parse_args(&args("note t1")).is_err());
+        assert!(parse_args(&args("drop t1")).is_err());
+    }
+
+    #[test]
+    fn test_inbox_round_trip() {
+        let path = std::env::temp_dir().join(format!("pm_position_inbox_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let note = ManualCommand::Note { token_id: "t1".into(), note: "hedged elsewhere".into() };
+        queue(&note, path).unwrap();
+        queue(&ManualCommand::Close { token_id: "t2".into(), price: None, note: None }, path).unwrap();
+        let taken = take_inbox(path);
+        assert_eq!(taken.len(), 2);
+        assert_eq!(taken[0], note);
+        assert!(take_inbox(path).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_keeps_tracker_and_ledger_in_step() {
+        let tracker = PositionTracker::new();
+        let ledger = StrategyLedger::new("manual", None, None);
+        tracker.add_position("t1".into(), 0.40, 100.0).await;
+        ledger.record("t1", true, 100.0, 0.40);
+
+        apply(&parse_args(&args("set t1 60 0.45 partial exit on UI")).unwrap(), &tracker, &ledger).await;
+        let pos = tracker.get_position("t1").await.unwrap();
+        assert_eq!((pos.shares, pos.entry_price), (60.0, 0.45));
+        assert_eq!(pos.note.as_deref(), Some("partial exit on UI"));
+        assert_eq!(ledger.held("t1"), Some((60.0, 0.45)));
+
+        apply(&parse_args(&args("close t1 0.55")).unwrap(), &tracker, &ledger).await;
+        assert!(tracker.get_position("t1").await.is_none());
+        assert!(ledger.held("t1").is_none());
+        assert!((ledger.snapshot()[0].1.realized_pnl - 6.0).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,7 @@
 /// Market cache management with automatic refresh
 /// Handles caching of market data, tokens, and live status
 
+use crate::timestamp;
 use rustc_hash::FxHashMap;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -145,10 +146,7 @@ impl MarketCaches {
         result.load_time_ms = elapsed.as_millis() as u64;
 
         // Update last refresh timestamp
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = timestamp::unix_secs();
         self.last_refresh.store(now, Ordering::Relaxed);
         self.stats.refresh_count.fetch_add(1, Ordering::Relaxed);
         self.stats.last_refresh_duration_ms.store(result.load_time_ms, Ordering::Relaxed);
@@ -239,10 +237,7 @@ impl MarketCaches {
     /// Check if cache refresh is needed
     pub fn needs_refresh(&self) -> bool {
         let last = self.last_refresh.load(Ordering::Relaxed);
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = timestamp::unix_secs();
         now - last >= CACHE_REFRESH_INTERVAL_SECS
     }
 }
@@ -295,7 +290,7 @@ pub fn global_caches() -> &'static MarketCaches {
 pub fn init_caches() -> CacheLoadResult {
     let caches = global_caches();
     let result = caches.load_all();
-    println!("📦 {}", result);
+    crate::console_println!("📦 {}", result);
     result
 }
 
@@ -303,7 +298,7 @@ pub fn init_caches() -> CacheLoadResult {
 pub fn refresh_caches() -> CacheLoadResult {
     let caches = global_caches();
     let result = caches.load_all();
-    println!("🔄 Cache refresh: {}", result);
+    crate::console_println!("🔄 Cache refresh: {}", result);
     result
 }
 
@@ -316,7 +311,7 @@ pub fn refresh_caches() -> CacheLoadResult {
 pub fn spawn_cache_refresh_task() -> tokio::task::JoinHandle<()> {
     tokio::spawn(async {
         let interval = Duration::from_secs(CACHE_REFRESH_INTERVAL_SECS);
-        println!("🔄 Cache refresh task started (interval: {}s)", CACHE_REFRESH_INTERVAL_SECS);
+        crate::console_println!("🔄 Cache refresh task started (interval: {}s)", CACHE_REFRESH_INTERVAL_SECS);
 
         loop {
             tokio::time::sleep(interval).await;
@@ -325,7 +320,7 @@ pub fn spawn_cache_refresh_task() -> tokio::task::JoinHandle<()> {
             let result = tokio::task::spawn_blocking(refresh_caches).await;
 
             if let Err(e) = result {
-                eprintln!("⚠️ Cache refresh task error: {}", e);
+                crate::console_eprintln!("⚠️ Cache refresh task error: {}", e);
             }
         }
     })
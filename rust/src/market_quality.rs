@@ -0,0 +1,383 @@
+//! Market quality screener
+//! Some market families trade chronically badly: wide spreads, a few dollars of depth, nobody
+//! taking. Copying the whale into them pays the spread in and out for little edge. Every token
+//! the whale enters is sampled periodically (spread and top-of-book depth from the CLOB, taker
+//! trades over the last hour from the public Data API) and the samples are pooled per family,
+//! the leading segments of the slug as in `clustering`. Once a family has enough samples in
+//! the rolling window, entries are skipped while its averages break a threshold. Families can
+//! be forced either way in config, and every change of a family's verdict is journaled
+
+use crate::clustering::cluster_key;
+use crate::depth_history::{best_ask, best_bid, Book, BookFetcher};
+use crate::timestamp;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Verdict changes, one JSON object per line
+pub const QUALITY_LOG_FILE: &str = "market_quality.jsonl";
+
+/// Public trade history (no auth), used for taker counts
+pub const DATA_API_BASE: &str = "https://data-api.polymarket.com";
+
+/// Book levels per side counted as depth
+const DEPTH_LEVELS: usize = 5;
+
+/// Trades looked at per market and sample
+const TRADES_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityPolicy {
+    /// Average best ask - best bid above this is poor
+    pub max_spread: Option<f64>,
+    /// Average USD on the top `DEPTH_LEVELS` levels of both sides below this is poor
+    pub min_depth_usd: Option<f64>,
+    /// Average taker trades per hour below this is poor
+    pub min_trades_per_hour: Option<f64>,
+    /// Samples older than this drop out of a family's averages
+    pub window_secs: u64,
+    /// A family is judged only once it has this many samples in the window
+    pub min_samples: usize,
+    pub sample_secs: u64,
+    /// Leading slug segments that make up the family key
+    pub slug_segments: usize,
+    /// Family keys (or prefixes of them) never skipped / always skipped
+    pub allow: Vec<String>,
+    pub block: Vec<String>,
+}
+
+/// Comma-separated family prefixes (QUALITY_ALLOW / QUALITY_BLOCK)
+pub fn parse_families(raw: &str) -> Vec<String> {
+    raw.split(',').map(str::trim).filter(|f| !f.is_empty()).map(String::from).collect()
+}
+
+impl QualityPolicy {
+    /// Whether anything can be skipped (otherwise the screener stays off)
+    pub fn is_active(&self) -> bool {
+        self.max_spread.is_some() || self.min_depth_usd.is_some() || self.min_trades_per_hour.is_some() || !self.block.is_empty()
+    }
+
+    fn listed(list: &[String], family: &str) -> bool {
+        list.iter().any(|p| family.starts_with(p.as_str()))
+    }
+}
+
+// ============================================================================
+// Samples and Stats
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub ts: u64,
+    pub spread: f64,
+    pub depth_usd: f64,
+    /// None when the trade lookup failed
+    pub trades_per_hour: Option<f64>,
+}
+
+/// Sample of a book with both sides quoted; one-sided books are left out
+pub fn book_sample(book: &Book, ts: u64, trades_per_hour: Option<f64>) -> Option<Sample> {
+    let spread = best_ask(book)? - best_bid(book)?;
+    let side_usd = |levels: &[(f64, f64)], best_first: fn(&(f64, f64), &(f64, f64)) -> std::cmp::Ordering| {
+        let mut levels = levels.to_vec();
+        levels.sort_by(best_first);
+        levels.iter().take(DEPTH_LEVELS).map(|(p, s)| p * s).sum::<f64>()
+    };
+    let depth_usd = side_usd(&book.0, |a, b| b.0.total_cmp(&a.0)) + side_usd(&book.1, |a, b| a.0.total_cmp(&b.0));
+    Some(Sample { ts, spread, depth_usd, trades_per_hour })
+}
+
+/// Trades in a Data API `/trades` response at or after `since` (unix seconds)
+pub fn count_trades_since(trades: &serde_json::Value, since: u64) -> usize {
+    trades.as_array()
+        .map(|arr| arr.iter().filter(|t| t["timestamp"].as_u64().is_some_and(|ts| ts >= since)).count())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FamilyStats {
+    pub samples: usize,
+    pub avg_spread: f64,
+    pub avg_depth_usd: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trades_per_hour: Option<f64>,
+}
+
+pub fn summarize(samples: &VecDeque<Sample>) -> FamilyStats {
+    let n = samples.len().max(1) as f64;
+    let trades: Vec<f64> = samples.iter().filter_map(|s| s.trades_per_hour).collect();
+    FamilyStats {
+        samples: samples.len(),
+        avg_spread: samples.iter().map(|s| s.spread).sum::<f64>() / n,
+        avg_depth_usd: samples.iter().map(|s| s.depth_usd).sum::<f64>() / n,
+        trades_per_hour: (!trades.is_empty()).then(|| trades.iter().sum::<f64>() / trades.len() as f64),
+    }
+}
+
+// ============================================================================
+// Screener
+// ============================================================================
+
+pub struct Screener {
+    policy: QualityPolicy,
+    /// Family key -> samples, oldest first
+    families: Mutex<FxHashMap<String, VecDeque<Sample>>>,
+    /// token_id -> (family key, last whale entry seen), the tokens sampled
+    watched: Mutex<FxHashMap<String, (String, u64)>>,
+    /// Families currently judged poor, for logging verdict changes
+    flagged: Mutex<FxHashSet<String>>,
+}
+
+impl Screener {
+    pub fn new(policy: QualityPolicy) -> Self {
+        Self {
+            policy,
+            families: Mutex::new(FxHashMap::default()),
+            watched: Mutex::new(FxHashMap::default()),
+            flagged: Mutex::new(FxHashSet::default()),
+        }
+    }
+
+    pub fn policy(&self) -> &QualityPolicy {
+        &self.policy
+    }
+
+    pub fn family_of(&self, token_id: &str, slug: Option<&str>) -> String {
+        cluster_key(token_id, slug, self.policy.slug_segments)
+    }
+
+    /// Sample `token_id` from now on (until the window passes without another entry)
+    pub fn watch(&self, token_id: &str, family: &str, now: u64) {
+        if let Ok(mut watched) = self.watched.lock() {
+            watched.insert(token_id.to_string(), (family.to_string(), now));
+        }
+    }
+
+    /// Watched tokens with their family, dropping those not entered within the window
+    pub fn watched(&self, now: u64) -> Vec<(String, String)> {
+        let Ok(mut watched) = self.watched.lock() else { return Vec::new() };
+        let cutoff = now.saturating_sub(self.policy.window_secs);
+        watched.retain(|_, (_, seen)| *seen > cutoff);
+        watched.iter().map(|(token, (family, _))| (token.clone(), family.clone())).collect()
+    }
+
+    pub fn record(&self, family: &str, sample: Sample) {
+        if let Ok(mut families) = self.families.lock() {
+            families.entry(family.to_string()).or_default().push_back(sample);
+        }
+    }
+
+    /// Family stats over the window at `now`, dropping older samples
+    pub fn stats(&self, family: &str, now: u64) -> Option<FamilyStats> {
+        let mut families = self.families.lock().ok()?;
+        let samples = families.get_mut(family)?;
+        let cutoff = now.saturating_sub(self.policy.window_secs);
+        while samples.front().is_some_and(|s| s.ts <= cutoff) {
+            samples.pop_front();
+        }
+        Some(summarize(samples))
+    }
+
+    /// Ok, or Err(reason) when entries into `family` should be skipped
+    pub fn check(&self, family: &str, now: u64) -> Result<(), String> {
+        let p = &self.policy;
+        if QualityPolicy::listed(&p.allow, family) {
+            return Ok(());
+        }
+        if QualityPolicy::listed(&p.block, family) {
+            return Err("blocked in QUALITY_BLOCK".into());
+        }
+        let Some(stats) = self.stats(family, now).filter(|s| s.samples >= p.min_samples.max(1)) else { return Ok(()) };
+        if let Some(max) = p.max_spread && stats.avg_spread > max {
+            return Err(format!("spread {:.3} > {:.3}", stats.avg_spread, max));
+        }
+        if let Some(min) = p.min_depth_usd && stats.avg_depth_usd < min {
+            return Err(format!("depth ${:.0} < ${:.0}", stats.avg_depth_usd, min));
+        }
+        if let Some(min) = p.min_trades_per_hour
+            && let Some(rate) = stats.trades_per_hour
+            && rate < min {
+                return Err(format!("{:.1} trades/h < {:.1}", rate, min));
+            }
+        Ok(())
+    }
+
+    /// Re-judge every sampled family; returns those whose verdict changed (family, reason if now poor)
+    pub fn verdict_changes(&self, now: u64) -> Vec<(String, Option<String>)> {
+        let families: Vec<String> = self.families.lock().map(|f| f.keys().cloned().collect()).unwrap_or_default();
+        let Ok(mut flagged) = self.flagged.lock() else { return Vec::new() };
+        let mut changes = Vec::new();
+        for family in families {
+            let reason = self.check(&family, now).err();
+            let was_poor = flagged.contains(&family);
+            match &reason {
+                Some(_) if !was_poor => { flagged.insert(family.clone()); }
+                None if was_poor => { flagged.remove(&family); }
+                _ => continue,
+            }
+            changes.push((family, reason));
+        }
+        changes
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct VerdictRecord<'a> {
+    ts: u64,
+    family: &'a str,
+    poor: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<FamilyStats>,
+}
+
+fn journal(record: &VerdictRecord) {
+    let Ok(line) = serde_json::to_string(record) else { return };
+    match OpenOptions::new().append(true).create(true).open(QUALITY_LOG_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Market quality log write failed: {}", e),
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_SCREENER: OnceLock<Option<Screener>> = OnceLock::new();
+
+/// Set once at startup (None = markets are not screened)
+pub fn init_screener(policy: Option<QualityPolicy>) {
+    let _ = GLOBAL_SCREENER.set(policy.filter(QualityPolicy::is_active).map(Screener::new));
+}
+
+pub fn screener() -> Option<&'static Screener> {
+    GLOBAL_SCREENER.get().and_then(Option::as_ref)
+}
+
+/// Family and reason when an entry into `token_id` should be skipped. Every entry attempt
+/// keeps its token sampled, so a skipped family can recover
+pub fn blocked_entry(token_id: &str) -> Option<(String, String)> {
+    let screener = screener()?;
+    let slug = crate::token_metadata::get(token_id).map(|m| m.slug);
+    let family = screener.family_of(token_id, slug.as_deref());
+    let now = timestamp::unix_secs();
+    screener.watch(token_id, &family, now);
+    screener.check(&family, now).err().map(|reason| (family, reason))
+}
+
+/// Taker trades over the last hour per market (condition id); failed lookups are left out
+fn fetch_trade_rates(conditions: Vec<String>, now: u64) -> FxHashMap<String, f64> {
+    let http = reqwest::blocking::Client::new();
+    let mut rates = FxHashMap::default();
+    for condition in conditions {
+        let url = format!("{}/trades?market={}&limit={}&takerOnly=true", DATA_API_BASE, condition, TRADES_LIMIT);
+        let Ok(resp) = http.get(&url).timeout(Duration::from_secs(5)).send() else { continue };
+        if let Ok(trades) = resp.json::<serde_json::Value>() {
+            rates.insert(condition, count_trades_since(&trades, now.saturating_sub(3600)) as f64);
+        }
+    }
+    rates
+}
+
+/// Sample the watched tokens every `sample_secs` and journal verdict changes
+pub fn spawn_sample_task(fetcher: Arc<dyn BookFetcher>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(screener) = screener() else { return };
+        let mut ticker = tokio::time::interval(Duration::from_secs(screener.policy().sample_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            let now = timestamp::unix_secs();
+            let watched: FxHashMap<String, String> = screener.watched(now).into_iter().collect();
+            if watched.is_empty() {
+                continue;
+            }
+            let tokens: Vec<String> = watched.keys().cloned().collect();
+            let condition_of: FxHashMap<String, String> = tokens.iter()
+                .filter_map(|t| Some((t.clone(), crate::token_metadata::get(t)?.condition_id)))
+                .filter(|(_, c)| !c.is_empty())
+                .collect();
+            let conditions: Vec<String> = condition_of.values().cloned().collect::<FxHashSet<_>>().into_iter().collect();
+            let rates = tokio::task::spawn_blocking(move || fetch_trade_rates(conditions, now)).await.unwrap_or_default();
+            for (token_id, book) in fetcher.fetch_books(&tokens).await {
+                let rate = condition_of.get(&token_id).and_then(|c| rates.get(c)).copied();
+                if let (Some(family), Some(sample)) = (watched.get(&token_id), book_sample(&book, now, rate)) {
+                    screener.record(family, sample);
+                }
+            }
+            for (family, reason) in screener.verdict_changes(now) {
+                let stats = screener.stats(&family, now);
+                match &reason {
+                    Some(r) => crate::console_println!("🧹 Market quality: skipping entries into {} ({})", family, r),
+                    None => crate::console_println!("🧹 Market quality: {} back within limits", family),
+                }
+                journal(&VerdictRecord { ts: now, family: &family, poor: reason.is_some(), reason: reason.as_deref(), stats });
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> QualityPolicy {
+        QualityPolicy {
+            max_spread: Some(0.05),
+            min_depth_usd: Some(100.0),
+            min_trades_per_hour: Some(2.0),
+            window_secs: 3600,
+            min_samples: 2,
+            sample_secs: 300,
+            slug_segments: 2,
+            allow: vec!["nba-finals".into()],
+            block: vec!["crypto-".into()],
+        }
+    }
+
+    #[test]
+    fn test_book_sample() {
+        let book: Book = (vec![(0.40, 100.0), (0.45, 100.0)], vec![(0.50, 100.0)]);
+        let s = book_sample(&book, 10, Some(3.0)).unwrap();
+        assert!((s.spread - 0.05).abs() < 1e-9);
+        assert!((s.depth_usd - 135.0).abs() < 1e-9);
+        assert!(book_sample(&(vec![(0.4, 10.0)], vec![]), 10, None).is_none());
+
+        let trades = serde_json::json!([{ "timestamp": 100 }, { "timestamp": 50 }, { "timestamp": 200 }]);
+        assert_eq!(count_trades_since(&trades, 100), 2);
+    }
+
+    #[test]
+    fn test_check_and_overrides() {
+        let screener = Screener::new(policy());
+        let wide = Sample { ts: 1000, spread: 0.10, depth_usd: 500.0, trades_per_hour: Some(10.0) };
+        screener.record("nba-lal", wide);
+        // One sample is not enough to judge
+        assert!(screener.check("nba-lal", 1000).is_ok());
+        screener.record("nba-lal", wide);
+        assert!(screener.check("nba-lal", 1000).unwrap_err().starts_with("spread"));
+        assert_eq!(screener.verdict_changes(1000), vec![("nba-lal".to_string(), Some("spread 0.100 > 0.050".to_string()))]);
+        assert!(screener.verdict_changes(1000).is_empty());
+        // Samples age out of the window and the family clears
+        assert!(screener.check("nba-lal", 5000).is_ok());
+        assert_eq!(screener.verdict_changes(5000), vec![("nba-lal".to_string(), None)]);
+
+        screener.record("nba-finals", wide);
+        screener.record("nba-finals", wide);
+        assert!(screener.check("nba-finals", 1000).is_ok());
+        assert!(screener.check("crypto-btc", 1000).is_err());
+
+        let quiet = Sample { ts: 2000, spread: 0.01, depth_usd: 500.0, trades_per_hour: Some(1.0) };
+        screener.record("epl-ars", quiet);
+        screener.record("epl-ars", quiet);
+        assert!(screener.check("epl-ars", 2000).unwrap_err().contains("trades/h"));
+    }
+}
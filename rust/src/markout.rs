@@ -0,0 +1,299 @@
+//! Post-fill markouts (adverse selection)
+//! Each of our fills is marked against the token's mid 10, 30 and 60 seconds later. A buy
+//! followed by a falling mid (or a sell by a rising one) means the other side knew more than
+//! we did; averaged per strategy tag and per token this shows whether we are being picked
+//! off. Completed markouts are appended to `markouts.jsonl`
+
+use crate::depth_history::{self, BookFetcher};
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Completed markouts (one JSON object per line)
+pub const MARKOUT_FILE: &str = "markouts.jsonl";
+
+/// Delays after the fill at which the mid is sampled
+pub const MARKOUT_HORIZONS: [Duration; 3] = [Duration::from_secs(10), Duration::from_secs(30), Duration::from_secs(60)];
+
+/// How often due horizons are sampled
+const MARKOUT_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Bound on fills waiting for their horizons (oldest dropped first)
+const MAX_PENDING: usize = 2_000;
+
+/// Tokens listed in the dump, worst 60s markout first
+const DUMP_WORST_TOKENS: usize = 5;
+
+// ============================================================================
+// Records
+// ============================================================================
+
+#[derive(Debug, Clone)]
+struct Pending {
+    at: Instant,
+    ts: u64,
+    tag: String,
+    token_id: String,
+    is_buy: bool,
+    price: f64,
+    shares: f64,
+    /// Markout per horizon sampled so far (None = no book at that moment)
+    marks: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Markout {
+    /// Unix seconds of the fill
+    pub ts: u64,
+    pub tag: String,
+    pub token_id: String,
+    pub side: &'static str,
+    pub price: f64,
+    pub shares: f64,
+    /// Favourable mid move per share at 10s / 30s / 60s (negative = adverse)
+    pub m10: Option<f64>,
+    pub m30: Option<f64>,
+    pub m60: Option<f64>,
+}
+
+/// Mid move in our favour per share: above a buy, below a sell
+pub fn markout(is_buy: bool, price: f64, mid: f64) -> f64 {
+    if is_buy { mid - price } else { price - mid }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MarkoutStats {
+    fills: u64,
+    /// Per horizon: samples, sum of markouts, adverse samples
+    n: [u64; 3],
+    sum: [f64; 3],
+    adverse: [u64; 3],
+}
+
+impl MarkoutStats {
+    fn add(&mut self, marks: &[Option<f64>]) {
+        self.fills += 1;
+        for (i, m) in marks.iter().enumerate().take(MARKOUT_HORIZONS.len()) {
+            let Some(m) = m else { continue };
+            self.n[i] += 1;
+            self.sum[i] += m;
+            if *m < 0.0 {
+                self.adverse[i] += 1;
+            }
+        }
+    }
+
+    /// Average markout at horizon `i`
+    pub fn avg(&self, i: usize) -> Option<f64> {
+        (self.n[i] > 0).then(|| self.sum[i] / self.n[i] as f64)
+    }
+
+    /// Share of fills the mid moved against at horizon `i`
+    pub fn adverse_share(&self, i: usize) -> Option<f64> {
+        (self.n[i] > 0).then(|| self.adverse[i] as f64 / self.n[i] as f64)
+    }
+
+    fn line(&self) -> String {
+        let mut out = format!("fills={}", self.fills);
+        for (i, h) in MARKOUT_HORIZONS.iter().enumerate() {
+            let avg = self.avg(i).map_or("-".to_string(), |m| format!("{:+.2}c", m * 100.0));
+            let _ = write!(out, " {}s={}", h.as_secs(), avg);
+        }
+        if let Some(share) = self.adverse_share(MARKOUT_HORIZONS.len() - 1) {
+            let _ = write!(out, " adverse={:.0}%", share * 100.0);
+        }
+        out
+    }
+}
+
+// ============================================================================
+// Tracker
+// ============================================================================
+
+#[derive(Default)]
+pub struct MarkoutTracker {
+    pending: Mutex<Vec<Pending>>,
+    by_tag: Mutex<FxHashMap<String, MarkoutStats>>,
+    by_token: Mutex<FxHashMap<String, MarkoutStats>>,
+}
+
+impl MarkoutTracker {
+    /// Start marking a fill of `shares` at `price`, attributed to strategy tag `tag`
+    pub fn record_fill(&self, tag: &str, token_id: &str, is_buy: bool, price: f64, shares: f64) {
+        let Ok(mut pending) = self.pending.lock() else { return };
+        if pending.len() >= MAX_PENDING {
+            pending.remove(0);
+        }
+        pending.push(Pending {
+            at: Instant::now(),
+            ts: timestamp::unix_secs(),
+            tag: tag.to_string(),
+            token_id: token_id.to_string(),
+            is_buy,
+            price,
+            shares,
+            marks: Vec::with_capacity(MARKOUT_HORIZONS.len()),
+        });
+    }
+
+    /// Tokens with a horizon due at `now`
+    pub fn due_tokens(&self, now: Instant) -> Vec<String> {
+        let Ok(pending) = self.pending.lock() else { return Vec::new() };
+        let mut tokens: Vec<String> = pending.iter()
+            .filter(|p| now.duration_since(p.at) >= MARKOUT_HORIZONS[p.marks.len()])
+            .map(|p| p.token_id.clone())
+            .collect();
+        tokens.sort_unstable();
+        tokens.dedup();
+        tokens
+    }
+
+    /// Sample every due horizon from `mids`; returns fills whose last horizon was sampled
+    pub fn apply_mids(&self, mids: &FxHashMap<String, f64>, now: Instant) -> Vec<Markout> {
+        let Ok(mut pending) = self.pending.lock() else { return Vec::new() };
+        for p in pending.iter_mut() {
+            // A late check samples every horizon it overslept with the same mid
+            while p.marks.len() < MARKOUT_HORIZONS.len() && now.duration_since(p.at) >= MARKOUT_HORIZONS[p.marks.len()] {
+                p.marks.push(mids.get(&p.token_id).map(|mid| markout(p.is_buy, p.price, *mid)));
+            }
+        }
+        let (done, waiting): (Vec<_>, Vec<_>) = pending.drain(..).partition(|p| p.marks.len() == MARKOUT_HORIZONS.len());
+        *pending = waiting;
+        drop(pending);
+
+        if let (Ok(mut by_tag), Ok(mut by_token)) = (self.by_tag.lock(), self.by_token.lock()) {
+            for p in &done {
+                by_tag.entry(p.tag.clone()).or_default().add(&p.marks);
+                by_token.entry(p.token_id.clone()).or_default().add(&p.marks);
+            }
+        }
+        done.into_iter()
+            .map(|p| Markout {
+                ts: p.ts,
+                side: if p.is_buy { "BUY" } else { "SELL" },
+                m10: p.marks[0],
+                m30: p.marks[1],
+                m60: p.marks[2],
+                tag: p.tag,
+                token_id: p.token_id,
+                price: p.price,
+                shares: p.shares,
+            })
+            .collect()
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.lock().map(|p| p.len()).unwrap_or(0)
+    }
+
+    /// Dump lines: one per strategy tag, then the tokens with the worst 60s markout
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        if let Ok(by_tag) = self.by_tag.lock() {
+            let mut rows: Vec<_> = by_tag.iter().collect();
+            rows.sort_by(|a, b| a.0.cmp(b.0));
+            for (tag, stats) in rows {
+                let _ = writeln!(out, "  {:<14} tag={} {}", "markout", tag, stats.line());
+            }
+        }
+        if let Ok(by_token) = self.by_token.lock() {
+            let last = MARKOUT_HORIZONS.len() - 1;
+            let mut rows: Vec<_> = by_token.iter().filter(|(_, s)| s.avg(last).is_some()).collect();
+            rows.sort_by(|a, b| a.1.avg(last).partial_cmp(&b.1.avg(last)).unwrap_or(std::cmp::Ordering::Equal));
+            for (token, stats) in rows.into_iter().take(DUMP_WORST_TOKENS) {
+                let name = crate::token_metadata::label(token).unwrap_or_else(|| token.clone());
+                let _ = writeln!(out, "  {:<14} token {} {}", "markout", name, stats.line());
+            }
+        }
+        out
+    }
+}
+
+static GLOBAL_MARKOUTS: OnceLock<MarkoutTracker> = OnceLock::new();
+
+/// Get the global markout tracker
+pub fn markouts() -> &'static MarkoutTracker {
+    GLOBAL_MARKOUTS.get_or_init(MarkoutTracker::default)
+}
+
+/// Start marking one of our fills under this instance's strategy tag
+pub fn record_fill(token_id: &str, is_buy: bool, price: f64, shares: f64) {
+    markouts().record_fill(&crate::strategy::strategy_ledger().tag(), token_id, is_buy, price, shares);
+}
+
+/// Spawn the task that samples due horizons from the books and appends completed markouts
+/// to `MARKOUT_FILE`
+pub fn spawn_markout_task(fetcher: Arc<dyn BookFetcher>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MARKOUT_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let tokens = markouts().due_tokens(Instant::now());
+            if tokens.is_empty() {
+                continue;
+            }
+            let mids: FxHashMap<String, f64> = fetcher.fetch_books(&tokens).await
+                .into_iter()
+                .filter_map(|(token, book)| Some((token, depth_history::mid(&book)?)))
+                .collect();
+            let done = markouts().apply_mids(&mids, Instant::now());
+            let mut lines = String::new();
+            for m in &done {
+                if let Ok(line) = serde_json::to_string(m) {
+                    lines.push_str(&line);
+                    lines.push('\n');
+                }
+            }
+            if lines.is_empty() {
+                continue;
+            }
+            let written = OpenOptions::new().append(true).create(true).open(MARKOUT_FILE)
+                .and_then(|mut f| f.write_all(lines.as_bytes()));
+            if let Err(e) = written {
+                crate::console_eprintln!("⚠️ Markout journal write failed: {}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_horizons_and_adverse_share() {
+        let t = MarkoutTracker::default();
+        t.record_fill("whale_a", "tok", true, 0.50, 10.0);
+        t.record_fill("whale_a", "tok", false, 0.50, 10.0);
+        let start = Instant::now();
+        assert!(t.due_tokens(start).is_empty());
+
+        let mut mids = FxHashMap::default();
+        mids.insert("tok".to_string(), 0.52);
+        assert!(t.apply_mids(&mids, start + Duration::from_secs(10)).is_empty());
+        assert_eq!(t.due_tokens(start + Duration::from_secs(30)), vec!["tok".to_string()]);
+
+        // No book at 30s; 60s is sampled with the mid down 3c
+        assert!(t.apply_mids(&FxHashMap::default(), start + Duration::from_secs(30)).is_empty());
+        mids.insert("tok".to_string(), 0.47);
+        let done = t.apply_mids(&mids, start + Duration::from_secs(61));
+        assert_eq!(done.len(), 2);
+        assert!((done[0].m10.unwrap() - 0.02).abs() < 1e-9);
+        assert_eq!(done[0].m30, None);
+        assert!((done[0].m60.unwrap() + 0.03).abs() < 1e-9);
+        assert!((done[1].m60.unwrap() - 0.03).abs() < 1e-9);
+        assert_eq!(t.pending_len(), 0);
+
+        let report = t.report();
+        assert!(report.contains("tag=whale_a fills=2 10s=+0.00c 30s=- 60s=+0.00c adverse=50%"), "{}", report);
+    }
+}
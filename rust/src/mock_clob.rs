@@ -0,0 +1,889 @@
+//! Mock CLOB exchange for end-to-end runs
+//! One in-process server stands in for everything `pm_bot` talks to: the CLOB REST API (API
+//! key derivation, books, order posting with matching, balance, trade history), the Gamma
+//! market lookups, the market channel and the Polygon log subscription that carries the
+//! whale's fills. Tests (or `cargo run --bin mock_clob`) script it: set books, emit a whale
+//! fill, resolve the market. Orders are matched against the scripted book, so a run goes from
+//! the whale's tick to settlement without the network and with the same result every time.
+//! Point the bot at it with the variables from `MockEndpoints::bot_env`
+
+use crate::settings::{MONITORED_ADDRESSES, ORDERS_FILLED_EVENT_SIGNATURE};
+use alloy::primitives::U256;
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Base64 secret of every key the mock issues (the bot only needs it to decode)
+const MOCK_SECRET: &str = "bW9jay1jbG9iLXNlY3JldA==";
+
+/// Whale logs and book events queued for a slow WebSocket client
+const EVENT_BUFFER: usize = 1024;
+
+/// USDC and share amounts on chain and in signed orders
+const AMOUNT_SCALE: f64 = 1e6;
+
+/// Most the signed USDC amount can be off size x limit price (clients round it to whole cents)
+const USD_ROUNDING: f64 = 0.01;
+
+// ============================================================================
+// Markets and Books
+// ============================================================================
+
+/// A binary market as Gamma describes it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockMarket {
+    pub slug: String,
+    #[serde(default)]
+    pub question: String,
+    /// Outcome tokens, decimal ids as on Polymarket
+    pub token_ids: Vec<String>,
+    #[serde(default = "default_outcomes")]
+    pub outcomes: Vec<String>,
+    #[serde(default = "default_tick")]
+    pub tick_size: f64,
+    #[serde(default = "default_min_size")]
+    pub min_order_size: f64,
+    /// Gamma's in-play flag
+    #[serde(default)]
+    pub live: bool,
+    #[serde(default)]
+    pub closed: bool,
+    /// Payout per token once closed
+    #[serde(default)]
+    pub payouts: Vec<f64>,
+}
+
+fn default_outcomes() -> Vec<String> {
+    vec!["Yes".into(), "No".into()]
+}
+
+fn default_tick() -> f64 {
+    0.01
+}
+
+fn default_min_size() -> f64 {
+    5.0
+}
+
+impl MockMarket {
+    pub fn binary(slug: &str, yes: &str, no: &str) -> Self {
+        Self {
+            slug: slug.to_string(),
+            question: format!("{}?", slug),
+            token_ids: vec![yes.to_string(), no.to_string()],
+            outcomes: default_outcomes(),
+            tick_size: default_tick(),
+            min_order_size: default_min_size(),
+            live: false,
+            closed: false,
+            payouts: Vec::new(),
+        }
+    }
+
+    fn gamma_json(&self) -> Value {
+        let list = |items: Vec<String>| serde_json::to_string(&items).unwrap_or_default();
+        json!({
+            "slug": self.slug,
+            "question": self.question,
+            "conditionId": format!("0x{:064x}", fnv(&self.slug)),
+            "clobTokenIds": list(self.token_ids.clone()),
+            "outcomes": list(self.outcomes.clone()),
+            "outcomePrices": list(self.payouts.iter().map(|p| p.to_string()).collect()),
+            "orderPriceMinTickSize": self.tick_size,
+            "orderMinSize": self.min_order_size,
+            "negRisk": false,
+            "active": !self.closed,
+            "closed": self.closed,
+        })
+    }
+}
+
+/// Price levels, bids best (highest) first and asks best (lowest) first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockBook {
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+impl MockBook {
+    pub fn new(mut bids: Vec<(f64, f64)>, mut asks: Vec<(f64, f64)>) -> Self {
+        bids.sort_by(|a, b| b.0.total_cmp(&a.0));
+        asks.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { bids, asks }
+    }
+
+    /// Take up to `size` from the opposite side at `limit` or better; (shares, notional)
+    fn take(&mut self, is_buy: bool, limit: f64, size: f64) -> (f64, f64) {
+        let levels = if is_buy { &mut self.asks } else { &mut self.bids };
+        let (mut filled, mut notional) = (0.0, 0.0);
+        for level in levels.iter_mut() {
+            let crosses = if is_buy { level.0 <= limit + 1e-9 } else { level.0 >= limit - 1e-9 };
+            if !crosses || filled >= size {
+                break;
+            }
+            let qty = level.1.min(size - filled);
+            level.1 -= qty;
+            filled += qty;
+            notional += qty * level.0;
+        }
+        levels.retain(|l| l.1 > 1e-9);
+        (round6(filled), round6(notional))
+    }
+
+    fn json(&self, token_id: &str, tick: f64, min_size: f64) -> Value {
+        let side = |levels: &[(f64, f64)]| -> Vec<Value> {
+            levels.iter().map(|(p, s)| json!({ "price": p.to_string(), "size": s.to_string() })).collect()
+        };
+        // The CLOB lists both sides worst first
+        let bids: Vec<(f64, f64)> = self.bids.iter().rev().copied().collect();
+        let asks: Vec<(f64, f64)> = self.asks.iter().rev().copied().collect();
+        json!({
+            "asset_id": token_id,
+            "bids": side(&bids),
+            "asks": side(&asks),
+            "tick_size": tick.to_string(),
+            "min_order_size": min_size.to_string(),
+            "neg_risk": false,
+            "timestamp": unix_ms().to_string(),
+        })
+    }
+}
+
+#[inline]
+fn round6(x: f64) -> f64 {
+    (x * 1e6).round() / 1e6
+}
+
+fn unix_ms() -> u64 {
+    crate::timestamp::unix_millis()
+}
+
+/// Stable id material for hashes the mock makes up
+fn fnv(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+// ============================================================================
+// Orders
+// ============================================================================
+
+/// Every order the mock received, accepted or not
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MockOrder {
+    pub order_id: String,
+    pub token_id: String,
+    pub is_buy: bool,
+    pub price: f64,
+    pub size: f64,
+    pub order_type: String,
+    pub post_only: bool,
+    /// Shares matched on arrival
+    pub filled: f64,
+    /// "matched", "live" (rest of a GTC/GTD, never filled later) or the rejection message
+    pub status: String,
+}
+
+#[derive(Debug)]
+struct OrderRequest {
+    token_id: String,
+    is_buy: bool,
+    price: f64,
+    size: f64,
+    order_type: String,
+    post_only: bool,
+}
+
+impl OrderRequest {
+    /// Price and size back from the signed amounts (USDC and shares, 6 decimals)
+    fn parse(body: &Value) -> Option<Self> {
+        let order = &body["order"];
+        let amount = |key: &str| order[key].as_str()?.parse::<f64>().ok().map(|a| a / AMOUNT_SCALE);
+        let (maker, taker) = (amount("makerAmount")?, amount("takerAmount")?);
+        let is_buy = order["side"].as_str()?.eq_ignore_ascii_case("BUY");
+        let (size, usd) = if is_buy { (taker, maker) } else { (maker, taker) };
+        if size <= 0.0 {
+            return None;
+        }
+        Some(Self {
+            token_id: order["tokenId"].as_str()?.to_string(),
+            is_buy,
+            price: round6(usd / size),
+            size,
+            order_type: body["orderType"].as_str().unwrap_or("GTC").to_ascii_uppercase(),
+            post_only: body["postOnly"].as_bool().unwrap_or(false),
+        })
+    }
+
+    /// Limit price the amounts were signed from: the implied price snapped to the tick, if
+    /// the USDC amount is within `USD_ROUNDING` of size x that price
+    fn limit_price(&self, tick: f64) -> Option<f64> {
+        let limit = round6((self.price / tick).round() * tick);
+        ((limit - self.price).abs() * self.size <= USD_ROUNDING + 1e-9).then_some(limit)
+    }
+}
+
+// ============================================================================
+// Exchange State
+// ============================================================================
+
+#[derive(Default)]
+struct State {
+    markets: Vec<MockMarket>,
+    books: FxHashMap<String, MockBook>,
+    balance_usd: f64,
+    positions: FxHashMap<String, f64>,
+    orders: Vec<MockOrder>,
+    /// Trade history rows (`/data/trades`)
+    trades: Vec<Value>,
+    /// Scripted rejections for the next orders, oldest first
+    rejections: Vec<String>,
+    api_key: String,
+}
+
+impl State {
+    fn market(&self, token_id: &str) -> Option<&MockMarket> {
+        self.markets.iter().find(|m| m.token_ids.iter().any(|t| t == token_id))
+    }
+}
+
+/// HTTP status and JSON body of a handled request
+pub type Reply = (u16, String);
+
+fn error(status: u16, message: &str) -> Reply {
+    (status, json!({ "error": message }).to_string())
+}
+
+pub struct MockClob {
+    state: Mutex<State>,
+    /// Log subscription lines for whale fills
+    whale_tx: broadcast::Sender<String>,
+    /// Market channel events (token, JSON)
+    book_tx: broadcast::Sender<(String, String)>,
+    whale_topic: String,
+    next_id: AtomicU64,
+}
+
+impl MockClob {
+    /// Exchange with `balance_usd` USDC for the bot, copying the 40-hex `whale` address
+    pub fn new(whale: &str, balance_usd: f64) -> Arc<Self> {
+        let whale = whale.trim_start_matches("0x").to_lowercase();
+        Arc::new(Self {
+            state: Mutex::new(State { balance_usd, api_key: "mock-key-1".into(), ..State::default() }),
+            whale_tx: broadcast::channel(EVENT_BUFFER).0,
+            book_tx: broadcast::channel(EVENT_BUFFER).0,
+            whale_topic: format!("0x000000000000000000000000{}", whale),
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn add_market(&self, market: MockMarket) {
+        self.lock().markets.push(market);
+    }
+
+    /// Replace a token's book and push it on the market channel
+    pub fn set_book(&self, token_id: &str, book: MockBook) {
+        let event = {
+            let mut state = self.lock();
+            let (tick, min_size) = state.market(token_id).map_or((default_tick(), default_min_size()), |m| (m.tick_size, m.min_order_size));
+            let mut event = book.json(token_id, tick, min_size);
+            event["event_type"] = json!("book");
+            state.books.insert(token_id.to_string(), book);
+            event
+        };
+        let _ = self.book_tx.send((token_id.to_string(), event.to_string()));
+    }
+
+    pub fn book(&self, token_id: &str) -> Option<MockBook> {
+        self.lock().books.get(token_id).cloned()
+    }
+
+    /// Emit the whale's fill on the log subscription, as the exchange contract would log it
+    pub fn whale_fill(&self, token_id: &str, is_buy: bool, shares: f64, price: f64) {
+        let n = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let _ = self.whale_tx.send(whale_log(&self.whale_topic, token_id, is_buy, shares, price, n));
+    }
+
+    /// Close the market of `winner`, paying 1 on it and 0 on the other outcomes
+    pub fn resolve(&self, winner: &str) {
+        let mut state = self.lock();
+        if let Some(market) = state.markets.iter_mut().find(|m| m.token_ids.iter().any(|t| t == winner)) {
+            market.payouts = market.token_ids.iter().map(|t| if t == winner { 1.0 } else { 0.0 }).collect();
+            market.closed = true;
+            market.live = false;
+        }
+    }
+
+    /// Reject the next order with `message` (classified like a real rejection)
+    pub fn reject_next(&self, message: &str) {
+        self.lock().rejections.push(message.to_string());
+    }
+
+    /// Revoke the current API key: requests signed with it get 401 and the next derive
+    /// returns a new one
+    pub fn rotate_api_key(&self) {
+        let n = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.lock().api_key = format!("mock-key-{}", n + 1);
+    }
+
+    /// Clients subscribed to whale logs (a bot that has connected and subscribed)
+    pub fn feed_subscribers(&self) -> usize {
+        self.whale_tx.receiver_count()
+    }
+
+    pub fn orders(&self) -> Vec<MockOrder> {
+        self.lock().orders.clone()
+    }
+
+    pub fn balance(&self) -> f64 {
+        self.lock().balance_usd
+    }
+
+    pub fn position(&self, token_id: &str) -> f64 {
+        self.lock().positions.get(token_id).copied().unwrap_or(0.0)
+    }
+
+    // ------------------------------------------------------------------------
+    // Routing
+    // ------------------------------------------------------------------------
+
+    /// Answer one request. `target` is the path with its query, `api_key` the POLY_API_KEY
+    /// header of L2-authenticated requests
+    pub fn handle(&self, method: &str, target: &str, api_key: Option<&str>, body: &str) -> Reply {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let param = |name: &str| query.split('&').find_map(|kv| kv.strip_prefix(name)?.strip_prefix('=')).unwrap_or_default();
+        let authed = |state: &State| api_key == Some(state.api_key.as_str());
+
+        match (method, path) {
+            ("GET", "/time") => (200, (unix_ms() / 1000).to_string()),
+            ("GET", "/auth/derive-api-key") | ("POST", "/auth/api-key") => {
+                let key = self.lock().api_key.clone();
+                (200, json!({ "apiKey": key, "secret": MOCK_SECRET, "passphrase": "mock" }).to_string())
+            }
+            ("GET", "/book") => {
+                let state = self.lock();
+                let token = param("token_id");
+                match (state.books.get(token), state.market(token)) {
+                    (Some(book), market) => {
+                        let (tick, min_size) = market.map_or((default_tick(), default_min_size()), |m| (m.tick_size, m.min_order_size));
+                        (200, book.json(token, tick, min_size).to_string())
+                    }
+                    (None, _) => error(404, "No orderbook exists for the requested token id"),
+                }
+            }
+            ("POST", "/books") => {
+                let state = self.lock();
+                let wanted: Vec<Value> = serde_json::from_str(body).unwrap_or_default();
+                let books: Vec<Value> = wanted.iter()
+                    .filter_map(|w| w["token_id"].as_str())
+                    .filter_map(|t| {
+                        let (tick, min_size) = state.market(t).map_or((default_tick(), default_min_size()), |m| (m.tick_size, m.min_order_size));
+                        Some(state.books.get(t)?.json(t, tick, min_size))
+                    })
+                    .collect();
+                (200, Value::Array(books).to_string())
+            }
+            ("POST", "/order") => self.post_order(api_key, body),
+            ("GET", "/balance-allowance") => {
+                let state = self.lock();
+                if !authed(&state) {
+                    return error(401, "Unauthorized/Invalid api key");
+                }
+                let allowances: serde_json::Map<String, Value> = MONITORED_ADDRESSES.iter()
+                    .map(|a| (a.to_string(), json!(U256::MAX.to_string())))
+                    .collect();
+                let balance = (state.balance_usd * AMOUNT_SCALE).floor() as u64;
+                (200, json!({ "balance": balance.to_string(), "allowances": allowances }).to_string())
+            }
+            ("GET", "/data/trades") => {
+                let state = self.lock();
+                if !authed(&state) {
+                    return error(401, "Unauthorized/Invalid api key");
+                }
+                let after: u64 = param("after").parse().unwrap_or(0);
+                let trades: Vec<&Value> = state.trades.iter()
+                    .filter(|t| t["match_time"].as_str().and_then(|s| s.parse::<u64>().ok()).unwrap_or(0) >= after)
+                    .collect();
+                (200, json!({ "data": trades, "next_cursor": "LTE=" }).to_string())
+            }
+            // Gamma
+            ("GET", "/markets") => {
+                let state = self.lock();
+                let token = param("clob_token_ids");
+                let found: Vec<Value> = state.market(token).map(MockMarket::gamma_json).into_iter().collect();
+                (200, Value::Array(found).to_string())
+            }
+            ("GET", p) if p.starts_with("/events/slug/") => {
+                let slug = &p["/events/slug/".len()..];
+                match self.lock().markets.iter().find(|m| m.slug == slug) {
+                    Some(m) => (200, json!({ "slug": m.slug, "live": m.live, "closed": m.closed }).to_string()),
+                    None => error(404, "not found"),
+                }
+            }
+            _ => error(404, "not found"),
+        }
+    }
+
+    fn post_order(&self, api_key: Option<&str>, body: &str) -> Reply {
+        let mut state = self.lock();
+        if api_key != Some(state.api_key.as_str()) {
+            return error(401, "Unauthorized/Invalid api key");
+        }
+        let Some(mut req) = serde_json::from_str::<Value>(body).ok().as_ref().and_then(OrderRequest::parse) else {
+            return error(400, "Invalid order payload");
+        };
+        let order_id = format!("0x{:064x}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut order = MockOrder {
+            order_id: order_id.clone(),
+            token_id: req.token_id.clone(),
+            is_buy: req.is_buy,
+            price: req.price,
+            size: req.size,
+            order_type: req.order_type.clone(),
+            post_only: req.post_only,
+            filled: 0.0,
+            status: String::new(),
+        };
+        let rejection = match Self::check(&state, &req) {
+            Err(message) => Some(message),
+            Ok(limit) => {
+                (req.price, order.price) = (limit, limit);
+                (!state.rejections.is_empty()).then(|| state.rejections.remove(0))
+            }
+        };
+        if let Some(message) = rejection {
+            order.status = message.clone();
+            state.orders.push(order);
+            return error(400, &message);
+        }
+
+        let book = state.books.entry(req.token_id.clone()).or_default();
+        let crosses = if req.is_buy {
+            book.asks.first().is_some_and(|a| a.0 <= req.price + 1e-9)
+        } else {
+            book.bids.first().is_some_and(|b| b.0 >= req.price - 1e-9)
+        };
+        let reject = |state: &mut State, mut order: MockOrder, message: &str| {
+            order.status = message.to_string();
+            state.orders.push(order);
+            error(400, message)
+        };
+        if req.post_only && crosses {
+            return reject(&mut state, order, "invalid post-only order: order crosses book");
+        }
+        let available = book.clone().take(req.is_buy, req.price, req.size).0;
+        match req.order_type.as_str() {
+            "FAK" if available <= 0.0 => {
+                return reject(&mut state, order, "no orders found to match with FAK order. FAK orders are partially filled or killed if no match is found.");
+            }
+            "FOK" if available + 1e-9 < req.size => {
+                return reject(&mut state, order, "order couldn't be fully filled. FOK orders are fully filled or killed.");
+            }
+            _ => {}
+        }
+
+        let (filled, notional) = if req.post_only { (0.0, 0.0) } else { book.take(req.is_buy, req.price, req.size) };
+        let event = {
+            let (tick, min_size) = state.market(&req.token_id).map_or((default_tick(), default_min_size()), |m| (m.tick_size, m.min_order_size));
+            let mut event = state.books[&req.token_id].json(&req.token_id, tick, min_size);
+            event["event_type"] = json!("book");
+            event
+        };
+        if filled > 0.0 {
+            let signed = if req.is_buy { 1.0 } else { -1.0 };
+            state.balance_usd = round6(state.balance_usd - signed * notional);
+            *state.positions.entry(req.token_id.clone()).or_default() += signed * filled;
+            let avg = round6(notional / filled);
+            let trade = json!({
+                "id": format!("mock-trade-{}", order_id),
+                "taker_order_id": order_id,
+                "asset_id": req.token_id,
+                "side": if req.is_buy { "BUY" } else { "SELL" },
+                "size": filled.to_string(),
+                "price": avg.to_string(),
+                "match_time": (unix_ms() / 1000).to_string(),
+                "trader_side": "TAKER",
+                "status": "CONFIRMED",
+            });
+            state.trades.push(trade);
+        }
+        let resting = filled + 1e-9 < req.size && matches!(req.order_type.as_str(), "GTC" | "GTD");
+        order.filled = filled;
+        order.status = if resting { "live".into() } else { "matched".into() };
+        let status = order.status.clone();
+        state.orders.push(order);
+        drop(state);
+        if filled > 0.0 {
+            let _ = self.book_tx.send((req.token_id.clone(), event.to_string()));
+        }
+
+        // Buys take shares and make USDC, sells the other way round
+        let (taking, making) = if req.is_buy { (filled, notional) } else { (notional, filled) };
+        let amount = |x: f64| if filled > 0.0 { x.to_string() } else { String::new() };
+        (200, json!({
+            "success": true,
+            "errorMsg": "",
+            "orderID": order_id,
+            "transactionsHashes": if filled > 0.0 { vec![format!("0x{:064x}", fnv(&order_id))] } else { Vec::new() },
+            "status": status,
+            "takingAmount": amount(taking),
+            "makingAmount": amount(making),
+        }).to_string())
+    }
+
+    /// Rejections the exchange makes before matching; Ok holds the order's limit price
+    fn check(state: &State, req: &OrderRequest) -> std::result::Result<f64, String> {
+        let Some(market) = state.market(&req.token_id) else {
+            return Err(format!("market for token {} does not exist", req.token_id));
+        };
+        if market.closed {
+            return Err("market is closed".into());
+        }
+        let price = match req.limit_price(market.tick_size) {
+            Some(p) if p > 0.0 && p < 1.0 => p,
+            _ => return Err(format!("order is invalid. Price ({}) breaks minimum tick size rule: {}", req.price, market.tick_size)),
+        };
+        if req.size + 1e-9 < market.min_order_size {
+            return Err(format!("Size ({}) lower than the minimum: {}", req.size, market.min_order_size));
+        }
+        let enough = if req.is_buy {
+            req.size * price <= state.balance_usd + 1e-9
+        } else {
+            state.positions.get(&req.token_id).copied().unwrap_or(0.0) + 1e-9 >= req.size
+        };
+        if !enough {
+            return Err("not enough balance / allowance".into());
+        }
+        Ok(price)
+    }
+}
+
+/// `eth_subscription` message for an OrderFilled log of the whale's fill
+pub fn whale_log(whale_topic: &str, token_id: &str, is_buy: bool, shares: f64, price: f64, n: u64) -> String {
+    let token = U256::from_str(token_id).unwrap_or_default();
+    let shares_amt = U256::from((shares * AMOUNT_SCALE).round() as u64);
+    let usd_amt = U256::from((shares * price * AMOUNT_SCALE).round() as u64);
+    // The whale's side pays USDC on a buy (maker asset 0) and shares on a sell
+    let (maker_asset, taker_asset, maker_amt, taker_amt) = if is_buy {
+        (U256::ZERO, token, usd_amt, shares_amt)
+    } else {
+        (token, U256::ZERO, shares_amt, usd_amt)
+    };
+    let word = |x: U256| x.to_be_bytes::<32>().iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    let data = format!("0x{}{}{}{}{}", word(maker_asset), word(taker_asset), word(maker_amt), word(taker_amt), word(U256::ZERO));
+    json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": {
+            "subscription": "0x1",
+            "result": {
+                "address": MONITORED_ADDRESSES[0],
+                "topics": [ORDERS_FILLED_EVENT_SIGNATURE, format!("0x{:064x}", fnv(&n.to_string())), whale_topic],
+                "data": data,
+                "blockNumber": format!("0x{:x}", 60_000_000 + n),
+                "transactionHash": format!("0x{:064x}", n),
+                "logIndex": "0x0",
+            }
+        }
+    }).to_string()
+}
+
+// ============================================================================
+// Servers
+// ============================================================================
+
+/// Where a started mock listens
+#[derive(Debug, Clone, Copy)]
+pub struct MockEndpoints {
+    pub http: SocketAddr,
+    pub ws: SocketAddr,
+}
+
+impl MockEndpoints {
+    /// Environment that points `pm_bot` at the mock (CLOB, Gamma, market channel, whale feed)
+    pub fn bot_env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("CLOB_API_URL", format!("http://{}", self.http)),
+            ("GAMMA_API_URL", format!("http://{}", self.http)),
+            ("MARKET_WS_URL", format!("ws://{}/ws/market", self.ws)),
+            ("WSS_URL", format!("ws://{}/", self.ws)),
+        ]
+    }
+}
+
+/// Serve the REST API on `http_addr` and both WebSocket feeds on `ws_addr` (port 0 = any)
+pub async fn start(mock: Arc<MockClob>, http_addr: &str, ws_addr: &str) -> Result<MockEndpoints> {
+    let http = TcpListener::bind(http_addr).await?;
+    let ws = TcpListener::bind(ws_addr).await?;
+    let endpoints = MockEndpoints { http: http.local_addr()?, ws: ws.local_addr()? };
+    let m = Arc::clone(&mock);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = http.accept().await {
+            tokio::spawn(serve_http(Arc::clone(&m), stream));
+        }
+    });
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = ws.accept().await {
+            tokio::spawn(serve_ws(Arc::clone(&mock), stream));
+        }
+    });
+    Ok(endpoints)
+}
+
+/// HTTP/1.1 with keep-alive, requests with a Content-Length body only
+async fn serve_http(mock: Arc<MockClob>, stream: TcpStream) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or_default().to_string(), parts.next().unwrap_or_default().to_string());
+
+        let (mut content_length, mut api_key) = (0usize, None);
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                } else if name.eq_ignore_ascii_case("poly_api_key") {
+                    api_key = Some(value.trim().to_string());
+                }
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        if reader.read_exact(&mut body).await.is_err() {
+            return;
+        }
+
+        let (status, reply) = mock.handle(&method, &target, api_key.as_deref(), &String::from_utf8_lossy(&body));
+        let reason = match status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            _ => "Not Found",
+        };
+        let response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status, reason, reply.len(), reply
+        );
+        if reader.get_mut().write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// The first message picks the feed: `eth_subscribe` gets whale logs, a market-channel
+/// subscription gets book events of its `assets_ids`
+async fn serve_ws(mock: Arc<MockClob>, stream: TcpStream) {
+    let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else { return };
+    let Some(Ok(Message::Text(first))) = ws.next().await else { return };
+    let sub: Value = serde_json::from_str(&first).unwrap_or_default();
+
+    if sub["method"].as_str() == Some("eth_subscribe") {
+        let mut rx = mock.whale_tx.subscribe();
+        let ack = json!({ "jsonrpc": "2.0", "id": sub["id"], "result": "0x1" }).to_string();
+        if ws.send(Message::Text(ack)).await.is_err() {
+            return;
+        }
+        loop {
+            tokio::select! {
+                line = rx.recv() => match line {
+                    Ok(line) => if ws.send(Message::Text(line)).await.is_err() { return },
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                msg = ws.next() => match msg {
+                    Some(Ok(Message::Ping(d))) => { let _ = ws.send(Message::Pong(d)).await; }
+                    Some(Ok(_)) => {}
+                    _ => return,
+                },
+            }
+        }
+    }
+
+    let assets: Vec<String> = sub["assets_ids"].as_array()
+        .map(|a| a.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    let mut rx = mock.book_tx.subscribe();
+    for token in &assets {
+        let Some(book) = mock.book(token) else { continue };
+        let mut event = book.json(token, default_tick(), default_min_size());
+        event["event_type"] = json!("book");
+        if ws.send(Message::Text(event.to_string())).await.is_err() {
+            return;
+        }
+    }
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok((token, event)) if assets.contains(&token) => {
+                    if ws.send(Message::Text(event)).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            msg = ws.next() => match msg {
+                Some(Ok(Message::Text(t))) if t == "PING" => { let _ = ws.send(Message::Text("PONG".into())).await; }
+                Some(Ok(Message::Ping(d))) => { let _ = ws.send(Message::Pong(d)).await; }
+                Some(Ok(_)) => {}
+                _ => return,
+            },
+        }
+    }
+}
+
+// ============================================================================
+// Scenarios
+// ============================================================================
+
+/// One scripted step (`mock_clob` reads them as JSON lines)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScenarioStep {
+    Market(MockMarket),
+    Book { token_id: String, bids: Vec<(f64, f64)>, asks: Vec<(f64, f64)> },
+    WhaleFill { token_id: String, side: String, shares: f64, price: f64 },
+    Resolve { winner: String },
+    Reject { message: String },
+    RotateKey,
+    /// Pause before the next step
+    Wait { ms: u64 },
+}
+
+impl ScenarioStep {
+    pub async fn run(&self, mock: &MockClob) {
+        match self {
+            Self::Market(m) => mock.add_market(m.clone()),
+            Self::Book { token_id, bids, asks } => mock.set_book(token_id, MockBook::new(bids.clone(), asks.clone())),
+            Self::WhaleFill { token_id, side, shares, price } => mock.whale_fill(token_id, side.eq_ignore_ascii_case("BUY"), *shares, *price),
+            Self::Resolve { winner } => mock.resolve(winner),
+            Self::Reject { message } => mock.reject_next(message),
+            Self::RotateKey => mock.rotate_api_key(),
+            Self::Wait { ms } => tokio::time::sleep(std::time::Duration::from_millis(*ms)).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHALE: &str = "204f72f35326db932158cba6adff0b9a1da95e14";
+
+    fn order_body(token: &str, is_buy: bool, price: f64, size: f64, order_type: &str, key: &str) -> String {
+        let (shares, usd) = ((size * AMOUNT_SCALE).round() as u64, (size * price * AMOUNT_SCALE).round() as u64);
+        let (maker, taker) = if is_buy { (usd, shares) } else { (shares, usd) };
+        json!({
+            "order": {
+                "tokenId": token, "makerAmount": maker.to_string(), "takerAmount": taker.to_string(),
+                "side": if is_buy { "BUY" } else { "SELL" },
+            },
+            "owner": key, "orderType": order_type,
+        }).to_string()
+    }
+
+    fn exchange() -> Arc<MockClob> {
+        let mock = MockClob::new(WHALE, 100.0);
+        mock.add_market(MockMarket::binary("will-it-rain", "101", "102"));
+        mock.set_book("101", MockBook::new(vec![(0.48, 50.0)], vec![(0.50, 20.0), (0.51, 30.0)]));
+        mock
+    }
+
+    #[test]
+    fn test_matching_and_settlement() {
+        let mock = exchange();
+        let key = "mock-key-1";
+
+        // Walks two levels up to the limit
+        let (status, body) = mock.handle("POST", "/order", Some(key), &order_body("101", true, 0.51, 40.0, "FAK", key));
+        assert_eq!(status, 200, "{}", body);
+        let reply = crate::OrderReply::parse(status, &body);
+        assert_eq!(reply.accepted().unwrap().filled(), Some((40.0, 20.2 / 40.0)));
+        assert_eq!(mock.position("101"), 40.0);
+        assert!((mock.balance() - 79.8).abs() < 1e-9);
+        assert_eq!(mock.book("101").unwrap().asks, vec![(0.51, 10.0)]);
+
+        // Nothing at the limit: FAK killed, classified as a miss
+        let (status, body) = mock.handle("POST", "/order", Some(key), &order_body("101", true, 0.50, 10.0, "FAK", key));
+        assert_eq!(crate::OrderReply::parse(status, &body).error(), Some(crate::OrderError::NoMatch));
+
+        // Revoked key, then a closed market
+        mock.rotate_api_key();
+        let (status, _) = mock.handle("POST", "/order", Some(key), &order_body("101", false, 0.48, 10.0, "FAK", key));
+        assert_eq!(status, 401);
+        mock.resolve("101");
+        let (_, gamma) = mock.handle("GET", "/markets?clob_token_ids=101", None, "");
+        let market: Value = serde_json::from_str(&gamma).unwrap();
+        assert_eq!(crate::token_metadata::settlement_price(&market[0], "101"), Some(1.0));
+        assert_eq!(crate::token_metadata::settlement_price(&market[0], "102"), Some(0.0));
+        let new_key = serde_json::from_str::<Value>(&mock.handle("GET", "/auth/derive-api-key", None, "").1).unwrap()["apiKey"].as_str().unwrap().to_string();
+        let (status, body) = mock.handle("POST", "/order", Some(&new_key), &order_body("101", false, 0.48, 10.0, "FAK", &new_key));
+        assert_eq!(crate::OrderReply::parse(status, &body).error(), Some(crate::OrderError::MarketClosed));
+        assert_eq!(mock.orders().len(), 3);
+    }
+
+    #[test]
+    fn test_limit_price_from_rounded_amounts() {
+        let mock = exchange();
+        let key = "mock-key-1";
+
+        // 9.8 shares at 0.51 signed as $4.99 (rounded down to the cent): still a 0.51 limit
+        let rounded = order_body("101", true, 0.51, 9.8, "FAK", key).replace("4998000", "4990000");
+        let (status, body) = mock.handle("POST", "/order", Some(key), &rounded);
+        assert_eq!(status, 200, "{}", body);
+        assert_eq!(mock.orders()[0].price, 0.51);
+
+        // Half a tick off is more than rounding explains
+        let (status, body) = mock.handle("POST", "/order", Some(key), &order_body("101", true, 0.505, 10.0, "FAK", key));
+        assert_eq!(status, 400);
+        assert!(body.contains("minimum tick size"), "{}", body);
+    }
+
+    #[test]
+    fn test_whale_log_and_book_shape() {
+        let line = whale_log(&format!("0x000000000000000000000000{}", WHALE), "101", true, 250.0, 0.4, 7);
+        let msg: crate::models::WsMessage = serde_json::from_str(&line).unwrap();
+        let log = msg.params.unwrap().result.unwrap();
+        assert_eq!(log.topics.len(), 3);
+        assert_eq!(log.data.len(), 2 + 64 * 5);
+        // Maker asset 0 (USDC) marks the whale's buy; the taker asset is the token
+        assert_eq!(&log.data[2..66], &"0".repeat(64));
+        assert_eq!(u64::from_str_radix(&log.data[66..130], 16).unwrap(), 101);
+        assert_eq!(u64::from_str_radix(&log.data[130..194], 16).unwrap(), 100_000_000);
+
+        let mock = exchange();
+        let (_, book) = mock.handle("GET", "/book?token_id=101", None, "");
+        let (bids, asks) = crate::depth_history::parse_book(&serde_json::from_str(&book).unwrap());
+        assert_eq!(bids, vec![(0.48, 50.0)]);
+        assert_eq!(asks, vec![(0.51, 30.0), (0.50, 20.0)]);
+        assert_eq!(mock.handle("GET", "/book?token_id=999", None, "").0, 404);
+    }
+}
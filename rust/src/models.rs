@@ -1,9 +1,11 @@
 // src/types.rs
 // Core types for the trading system
 
+use crate::timestamp::Timestamp;
 use serde::Deserialize;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::oneshot;
 
 /// Parsed order information from blockchain events
@@ -19,21 +21,34 @@ pub struct OrderInfo {
 /// Fully parsed blockchain event ready for processing
 #[derive(Debug, Clone)]
 pub struct ParsedEvent {
+    /// When the log reached this bot (the feed process in a split setup); chain logs carry no
+    /// block time, so this is the earliest time known for the fill
+    pub received_at: Timestamp,
     pub block_number: u64,
     pub tx_hash: String,
+    pub log_index: u64,
     pub order: OrderInfo,
 }
 
+impl ParsedEvent {
+    /// Stable id for the copied order ("<tx_hash>:<log_index>"), used to refuse duplicates
+    pub fn intent_id(&self) -> String {
+        format!("{}:{}", self.tx_hash, self.log_index)
+    }
+}
+
 /// Work item for the order processing queue
 #[derive(Debug)]
 pub struct WorkItem {
     pub event: ParsedEvent,
     pub respond_to: oneshot::Sender<String>,
     pub is_live: Option<bool>,
+    /// When the signal was handed to the order worker
+    pub queued_at: Instant,
 }
 
 /// Size calculation result 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SizeType {
     Scaled,
     ProbHit(u8),   // percentage
@@ -98,4 +113,6 @@ pub struct LogResult {
     pub block_number: Option<String>,
     #[serde(rename = "transactionHash")]
     pub transaction_hash: Option<String>,
+    #[serde(rename = "logIndex")]
+    pub log_index: Option<String>,
 }
\ No newline at end of file
@@ -0,0 +1,197 @@
+//! Typed order POST replies
+//! The CLOB answers an order POST with an `OrderResponse` on success and `{"error": ...}` (or
+//! `success: false` with `errorMsg`) otherwise. `OrderReply` carries either, with rejections
+//! classified from the documented error messages and the HTTP status so callers branch on
+//! `OrderError` instead of searching the body text
+
+use crate::OrderResponse;
+use serde_json::Value;
+use std::fmt;
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderError {
+    /// FAK found nothing to match, or FOK could not fill in full
+    NoMatch,
+    /// Not enough USDC (buys) or shares (sells), or missing allowance
+    InsufficientBalance,
+    /// Order book gone or not accepting orders (resolved, closed or not yet ready)
+    MarketClosed,
+    /// Price off the market's tick grid
+    TickSize,
+    /// Size below the market minimum
+    MinSize,
+    /// Same order already placed
+    Duplicate,
+    /// Expiration in the past or too close
+    Expiration,
+    /// Bad API key or signature
+    Unauthorized,
+    RateLimited,
+    /// Exchange in cancel-only mode or restarting the matching engine
+    TradingDisabled,
+    /// Accepted but matching was delayed, or the engine failed to run it
+    Execution,
+    Other,
+}
+
+impl OrderError {
+    /// Classify from the HTTP status and the error message
+    pub fn classify(status: u16, message: &str) -> Self {
+        let m = message.to_ascii_lowercase();
+        let has = |s: &str| m.contains(s);
+        if has("fak") || has("fok") || has("no orders found to match") || has("couldn't be fully filled") {
+            Self::NoMatch
+        } else if has("not enough balance") || has("allowance") {
+            Self::InsufficientBalance
+        } else if has("does not exist") || has("closed") || has("not yet ready") || has("market_not_ready") {
+            Self::MarketClosed
+        } else if has("tick size") {
+            Self::TickSize
+        } else if has("lower than the minimum") || has("min_size") {
+            Self::MinSize
+        } else if has("duplicated") {
+            Self::Duplicate
+        } else if has("expiration") {
+            Self::Expiration
+        } else if has("cancel-only") || has("trading is currently disabled") || status == 503 || status == 425 {
+            Self::TradingDisabled
+        } else if status == 401 || status == 403 || has("api key") || has("invalid signature") || has("unauthorized") {
+            Self::Unauthorized
+        } else if status == 429 {
+            Self::RateLimited
+        } else if has("could not run the execution") || has("delay") || has("could not insert order") {
+            Self::Execution
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoMatch => "NO_MATCH",
+            Self::InsufficientBalance => "INSUFFICIENT_BALANCE",
+            Self::MarketClosed => "MARKET_CLOSED",
+            Self::TickSize => "TICK_SIZE",
+            Self::MinSize => "MIN_SIZE",
+            Self::Duplicate => "DUPLICATE",
+            Self::Expiration => "EXPIRATION",
+            Self::Unauthorized => "UNAUTHORIZED",
+            Self::RateLimited => "RATE_LIMITED",
+            Self::TradingDisabled => "TRADING_DISABLED",
+            Self::Execution => "EXECUTION",
+            Self::Other => "OTHER",
+        }
+    }
+
+    /// Whether the same order may succeed on a later attempt
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::NoMatch | Self::RateLimited | Self::TradingDisabled | Self::Execution)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRejection {
+    pub kind: OrderError,
+    /// Exchange error message (raw body when it was not JSON)
+    pub message: String,
+}
+
+impl fmt::Display for OrderRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.as_str(), self.message)
+    }
+}
+
+// ============================================================================
+// Reply
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct OrderReply {
+    /// HTTP status code
+    pub status: u16,
+    pub result: Result<OrderResponse, OrderRejection>,
+}
+
+impl OrderReply {
+    pub fn parse(status: u16, body: &str) -> Self {
+        let json: Option<Value> = serde_json::from_str(body).ok();
+        if (200..300).contains(&status) {
+            match json.as_ref().and_then(|v| serde_json::from_value::<OrderResponse>(v.clone()).ok()) {
+                Some(r) if r.success => return Self { status, result: Ok(r) },
+                Some(r) => {
+                    let kind = OrderError::classify(status, &r.error_msg);
+                    return Self { status, result: Err(OrderRejection { kind, message: r.error_msg }) };
+                }
+                // Accepted without a body we understand: no fill amounts to report
+                None => return Self { status, result: Ok(OrderResponse { success: true, ..OrderResponse::default() }) },
+            }
+        }
+        let message = json.as_ref()
+            .and_then(|v| v["error"].as_str().or_else(|| v["errorMsg"].as_str()))
+            .map_or_else(|| body.trim().to_string(), str::to_string);
+        Self { status, result: Err(OrderRejection { kind: OrderError::classify(status, &message), message }) }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    pub fn accepted(&self) -> Option<&OrderResponse> {
+        self.result.as_ref().ok()
+    }
+
+    pub fn rejection(&self) -> Option<&OrderRejection> {
+        self.result.as_ref().err()
+    }
+
+    pub fn error(&self) -> Option<OrderError> {
+        self.rejection().map(|r| r.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_success_and_fills() {
+        let reply = OrderReply::parse(200, r#"{"success":true,"errorMsg":"","orderID":"0xabc","transactionsHashes":["0x1"],"status":"matched","takingAmount":"10","makingAmount":"5.2"}"#);
+        let r = reply.accepted().unwrap();
+        assert_eq!(r.order_id, "0xabc");
+        assert_eq!(r.filled(), Some((10.0, 0.52)));
+
+        let resting = OrderReply::parse(200, r#"{"success":true,"orderID":"0xdef","status":"live","takingAmount":"","makingAmount":""}"#);
+        assert!(resting.accepted().unwrap().is_resting());
+        assert_eq!(resting.accepted().unwrap().filled(), None);
+    }
+
+    #[test]
+    fn test_documented_errors() {
+        let fak = OrderReply::parse(400, r#"{"error":"no orders found to match with FAK order. FAK orders are partially filled or killed if no match is found."}"#);
+        assert_eq!(fak.error(), Some(OrderError::NoMatch));
+        assert!(fak.error().unwrap().is_retryable());
+
+        let cases = [
+            (400, r#"{"error":"not enough balance / allowance"}"#, OrderError::InsufficientBalance),
+            (400, r#"{"error":"the orderbook 123 does not exist"}"#, OrderError::MarketClosed),
+            (400, r#"{"error":"the market is not yet ready to process new orders"}"#, OrderError::MarketClosed),
+            (400, r#"{"error":"order 0x1 is invalid. Price (0.555) breaks minimum tick size rule: 0.01"}"#, OrderError::TickSize),
+            (400, r#"{"error":"order 0x1 is invalid. Size (1) lower than the minimum: 5"}"#, OrderError::MinSize),
+            (400, r#"{"error":"order 0x1 is invalid. Duplicated. Same order has already been placed, can't be placed again"}"#, OrderError::Duplicate),
+            (401, r#"{"error":"Unauthorized/Invalid api key"}"#, OrderError::Unauthorized),
+            (429, "Too Many Requests", OrderError::RateLimited),
+            (503, r#"{"error":"Trading is currently cancel-only"}"#, OrderError::TradingDisabled),
+            (200, r#"{"success":false,"errorMsg":"could not run the execution"}"#, OrderError::Execution),
+        ];
+        for (status, body, kind) in cases {
+            assert_eq!(OrderReply::parse(status, body).error(), Some(kind), "{}", body);
+        }
+        assert_eq!(OrderReply::parse(429, "Too Many Requests").rejection().unwrap().to_string(), "RATE_LIMITED: Too Many Requests");
+        assert!(!OrderError::InsufficientBalance.is_retryable());
+    }
+}
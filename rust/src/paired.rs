@@ -0,0 +1,314 @@
+//! Paired (two-leg) execution
+//! Some entries only make sense as a pair: both outcomes of a market below $1 together, or an
+//! entry with its hedge. `execute_pair` sends leg A as FAK, sizes leg B to what A filled and
+//! sends it as FAK, then unwinds whatever of A that B did not match, so the pair ends both
+//! filled (same shares) or flat. Leg B is not sent after the deadline. Unwinds cross at up to
+//! `max_concession` from the leg's price and are retried a few times; anything left is
+//! reported as stranded. Every pair is journaled with its outcome
+
+use crate::{OrderArgs, PreparedCreds, RustClobClient};
+use crate::timestamp;
+use anyhow::Result;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// One line per executed pair
+pub const PAIRED_ORDERS_FILE: &str = "paired_orders.jsonl";
+
+/// Unwind attempts before the rest of leg A is reported stranded
+const UNWIND_ATTEMPTS: usize = 3;
+
+/// Fills below this are dust the exchange will not take back
+const MIN_LEG_SHARES: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Leg {
+    pub token_id: String,
+    pub is_buy: bool,
+    /// Limit price
+    pub price: f64,
+    pub shares: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairPolicy {
+    /// Leg B is not sent once this long has passed since leg A was sent
+    pub deadline: Duration,
+    /// Furthest an unwind may cross from leg A's price
+    pub max_concession: f64,
+}
+
+impl Default for PairPolicy {
+    fn default() -> Self {
+        Self { deadline: Duration::from_secs(2), max_concession: 0.05 }
+    }
+}
+
+/// Where legs are sent. `send` places a FAK order and returns (filled shares, average price);
+/// nothing filled is Ok((0, _)), a rejection is Err
+pub trait LegVenue {
+    fn send(&mut self, leg: &Leg) -> Result<(f64, f64)>;
+}
+
+// ============================================================================
+// Outcome
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PairStatus {
+    /// Both legs filled in full
+    Complete,
+    /// Both legs filled the same, smaller size; any excess of A was unwound
+    Partial,
+    /// Leg A filled, B did not; A was unwound
+    Unwound,
+    /// Leg A did not fill; nothing was sent for B
+    NotFilled,
+    /// Part of leg A could not be unwound and is held unhedged
+    Stranded,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairReport {
+    pub ts: u64,
+    pub status: PairStatus,
+    pub leg_a: Leg,
+    pub leg_b: Leg,
+    pub a_filled: f64,
+    pub a_price: f64,
+    pub b_filled: f64,
+    pub b_price: f64,
+    /// Shares of A sold back (or bought back) and their average price
+    pub unwound: f64,
+    pub unwind_price: f64,
+    /// Shares of A left unhedged after every unwind attempt
+    pub stranded: f64,
+    pub elapsed_ms: u64,
+    /// Errors and skipped steps, in order
+    pub notes: Vec<String>,
+}
+
+impl PairReport {
+    /// Cash spent (negative = received) by everything that filled
+    pub fn net_cost(&self) -> f64 {
+        let signed = |is_buy: bool, shares: f64, price: f64| if is_buy { shares * price } else { -shares * price };
+        signed(self.leg_a.is_buy, self.a_filled, self.a_price)
+            + signed(self.leg_b.is_buy, self.b_filled, self.b_price)
+            + signed(!self.leg_a.is_buy, self.unwound, self.unwind_price)
+    }
+
+    /// One status line for the log
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "PAIR {:?}: A {:.2}/{:.2} @ {:.3}, B {:.2}/{:.2} @ {:.3}",
+            self.status, self.a_filled, self.leg_a.shares, self.a_price, self.b_filled, self.leg_b.shares, self.b_price
+        );
+        if self.unwound > 0.0 {
+            out.push_str(&format!(", unwound {:.2} @ {:.3}", self.unwound, self.unwind_price));
+        }
+        if self.stranded > 0.0 {
+            out.push_str(&format!(", STRANDED {:.2}", self.stranded));
+        }
+        out.push_str(&format!(" in {}ms", self.elapsed_ms));
+        out
+    }
+}
+
+// ============================================================================
+// Execution
+// ============================================================================
+
+/// Unwind `shares` of `leg` at up to `max_concession` past its price. Returns (unwound, avg price)
+fn unwind(venue: &mut impl LegVenue, leg: &Leg, shares: f64, max_concession: f64, notes: &mut Vec<String>) -> (f64, f64) {
+    let price = if leg.is_buy { (leg.price - max_concession).max(0.01) } else { (leg.price + max_concession).min(0.99) };
+    let (mut done, mut cost) = (0.0, 0.0);
+    for attempt in 1..=UNWIND_ATTEMPTS {
+        let left = crate::signal_math::floor_cents(shares - done);
+        if left < MIN_LEG_SHARES {
+            break;
+        }
+        let back = Leg { token_id: leg.token_id.clone(), is_buy: !leg.is_buy, price, shares: left };
+        match venue.send(&back) {
+            Ok((filled, avg)) if filled > 0.0 => {
+                done += filled;
+                cost += filled * avg;
+            }
+            Ok(_) => notes.push(format!("unwind {} matched nothing", attempt)),
+            Err(e) => notes.push(format!("unwind {} failed: {:#}", attempt, e)),
+        }
+    }
+    (done, if done > 0.0 { cost / done } else { 0.0 })
+}
+
+/// Send leg A, then leg B sized to A's fill, and unwind A's unmatched part
+pub fn execute_pair(venue: &mut impl LegVenue, leg_a: Leg, leg_b: Leg, policy: &PairPolicy) -> PairReport {
+    let started = Instant::now();
+    let mut notes = Vec::new();
+    let mut report = PairReport {
+        ts: timestamp::unix_secs(),
+        status: PairStatus::NotFilled,
+        leg_a: leg_a.clone(),
+        leg_b: leg_b.clone(),
+        a_filled: 0.0,
+        a_price: 0.0,
+        b_filled: 0.0,
+        b_price: 0.0,
+        unwound: 0.0,
+        unwind_price: 0.0,
+        stranded: 0.0,
+        elapsed_ms: 0,
+        notes: Vec::new(),
+    };
+
+    match venue.send(&leg_a) {
+        Ok((filled, price)) => (report.a_filled, report.a_price) = (filled, price),
+        Err(e) => notes.push(format!("leg A failed: {:#}", e)),
+    }
+    if report.a_filled >= MIN_LEG_SHARES {
+        // Leg B matches what A actually got, never more than asked
+        let b_shares = crate::signal_math::floor_cents(report.a_filled.min(leg_b.shares));
+        if started.elapsed() > policy.deadline {
+            notes.push(format!("leg B not sent: deadline {}ms passed", policy.deadline.as_millis()));
+        } else {
+            match venue.send(&Leg { shares: b_shares, ..leg_b.clone() }) {
+                Ok((filled, price)) => (report.b_filled, report.b_price) = (filled, price),
+                Err(e) => notes.push(format!("leg B failed: {:#}", e)),
+            }
+        }
+
+        let excess = report.a_filled - report.b_filled;
+        if excess >= MIN_LEG_SHARES {
+            (report.unwound, report.unwind_price) = unwind(venue, &leg_a, excess, policy.max_concession, &mut notes);
+            report.stranded = crate::signal_math::floor_cents((excess - report.unwound).max(0.0));
+        }
+        report.status = if report.stranded >= MIN_LEG_SHARES {
+            PairStatus::Stranded
+        } else if report.b_filled < MIN_LEG_SHARES {
+            PairStatus::Unwound
+        } else if report.b_filled + 1e-9 >= leg_b.shares.min(leg_a.shares) {
+            PairStatus::Complete
+        } else {
+            PairStatus::Partial
+        };
+    }
+    report.elapsed_ms = started.elapsed().as_millis() as u64;
+    report.notes = notes;
+    report
+}
+
+/// Append a pair's outcome to `PAIRED_ORDERS_FILE`
+pub fn record(report: &PairReport) {
+    let Ok(line) = serde_json::to_string(report) else { return };
+    match OpenOptions::new().append(true).create(true).open(PAIRED_ORDERS_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Paired order journal write failed: {}", e),
+    }
+}
+
+// ============================================================================
+// CLOB Venue
+// ============================================================================
+
+/// Sends legs to the CLOB as FAK orders (blocking)
+pub struct ClobVenue<'a> {
+    pub client: &'a mut RustClobClient,
+    pub creds: &'a PreparedCreds,
+}
+
+impl LegVenue for ClobVenue<'_> {
+    fn send(&mut self, leg: &Leg) -> Result<(f64, f64)> {
+        let args = OrderArgs {
+            token_id: leg.token_id.clone(),
+            price: leg.price,
+            size: crate::signal_math::floor_cents(leg.shares),
+            side: if leg.is_buy { "BUY" } else { "SELL" }.into(),
+            fee_rate_bps: None,
+            nonce: Some(0),
+            expiration: Some("0".into()),
+            taker: None,
+            order_type: Some("FAK".into()),
+        };
+        let signed = self.client.create_order(args)?;
+        let reply = self.client.post_order(signed.post_body(&self.creds.api_key, "FAK"), self.creds)?;
+        match &reply.result {
+            Ok(r) => Ok(r.filled().unwrap_or((0.0, leg.price))),
+            // A FAK with nothing to match is a miss, not a failure
+            Err(rejection) if rejection.kind == crate::order_reply::OrderError::NoMatch => Ok((0.0, leg.price)),
+            Err(rejection) => Err(anyhow::anyhow!("{}", rejection)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Answers each send with the next scripted fill ratio (None = rejected)
+    struct Scripted {
+        replies: VecDeque<Option<f64>>,
+        sent: Vec<Leg>,
+    }
+
+    impl LegVenue for Scripted {
+        fn send(&mut self, leg: &Leg) -> Result<(f64, f64)> {
+            self.sent.push(leg.clone());
+            match self.replies.pop_front().flatten() {
+                Some(ratio) => Ok((crate::signal_math::floor_cents(leg.shares * ratio), leg.price)),
+                None => Err(anyhow::anyhow!("rejected")),
+            }
+        }
+    }
+
+    fn venue(replies: &[Option<f64>]) -> Scripted {
+        Scripted { replies: replies.iter().copied().collect(), sent: Vec::new() }
+    }
+
+    fn legs() -> (Leg, Leg) {
+        (
+            Leg { token_id: "yes".into(), is_buy: true, price: 0.48, shares: 100.0 },
+            Leg { token_id: "no".into(), is_buy: true, price: 0.50, shares: 100.0 },
+        )
+    }
+
+    #[test]
+    fn test_complete_and_partial() {
+        let (a, b) = legs();
+        let r = execute_pair(&mut venue(&[Some(1.0), Some(1.0)]), a.clone(), b.clone(), &PairPolicy::default());
+        assert_eq!(r.status, PairStatus::Complete);
+        assert!((r.net_cost() - 98.0).abs() < 1e-9);
+
+        // A fills 60, B is sized to 60 and fills half: 30 of A are sold back
+        let mut v = venue(&[Some(0.6), Some(0.5), Some(1.0)]);
+        let r = execute_pair(&mut v, a, b, &PairPolicy::default());
+        assert_eq!(r.status, PairStatus::Partial);
+        assert_eq!(v.sent[1].shares, 60.0);
+        assert_eq!((r.b_filled, r.unwound), (30.0, 30.0));
+        assert!(!v.sent[2].is_buy && (v.sent[2].price - 0.43).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_leg_b_failure_unwinds_or_strands() {
+        let (a, b) = legs();
+        let r = execute_pair(&mut venue(&[Some(1.0), None, Some(1.0)]), a.clone(), b.clone(), &PairPolicy::default());
+        assert_eq!(r.status, PairStatus::Unwound);
+        assert_eq!(r.unwound, 100.0);
+        assert_eq!(r.notes, vec!["leg B failed: rejected"]);
+
+        // Every unwind attempt misses: the position is reported stranded
+        let r = execute_pair(&mut venue(&[Some(1.0), None, Some(0.0), None, Some(0.5)]), a.clone(), b.clone(), &PairPolicy::default());
+        assert_eq!(r.status, PairStatus::Stranded);
+        assert_eq!(r.stranded, 50.0);
+
+        let mut v = venue(&[Some(0.0)]);
+        assert_eq!(execute_pair(&mut v, a, b, &PairPolicy::default()).status, PairStatus::NotFilled);
+        assert_eq!(v.sent.len(), 1);
+    }
+}
@@ -0,0 +1,253 @@
+//! Settlement P&L attribution
+//! For shares held until the market resolves, P&L splits into entry edge (fair probability at
+//! entry minus the price paid) and resolution luck (payout minus that fair probability). The
+//! fair probability is the book mid when we entered, or the whale's price without a book.
+//! Summed per asset, edge shows what the entry logic earned and luck what the outcome handed
+//! out, so tuning can target the component that actually moved
+
+use crate::display;
+use crate::position_tracker::PositionTracker;
+use crate::strategy::strategy_ledger;
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Share-weighted fair probability at entry per held token
+pub const FAIR_AT_ENTRY_FILE: &str = ".pnl_attribution_fair.json";
+
+/// One line per settled position
+pub const ATTRIBUTION_FILE: &str = "pnl_attribution.jsonl";
+
+/// How often held tokens are checked for resolution (default of SETTLEMENT_CHECK_SECS)
+pub const SETTLEMENT_CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+// ============================================================================
+// Decomposition
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settlement {
+    pub ts: u64,
+    pub token_id: String,
+    /// Outcome and question when known
+    pub label: String,
+    pub shares: f64,
+    /// Average entry price of the shares held to resolution
+    pub entry: f64,
+    /// Fair probability at entry
+    pub fair: f64,
+    /// 1 for the winning outcome, 0 for a loser (fractions for split resolutions)
+    pub payout: f64,
+    /// shares × (fair - entry)
+    pub entry_edge: f64,
+    /// shares × (payout - fair)
+    pub resolution_luck: f64,
+}
+
+impl Settlement {
+    pub fn new(token_id: &str, label: &str, shares: f64, entry: f64, fair: f64, payout: f64) -> Self {
+        Self {
+            ts: timestamp::unix_secs(),
+            token_id: token_id.to_string(),
+            label: label.to_string(),
+            shares,
+            entry,
+            fair,
+            payout,
+            entry_edge: shares * (fair - entry),
+            resolution_luck: shares * (payout - fair),
+        }
+    }
+
+    pub fn pnl(&self) -> f64 {
+        self.entry_edge + self.resolution_luck
+    }
+}
+
+// ============================================================================
+// Fair Value at Entry
+// ============================================================================
+
+/// token_id -> (shares, sum of shares × fair)
+#[derive(Default)]
+pub struct FairAtEntry {
+    entries: Mutex<FxHashMap<String, (f64, f64)>>,
+    path: Option<String>,
+}
+
+impl FairAtEntry {
+    pub fn new(path: Option<&str>) -> Self {
+        let entries = path
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Self { entries: Mutex::new(entries), path: path.map(String::from) }
+    }
+
+    fn persist(&self, entries: &FxHashMap<String, (f64, f64)>) {
+        let Some(path) = &self.path else { return };
+        if let Ok(data) = serde_json::to_string(entries)
+            && let Err(e) = fs::write(path, data) {
+                crate::console_eprintln!("⚠️ Fair-at-entry write failed: {}", e);
+            }
+    }
+
+    /// A buy of `shares` filled while the fair probability was `fair`
+    pub fn record_entry(&self, token_id: &str, shares: f64, fair: f64) {
+        if shares <= 0.0 {
+            return;
+        }
+        let Ok(mut entries) = self.entries.lock() else { return };
+        let e = entries.entry(token_id.to_string()).or_default();
+        e.0 += shares;
+        e.1 += shares * fair;
+        self.persist(&entries);
+    }
+
+    /// Share-weighted fair probability across the token's entries
+    pub fn fair(&self, token_id: &str) -> Option<f64> {
+        let entries = self.entries.lock().ok()?;
+        entries.get(token_id).filter(|e| e.0 > 0.0).map(|e| e.1 / e.0)
+    }
+
+    pub fn remove(&self, token_id: &str) {
+        let Ok(mut entries) = self.entries.lock() else { return };
+        if entries.remove(token_id).is_some() {
+            self.persist(&entries);
+        }
+    }
+}
+
+// ============================================================================
+// Settlement Task
+// ============================================================================
+
+fn append(s: &Settlement, path: &str) -> std::io::Result<()> {
+    let line = serde_json::to_string(s).map_err(std::io::Error::other)?;
+    writeln!(OpenOptions::new().append(true).create(true).open(path)?, "{}", line)
+}
+
+/// Close a resolved token: the ledger realizes it at the payout, the stop-loss stops watching it
+/// and the decomposition is journaled. None if this tag holds nothing there
+pub async fn settle(token_id: &str, payout: f64, tracker: &PositionTracker) -> Option<Settlement> {
+    let (shares, entry) = strategy_ledger().held(token_id)?;
+    // No recorded fair value (e.g. entered before tracking): all of the P&L counts as luck
+    let fair = fair_at_entry().fair(token_id).unwrap_or(entry);
+    let label = crate::token_metadata::label(token_id).unwrap_or_default();
+    let settlement = Settlement::new(token_id, &label, shares, entry, fair, payout);
+
+    strategy_ledger().record(token_id, false, shares, payout);
+    tracker.remove_position(token_id).await;
+    fair_at_entry().remove(token_id);
+    if let Err(e) = append(&settlement, ATTRIBUTION_FILE) {
+        crate::console_eprintln!("⚠️ P&L attribution write failed: {}", e);
+    }
+    Some(settlement)
+}
+
+/// Check every held token for resolution each `interval`
+pub fn spawn_settlement_task(tracker: Arc<PositionTracker>, client: reqwest::Client, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            for token_id in strategy_ledger().held_tokens() {
+                let Some(payout) = crate::token_metadata::fetch_settlement(&token_id, &client).await else { continue };
+                if let Some(s) = settle(&token_id, payout, &tracker).await {
+                    crate::console_println!(
+                        "🏁 Settled {} @ {:.2}: {} shares, P&L {} = edge {} + luck {}",
+                        if s.label.is_empty() { &s.token_id } else { &s.label }, payout, display::shares(s.shares),
+                        display::signed_usd(s.pnl()), display::signed_usd(s.entry_edge), display::signed_usd(s.resolution_luck)
+                    );
+                }
+            }
+        }
+    })
+}
+
+// ============================================================================
+// Report
+// ============================================================================
+
+pub fn load_settlements(path: &str) -> Vec<Settlement> {
+    fs::read_to_string(path)
+        .map(|data| data.lines().filter_map(|l| serde_json::from_str(l).ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Per-asset table for `pm_bot attribution`, with totals
+pub fn render_report(settlements: &[Settlement]) -> String {
+    // token -> (label, count, shares, edge, luck)
+    let mut assets: BTreeMap<&str, (&str, usize, f64, f64, f64)> = BTreeMap::new();
+    for s in settlements {
+        let a = assets.entry(&s.token_id).or_insert((&s.label, 0, 0.0, 0.0, 0.0));
+        a.1 += 1;
+        a.2 += s.shares;
+        a.3 += s.entry_edge;
+        a.4 += s.resolution_luck;
+    }
+    let mut out = format!("{:<40} {:>4} {:>10} {:>10} {:>10} {:>10}\n", "ASSET", "N", "SHARES", "EDGE", "LUCK", "PNL");
+    let (mut edge, mut luck) = (0.0, 0.0);
+    for (token, (label, n, shares, e, l)) in &assets {
+        let name = if label.is_empty() { token } else { label };
+        let name: String = name.chars().take(40).collect();
+        out.push_str(&format!("{:<40} {:>4} {:>10.2} {:>+10.2} {:>+10.2} {:>+10.2}\n", name, n, shares, e, l, e + l));
+        edge += e;
+        luck += l;
+    }
+    out.push_str(&format!(
+        "{:<40} {:>4} {:>10} {:>+10.2} {:>+10.2} {:>+10.2}\n",
+        "TOTAL", settlements.len(), "", edge, luck, edge + luck
+    ));
+    out
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_FAIR_AT_ENTRY: OnceLock<FairAtEntry> = OnceLock::new();
+
+pub fn fair_at_entry() -> &'static FairAtEntry {
+    GLOBAL_FAIR_AT_ENTRY.get_or_init(|| FairAtEntry::new(Some(FAIR_AT_ENTRY_FILE)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decomposition_sums_to_pnl() {
+        // Bought 0.45 when the mid said 0.50, then it resolved NO
+        let s = Settlement::new("t", "", 100.0, 0.45, 0.50, 0.0);
+        assert!((s.entry_edge - 5.0).abs() < 1e-9);
+        assert!((s.resolution_luck + 50.0).abs() < 1e-9);
+        assert!((s.pnl() - 100.0 * (0.0 - 0.45)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fair_is_share_weighted_and_report_totals() {
+        let f = FairAtEntry::new(None);
+        f.record_entry("t", 100.0, 0.50);
+        f.record_entry("t", 300.0, 0.60);
+        assert!((f.fair("t").unwrap() - 0.575).abs() < 1e-9);
+        f.remove("t");
+        assert!(f.fair("t").is_none());
+
+        let report = render_report(&[
+            Settlement::new("a", "Yes | Will it rain?", 10.0, 0.40, 0.45, 1.0),
+            Settlement::new("a", "Yes | Will it rain?", 10.0, 0.50, 0.45, 1.0),
+            Settlement::new("b", "", 20.0, 0.30, 0.35, 0.0),
+        ]);
+        assert!(report.contains("Yes | Will it rain?                         2      20.00      +0.00     +11.00     +11.00"));
+        assert!(report.contains("TOTAL                                       3                 +1.00      +4.00      +5.00"));
+    }
+}
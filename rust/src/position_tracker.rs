@@ -1,9 +1,11 @@
 //! Position Tracker with Stop-Loss
 //! Tracks open positions and triggers stop-loss sells when price drops below threshold
 
+use crate::display;
 use rustc_hash::FxHashMap;
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 // =============================================================================
@@ -13,9 +15,18 @@ use tokio::sync::RwLock;
 /// Stop-loss threshold as a percentage (e.g., 0.05 = 5% loss triggers sell)
 pub const STOP_LOSS_PCT: f64 = 0.05;
 
-/// How often to check positions for stop-loss (in seconds)
+/// How often to check positions for stop-loss while prices move (in seconds)
 pub const STOP_LOSS_CHECK_INTERVAL_SECS: u64 = 10;
 
+/// Check cadence while a position is within `NEAR_STOP_DISTANCE` of its stop
+pub const STOP_LOSS_FAST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest wait between checks once prices stop moving (or nothing is held)
+pub const STOP_LOSS_QUIET_INTERVAL: Duration = Duration::from_secs(30);
+
+/// P&L this close above the stop-loss switches to the fast cadence (0.02 = within 2%)
+pub const NEAR_STOP_DISTANCE: f64 = 0.02;
+
 /// Minimum position age before stop-loss can trigger (avoid selling immediately)
 pub const MIN_POSITION_AGE_SECS: u64 = 30;
 
@@ -36,6 +47,8 @@ pub struct Position {
     pub opened_at: Instant,
     /// Whether this position is from a BUY (true) or we're tracking a SELL position (false)
     pub is_long: bool,
+    /// Free-text note attached by a manual adjustment
+    pub note: Option<String>,
 }
 
 impl Position {
@@ -46,6 +59,7 @@ impl Position {
             shares,
             opened_at: Instant::now(),
             is_long,
+            note: None,
         }
     }
 
@@ -107,16 +121,16 @@ impl PositionTracker {
             let total_cost = (existing.entry_price * existing.shares) + (entry_price * shares);
             existing.entry_price = total_cost / total_shares;
             existing.shares = total_shares;
-            println!(
-                "📊 Position updated: {} | avg price: {:.4} | total shares: {:.2}",
-                token_id, existing.entry_price, existing.shares
+            crate::console_println!(
+                "📊 Position updated: {} | avg price: {} | total shares: {}",
+                token_id, display::avg_price(&token_id, existing.entry_price), display::shares(existing.shares)
             );
         } else {
             // New position
             let position = Position::new(token_id.clone(), entry_price, shares, true);
-            println!(
-                "📊 Position opened: {} | entry: {:.4} | shares: {:.2}",
-                token_id, entry_price, shares
+            crate::console_println!(
+                "📊 Position opened: {} | entry: {} | shares: {}",
+                token_id, display::avg_price(&token_id, entry_price), display::shares(shares)
             );
             positions.insert(token_id, position);
         }
@@ -135,16 +149,59 @@ impl PositionTracker {
             position.shares -= shares_sold;
             if position.shares <= 0.0 {
                 positions.remove(token_id);
-                println!("📊 Position closed: {}", token_id);
+                crate::console_println!("📊 Position closed: {}", token_id);
             } else {
-                println!(
-                    "📊 Position reduced: {} | remaining shares: {:.2}",
-                    token_id, position.shares
+                crate::console_println!(
+                    "📊 Position reduced: {} | remaining shares: {}",
+                    token_id, display::shares(position.shares)
                 );
             }
         }
     }
 
+    /// Overwrite size and entry price (manual adjustment); 0 shares removes the position
+    pub async fn set_position(&self, token_id: String, entry_price: f64, shares: f64) {
+        let mut positions = self.positions.write().await;
+        if shares <= 0.0 {
+            positions.remove(&token_id);
+            return;
+        }
+        positions.entry(token_id.clone())
+            .and_modify(|p| {
+                p.entry_price = entry_price;
+                p.shares = shares;
+            })
+            .or_insert_with(|| Position::new(token_id, entry_price, shares, true));
+    }
+
+    /// Seed positions from this tag's ledger book after a restart (tracked ones are kept);
+    /// returns how many were added
+    pub async fn restore_from_ledger(&self, ledger: &crate::strategy::StrategyLedger) -> usize {
+        let mut positions = self.positions.write().await;
+        let mut restored = 0;
+        for token_id in ledger.held_tokens() {
+            let Some((shares, avg)) = ledger.held(&token_id) else { continue };
+            if positions.contains_key(&token_id) {
+                continue;
+            }
+            positions.insert(token_id.clone(), Position::new(token_id, avg, shares, true));
+            restored += 1;
+        }
+        restored
+    }
+
+    /// Attach a note to an open position; false if there is none
+    pub async fn set_note(&self, token_id: &str, note: String) -> bool {
+        let mut positions = self.positions.write().await;
+        match positions.get_mut(token_id) {
+            Some(p) => {
+                p.note = Some(note);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get a snapshot of all positions
     pub async fn get_all_positions(&self) -> Vec<Position> {
         let positions = self.positions.read().await;
@@ -166,10 +223,10 @@ impl PositionTracker {
         for (token_id, position) in positions.iter() {
             if let Some(current_price) = price_fetcher.get_current_price(token_id).await {
                 if position.should_stop_loss(current_price) {
-                    let pnl_pct = position.pnl_pct(current_price) * 100.0;
-                    println!(
-                        "🛑 STOP-LOSS TRIGGERED: {} | entry: {:.4} | current: {:.4} | P&L: {:.2}%",
-                        token_id, position.entry_price, current_price, pnl_pct
+                    crate::console_println!(
+                        "🛑 STOP-LOSS TRIGGERED: {} | entry: {} | current: {} | P&L: {}",
+                        token_id, display::avg_price(token_id, position.entry_price), display::price(token_id, current_price),
+                        display::pct(position.pnl_pct(current_price))
                     );
                     to_sell.push((token_id.clone(), position.clone(), current_price));
                 }
@@ -179,6 +236,11 @@ impl PositionTracker {
         to_sell
     }
 
+    /// Number of open positions without waiting on the lock (None while a writer holds it)
+    pub fn try_position_count(&self) -> Option<usize> {
+        self.positions.try_read().ok().map(|p| p.len())
+    }
+
     /// Get shared reference for cloning
     pub fn get_shared(&self) -> Arc<RwLock<FxHashMap<String, Position>>> {
         Arc::clone(&self.positions)
@@ -191,6 +253,48 @@ impl Default for PositionTracker {
     }
 }
 
+// =============================================================================
+// Check Cadence
+// =============================================================================
+
+/// Stop-loss check interval scaled with activity: fast near a stop, the base interval while
+/// prices move, doubling toward the quiet interval while they don't
+pub struct StopLossCadence {
+    current_ms: AtomicU64,
+}
+
+impl Default for StopLossCadence {
+    fn default() -> Self {
+        Self { current_ms: AtomicU64::new(STOP_LOSS_CHECK_INTERVAL_SECS * 1000) }
+    }
+}
+
+impl StopLossCadence {
+    /// Wait before the next check. `nearest` is the smallest P&L distance above the stop across
+    /// held positions (None when nothing is held); `moved` is whether any price changed
+    pub fn next(&self, nearest: Option<f64>, moved: bool) -> Duration {
+        let base = Duration::from_secs(STOP_LOSS_CHECK_INTERVAL_SECS);
+        let next = match nearest {
+            None => STOP_LOSS_QUIET_INTERVAL,
+            Some(d) if d <= NEAR_STOP_DISTANCE => STOP_LOSS_FAST_INTERVAL,
+            Some(_) if moved => base,
+            Some(_) => (self.current().max(base) * 2).min(STOP_LOSS_QUIET_INTERVAL),
+        };
+        self.current_ms.store(next.as_millis() as u64, Ordering::Relaxed);
+        next
+    }
+
+    pub fn current(&self) -> Duration {
+        Duration::from_millis(self.current_ms.load(Ordering::Relaxed))
+    }
+}
+
+static GLOBAL_STOP_LOSS_CADENCE: OnceLock<StopLossCadence> = OnceLock::new();
+
+pub fn stop_loss_cadence() -> &'static StopLossCadence {
+    GLOBAL_STOP_LOSS_CADENCE.get_or_init(StopLossCadence::default)
+}
+
 // =============================================================================
 // Price Fetcher Trait
 // =============================================================================
@@ -209,6 +313,20 @@ pub trait PriceFetcher: Send + Sync {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_stop_loss_cadence() {
+        let c = StopLossCadence::default();
+        assert_eq!(c.next(Some(0.01), false), STOP_LOSS_FAST_INTERVAL);
+        assert_eq!(c.next(Some(0.10), true), Duration::from_secs(10));
+        assert_eq!(c.next(Some(0.10), false), Duration::from_secs(20));
+        assert_eq!(c.next(Some(0.10), false), STOP_LOSS_QUIET_INTERVAL);
+        assert_eq!(c.current(), STOP_LOSS_QUIET_INTERVAL);
+        // Leaving the fast cadence backs off from the base interval, not from 1s
+        c.next(Some(0.0), true);
+        assert_eq!(c.next(Some(0.10), false), Duration::from_secs(20));
+        assert_eq!(c.next(None, false), STOP_LOSS_QUIET_INTERVAL);
+    }
+
     #[test]
     fn test_position_pnl() {
         let position = Position::new("test".into(), 0.50, 100.0, true);
@@ -244,4 +362,22 @@ mod tests {
         // 10% loss but position is too new
         assert!(!position.should_stop_loss(0.45));
     }
+
+    #[tokio::test]
+    async fn test_restore_from_ledger() {
+        let ledger = crate::strategy::StrategyLedger::new("restore", None, None);
+        ledger.record("t1", true, 100.0, 0.40);
+        ledger.record("t2", true, 50.0, 0.60);
+        ledger.record("t2", false, 50.0, 0.65);
+
+        let tracker = PositionTracker::new();
+        tracker.set_position("t1".into(), 0.42, 90.0).await;
+        assert_eq!(tracker.restore_from_ledger(&ledger).await, 0);
+
+        let fresh = PositionTracker::new();
+        assert_eq!(fresh.restore_from_ledger(&ledger).await, 1);
+        let p = fresh.get_position("t1").await.unwrap();
+        assert_eq!((p.shares, p.entry_price), (100.0, 0.40));
+        assert!(fresh.get_position("t2").await.is_none());
+    }
 }
@@ -0,0 +1,155 @@
+//! Soft-entry probing
+//! A FAK entry large enough to split first sends a small probe at the same limit. Only a probe
+//! that fills completely at or under the limit is followed by the remainder; anything less
+//! means the quote we reacted to is already gone, so the FAK's unfilled part lapses and the
+//! entry is skipped
+
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProbePolicy {
+    /// Probe size; raised to the exchange minimum at the order's price
+    pub shares: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProbeVerdict {
+    /// Filled in full at or under the limit: send the remainder
+    Pass,
+    /// Filled partly; keep what filled, skip the remainder
+    Partial,
+    Miss,
+}
+
+impl ProbePolicy {
+    /// Probe size for an entry of `shares` at `price`, None when the entry is too small to split
+    /// (the remainder must itself be a valid order)
+    pub fn probe_size(&self, shares: f64, price: f64, min_shares: f64, min_cash: f64) -> Option<f64> {
+        let floor = min_shares.max(min_cash / price.max(0.0001));
+        let probe = ((self.shares.max(floor)) * 100.0).ceil() / 100.0;
+        (shares - probe >= floor).then_some(probe)
+    }
+
+    pub fn verdict(&self, requested: f64, filled: f64, avg_price: f64, limit: f64) -> ProbeVerdict {
+        if filled <= 0.0 {
+            ProbeVerdict::Miss
+        } else if filled + 0.005 >= requested && avg_price <= limit + 1e-9 {
+            ProbeVerdict::Pass
+        } else {
+            ProbeVerdict::Partial
+        }
+    }
+}
+
+// ============================================================================
+// Statistics
+// ============================================================================
+
+#[derive(Default)]
+struct Counts {
+    sent: u64,
+    passed: u64,
+    partial: u64,
+    missed: u64,
+    /// Sum of (fill - whale price) over probes that filled anything
+    slippage_sum: f64,
+    filled: u64,
+}
+
+#[derive(Default)]
+pub struct ProbeStats {
+    counts: Mutex<Counts>,
+}
+
+impl ProbeStats {
+    pub fn record(&self, verdict: ProbeVerdict, fill_price: f64, whale_price: f64) {
+        let Ok(mut c) = self.counts.lock() else { return };
+        c.sent += 1;
+        match verdict {
+            ProbeVerdict::Pass => c.passed += 1,
+            ProbeVerdict::Partial => c.partial += 1,
+            ProbeVerdict::Miss => c.missed += 1,
+        }
+        if verdict != ProbeVerdict::Miss {
+            c.filled += 1;
+            c.slippage_sum += fill_price - whale_price;
+        }
+    }
+
+    /// (sent, passed, partial, missed)
+    pub fn counts(&self) -> (u64, u64, u64, u64) {
+        self.counts.lock().map(|c| (c.sent, c.passed, c.partial, c.missed)).unwrap_or_default()
+    }
+
+    /// Dump line, empty until a probe was sent
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let Ok(c) = self.counts.lock() else { return out };
+        if c.sent == 0 {
+            return out;
+        }
+        let _ = write!(
+            out, "  {:<14} sent={} passed={} partial={} missed={} pass_rate={:.0}%",
+            "probes", c.sent, c.passed, c.partial, c.missed, c.passed as f64 * 100.0 / c.sent as f64
+        );
+        if c.filled > 0 {
+            let _ = write!(out, " avg_slip_vs_whale={:+.3}", c.slippage_sum / c.filled as f64);
+        }
+        out.push('\n');
+        out
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_PROBE: OnceLock<Option<ProbePolicy>> = OnceLock::new();
+static GLOBAL_PROBE_STATS: OnceLock<ProbeStats> = OnceLock::new();
+
+/// Set once at startup (None = probing off)
+pub fn init_probe_policy(policy: Option<ProbePolicy>) {
+    let _ = GLOBAL_PROBE.set(policy);
+}
+
+pub fn probe_policy() -> Option<ProbePolicy> {
+    GLOBAL_PROBE.get().copied().flatten()
+}
+
+pub fn probe_stats() -> &'static ProbeStats {
+    GLOBAL_PROBE_STATS.get_or_init(ProbeStats::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_size_needs_room_for_remainder() {
+        let p = ProbePolicy { shares: 5.0 };
+        assert_eq!(p.probe_size(100.0, 0.50, 5.0, 1.0), Some(5.0));
+        // $1 minimum at 0.10 is 10 shares, so the probe is raised to 10
+        assert_eq!(p.probe_size(100.0, 0.10, 5.0, 1.0), Some(10.0));
+        assert_eq!(p.probe_size(14.0, 0.10, 5.0, 1.0), None);
+    }
+
+    #[test]
+    fn test_verdict_and_stats() {
+        let p = ProbePolicy { shares: 5.0 };
+        assert_eq!(p.verdict(5.0, 5.0, 0.51, 0.51), ProbeVerdict::Pass);
+        assert_eq!(p.verdict(5.0, 3.0, 0.50, 0.51), ProbeVerdict::Partial);
+        assert_eq!(p.verdict(5.0, 0.0, 0.0, 0.51), ProbeVerdict::Miss);
+
+        let s = ProbeStats::default();
+        assert!(s.report().is_empty());
+        s.record(ProbeVerdict::Pass, 0.51, 0.50);
+        s.record(ProbeVerdict::Miss, 0.0, 0.50);
+        assert_eq!(s.counts(), (2, 1, 0, 1));
+        assert!(s.report().contains("probes         sent=2 passed=1 partial=0 missed=1 pass_rate=50% avg_slip_vs_whale=+0.010"));
+    }
+}
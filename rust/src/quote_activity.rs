@@ -0,0 +1,379 @@
+//! Quote activity surges
+//! A burst of book updates on a token often comes just before it reprices. This counts
+//! market-channel events per token for the tokens the whale has been trading (and the ones
+//! held), and compares the rate of the last `RECENT_WINDOW` with the rate over the
+//! `BASELINE_WINDOW` before it. At each whale entry the ratio is journaled, and with
+//! QUOTE_SURGE_MODE it can also boost the copy size or skip the entry while the ratio is
+//! above QUOTE_SURGE_RATIO. `record` only journals, to evaluate the signal offline first
+
+use crate::diagnostics::diagnostics;
+use crate::timestamp;
+use anyhow::{anyhow, Result};
+use futures::{SinkExt, StreamExt};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// One line per whale entry evaluated
+pub const QUOTE_ACTIVITY_FILE: &str = "quote_activity.jsonl";
+
+/// Window of the current rate
+const RECENT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Window of the baseline rate, before the recent one
+const BASELINE_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// A token needs this much history and this many baseline events before it has a ratio
+const MIN_OBSERVED: Duration = Duration::from_secs(2 * 60);
+const MIN_BASELINE_EVENTS: usize = 5;
+
+/// Tokens the whale traded stay subscribed this long after the last trade
+const WATCH_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Most tokens subscribed at once (least recently traded dropped first)
+const MAX_WATCHED: usize = 100;
+
+/// How often the watched set is compared with the subscription (and the channel pinged)
+const WATCH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+const FEED_TIMEOUT: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurgeMode {
+    /// Journal the ratio only
+    Record,
+    /// Multiply the copy size by the boost while surging
+    Boost,
+    /// Skip entries while surging
+    Suppress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurgePolicy {
+    pub mode: SurgeMode,
+    /// Recent rate over baseline rate that counts as a surge
+    pub ratio: f64,
+    /// Size multiplier in boost mode
+    pub boost: f64,
+}
+
+impl SurgePolicy {
+    /// QUOTE_SURGE_MODE (off, record, boost, suppress); None when off
+    pub fn parse(mode: &str, ratio: f64, boost: f64) -> Result<Option<Self>> {
+        let mode = match mode.trim().to_ascii_lowercase().as_str() {
+            "" | "off" => return Ok(None),
+            "record" => SurgeMode::Record,
+            "boost" => SurgeMode::Boost,
+            "suppress" => SurgeMode::Suppress,
+            other => anyhow::bail!("QUOTE_SURGE_MODE must be off, record, boost or suppress (got '{}')", other),
+        };
+        if ratio <= 1.0 {
+            anyhow::bail!("QUOTE_SURGE_RATIO must be above 1 (got {})", ratio);
+        }
+        Ok(Some(Self { mode, ratio, boost: boost.clamp(1.0, 3.0) }))
+    }
+}
+
+// ============================================================================
+// Rates
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Activity {
+    /// Events per minute over the recent window
+    pub recent_per_min: f64,
+    /// Events per minute over the baseline window
+    pub baseline_per_min: f64,
+}
+
+impl Activity {
+    pub fn ratio(&self) -> f64 {
+        if self.baseline_per_min > 0.0 { self.recent_per_min / self.baseline_per_min } else { f64::INFINITY }
+    }
+}
+
+#[derive(Default)]
+struct TokenEvents {
+    /// Unix ms of the first event seen
+    first_ms: u64,
+    /// Unix ms of each event within the baseline and recent windows, oldest first
+    times: VecDeque<u64>,
+}
+
+#[derive(Default)]
+pub struct QuoteActivity {
+    tokens: Mutex<FxHashMap<String, TokenEvents>>,
+    /// token -> unix ms of the whale's last trade in it
+    watched: Mutex<FxHashMap<String, u64>>,
+}
+
+impl QuoteActivity {
+    pub fn record(&self, token_id: &str, now_ms: u64) {
+        let Ok(mut tokens) = self.tokens.lock() else { return };
+        let t = tokens.entry(token_id.to_string()).or_insert_with(|| TokenEvents { first_ms: now_ms, ..Default::default() });
+        let keep = (BASELINE_WINDOW + RECENT_WINDOW).as_millis() as u64;
+        while t.times.front().is_some_and(|&at| at + keep < now_ms) {
+            t.times.pop_front();
+        }
+        t.times.push_back(now_ms);
+    }
+
+    /// Recent and baseline rates, None until the token has enough history
+    pub fn activity(&self, token_id: &str, now_ms: u64) -> Option<Activity> {
+        let tokens = self.tokens.lock().ok()?;
+        let t = tokens.get(token_id)?;
+        let recent_ms = RECENT_WINDOW.as_millis() as u64;
+        let recent_start = now_ms.saturating_sub(recent_ms);
+        let baseline_start = recent_start.saturating_sub(BASELINE_WINDOW.as_millis() as u64).max(t.first_ms);
+        if now_ms.saturating_sub(t.first_ms) < MIN_OBSERVED.as_millis() as u64 {
+            return None;
+        }
+        let recent = t.times.iter().filter(|&&at| at > recent_start && at <= now_ms).count();
+        let baseline = t.times.iter().filter(|&&at| at > baseline_start && at <= recent_start).count();
+        if baseline < MIN_BASELINE_EVENTS {
+            return None;
+        }
+        let per_min = |n: usize, span_ms: u64| n as f64 * 60_000.0 / span_ms.max(1) as f64;
+        Some(Activity {
+            recent_per_min: per_min(recent, recent_ms),
+            baseline_per_min: per_min(baseline, recent_start - baseline_start),
+        })
+    }
+
+    /// Subscribe to `token_id` for the next `WATCH_TTL`
+    pub fn watch(&self, token_id: &str, now_ms: u64) {
+        let Ok(mut watched) = self.watched.lock() else { return };
+        watched.insert(token_id.to_string(), now_ms);
+        let ttl = WATCH_TTL.as_millis() as u64;
+        watched.retain(|_, at| *at + ttl >= now_ms);
+        while watched.len() > MAX_WATCHED {
+            let Some(oldest) = watched.iter().min_by_key(|(_, at)| **at).map(|(t, _)| t.clone()) else { break };
+            watched.remove(&oldest);
+        }
+    }
+
+    /// Tokens to subscribe: recently traded by the whale, plus `held`
+    pub fn watched_tokens(&self, held: Vec<String>, now_ms: u64) -> FxHashSet<String> {
+        let ttl = WATCH_TTL.as_millis() as u64;
+        let mut out: FxHashSet<String> = held.into_iter().collect();
+        if let Ok(watched) = self.watched.lock() {
+            out.extend(watched.iter().filter(|(_, at)| **at + ttl >= now_ms).map(|(t, _)| t.clone()));
+        }
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.retain(|t, _| out.contains(t));
+        }
+        crate::taker_flow::taker_flow().retain(|t| out.contains(t));
+        out
+    }
+}
+
+/// Tokens a market-channel message touches (book snapshots and price changes on either side)
+pub fn message_tokens(msg: &Value) -> Vec<String> {
+    let events = msg.as_array().map(Vec::as_slice).unwrap_or(std::slice::from_ref(msg));
+    let mut out: Vec<String> = Vec::new();
+    for e in events {
+        let changes = e["price_changes"].as_array().or_else(|| e["changes"].as_array());
+        let ids = std::iter::once(e["asset_id"].as_str())
+            .chain(changes.into_iter().flatten().map(|c| c["asset_id"].as_str()))
+            .flatten();
+        for id in ids {
+            if !out.iter().any(|t| t == id) {
+                out.push(id.to_string());
+            }
+        }
+    }
+    out
+}
+
+// ============================================================================
+// Journal
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+struct SurgeRecord<'a> {
+    ts: u64,
+    token_id: &'a str,
+    /// Recent over baseline rate; absent without enough history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ratio: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recent_per_min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline_per_min: Option<f64>,
+    /// "none", "boost" or "suppress"
+    action: &'static str,
+}
+
+fn journal(token_id: &str, activity: Option<Activity>, action: &'static str) {
+    let rec = SurgeRecord {
+        ts: timestamp::unix_secs(),
+        token_id,
+        ratio: activity.map(|a| a.ratio()).filter(|r| r.is_finite()),
+        recent_per_min: activity.map(|a| a.recent_per_min),
+        baseline_per_min: activity.map(|a| a.baseline_per_min),
+        action,
+    };
+    let Ok(line) = serde_json::to_string(&rec) else { return };
+    match OpenOptions::new().append(true).create(true).open(QUOTE_ACTIVITY_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Quote activity journal write failed: {}", e),
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_QUOTE_ACTIVITY: OnceLock<QuoteActivity> = OnceLock::new();
+static GLOBAL_SURGE_POLICY: OnceLock<Option<SurgePolicy>> = OnceLock::new();
+
+pub fn quote_activity() -> &'static QuoteActivity {
+    GLOBAL_QUOTE_ACTIVITY.get_or_init(QuoteActivity::default)
+}
+
+/// Set once at startup (None = quote activity is not tracked)
+pub fn init_surge_policy(policy: Option<SurgePolicy>) {
+    let _ = GLOBAL_SURGE_POLICY.set(policy);
+}
+
+pub fn surge_policy() -> Option<&'static SurgePolicy> {
+    GLOBAL_SURGE_POLICY.get().and_then(Option::as_ref)
+}
+
+/// Whale entry into `token_id`: watch the token, journal its quote activity and return the
+/// size multiplier, or Err(ratio) when suppress mode skips the entry
+pub fn evaluate_entry(token_id: &str) -> Result<f64, f64> {
+    let Some(policy) = surge_policy() else { return Ok(1.0) };
+    let now = timestamp::unix_millis();
+    quote_activity().watch(token_id, now);
+    let activity = quote_activity().activity(token_id, now);
+    let surging = activity.map(|a| a.ratio()).filter(|r| *r >= policy.ratio);
+    let action = match (policy.mode, surging) {
+        (SurgeMode::Boost, Some(_)) => "boost",
+        (SurgeMode::Suppress, Some(_)) => "suppress",
+        _ => "none",
+    };
+    journal(token_id, activity, action);
+    match (policy.mode, surging) {
+        (SurgeMode::Boost, Some(_)) => Ok(policy.boost),
+        (SurgeMode::Suppress, Some(ratio)) => Err(ratio),
+        _ => Ok(1.0),
+    }
+}
+
+// ============================================================================
+// Feed Task
+// ============================================================================
+
+fn subscription() -> FxHashSet<String> {
+    quote_activity().watched_tokens(crate::strategy::strategy_ledger().held_tokens(), timestamp::unix_millis())
+}
+
+/// One subscription; returns Ok when the watched set changed and a resubscribe is due
+async fn run_feed(watched: &FxHashSet<String>) -> Result<()> {
+    let (mut ws, _) = connect_async(crate::book_feed::market_ws_url()).await?;
+    let sub = serde_json::json!({ "assets_ids": watched.iter().collect::<Vec<_>>(), "type": "market" }).to_string();
+    ws.send(Message::Text(sub)).await?;
+    diagnostics().heartbeat("quote_activity", "subscribed");
+    let mut check = tokio::time::interval(WATCH_CHECK_INTERVAL);
+    check.tick().await;
+    loop {
+        tokio::select! {
+            msg = tokio::time::timeout(FEED_TIMEOUT, ws.next()) => {
+                let msg = msg.map_err(|_| anyhow!("quote feed timeout"))?
+                    .ok_or_else(|| anyhow!("quote feed closed"))??;
+                let text = match msg {
+                    Message::Text(text) => text,
+                    Message::Ping(d) => { ws.send(Message::Pong(d)).await?; continue; }
+                    Message::Close(f) => return Err(anyhow!("quote feed closed: {:?}", f)),
+                    _ => continue,
+                };
+                let Ok(value) = serde_json::from_str::<Value>(&text) else { continue };
+                let now = timestamp::unix_millis();
+                for token in message_tokens(&value) {
+                    quote_activity().record(&token, now);
+                }
+                crate::taker_flow::record_message(&value, now);
+            }
+            _ = check.tick() => {
+                ws.send(Message::Text("PING".into())).await?;
+                if subscription() != *watched {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Count market-channel events of the watched tokens (and pass trade prints to taker_flow)
+pub fn spawn_activity_feed_task() -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let watched = subscription();
+            if watched.is_empty() {
+                diagnostics().heartbeat("quote_activity", "idle");
+                tokio::time::sleep(WATCH_CHECK_INTERVAL).await;
+                continue;
+            }
+            if let Err(e) = run_feed(&watched).await {
+                crate::console_eprintln!("⚠️ Quote activity feed: {} (reconnecting)", e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_surge_ratio() {
+        let qa = QuoteActivity::default();
+        let start = 1_000_000;
+        // One event every 10s for five minutes, then ten more in the last 30s
+        for i in 0..30 {
+            qa.record("t", start + i * 10_000);
+        }
+        let now = start + 300_000;
+        assert!(qa.activity("t", start + 60_000).is_none(), "too little history");
+        for i in 0..10 {
+            qa.record("t", now - 29_000 + i * 2_900);
+        }
+        let a = qa.activity("t", now).unwrap();
+        // 12 events in the last 30s against 27 over the 270s before
+        assert!((a.recent_per_min - 24.0).abs() < 1e-9, "{:?}", a);
+        assert!((a.baseline_per_min - 6.0).abs() < 1e-9, "{:?}", a);
+        assert!(a.ratio() > 3.0);
+        assert!(qa.activity("other", now).is_none());
+    }
+
+    #[test]
+    fn test_message_tokens_and_policy() {
+        let msg = json!([
+            { "event_type": "book", "asset_id": "a", "bids": [] },
+            { "event_type": "price_change", "price_changes": [
+                { "asset_id": "b", "side": "SELL", "price": "0.5", "size": "10" },
+                { "asset_id": "a", "side": "BUY", "price": "0.4", "size": "10" }
+            ]}
+        ]);
+        assert_eq!(message_tokens(&msg), vec!["a", "b"]);
+
+        assert_eq!(SurgePolicy::parse("off", 3.0, 1.5).unwrap(), None);
+        assert_eq!(SurgePolicy::parse("boost", 3.0, 9.0).unwrap().unwrap().boost, 3.0);
+        assert!(SurgePolicy::parse("suppress", 0.5, 1.0).is_err());
+        assert!(SurgePolicy::parse("mute", 3.0, 1.0).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Automatic order remediation
+//! Each classified rejection (`OrderError`) maps to a `Remediation`. The safe ones run
+//! automatically when listed in `AUTO_REMEDIATE`: a closed market is paused for copy orders, a
+//! tick-size rejection is re-priced onto the tick the exchange names and sent once more, and a
+//! buy refused for balance is resized to the USDC actually there and sent once more. The rest
+//! are reported with a hint for the operator
+
+use crate::order_reply::{OrderError, OrderRejection};
+use rustc_hash::FxHashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Part of the fetched balance a resized buy may spend (fees, rounding)
+const RESIZE_BALANCE_SHARE: f64 = 0.98;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remediation {
+    /// Same order may succeed later (the resubmitter escalates FAK misses)
+    Retry,
+    /// Shrink the buy to the available balance
+    Resize,
+    /// Move the price onto the market's tick grid
+    Reprice,
+    /// Stop copying this market for a while
+    PauseMarket,
+    /// Needs a person: credentials, clock, configuration
+    Operator,
+    /// Nothing to do
+    Drop,
+}
+
+impl Remediation {
+    pub fn for_error(error: OrderError) -> Self {
+        match error {
+            OrderError::NoMatch | OrderError::RateLimited | OrderError::TradingDisabled | OrderError::Execution => Self::Retry,
+            OrderError::InsufficientBalance => Self::Resize,
+            OrderError::TickSize => Self::Reprice,
+            OrderError::MarketClosed => Self::PauseMarket,
+            OrderError::Unauthorized | OrderError::Expiration | OrderError::Other => Self::Operator,
+            OrderError::MinSize | OrderError::Duplicate => Self::Drop,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Retry => "retry",
+            Self::Resize => "resize",
+            Self::Reprice => "reprice",
+            Self::PauseMarket => "pause",
+            Self::Operator => "operator",
+            Self::Drop => "drop",
+        }
+    }
+}
+
+/// What to do about `error`, for the order log
+pub fn hint(error: OrderError) -> &'static str {
+    match error {
+        OrderError::NoMatch => "book moved; resubmitted at a higher price",
+        OrderError::InsufficientBalance => "top up USDC or set allowance; resize shrinks buys to the balance",
+        OrderError::MarketClosed => "market resolved or paused; pause stops copying it",
+        OrderError::TickSize => "tick size changed; reprice moves the limit onto the new tick",
+        OrderError::MinSize => "below the market minimum; raise MIN_ORDER_SHARES",
+        OrderError::Duplicate => "already placed",
+        OrderError::Expiration => "GTD expiry too close; check the system clock",
+        OrderError::Unauthorized => "API key rejected; re-derived from the private key within a minute",
+        OrderError::RateLimited => "rate limited; retried later",
+        OrderError::TradingDisabled => "exchange cancel-only or restarting; retried later",
+        OrderError::Execution => "matching engine delay; retried later",
+        OrderError::Other => "unclassified; see the message",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RemediationPolicy {
+    /// Closed markets are skipped this long (None = not paused)
+    pub pause: Option<Duration>,
+    pub reprice: bool,
+    pub resize: bool,
+}
+
+impl RemediationPolicy {
+    /// Parse `AUTO_REMEDIATE` ("pause,reprice,resize" or "all"); `pause_secs` is how long a
+    /// closed market stays paused
+    pub fn parse(spec: &str, pause_secs: u64) -> anyhow::Result<Self> {
+        let mut policy = Self::default();
+        for action in spec.split(',').map(|a| a.trim().to_ascii_lowercase()).filter(|a| !a.is_empty()) {
+            match action.as_str() {
+                "pause" => policy.pause = Some(Duration::from_secs(pause_secs.max(1))),
+                "reprice" => policy.reprice = true,
+                "resize" => policy.resize = true,
+                "all" => return Self::parse("pause,reprice,resize", pause_secs),
+                _ => anyhow::bail!("AUTO_REMEDIATE: '{}' is not pause, reprice, resize or all", action),
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Whether `remediation` runs automatically
+    pub fn applies(&self, remediation: Remediation) -> bool {
+        match remediation {
+            Remediation::PauseMarket => self.pause.is_some(),
+            Remediation::Reprice => self.reprice,
+            Remediation::Resize => self.resize,
+            _ => false,
+        }
+    }
+}
+
+// ============================================================================
+// Actions
+// ============================================================================
+
+/// Tick named in a tick-size rejection ("... breaks minimum tick size rule: 0.01")
+pub fn rejected_tick(rejection: &OrderRejection) -> Option<f64> {
+    let (_, tail) = rejection.message.rsplit_once(':')?;
+    tail.trim().trim_end_matches(['"', '}', '.']).parse().ok().filter(|t: &f64| *t > 0.0 && *t < 1.0)
+}
+
+/// `price` on the `tick` grid, rounded toward the passive side (down for buys, up for sells)
+pub fn repriced(price: f64, tick: f64, is_buy: bool) -> f64 {
+    let steps = price / tick;
+    let steps = if is_buy { (steps + 1e-9).floor() } else { (steps - 1e-9).ceil() };
+    ((steps * tick) * 1e6).round() / 1e6
+}
+
+/// Shares a buy at `price` can afford from `balance` USDC, to the cent
+pub fn resized(balance: f64, price: f64) -> f64 {
+    crate::signal_math::floor_cents((balance * RESIZE_BALANCE_SHARE).max(0.0) / price.max(0.0001))
+}
+
+/// Markets paused after a closed-market rejection
+#[derive(Default)]
+pub struct MarketPauses {
+    until: Mutex<FxHashMap<String, Instant>>,
+}
+
+impl MarketPauses {
+    pub fn pause(&self, token_id: &str, duration: Duration, now: Instant) {
+        if let Ok(mut until) = self.until.lock() {
+            until.insert(token_id.to_string(), now + duration);
+        }
+    }
+
+    /// Time left on the token's pause (None = not paused)
+    pub fn remaining(&self, token_id: &str, now: Instant) -> Option<Duration> {
+        let mut until = self.until.lock().ok()?;
+        let end = *until.get(token_id)?;
+        if end <= now {
+            until.remove(token_id);
+            return None;
+        }
+        Some(end - now)
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_POLICY: OnceLock<RemediationPolicy> = OnceLock::new();
+static GLOBAL_PAUSES: OnceLock<MarketPauses> = OnceLock::new();
+
+/// Set once at startup
+pub fn init_remediation(policy: RemediationPolicy) {
+    let _ = GLOBAL_POLICY.set(policy);
+}
+
+pub fn remediation_policy() -> RemediationPolicy {
+    GLOBAL_POLICY.get().copied().unwrap_or_default()
+}
+
+pub fn market_pauses() -> &'static MarketPauses {
+    GLOBAL_PAUSES.get_or_init(MarketPauses::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_and_mapping() {
+        let p = RemediationPolicy::parse("Pause, reprice", 600).unwrap();
+        assert_eq!(p.pause, Some(Duration::from_secs(600)));
+        assert!(p.applies(Remediation::for_error(OrderError::TickSize)));
+        assert!(!p.applies(Remediation::for_error(OrderError::InsufficientBalance)));
+        assert!(!p.applies(Remediation::for_error(OrderError::Unauthorized)));
+        assert!(RemediationPolicy::parse("all", 600).unwrap().resize);
+        assert!(RemediationPolicy::parse("cancel", 600).is_err());
+        assert_eq!(RemediationPolicy::parse("", 600).unwrap(), RemediationPolicy::default());
+    }
+
+    #[test]
+    fn test_reprice_and_resize() {
+        let rejection = OrderRejection {
+            kind: OrderError::TickSize,
+            message: "order 0x1 is invalid. Price (0.975) breaks minimum tick size rule: 0.01".into(),
+        };
+        assert_eq!(rejected_tick(&rejection), Some(0.01));
+        assert_eq!(repriced(0.975, 0.01, true), 0.97);
+        assert_eq!(repriced(0.975, 0.01, false), 0.98);
+        assert_eq!(repriced(0.97, 0.01, true), 0.97);
+        assert_eq!(resized(10.0, 0.5), 19.6);
+
+        let pauses = MarketPauses::default();
+        let now = Instant::now();
+        pauses.pause("tok", Duration::from_secs(60), now);
+        assert_eq!(pauses.remaining("tok", now + Duration::from_secs(20)), Some(Duration::from_secs(40)));
+        assert_eq!(pauses.remaining("tok", now + Duration::from_secs(60)), None);
+    }
+}
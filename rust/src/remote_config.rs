@@ -0,0 +1,411 @@
+//! Remote config store
+//! A fleet of instances can be retuned from one place: with REMOTE_CONFIG_URL each bot reads a
+//! `KEY=VALUE` document (the keys experiments accept) from a Redis key or an etcd key and
+//! polls it for changes. A new document is validated against the base config as a whole and
+//! applied only if every line passes; otherwise the previous overrides stay. Every applied or
+//! rejected document is journaled with the keys it changed. The last good document is cached
+//! locally, so a bot that starts while the store is unreachable still runs with it
+
+use crate::settings::Config;
+use crate::timestamp;
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Last document that passed validation
+pub const REMOTE_CONFIG_CACHE_FILE: &str = ".remote_config_cache";
+
+/// One line per applied or rejected document
+pub const REMOTE_CONFIG_AUDIT_FILE: &str = "remote_config_audit.jsonl";
+
+const STORE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest config document accepted from the store
+const MAX_DOCUMENT_BYTES: usize = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteStore {
+    /// `redis://[:password@]host:port/<key>`, read with GET
+    Redis { addr: String, password: Option<String>, key: String },
+    /// `etcd://host:port/<key>` (or `etcds://` for TLS), read through the v3 JSON gateway
+    Etcd { base: String, key: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteConfigSource {
+    pub store: RemoteStore,
+    /// etcd auth token sent as Authorization (REMOTE_CONFIG_TOKEN)
+    pub token: Option<String>,
+    pub poll: Duration,
+}
+
+impl RemoteStore {
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = url.trim();
+        let (scheme, rest) = url.split_once("://")
+            .ok_or_else(|| anyhow::anyhow!("REMOTE_CONFIG_URL '{}' has no scheme (redis:// or etcd://)", url))?;
+        let (authority, key) = rest.split_once('/')
+            .filter(|(_, key)| !key.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("REMOTE_CONFIG_URL '{}' names no key after the host", url))?;
+        match scheme {
+            "redis" => {
+                let (password, addr) = match authority.rsplit_once('@') {
+                    Some((userinfo, addr)) => {
+                        let password = userinfo.split_once(':').map_or(userinfo, |(_, p)| p);
+                        (Some(password.to_string()).filter(|p| !p.is_empty()), addr)
+                    }
+                    None => (None, authority),
+                };
+                let addr = if addr.contains(':') { addr.to_string() } else { format!("{}:6379", addr) };
+                Ok(Self::Redis { addr, password, key: key.to_string() })
+            }
+            "etcd" | "etcds" => {
+                let http = if scheme == "etcds" { "https" } else { "http" };
+                Ok(Self::Etcd { base: format!("{}://{}", http, authority), key: key.to_string() })
+            }
+            _ => anyhow::bail!("REMOTE_CONFIG_URL scheme '{}' is not redis, etcd or etcds", scheme),
+        }
+    }
+
+    /// Host and key for logs, without credentials
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Redis { addr, key, .. } => format!("redis {} key {}", addr, key),
+            Self::Etcd { base, key } => format!("etcd {} key {}", base, key),
+        }
+    }
+}
+
+// ============================================================================
+// Store Access
+// ============================================================================
+
+/// Encode a command as a RESP array
+fn resp_command(args: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        out.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        out.extend_from_slice(arg.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+/// Read one RESP reply: Some(bulk) for a bulk string, None for nil, an error for `-ERR`
+fn read_resp(reader: &mut impl BufRead) -> Result<Option<String>> {
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    let header = header.trim_end();
+    match header.chars().next() {
+        Some('+') | Some(':') => Ok(Some(header[1..].to_string())),
+        Some('-') => anyhow::bail!("redis: {}", &header[1..]),
+        Some('$') => {
+            let len: i64 = header[1..].parse().context("redis: bad bulk length")?;
+            if len < 0 {
+                return Ok(None);
+            }
+            if len as usize > MAX_DOCUMENT_BYTES {
+                anyhow::bail!("redis: value of {} bytes is over the {} byte limit", len, MAX_DOCUMENT_BYTES);
+            }
+            let mut buf = vec![0u8; len as usize + 2];
+            reader.read_exact(&mut buf)?;
+            buf.truncate(len as usize);
+            Ok(Some(String::from_utf8(buf).context("redis: value is not UTF-8")?))
+        }
+        _ => anyhow::bail!("redis: unexpected reply '{}'", header),
+    }
+}
+
+fn fetch_redis(addr: &str, password: Option<&str>, key: &str) -> Result<Option<String>> {
+    let sock = addr.to_socket_addrs()?.next().ok_or_else(|| anyhow::anyhow!("cannot resolve {}", addr))?;
+    let mut stream = TcpStream::connect_timeout(&sock, STORE_TIMEOUT)?;
+    stream.set_read_timeout(Some(STORE_TIMEOUT))?;
+    stream.set_write_timeout(Some(STORE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    if let Some(password) = password {
+        stream.write_all(&resp_command(&["AUTH", password]))?;
+        read_resp(&mut reader)?;
+    }
+    stream.write_all(&resp_command(&["GET", key]))?;
+    read_resp(&mut reader)
+}
+
+fn fetch_etcd(base: &str, key: &str, token: Option<&str>) -> Result<Option<String>> {
+    let mut req = reqwest::blocking::Client::new().post(format!("{}/v3/kv/range", base.trim_end_matches('/')))
+        .json(&serde_json::json!({ "key": STANDARD.encode(key) }))
+        .timeout(STORE_TIMEOUT);
+    if let Some(token) = token {
+        req = req.header("Authorization", token);
+    }
+    let resp = req.send()?;
+    if !resp.status().is_success() {
+        anyhow::bail!("etcd: HTTP {}", resp.status());
+    }
+    let body: serde_json::Value = resp.json()?;
+    let Some(value) = body["kvs"].get(0).and_then(|kv| kv["value"].as_str()) else { return Ok(None) };
+    let bytes = STANDARD.decode(value).context("etcd: value is not base64")?;
+    Ok(Some(String::from_utf8(bytes).context("etcd: value is not UTF-8")?))
+}
+
+impl RemoteConfigSource {
+    /// The document in the store (blocking); None when the key does not exist
+    pub fn fetch(&self) -> Result<Option<String>> {
+        match &self.store {
+            RemoteStore::Redis { addr, password, key } => fetch_redis(addr, password.as_deref(), key),
+            RemoteStore::Etcd { base, key } => fetch_etcd(base, key, self.token.as_deref()),
+        }
+    }
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+/// `KEY=VALUE` lines; blank lines and `#` comments are skipped
+pub fn parse_document(doc: &str) -> Result<Vec<(String, String)>> {
+    doc.lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .map(|(n, l)| {
+            let Some((key, value)) = l.split_once('=') else {
+                anyhow::bail!("line {}: '{}' is not KEY=VALUE", n, l);
+            };
+            let key = key.trim().to_ascii_uppercase();
+            if crate::session::STARTUP_ONLY_KEYS.contains(&key.as_str()) {
+                anyhow::bail!("line {}: {} is read at startup and cannot change remotely", n, key);
+            }
+            Ok((key, value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// `base` with `overrides` applied; fails on the first key or value the config refuses
+pub fn resolve(base: &Config, overrides: &[(String, String)]) -> Result<Config> {
+    let mut cfg = base.clone();
+    for (key, value) in overrides {
+        cfg.apply_override(key, value)?;
+    }
+    Ok(cfg)
+}
+
+/// Keys whose value differs between two override sets: (key, old, new), "" for unset
+pub fn diff(old: &[(String, String)], new: &[(String, String)]) -> Vec<(String, String, String)> {
+    let value = |set: &[(String, String)], key: &str| set.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.clone()).unwrap_or_default();
+    let mut keys: Vec<&str> = old.iter().chain(new).map(|(k, _)| k.as_str()).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys.into_iter()
+        .map(|k| (k.to_string(), value(old, k), value(new, k)))
+        .filter(|(_, o, n)| o != n)
+        .collect()
+}
+
+// ============================================================================
+// Audit
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    ts: u64,
+    source: &'a str,
+    applied: bool,
+    /// (key, old, new)
+    changes: &'a [(String, String, String)],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn audit(source: &str, changes: &[(String, String, String)], error: Option<String>) {
+    let rec = AuditRecord { ts: timestamp::unix_secs(), source, applied: error.is_none(), changes, error };
+    let Ok(line) = serde_json::to_string(&rec) else { return };
+    match OpenOptions::new().append(true).create(true).open(REMOTE_CONFIG_AUDIT_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Remote config audit write failed: {}", e),
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+/// Overrides in force, already validated
+static GLOBAL_REMOTE_OVERRIDES: OnceLock<Mutex<Vec<(String, String)>>> = OnceLock::new();
+
+fn overrides() -> &'static Mutex<Vec<(String, String)>> {
+    GLOBAL_REMOTE_OVERRIDES.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+pub fn current_overrides() -> Vec<(String, String)> {
+    overrides().lock().map(|o| o.clone()).unwrap_or_default()
+}
+
+/// `base` with the remote overrides in force (`base` itself without a store)
+pub fn with_remote(base: &Config) -> Config {
+    resolve(base, &current_overrides()).unwrap_or_else(|_| base.clone())
+}
+
+/// Validate `doc` and make it the overrides in force; journals the outcome.
+/// Returns whether anything changed
+pub fn accept(doc: &str, base: &Config, source: &str) -> Result<bool> {
+    let old = current_overrides();
+    let checked = parse_document(doc).and_then(|new| resolve(base, &new).map(|_| new));
+    let new = match checked {
+        Ok(new) => new,
+        Err(e) => {
+            audit(source, &[], Some(format!("{:#}", e)));
+            return Err(e);
+        }
+    };
+    let changes = diff(&old, &new);
+    if changes.is_empty() {
+        return Ok(false);
+    }
+    audit(source, &changes, None);
+    if let Ok(mut o) = overrides().lock() {
+        *o = new;
+    }
+    Ok(true)
+}
+
+/// Load the overrides at startup: from the store, or the cached last good document when the
+/// store cannot be reached
+pub async fn load_startup(source: &RemoteConfigSource, base: &Config) {
+    let src = source.clone();
+    let fetched = tokio::task::spawn_blocking(move || src.fetch())
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|r| r);
+    let (doc, from) = match fetched {
+        Ok(doc) => (doc.unwrap_or_default(), "store"),
+        Err(e) => {
+            crate::console_eprintln!("⚠️ Remote config unreachable ({:#}), using {}", e, REMOTE_CONFIG_CACHE_FILE);
+            (fs::read_to_string(REMOTE_CONFIG_CACHE_FILE).unwrap_or_default(), "cache")
+        }
+    };
+    match accept(&doc, base, from) {
+        Ok(_) => {
+            if from == "store" {
+                let _ = fs::write(REMOTE_CONFIG_CACHE_FILE, &doc);
+            }
+            crate::console_println!("🛰️ Remote config from {} ({}): {} override(s)", source.store.describe(), from, current_overrides().len());
+        }
+        Err(e) => crate::console_eprintln!("⚠️ Remote config rejected, running on the local config: {:#}", e),
+    }
+}
+
+/// Poll the store and hand validated changes to the order worker and the ledger, on top of the
+/// base config (the session in force still applies on top). A running experiment keeps its
+/// tunables; its revert picks up the remote values
+pub fn spawn_watch_task(source: RemoteConfigSource, base: Config) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(source.poll);
+        let mut last_doc: Option<String> = None;
+        let mut unreachable = false;
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let src = source.clone();
+            let fetched = tokio::task::spawn_blocking(move || src.fetch())
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|r| r);
+            let doc = match fetched {
+                Ok(doc) => doc.unwrap_or_default(),
+                Err(e) => {
+                    if !unreachable {
+                        crate::console_eprintln!("⚠️ Remote config unreachable, keeping current values: {:#}", e);
+                    }
+                    unreachable = true;
+                    continue;
+                }
+            };
+            unreachable = false;
+            if last_doc.as_deref() == Some(doc.as_str()) {
+                continue;
+            }
+            last_doc = Some(doc.clone());
+            match accept(&doc, &base, "store") {
+                Ok(false) => {}
+                Ok(true) => {
+                    let _ = fs::write(REMOTE_CONFIG_CACHE_FILE, &doc);
+                    let shown: Vec<String> = current_overrides().iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                    crate::console_println!(
+                        "🛰️ Remote config changed: {}",
+                        if shown.is_empty() { "back to the local config".to_string() } else { shown.join(", ") }
+                    );
+                    if crate::session::experiment_running() {
+                        continue;
+                    }
+                    let cfg = crate::session::config_now(&base);
+                    crate::experiment::queue_risk_config(cfg.risk_guard_config());
+                    let ledger = crate::strategy::strategy_ledger();
+                    ledger.retag(&ledger.tag(), cfg.strategy_cap());
+                }
+                Err(e) => crate::console_eprintln!("⚠️ Remote config rejected, keeping current values: {:#}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_store_url() {
+        assert_eq!(
+            RemoteStore::parse("redis://:s3cret@cfg.internal/pm_bot/fleet").unwrap(),
+            RemoteStore::Redis { addr: "cfg.internal:6379".into(), password: Some("s3cret".into()), key: "pm_bot/fleet".into() }
+        );
+        assert_eq!(
+            RemoteStore::parse("etcds://10.0.0.5:2379/pm_bot").unwrap(),
+            RemoteStore::Etcd { base: "https://10.0.0.5:2379".into(), key: "pm_bot".into() }
+        );
+        assert!(matches!(
+            RemoteStore::parse("redis://user:pa:ss@cfg.internal:6380/k").unwrap(),
+            RemoteStore::Redis { password: Some(p), .. } if p == "pa:ss"
+        ));
+        assert!(RemoteStore::parse("redis://host:6379/").is_err());
+        assert!(RemoteStore::parse("consul://host/key").is_err());
+    }
+
+    #[test]
+    fn test_resp_replies() {
+        assert_eq!(resp_command(&["GET", "k"]), b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\n".to_vec());
+        let mut reply: &[u8] = b"$10\r\nA=1\r\nB=2\r\n\r\n";
+        assert_eq!(read_resp(&mut reply).unwrap(), Some("A=1\r\nB=2\r\n".to_string()));
+        let mut nil: &[u8] = b"$-1\r\n";
+        assert_eq!(read_resp(&mut nil).unwrap(), None);
+        let mut err: &[u8] = b"-WRONGPASS invalid password\r\n";
+        assert!(read_resp(&mut err).is_err());
+        let mut huge: &[u8] = b"$9999999999\r\n";
+        assert!(read_resp(&mut huge).is_err());
+    }
+
+    #[test]
+    fn test_parse_document_and_diff() {
+        let doc = "# fleet tuning\nmin_order_usd = 2\n\nSLIPPAGE_BUDGET=0.02\n";
+        let new = parse_document(doc).unwrap();
+        assert_eq!(new, vec![("MIN_ORDER_USD".into(), "2".into()), ("SLIPPAGE_BUDGET".into(), "0.02".into())]);
+        assert!(parse_document("RR_MIN_RATIO=2").is_err());
+        assert!(parse_document("MIN_ORDER_USD").is_err());
+
+        let old = vec![("MIN_ORDER_USD".to_string(), "1".to_string()), ("CB_MIN_DEPTH_USD".to_string(), "200".to_string())];
+        assert_eq!(diff(&old, &new), vec![
+            ("CB_MIN_DEPTH_USD".to_string(), "200".to_string(), String::new()),
+            ("MIN_ORDER_USD".to_string(), "1".to_string(), "2".to_string()),
+            ("SLIPPAGE_BUDGET".to_string(), String::new(), "0.02".to_string()),
+        ]);
+    }
+}
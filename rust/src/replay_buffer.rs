@@ -0,0 +1,168 @@
+//! Rolling buffer of raw feed messages
+//! Keeps the last REPLAY_BUFFER_MINS of raw whale-feed and book-feed messages in memory so the
+//! context of an anomaly can be captured after the fact, without recording every session to
+//! disk. `pm_bot replay-dump [note]` asks the running bot to write the buffer to
+//! `replay_dumps/`: one header line, then one line per message, oldest first
+
+use crate::timestamp;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Written by `pm_bot replay-dump`, picked up by the running bot
+pub const DUMP_REQUEST_FILE: &str = "replay_dump.request";
+
+/// Where dumps are written
+pub const DUMP_DIR: &str = "replay_dumps";
+
+/// How often the bot looks for a dump request
+pub const DUMP_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Bound on buffered messages whatever the window (oldest dropped first)
+const MAX_EVENTS: usize = 200_000;
+
+// ============================================================================
+// Buffer
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RawEvent {
+    /// Unix milliseconds when the message arrived
+    pub ts_ms: u64,
+    /// "whale" or "book"
+    pub source: &'static str,
+    pub raw: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DumpHeader<'a> {
+    dumped_at_ms: u64,
+    note: &'a str,
+    window_secs: u64,
+    events: usize,
+}
+
+pub struct ReplayBuffer {
+    window_ms: u64,
+    events: Mutex<VecDeque<RawEvent>>,
+}
+
+impl ReplayBuffer {
+    pub fn new(window: Duration) -> Self {
+        Self { window_ms: window.as_millis() as u64, events: Mutex::new(VecDeque::new()) }
+    }
+
+    pub fn push(&self, source: &'static str, raw: &str, now_ms: u64) {
+        let Ok(mut events) = self.events.lock() else { return };
+        let cutoff = now_ms.saturating_sub(self.window_ms);
+        while events.front().is_some_and(|e| e.ts_ms < cutoff) || events.len() >= MAX_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(RawEvent { ts_ms: now_ms, source, raw: raw.to_string() });
+    }
+
+    /// Messages within the window at `now_ms`, oldest first
+    pub fn snapshot(&self, now_ms: u64) -> Vec<RawEvent> {
+        let cutoff = now_ms.saturating_sub(self.window_ms);
+        self.events.lock()
+            .map(|events| events.iter().filter(|e| e.ts_ms >= cutoff).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Write the window to a new file under `dir`; returns its path and message count
+    pub fn dump(&self, dir: &str, note: &str, now_ms: u64) -> std::io::Result<(PathBuf, usize)> {
+        let events = self.snapshot(now_ms);
+        fs::create_dir_all(dir)?;
+        let path = PathBuf::from(dir).join(format!("replay_{}.jsonl", now_ms));
+        let mut f = std::io::BufWriter::new(fs::File::create(&path)?);
+        let header = DumpHeader { dumped_at_ms: now_ms, note, window_secs: self.window_ms / 1000, events: events.len() };
+        writeln!(f, "{}", serde_json::to_string(&header)?)?;
+        for e in &events {
+            writeln!(f, "{}", serde_json::to_string(e)?)?;
+        }
+        f.flush()?;
+        Ok((path, events.len()))
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_REPLAY_BUFFER: OnceLock<Option<ReplayBuffer>> = OnceLock::new();
+
+/// Set once at startup (None = nothing is buffered)
+pub fn init_replay_buffer(window: Option<Duration>) {
+    let _ = GLOBAL_REPLAY_BUFFER.set(window.map(ReplayBuffer::new));
+}
+
+pub fn replay_buffer() -> Option<&'static ReplayBuffer> {
+    GLOBAL_REPLAY_BUFFER.get().and_then(Option::as_ref)
+}
+
+/// Buffer a raw feed message (no-op when the buffer is off)
+#[inline]
+pub fn record(source: &'static str, raw: &str) {
+    if let Some(buffer) = replay_buffer() {
+        buffer.push(source, raw, timestamp::unix_millis());
+    }
+}
+
+/// Ask the running bot for a dump (`pm_bot replay-dump [note]`)
+pub fn request_dump(note: &str) -> std::io::Result<()> {
+    fs::write(DUMP_REQUEST_FILE, note)
+}
+
+/// Write a dump whenever `pm_bot replay-dump` leaves a request
+pub fn spawn_dump_task() -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DUMP_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Ok(note) = fs::read_to_string(DUMP_REQUEST_FILE) else { continue };
+            let _ = fs::remove_file(DUMP_REQUEST_FILE);
+            let Some(buffer) = replay_buffer() else {
+                crate::console_eprintln!("⚠️ Replay dump requested but REPLAY_BUFFER_MINS is 0");
+                continue;
+            };
+            match buffer.dump(DUMP_DIR, note.trim(), timestamp::unix_millis()) {
+                Ok((path, n)) => crate::console_println!("📼 Dumped {} feed messages to {}", n, path.display()),
+                Err(e) => crate::console_eprintln!("⚠️ Replay dump failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_and_dump() {
+        let buffer = ReplayBuffer::new(Duration::from_secs(60));
+        buffer.push("whale", "a", 1_000);
+        buffer.push("book", "b", 30_000);
+        buffer.push("whale", "c", 62_000);
+        // "a" fell out of the window when "c" arrived
+        let events = buffer.snapshot(62_000);
+        assert_eq!(events.iter().map(|e| e.raw.as_str()).collect::<Vec<_>>(), vec!["b", "c"]);
+        assert_eq!(buffer.snapshot(95_000).len(), 1);
+
+        let dir = std::env::temp_dir().join(format!("replay_buffer_test_{}", std::process::id()));
+        let (path, n) = buffer.dump(dir.to_str().unwrap(), "odd fill", 62_000).unwrap();
+        assert_eq!(n, 2);
+        let lines: Vec<String> = fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("\"note\":\"odd fill\""));
+        assert!(lines[2].contains("\"source\":\"whale\""));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,148 @@
+//! Retention policies for long runs
+//! Size-based rotation of the append-only trade logs and time-based eviction of per-token
+//! in-memory state, with current usage reported through the diagnostics dump
+
+use crate::asset_state::asset_states;
+use crate::depth_history::DEPTH_HISTORY_FILE;
+use crate::diagnostics::diagnostics;
+use crate::execution_stats::execution_stats;
+use crate::settings::{CSV_FILE, CSV_HEADER};
+use crate::shadow::SHADOW_ORDERS_FILE;
+use crate::token_metadata::global_token_metadata;
+use crate::what_if::WHAT_IF_FILE;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+// =============================================================================
+// Configuration
+// =============================================================================
+
+/// How often the retention sweep runs
+pub const RETENTION_INTERVAL_SECS: u64 = 10 * 60;
+
+/// Rotated generations kept per log (`file.1` newest ... `file.N` oldest)
+pub const ROTATED_FILES_KEPT: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionConfig {
+    /// Rotate a log once it grows past this size
+    pub log_max_bytes: u64,
+    /// Drop token metadata not refreshed for this long
+    pub metadata_max_age: Duration,
+}
+
+/// Append-only logs that are rotated here and archived daily (path, CSV header)
+pub const ROTATED_LOGS: [(&str, Option<&str>); 4] =
+    [(CSV_FILE, Some(CSV_HEADER)), (SHADOW_ORDERS_FILE, None), (WHAT_IF_FILE, None), (DEPTH_HISTORY_FILE, None)];
+
+// =============================================================================
+// Rotation
+// =============================================================================
+
+/// Rotate `path` to `path.1` (shifting older generations) if it is larger than `max_bytes`.
+/// The fresh file starts with `header` when given. Returns true if a rotation happened
+pub fn rotate_if_larger(path: &str, max_bytes: u64, keep: usize, header: Option<&str>) -> std::io::Result<bool> {
+    let size = match fs::metadata(path) {
+        Ok(m) => m.len(),
+        Err(_) => return Ok(false),
+    };
+    if size <= max_bytes || keep == 0 {
+        return Ok(false);
+    }
+
+    let _ = fs::remove_file(format!("{}.{}", path, keep));
+    for n in (1..keep).rev() {
+        let from = format!("{}.{}", path, n);
+        if Path::new(&from).exists() {
+            fs::rename(&from, format!("{}.{}", path, n + 1))?;
+        }
+    }
+    fs::rename(path, format!("{}.1", path))?;
+
+    if let Some(header) = header {
+        let mut f = fs::File::create(path)?;
+        writeln!(f, "{}", header)?;
+    }
+    Ok(true)
+}
+
+fn file_kb(path: &str) -> usize {
+    fs::metadata(path).map(|m| (m.len() / 1024) as usize).unwrap_or(0)
+}
+
+// =============================================================================
+// Sweep
+// =============================================================================
+
+/// One retention pass: rotate oversized logs, evict stale in-memory state
+pub fn sweep(cfg: &RetentionConfig) {
+    for (path, header) in ROTATED_LOGS {
+        match rotate_if_larger(path, cfg.log_max_bytes, ROTATED_FILES_KEPT, header) {
+            Ok(true) => crate::console_println!("🗂️ Rotated {} (> {} KB)", path, cfg.log_max_bytes / 1024),
+            Ok(false) => {}
+            Err(e) => crate::console_eprintln!("⚠️ Log rotation failed for {}: {}", path, e),
+        }
+    }
+
+    let idle = asset_states().prune_idle();
+    let metadata = global_token_metadata().evict_older_than(cfg.metadata_max_age.as_secs());
+    // Fill stats live as long as the token's metadata
+    execution_stats().retain_tokens(|t| global_token_metadata().get(t).is_some());
+    if metadata > 0 {
+        global_token_metadata().persist();
+    }
+    if idle + metadata > 0 {
+        crate::console_println!("🧹 Retention: evicted {} idle token states, {} stale metadata entries", idle, metadata);
+    }
+}
+
+/// Expose current memory/disk usage as diagnostics gauges
+pub fn register_usage_gauges() {
+    diagnostics().register_gauge("csv_kb", || file_kb(CSV_FILE));
+    diagnostics().register_gauge("shadow_kb", || file_kb(SHADOW_ORDERS_FILE));
+    diagnostics().register_gauge("token_metadata", || global_token_metadata().len());
+    diagnostics().register_gauge("token_states", || asset_states().len());
+    diagnostics().register_gauge("fill_stats", || execution_stats().tracked_tokens());
+}
+
+/// Spawn the periodic retention sweep (gauges are registered separately via `register_usage_gauges`)
+pub fn spawn_retention_task(cfg: RetentionConfig) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let interval = Duration::from_secs(RETENTION_INTERVAL_SECS);
+        loop {
+            tokio::time::sleep(interval).await;
+            if let Err(e) = tokio::task::spawn_blocking(move || sweep(&cfg)).await {
+                crate::console_eprintln!("⚠️ Retention sweep error: {}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_shifts_generations() {
+        let dir = std::env::temp_dir().join(format!("pm_retention_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("log.csv");
+        let path = path.to_str().unwrap();
+
+        fs::write(path, "h\nrow1\n").unwrap();
+        assert!(!rotate_if_larger(path, 1024, 2, Some("h")).unwrap());
+
+        assert!(rotate_if_larger(path, 4, 2, Some("h")).unwrap());
+        assert_eq!(fs::read_to_string(path).unwrap(), "h\n");
+        assert_eq!(fs::read_to_string(format!("{}.1", path)).unwrap(), "h\nrow1\n");
+
+        fs::write(path, "h\nrow2\n").unwrap();
+        assert!(rotate_if_larger(path, 4, 2, Some("h")).unwrap());
+        assert_eq!(fs::read_to_string(format!("{}.1", path)).unwrap(), "h\nrow2\n");
+        assert_eq!(fs::read_to_string(format!("{}.2", path)).unwrap(), "h\nrow1\n");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
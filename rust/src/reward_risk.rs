@@ -0,0 +1,199 @@
+//! Per-trade reward/risk gate
+//! Before an entry, the realistic exit on both sides is estimated from the live spread: the
+//! take-profit is sold into the bid a spread below the mark, and the stop-loss sells a tick
+//! under a bid that already sits at the stop. Entries whose reward/risk falls short of the
+//! minimum are skipped. Each accepted estimate is kept until the position is sold, and the
+//! realized result (in units of the estimated risk) is journaled for calibration
+
+use crate::position_tracker::STOP_LOSS_PCT;
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Estimated vs realized R/R, one line per closed entry
+pub const RR_CALIBRATION_FILE: &str = "rr_calibration.jsonl";
+
+/// Stop-loss orders go out one tick under the bid
+const STOP_SLIPPAGE: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RrGate {
+    /// Skip entries with estimated reward/risk below this
+    pub min_ratio: f64,
+    /// Take-profit target above entry, as a fraction of entry
+    pub take_profit_pct: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RrEstimate {
+    pub entry: f64,
+    pub spread: f64,
+    /// Per share, after exit costs
+    pub reward: f64,
+    pub risk: f64,
+    pub ratio: f64,
+}
+
+impl RrGate {
+    /// Estimate for buying at `entry` with the book at `best_bid` / `best_ask`
+    pub fn estimate(&self, entry: f64, best_bid: f64, best_ask: f64) -> RrEstimate {
+        let spread = (best_ask - best_bid).max(0.0);
+        let take_profit = (entry * (1.0 + self.take_profit_pct)).min(0.99);
+        let stop = entry * (1.0 - STOP_LOSS_PCT);
+        let reward = take_profit - spread - entry;
+        let risk = entry - (stop - STOP_SLIPPAGE) + spread;
+        let ratio = if reward > 0.0 && risk > 0.0 { reward / risk } else { 0.0 };
+        RrEstimate { entry, spread, reward, risk, ratio }
+    }
+}
+
+// ============================================================================
+// Calibration
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RrOutcome {
+    pub ts: u64,
+    pub token_id: String,
+    #[serde(flatten)]
+    pub estimate: RrEstimate,
+    pub exit: f64,
+    /// (exit - entry) / estimated risk
+    pub realized_r: f64,
+}
+
+#[derive(Default)]
+struct Totals {
+    closed: u64,
+    est_ratio_sum: f64,
+    realized_r_sum: f64,
+    wins: u64,
+}
+
+#[derive(Default)]
+pub struct RrCalibration {
+    open: Mutex<FxHashMap<String, RrEstimate>>,
+    totals: Mutex<Totals>,
+    skipped: Mutex<u64>,
+}
+
+impl RrCalibration {
+    pub fn record_skip(&self) {
+        if let Ok(mut n) = self.skipped.lock() {
+            *n += 1;
+        }
+    }
+
+    /// An entry filled; `estimate.entry` should be the fill price
+    pub fn record_entry(&self, token_id: &str, estimate: RrEstimate) {
+        if let Ok(mut open) = self.open.lock() {
+            open.insert(token_id.to_string(), estimate);
+        }
+    }
+
+    /// A sell of `token_id` filled at `exit`; closes the estimate taken at entry
+    pub fn record_exit(&self, token_id: &str, exit: f64) -> Option<RrOutcome> {
+        let estimate = self.open.lock().ok()?.remove(token_id)?;
+        let realized_r = (exit - estimate.entry) / estimate.risk.max(1e-9);
+        if let Ok(mut t) = self.totals.lock() {
+            t.closed += 1;
+            t.est_ratio_sum += estimate.ratio;
+            t.realized_r_sum += realized_r;
+            if realized_r > 0.0 {
+                t.wins += 1;
+            }
+        }
+        let ts = timestamp::unix_secs();
+        Some(RrOutcome { ts, token_id: token_id.to_string(), estimate, exit, realized_r })
+    }
+
+    /// Dump line: skips, open estimates and estimated vs realized averages
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let (Ok(t), Ok(open), Ok(skipped)) = (self.totals.lock(), self.open.lock(), self.skipped.lock()) else { return out };
+        if t.closed == 0 && open.is_empty() && *skipped == 0 {
+            return out;
+        }
+        let _ = write!(out, "  {:<14} skipped={} open={} closed={}", "rr_gate", skipped, open.len(), t.closed);
+        if t.closed > 0 {
+            let n = t.closed as f64;
+            let _ = write!(
+                out, " avg_est_rr={:.2} avg_realized_r={:+.2} win_rate={:.0}%",
+                t.est_ratio_sum / n, t.realized_r_sum / n, t.wins as f64 * 100.0 / n
+            );
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// Close the estimate for a filled sell and journal the outcome
+pub fn record_exit(token_id: &str, exit: f64) {
+    let Some(outcome) = rr_calibration().record_exit(token_id, exit) else { return };
+    let Ok(line) = serde_json::to_string(&outcome) else { return };
+    match OpenOptions::new().append(true).create(true).open(RR_CALIBRATION_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ R/R calibration write failed: {}", e),
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_RR_GATE: OnceLock<Option<RrGate>> = OnceLock::new();
+static GLOBAL_RR_CALIBRATION: OnceLock<RrCalibration> = OnceLock::new();
+
+/// Set once at startup (None = gate off)
+pub fn init_rr_gate(gate: Option<RrGate>) {
+    let _ = GLOBAL_RR_GATE.set(gate);
+}
+
+pub fn rr_gate() -> Option<RrGate> {
+    GLOBAL_RR_GATE.get().copied().flatten()
+}
+
+pub fn rr_calibration() -> &'static RrCalibration {
+    GLOBAL_RR_CALIBRATION.get_or_init(RrCalibration::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_includes_spread() {
+        let gate = RrGate { min_ratio: 1.0, take_profit_pct: 0.20 };
+        let tight = gate.estimate(0.50, 0.49, 0.50);
+        // TP 0.60 - spread 0.01 = 0.09 reward; stop 0.475 - 0.01 => 0.035 + 0.01 spread = 0.045 risk
+        assert!((tight.reward - 0.09).abs() < 1e-9);
+        assert!((tight.risk - 0.045).abs() < 1e-9);
+        assert!((tight.ratio - 2.0).abs() < 1e-9);
+
+        let wide = gate.estimate(0.50, 0.40, 0.50);
+        assert_eq!(wide.ratio, 0.0);
+        // Near 1 the take-profit is capped at 0.99
+        assert!(gate.estimate(0.95, 0.94, 0.95).reward < 0.04);
+    }
+
+    #[test]
+    fn test_calibration_realized_r() {
+        let c = RrCalibration::default();
+        let est = RrGate { min_ratio: 1.0, take_profit_pct: 0.20 }.estimate(0.50, 0.49, 0.50);
+        c.record_entry("t1", est);
+        c.record_skip();
+        assert!(c.record_exit("t2", 0.6).is_none());
+        let outcome = c.record_exit("t1", 0.59).unwrap();
+        assert!((outcome.realized_r - 2.0).abs() < 1e-9);
+        assert!(c.record_exit("t1", 0.59).is_none());
+        assert!(c.report().contains("rr_gate        skipped=1 open=0 closed=1 avg_est_rr=2.00 avg_realized_r=+2.00 win_rate=100%"));
+    }
+}
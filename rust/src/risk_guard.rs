@@ -2,6 +2,7 @@
 //! Risk management and safety guard for trade execution
 //! Provides protection against dangerous market conditions
 
+use crate::signal_math::OrderMinimums;
 use rustc_hash::FxHashMap;
 use std::time::{Duration, Instant};
 
@@ -65,6 +66,22 @@ pub struct RiskGuardConfig {
     pub sequence_window: Duration,
     pub min_depth_beyond_usd: f64,
     pub trip_duration: Duration,
+    /// Largest fraction of the visible ask depth (at our limit) an entry may take; 0 = off
+    pub max_depth_fraction: f64,
+    /// Book levels counted as visible depth
+    pub depth_levels: usize,
+    /// Most an entry may pay above the whale's price when the ask moved before submit; 0 = off
+    pub slippage_budget: f64,
+    /// Signals queued longer than this are dropped; zero = off
+    pub max_signal_age: Duration,
+    /// Entries not submitted within this of the signal are abandoned right before the POST; zero = off
+    pub submit_deadline: Duration,
+    /// Skip the optional pre-trade book fetch (slippage, impact cap, R/R) and probe
+    pub latency_mode: bool,
+    /// Smallest entry placed, and whether smaller copies are lifted to it or rejected
+    pub order_minimums: OrderMinimums,
+    /// Random size and passive price offsets on entries
+    pub jitter: crate::jitter::Jitter,
 }
 
 impl Default for RiskGuardConfig {
@@ -75,6 +92,14 @@ impl Default for RiskGuardConfig {
             sequence_window: Duration::from_secs(40),
             min_depth_beyond_usd: 200.0,
             trip_duration: Duration::from_secs(60 * 60 * 5), // 5 hours
+            max_depth_fraction: 0.0,
+            depth_levels: 5,
+            slippage_budget: 0.0,
+            max_signal_age: Duration::ZERO,
+            submit_deadline: Duration::ZERO,
+            latency_mode: false,
+            order_minimums: OrderMinimums::EXCHANGE,
+            jitter: crate::jitter::Jitter::default(),
         }
     }
 }
@@ -83,6 +108,9 @@ impl Default for RiskGuardConfig {
 // State
 // =============================================================================
 
+/// Above this many tracked tokens, quiet ones are pruned before inserting a new one
+pub const MAX_TRACKED_TOKENS: usize = 1024;
+
 struct TokenState {
     large_trades: Vec<(Instant, f64)>,
     tripped_until: Option<Instant>,
@@ -120,11 +148,50 @@ impl RiskGuard {
         }
     }
     
+    /// Swap thresholds in place; per-token sequences and trips are kept
+    pub fn set_config(&mut self, config: RiskGuardConfig) {
+        self.config = config;
+    }
+
+    /// (fraction, levels) when the market impact cap is enabled
+    pub fn depth_cap(&self) -> Option<(f64, usize)> {
+        (self.config.max_depth_fraction > 0.0).then_some((self.config.max_depth_fraction, self.config.depth_levels))
+    }
+
+    pub fn slippage_budget(&self) -> Option<f64> {
+        (self.config.slippage_budget > 0.0).then_some(self.config.slippage_budget)
+    }
+
+    pub fn max_signal_age(&self) -> Option<Duration> {
+        (!self.config.max_signal_age.is_zero()).then_some(self.config.max_signal_age)
+    }
+
+    pub fn submit_deadline(&self) -> Option<Duration> {
+        (!self.config.submit_deadline.is_zero()).then_some(self.config.submit_deadline)
+    }
+
+    pub fn latency_mode(&self) -> bool {
+        self.config.latency_mode
+    }
+
+    pub fn order_minimums(&self) -> OrderMinimums {
+        self.config.order_minimums
+    }
+
+    pub fn jitter(&self) -> crate::jitter::Jitter {
+        self.config.jitter
+    }
+
     /// Hot path - no allocations if token exists
     #[inline]
     pub fn check_fast(&mut self, token_id: &str, whale_shares: f64) -> SafetyEvaluation {
         let now = Instant::now();
         
+        // Bound memory on long runs: drop tokens with no recent large trades and no active trip
+        if self.tokens.len() >= MAX_TRACKED_TOKENS && !self.tokens.contains_key(token_id) {
+            self.prune(now);
+        }
+        
         // Use entry API - single lookup instead of get_mut + insert + get_mut
         let state = self.tokens.entry(token_id.to_string()).or_insert_with(TokenState::new);
         
@@ -225,6 +292,21 @@ impl RiskGuard {
         }
     }
     
+    /// Remove tokens whose large trades are outside the window and that are not tripped
+    pub fn prune(&mut self, now: Instant) -> usize {
+        let cutoff = now - self.config.sequence_window;
+        let before = self.tokens.len();
+        self.tokens.retain(|_, st| {
+            st.tripped_until.is_some_and(|until| until > now)
+                || st.large_trades.iter().any(|(ts, _)| *ts > cutoff)
+        });
+        before - self.tokens.len()
+    }
+    
+    pub fn tracked_tokens(&self) -> usize {
+        self.tokens.len()
+    }
+    
     #[inline]
     fn count_large_in_window(
         state: &TokenState,
@@ -268,10 +350,69 @@ pub fn calc_liquidity_depth(side: TradeSide, levels: &[(f64, f64)], threshold: f
     total
 }
 
+/// What to do with an entry when the ask at submit time is above our limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AskMove {
+    /// Ask still at or below the limit
+    Keep,
+    /// Ask moved, but within the budget over the signal price: pay it
+    Requote(f64),
+    /// Ask moved beyond the budget
+    Abort,
+}
+
+/// Compare the ask at submit time with the whale's (signal) price and our limit
+pub fn check_ask_move(signal_price: f64, limit: f64, best_ask: f64, budget: f64) -> AskMove {
+    if best_ask <= limit + 1e-9 {
+        AskMove::Keep
+    } else if best_ask <= (signal_price + budget).min(0.99) + 1e-9 {
+        AskMove::Requote(best_ask)
+    } else {
+        AskMove::Abort
+    }
+}
+
+/// Shares on the best `top_n` levels we could take at `limit` (asks at or below it for a buy,
+/// bids at or above it for a sell). Levels may arrive in any order
+pub fn visible_depth_shares(side: TradeSide, levels: &[(f64, f64)], limit: f64, top_n: usize) -> f64 {
+    let mut takeable: Vec<(f64, f64)> = levels.iter()
+        .copied()
+        .filter(|&(price, _)| if side == TradeSide::Buy { price <= limit + 1e-9 } else { price >= limit - 1e-9 })
+        .collect();
+    if side == TradeSide::Buy {
+        takeable.sort_by(|a, b| a.0.total_cmp(&b.0));
+    } else {
+        takeable.sort_by(|a, b| b.0.total_cmp(&a.0));
+    }
+    takeable.iter().take(top_n).map(|&(_, size)| size).sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_check_ask_move() {
+        assert_eq!(check_ask_move(0.50, 0.51, 0.51, 0.02), AskMove::Keep);
+        assert_eq!(check_ask_move(0.50, 0.50, 0.52, 0.02), AskMove::Requote(0.52));
+        assert_eq!(check_ask_move(0.50, 0.50, 0.53, 0.02), AskMove::Abort);
+        assert_eq!(check_ask_move(0.98, 0.98, 0.995, 0.05), AskMove::Abort);
+    }
+
+    #[test]
+    fn test_visible_depth_shares() {
+        let asks = [(0.55, 300.0), (0.50, 100.0), (0.52, 50.0), (0.51, 20.0)];
+        assert_eq!(visible_depth_shares(TradeSide::Buy, &asks, 0.52, 5), 170.0);
+        assert_eq!(visible_depth_shares(TradeSide::Buy, &asks, 0.52, 2), 120.0);
+        assert_eq!(visible_depth_shares(TradeSide::Buy, &asks, 0.49, 5), 0.0);
+        let bids = [(0.40, 10.0), (0.45, 30.0)];
+        assert_eq!(visible_depth_shares(TradeSide::Sell, &bids, 0.42, 5), 30.0);
+
+        let guard = RiskGuard::new(RiskGuardConfig { max_depth_fraction: 0.25, ..Default::default() });
+        assert_eq!(guard.depth_cap(), Some((0.25, 5)));
+        assert_eq!(RiskGuard::new(RiskGuardConfig::default()).depth_cap(), None);
+    }
+
     #[test]
     fn test_small_trade_allows() {
         let mut guard = RiskGuard::new(RiskGuardConfig::default());
@@ -328,6 +469,18 @@ mod tests {
         assert_eq!(eval.decision, SafetyDecision::Block);
     }
 
+    #[test]
+    fn test_prune_keeps_tripped_and_recent() {
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        guard.check_fast("quiet", 100.0);
+        guard.check_fast("recent", 2000.0);
+        guard.check_with_book("tripped", 2, 50.0);
+        
+        assert_eq!(guard.prune(Instant::now()), 1);
+        assert_eq!(guard.tracked_tokens(), 2);
+        assert_eq!(guard.check_fast("tripped", 100.0).decision, SafetyDecision::Block);
+    }
+
     #[test]
     fn test_different_tokens_independent() {
         let mut guard = RiskGuard::new(RiskGuardConfig::default());
@@ -0,0 +1,247 @@
+//! Monte Carlo P&L risk estimate
+//! Resamples the realized P&L of past closing trades from the strategy ledger to estimate the
+//! distribution of the next day's and week's P&L. Each simulated day draws a trade count from
+//! the history's trades per day (idle days included) and that many trade outcomes, with
+//! replacement. With RISK_LOSS_LIMIT_USD the report also gives the chance that the running P&L
+//! touches the limit within the horizon. Printed by `pm_bot risk` and once per UTC day by the
+//! running bot, which also journals it
+//!
+//! The history reflects the sizing in force when each trade was made; after a sizing change
+//! the estimate lags until new trades replace the old ones
+
+use crate::strategy::{FillKind, StrategyFill, TagBook};
+use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// One line per daily report
+pub const RISK_REPORT_FILE: &str = "risk_report.jsonl";
+
+/// Simulated paths per horizon
+pub const SIMULATIONS: usize = 10_000;
+
+/// Fewer closing trades than this give no estimate
+const MIN_TRADES: usize = 20;
+
+/// How often the daily report checks for a day change
+const REPORT_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+// ============================================================================
+// History
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeHistory {
+    /// Realized P&L of each closing trade
+    pub outcomes: Vec<f64>,
+    /// Closing trades on each UTC day from the first fill to the last, idle days as 0
+    pub per_day: Vec<usize>,
+}
+
+/// Replay ledger fills (every tag) into per-close realized P&L and trades per day
+pub fn history(fills: &[StrategyFill]) -> TradeHistory {
+    let mut books: FxHashMap<&str, TagBook> = FxHashMap::default();
+    let mut closes: Vec<(u64, f64)> = Vec::new();
+    for fill in fills {
+        let book = books.entry(fill.tag.as_str()).or_default();
+        let before = book.realized_pnl;
+        book.apply(fill);
+        if fill.kind == FillKind::Trade && !fill.is_buy {
+            closes.push((fill.ts, book.realized_pnl - before));
+        }
+    }
+    let (Some(first), Some(last)) = (fills.iter().map(|f| f.ts / DAY_SECS).min(), fills.iter().map(|f| f.ts / DAY_SECS).max()) else {
+        return TradeHistory { outcomes: Vec::new(), per_day: Vec::new() };
+    };
+    let mut per_day = vec![0; (last - first + 1) as usize];
+    for (ts, _) in &closes {
+        per_day[(ts / DAY_SECS - first) as usize] += 1;
+    }
+    TradeHistory { outcomes: closes.into_iter().map(|(_, pnl)| pnl).collect(), per_day }
+}
+
+// ============================================================================
+// Simulation
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct HorizonEstimate {
+    pub days: u32,
+    pub mean: f64,
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+    /// Share of paths whose running P&L touched -loss_limit (None without a limit)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p_hit_limit: Option<f64>,
+}
+
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let i = ((sorted.len() - 1) as f64 * q).round() as usize;
+    sorted[i]
+}
+
+/// Simulate `sims` paths of `days` days. None when the history is too short
+pub fn simulate(h: &TradeHistory, days: u32, sims: usize, loss_limit: Option<f64>, rng: &mut impl Rng) -> Option<HorizonEstimate> {
+    if h.outcomes.len() < MIN_TRADES || h.per_day.is_empty() || sims == 0 {
+        return None;
+    }
+    let mut totals = Vec::with_capacity(sims);
+    let mut hits = 0;
+    for _ in 0..sims {
+        let (mut pnl, mut low) = (0.0_f64, 0.0_f64);
+        for _ in 0..days {
+            let trades = h.per_day[rng.gen_range(0..h.per_day.len())];
+            for _ in 0..trades {
+                pnl += h.outcomes[rng.gen_range(0..h.outcomes.len())];
+                low = low.min(pnl);
+            }
+        }
+        if loss_limit.is_some_and(|limit| low <= -limit) {
+            hits += 1;
+        }
+        totals.push(pnl);
+    }
+    totals.sort_by(f64::total_cmp);
+    Some(HorizonEstimate {
+        days,
+        mean: totals.iter().sum::<f64>() / sims as f64,
+        p5: percentile(&totals, 0.05),
+        p50: percentile(&totals, 0.50),
+        p95: percentile(&totals, 0.95),
+        p_hit_limit: loss_limit.map(|_| hits as f64 / sims as f64),
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskReport {
+    pub ts: u64,
+    pub trades: usize,
+    pub days_of_history: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loss_limit: Option<f64>,
+    pub day: HorizonEstimate,
+    pub week: HorizonEstimate,
+}
+
+/// Day and week estimates from the ledger at `path`
+pub fn report(path: &str, loss_limit: Option<f64>, seed: u64) -> Option<RiskReport> {
+    let h = history(&crate::session::load_fills(path));
+    let mut rng = StdRng::seed_from_u64(seed);
+    Some(RiskReport {
+        ts: Utc::now().timestamp().max(0) as u64,
+        trades: h.outcomes.len(),
+        days_of_history: h.per_day.len(),
+        loss_limit,
+        day: simulate(&h, 1, SIMULATIONS, loss_limit, &mut rng)?,
+        week: simulate(&h, 7, SIMULATIONS, loss_limit, &mut rng)?,
+    })
+}
+
+/// Table for `pm_bot risk` and the daily log line
+pub fn render_report(r: &RiskReport) -> String {
+    let mut out = format!(
+        "🎲 P&L risk from {} closing trades over {} days ({} paths)\n",
+        r.trades, r.days_of_history, SIMULATIONS
+    );
+    let _ = writeln!(out, "{:<8} {:>10} {:>10} {:>10} {:>10} {:>10}", "HORIZON", "MEAN", "P5", "P50", "P95", "HIT LIMIT");
+    for e in [&r.day, &r.week] {
+        let hit = e.p_hit_limit.map_or_else(|| "-".to_string(), |p| format!("{:.1}%", p * 100.0));
+        let _ = writeln!(out, "{:<8} {:>+10.2} {:>+10.2} {:>+10.2} {:>+10.2} {:>10}", format!("{}d", e.days), e.mean, e.p5, e.p50, e.p95, hit);
+    }
+    if let Some(limit) = r.loss_limit {
+        let _ = writeln!(out, "Loss limit ${:.2}: chance the running P&L reaches -${:.2} within the horizon", limit, limit);
+    }
+    out
+}
+
+fn journal(r: &RiskReport) {
+    let Ok(line) = serde_json::to_string(r) else { return };
+    match OpenOptions::new().append(true).create(true).open(RISK_REPORT_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Risk report write failed: {}", e),
+    }
+}
+
+/// Print and journal the estimate at each UTC day change
+pub fn spawn_daily_report_task(loss_limit: Option<f64>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut open_day = Utc::now().date_naive();
+        loop {
+            tokio::time::sleep(REPORT_CHECK_INTERVAL).await;
+            let today = Utc::now().date_naive();
+            if today == open_day {
+                continue;
+            }
+            open_day = today;
+            let seed = Utc::now().timestamp() as u64;
+            let built = tokio::task::spawn_blocking(move || report(crate::strategy::STRATEGY_LEDGER_FILE, loss_limit, seed)).await;
+            match built {
+                Ok(Some(r)) => {
+                    crate::console_println!("{}", render_report(&r).trim_end());
+                    journal(&r);
+                }
+                Ok(None) => crate::console_println!("🎲 P&L risk: fewer than {} closing trades in the ledger, no estimate yet", MIN_TRADES),
+                Err(e) => crate::console_eprintln!("⚠️ Risk report task error: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(ts: u64, is_buy: bool, price: f64) -> StrategyFill {
+        StrategyFill {
+            ts,
+            tag: "default".into(),
+            token_id: "t".into(),
+            is_buy,
+            shares: 10.0,
+            price,
+            kind: FillKind::Trade,
+            session: None,
+        }
+    }
+
+    #[test]
+    fn test_history_counts_idle_days() {
+        let fills = vec![
+            fill(0, true, 0.5), fill(100, false, 0.6),
+            fill(2 * DAY_SECS, true, 0.5), fill(2 * DAY_SECS + 1, false, 0.4),
+        ];
+        let h = history(&fills);
+        assert_eq!(h.per_day, vec![1, 0, 1]);
+        assert_eq!(h.outcomes.len(), 2);
+        assert!((h.outcomes[0] - 1.0).abs() < 1e-9 && (h.outcomes[1] + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_simulate_bounds_and_limit() {
+        // Every trade loses $1, five trades a day
+        let h = TradeHistory { outcomes: vec![-1.0; 30], per_day: vec![5; 6] };
+        let mut rng = StdRng::seed_from_u64(7);
+        let day = simulate(&h, 1, 200, Some(3.0), &mut rng).unwrap();
+        assert_eq!((day.p5, day.p50, day.p95), (-5.0, -5.0, -5.0));
+        assert_eq!(day.p_hit_limit, Some(1.0));
+        let week = simulate(&h, 7, 200, None, &mut rng).unwrap();
+        assert_eq!(week.mean, -35.0);
+        assert_eq!(week.p_hit_limit, None);
+
+        let short = TradeHistory { outcomes: vec![1.0; 5], per_day: vec![5] };
+        assert!(simulate(&short, 1, 100, None, &mut rng).is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! Intraday parameter sessions
+//! `SESSIONS=asia@00:00,europe@07:00,us@13:30` splits the UTC day into named sessions, each
+//! running until the next one starts. `SESSION_<NAME>=KEY=VALUE,...` overrides tunables for
+//! that session with the keys experiments accept; at each boundary the order worker and the
+//! strategy cap switch to the new values. Ledger fills carry the session they were made in
+//! and `pm_bot sessions` totals them per session
+
+use crate::settings::Config;
+use crate::strategy::{strategy_ledger, FillKind, StrategyFill, TagBook};
+use crate::timestamp;
+use anyhow::{Context, Result};
+use chrono::{NaiveTime, Utc};
+use rustc_hash::FxHashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// How often the running bot checks for a session boundary
+pub const SESSION_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Read once at startup, so a per-session value would never take effect
+pub(crate) const STARTUP_ONLY_KEYS: &[&str] = &["RR_MIN_RATIO", "RR_TAKE_PROFIT_PCT"];
+
+/// Ledger fills made without a schedule
+const NO_SESSION: &str = "-";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub name: String,
+    /// UTC start; the session runs until the next one starts
+    pub start: NaiveTime,
+    /// Env var name -> value, applied on top of the base config
+    pub overrides: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSchedule {
+    /// At least one, sorted by start
+    sessions: Vec<Session>,
+}
+
+/// Env var holding a session's overrides
+pub fn overrides_var(name: &str) -> String {
+    format!("SESSION_{}", name.to_ascii_uppercase())
+}
+
+fn parse_overrides(name: &str, raw: &str) -> Result<Vec<(String, String)>> {
+    raw.split(',').map(str::trim).filter(|kv| !kv.is_empty())
+        .map(|kv| {
+            let Some((key, value)) = kv.split_once('=') else {
+                anyhow::bail!("{}: '{}' is not KEY=VALUE", overrides_var(name), kv);
+            };
+            let key = key.trim().to_ascii_uppercase();
+            if STARTUP_ONLY_KEYS.contains(&key.as_str()) {
+                anyhow::bail!("{}: {} is read at startup and cannot change by session", overrides_var(name), key);
+            }
+            Ok((key, value.trim().to_string()))
+        })
+        .collect()
+}
+
+impl SessionSchedule {
+    /// Parse `name@HH:MM,...`; `overrides_for(var)` returns the value of `SESSION_<NAME>`
+    pub fn parse(spec: &str, overrides_for: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut sessions: Vec<Session> = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((name, start)) = entry.split_once('@') else {
+                anyhow::bail!("SESSIONS entry '{}' is not name@HH:MM", entry);
+            };
+            let name = name.trim().to_ascii_lowercase();
+            if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+                anyhow::bail!("session name '{}' must be letters, digits or '_'", name);
+            }
+            if sessions.iter().any(|s| s.name == name) {
+                anyhow::bail!("session '{}' is listed twice in SESSIONS", name);
+            }
+            let start = NaiveTime::parse_from_str(start.trim(), "%H:%M")
+                .with_context(|| format!("SESSIONS entry '{}' does not start at HH:MM", entry))?;
+            let overrides = parse_overrides(&name, &overrides_for(&overrides_var(&name)).unwrap_or_default())?;
+            sessions.push(Session { name, start, overrides });
+        }
+        if sessions.is_empty() {
+            anyhow::bail!("SESSIONS lists no session");
+        }
+        sessions.sort_by_key(|s| s.start);
+        if let Some(w) = sessions.windows(2).find(|w| w[0].start == w[1].start) {
+            anyhow::bail!("sessions '{}' and '{}' start at the same time", w[0].name, w[1].name);
+        }
+        Ok(Self { sessions })
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// Session in force at `now` (UTC). Before the first start of the day the last one still runs
+    pub fn active(&self, now: NaiveTime) -> &Session {
+        self.sessions.iter().rev().find(|s| s.start <= now).unwrap_or(&self.sessions[self.sessions.len() - 1])
+    }
+
+    /// `base` with `session`'s overrides applied
+    pub fn config_for(session: &Session, base: &Config) -> Result<Config> {
+        let mut cfg = base.clone();
+        for (key, value) in &session.overrides {
+            cfg.apply_override(key, value).with_context(|| overrides_var(&session.name))?;
+        }
+        Ok(cfg)
+    }
+
+    /// Check every session's overrides against `base` (startup)
+    pub fn validate(&self, base: &Config) -> Result<()> {
+        self.sessions.iter().try_for_each(|s| Self::config_for(s, base).map(drop))
+    }
+}
+
+// ============================================================================
+// Switching
+// ============================================================================
+
+/// `base` with the remote overrides (REMOTE_CONFIG_URL) and those of the session in force now
+pub fn config_now(base: &Config) -> Config {
+    let base = crate::remote_config::with_remote(base);
+    let Some(schedule) = session_schedule() else { return base };
+    SessionSchedule::config_for(schedule.active(Utc::now().time()), &base).unwrap_or(base)
+}
+
+/// Name of the session in force now, for annotating fills
+pub fn current_session() -> Option<String> {
+    session_schedule().map(|s| s.active(Utc::now().time()).name.clone())
+}
+
+pub(crate) fn experiment_running() -> bool {
+    let now = timestamp::unix_secs();
+    crate::experiment::load(crate::experiment::EXPERIMENT_FILE).is_some_and(|e| !e.is_over(now))
+}
+
+/// Hand the active session's tunables to the order worker and the ledger at each boundary.
+/// A running experiment owns the tunables; its revert hands back the session's values
+pub fn spawn_session_task(base: Config) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(schedule) = session_schedule() else { return };
+        let mut interval = tokio::time::interval(SESSION_POLL_INTERVAL);
+        let mut applied: Option<String> = None;
+        loop {
+            interval.tick().await;
+            if experiment_running() {
+                applied = None;
+                continue;
+            }
+            let session = schedule.active(Utc::now().time());
+            if applied.as_deref() == Some(session.name.as_str()) {
+                continue;
+            }
+            let Ok(cfg) = SessionSchedule::config_for(session, &crate::remote_config::with_remote(&base)) else { continue };
+            crate::experiment::queue_risk_config(cfg.risk_guard_config());
+            let ledger = strategy_ledger();
+            ledger.retag(&ledger.tag(), cfg.strategy_cap());
+            let overrides: Vec<String> = session.overrides.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            crate::console_println!(
+                "🕐 Session '{}' from {} UTC{}",
+                session.name, session.start.format("%H:%M"),
+                if overrides.is_empty() { " (base config)".to_string() } else { format!(": {}", overrides.join(", ")) }
+            );
+            applied = Some(session.name.clone());
+        }
+    })
+}
+
+// ============================================================================
+// Report
+// ============================================================================
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionTotals {
+    pub fills: usize,
+    pub volume_usd: f64,
+    /// Realized on sells made in the session, against the tag's average entry
+    pub realized_pnl: f64,
+}
+
+/// Every fill in a ledger file, in order
+pub fn load_fills(path: &str) -> Vec<StrategyFill> {
+    std::fs::read_to_string(path).unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+/// Replay ledger fills and total them by the session they were made in
+pub fn totals(fills: &[StrategyFill]) -> Vec<(String, SessionTotals)> {
+    let mut books: FxHashMap<&str, TagBook> = FxHashMap::default();
+    let mut out: FxHashMap<String, SessionTotals> = FxHashMap::default();
+    for fill in fills {
+        let book = books.entry(fill.tag.as_str()).or_default();
+        let before = book.realized_pnl;
+        book.apply(fill);
+        if fill.kind != FillKind::Trade {
+            continue;
+        }
+        let t = out.entry(fill.session.clone().unwrap_or_else(|| NO_SESSION.to_string())).or_default();
+        t.fills += 1;
+        t.volume_usd += fill.shares * fill.price;
+        t.realized_pnl += book.realized_pnl - before;
+    }
+    let mut out: Vec<_> = out.into_iter().collect();
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+/// Per-session table for `pm_bot sessions`
+pub fn render_report(totals: &[(String, SessionTotals)]) -> String {
+    let mut out = format!("{:<16} {:>6} {:>12} {:>12}\n", "SESSION", "FILLS", "VOLUME", "REALIZED");
+    for (session, t) in totals {
+        out.push_str(&format!("{:<16} {:>6} {:>12.2} {:>+12.2}\n", session, t.fills, t.volume_usd, t.realized_pnl));
+    }
+    out
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_SCHEDULE: OnceLock<Option<SessionSchedule>> = OnceLock::new();
+
+/// Set once at startup (None = no schedule)
+pub fn init_session_schedule(schedule: Option<SessionSchedule>) {
+    let _ = GLOBAL_SCHEDULE.set(schedule);
+}
+
+pub fn session_schedule() -> Option<&'static SessionSchedule> {
+    GLOBAL_SCHEDULE.get().and_then(Option::as_ref)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hhmm: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(hhmm, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_active_session() {
+        let vars = |var: &str| (var == "SESSION_US").then(|| "cb_min_depth_usd=400, STRATEGY_MAX_OPEN_USD=80".to_string());
+        let s = SessionSchedule::parse("us@13:30, asia@00:00,europe@07:00", vars).unwrap();
+        let names: Vec<_> = s.sessions().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["asia", "europe", "us"]);
+        assert_eq!(s.sessions()[2].overrides, vec![
+            ("CB_MIN_DEPTH_USD".to_string(), "400".to_string()),
+            ("STRATEGY_MAX_OPEN_USD".to_string(), "80".to_string()),
+        ]);
+        assert_eq!(s.active(at("06:59")).name, "asia");
+        assert_eq!(s.active(at("13:30")).name, "us");
+
+        // Day starting mid-session: the last session wraps past midnight
+        let late = SessionSchedule::parse("europe@07:00,us@13:30", |_| None).unwrap();
+        assert_eq!(late.active(at("03:00")).name, "us");
+
+        assert!(SessionSchedule::parse("asia@00:00,us@00:00", |_| None).is_err());
+        assert!(SessionSchedule::parse("asia", |_| None).is_err());
+        assert!(SessionSchedule::parse("asia@00:00", |_| Some("RR_MIN_RATIO=2".into())).is_err());
+    }
+
+    #[test]
+    fn test_totals_by_session() {
+        let fill = |is_buy: bool, shares: f64, price: f64, session: Option<&str>| StrategyFill {
+            ts: 0,
+            tag: "t".into(),
+            token_id: "tok".into(),
+            is_buy,
+            shares,
+            price,
+            kind: Default::default(),
+            session: session.map(String::from),
+        };
+        let totals = totals(&[
+            fill(true, 100.0, 0.40, Some("asia")),
+            fill(false, 50.0, 0.50, Some("us")),
+            fill(false, 50.0, 0.30, None),
+        ]);
+        assert_eq!(totals.len(), 3);
+        assert_eq!(totals[0].0, "-");
+        assert!((totals[0].1.realized_pnl + 5.0).abs() < 1e-9);
+        assert_eq!((totals[1].0.as_str(), totals[1].1.fills, totals[1].1.realized_pnl), ("asia", 1, 0.0));
+        assert!((totals[2].1.realized_pnl - 5.0).abs() < 1e-9);
+        assert!(render_report(&totals).contains("us                    1        25.00        +5.00"));
+    }
+}
@@ -4,10 +4,36 @@
 use anyhow::{Context, Result};
 use std::env;
 use std::path::Path;
+use std::sync::OnceLock;
 use std::time::Duration;
 use crate::risk_guard;
+use crate::retention;
+use crate::strategy;
 use crate::tennis_markets;
 use crate::soccer_markets;
+use crate::flatten;
+use crate::conflict;
+use crate::clustering;
+use crate::market_quality;
+use crate::compliance;
+use crate::taker_flow;
+use crate::live_interlock;
+use crate::size_ramp;
+use crate::latency_budget;
+use crate::cost_budget;
+use crate::healthcheck;
+use crate::session;
+use crate::feed_ipc;
+use crate::remediation;
+use crate::jitter;
+use crate::reward_risk;
+use crate::probe;
+use crate::archive;
+use crate::pnl_attribution;
+use crate::lockup;
+use crate::remote_config;
+use crate::quote_activity;
+use crate::signal_math;
 
 // ============================================================================
 // Blockchain Constants
@@ -42,7 +68,28 @@ pub const MONITORED_ADDRESSES: [&str; 3] = [
 // ============================================================================
 
 pub const CLOB_API_BASE: &str = "https://clob.polymarket.com";
+pub const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
+
+/// CLOB host, `CLOB_API_URL` when set (e.g. `pm_bot`'s mock exchange, see `mock_clob`)
+pub fn clob_api_base() -> &'static str {
+    static BASE: OnceLock<String> = OnceLock::new();
+    BASE.get_or_init(|| env_base("CLOB_API_URL", CLOB_API_BASE))
+}
+
+/// Gamma host, `GAMMA_API_URL` when set
+pub fn gamma_api_base() -> &'static str {
+    static BASE: OnceLock<String> = OnceLock::new();
+    BASE.get_or_init(|| env_base("GAMMA_API_URL", GAMMA_API_BASE))
+}
+
+fn env_base(key: &str, default: &str) -> String {
+    env::var(key).ok()
+        .map(|v| v.trim().trim_end_matches('/').to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
 pub const CSV_FILE: &str = "matches_optimized.csv";
+pub const CSV_HEADER: &str = "timestamp,block,clob_asset_id,usd_value,shares,price_per_share,direction,order_status,best_price,best_size,second_price,second_size,tx_hash,is_live";
 
 // Debug flag - set to true to print full API error messages (remove after debugging)
 pub const DEBUG_FULL_ERRORS: bool = true;
@@ -114,6 +161,8 @@ pub fn get_resubmit_max_buffer(whale_shares: f64) -> f64 {
 pub const BOOK_REQ_TIMEOUT: Duration = Duration::from_millis(2500);
 pub const WS_PING_TIMEOUT: Duration = Duration::from_secs(300);
 pub const WS_RECONNECT_DELAY: Duration = Duration::from_secs(3);
+/// Reconnect delay once the whale feed is marked degraded (50 drops within an hour)
+pub const WS_DEGRADED_RETRY: Duration = Duration::from_secs(30);
 
 // ============================================================================
 // Execution Tiers
@@ -187,10 +236,35 @@ pub struct Config {
     
     // WebSocket
     pub wss_url: String,
+    /// All configured WS providers (primary first), latency probing picks between them
+    pub wss_urls: Vec<String>,
     
     // Trading flags
     pub enable_trading: bool,
     pub mock_trading: bool,
+    /// Build and sign real orders but record them instead of posting (SHADOW_TRADING)
+    pub shadow_trading: bool,
+    
+    /// Widen/tighten the FAK buffer per token from its recent miss rate (ADAPTIVE_OFFSET)
+    pub adaptive_offset: bool,
+    /// Maker rebate in bps used to value resting orders in the execution report (MAKER_REBATE_BPS)
+    pub maker_rebate_bps: f64,
+    
+    // Strategy attribution
+    /// Tag this instance's fills are attributed to when several share a wallet (STRATEGY_TAG)
+    pub strategy_tag: String,
+    /// Cap on this tag's open cost basis in USD, 0 = uncapped (STRATEGY_MAX_OPEN_USD)
+    pub strategy_max_open_usd: f64,
+    
+    // UI
+    /// Render the terminal blotter instead of plain log lines (UI_MODE=tui or --tui)
+    pub tui: bool,
+    
+    // Retention
+    /// Rotate the trade CSV / shadow journal past this size (LOG_MAX_MB)
+    pub log_max_mb: u64,
+    /// Evict token metadata not refreshed for this long (METADATA_RETENTION_HOURS)
+    pub metadata_retention_hours: u64,
     
     // Circuit breaker
     pub cb_large_trade_shares: f64,
@@ -198,6 +272,130 @@ pub struct Config {
     pub cb_sequence_window_secs: u64,
     pub cb_min_depth_usd: f64,
     pub cb_trip_duration_secs: u64,
+    
+    // Market impact
+    /// Cap entries at this fraction of the visible ask depth at our limit, 0 = off (IMPACT_MAX_DEPTH_FRACTION)
+    pub impact_max_depth_fraction: f64,
+    /// Ask levels counted as visible depth (IMPACT_DEPTH_LEVELS)
+    pub impact_depth_levels: usize,
+    
+    // Signal-to-submit protection
+    /// Most an entry pays above the whale's price if the ask moved, 0 = off (SLIPPAGE_BUDGET)
+    pub slippage_budget: f64,
+    /// Drop whale signals that waited longer than this in the order queue, 0 = off (SIGNAL_MAX_AGE_MS)
+    pub signal_max_age_ms: u64,
+    /// Abandon entries not yet posted this long after the signal, 0 = off (SUBMIT_DEADLINE_MS)
+    pub submit_deadline_ms: u64,
+    /// Skip optional pre-trade book checks and the probe (LATENCY_MODE)
+    pub latency_mode: bool,
+    /// Median POST-to-response budget in the latency breakdown, 0 = unchecked (LATENCY_BUDGET_ACK_MS)
+    pub ack_budget_ms: u64,
+    /// Oldest held book on the book feed budget, 0 = unchecked (LATENCY_BUDGET_BOOK_AGE_MS)
+    pub book_age_budget_ms: u64,
+    
+    /// Seconds between order book snapshots of held tokens, 0 = off (DEPTH_SNAPSHOT_SECS)
+    pub depth_snapshot_secs: u64,
+    
+    /// Nightly flat schedule (FLATTEN_ENTRY_CUTOFF / FLATTEN_BY / FLATTEN_RESUME / FLATTEN_MAX_CONCESSION)
+    pub flatten: Option<flatten::FlattenPolicy>,
+    
+    // Reward/risk gate
+    /// Minimum estimated reward/risk for an entry, 0 = off (RR_MIN_RATIO)
+    pub rr_min_ratio: f64,
+    /// Take-profit target above entry the reward is measured to (RR_TAKE_PROFIT_PCT)
+    pub rr_take_profit_pct: f64,
+    
+    /// Probe size sent before a FAK entry, 0 = off (PROBE_SHARES)
+    pub probe_shares: f64,
+    
+    /// Daily journal archiving (ARCHIVE_DAILY / ARCHIVE_DIR / ARCHIVE_S3_*)
+    pub archive: Option<archive::ArchiveConfig>,
+    
+    /// Seconds between trade history polls for fills made outside the bot, 0 = off (TRADE_SYNC_SECS)
+    pub trade_sync_secs: u64,
+    
+    /// Evaluate the stop-loss on live book updates of held tokens (EXIT_BOOK_FEED)
+    pub exit_book_feed: bool,
+    
+    /// Entries into the opposite outcome of a held market (CONFLICT_MODE / CONFLICT_MODE_BY_SLUG)
+    pub conflict: Option<conflict::ConflictPolicy>,
+    
+    /// Entries per correlated market group within a window, None = off
+    /// (CLUSTER_MAX_ENTRIES / CLUSTER_WINDOW_SECS / CLUSTER_SLUG_SEGMENTS)
+    pub cluster: Option<clustering::ClusterLimit>,
+    
+    /// Share of equity (USDC + open cost) kept out of new entries, 0 = off (CASH_RESERVE_PCT)
+    pub cash_reserve_pct: f64,
+    
+    /// Entry + exit slippage allowed per share, 0 = off (COST_BUDGET)
+    pub cost_budget_per_share: f64,
+    /// Part of COST_BUDGET the entry may use (COST_BUDGET_ENTRY_SHARE)
+    pub cost_budget_entry_share: f64,
+    
+    /// Smallest entry in USD, on top of the exchange's $1 (MIN_ORDER_USD)
+    pub min_order_usd: f64,
+    /// Smallest entry in shares, on top of the exchange's 5 (MIN_ORDER_SHARES)
+    pub min_order_shares: f64,
+    /// Lift copies below the minimum to it instead of rejecting them (LIFT_TO_MINIMUM)
+    pub lift_to_minimum: bool,
+    
+    /// Entry size scaled by a random factor within ±this share, 0 = off (SIZE_JITTER_PCT)
+    pub size_jitter_pct: f64,
+    /// Entry limit moved up to this many ticks toward the passive side, 0 = off (PRICE_JITTER_TICKS)
+    pub price_jitter_ticks: u32,
+    
+    /// Rejections fixed automatically (AUTO_REMEDIATE / REMEDIATE_PAUSE_SECS)
+    pub remediation: remediation::RemediationPolicy,
+    
+    /// Health status file for container healthchecks, None = not written (HEALTH_FILE)
+    pub health_file: Option<String>,
+    /// Whale feed or stop-loss silent this long makes the bot unhealthy (HEALTH_MAX_SILENCE_SECS)
+    pub health_max_silence_secs: u64,
+    
+    /// Intraday sessions with their own tunables (SESSIONS / SESSION_<NAME>)
+    pub sessions: Option<session::SessionSchedule>,
+    
+    /// Run as one process, the feed only or the executor only (PROCESS_ROLE)
+    pub process_role: feed_ipc::ProcessRole,
+    /// Unix socket between feed and executor processes (FEED_SOCKET)
+    pub feed_socket: String,
+    
+    /// Seconds between resolution checks of held tokens (SETTLEMENT_CHECK_SECS)
+    pub settlement_check_secs: u64,
+    /// Expected seconds from a market's end to its payout, for the lockup forecast (SETTLEMENT_LAG_SECS)
+    pub settlement_lag_secs: u64,
+    
+    /// Minutes of raw feed messages kept in memory for `pm_bot replay-dump`, 0 = off (REPLAY_BUFFER_MINS)
+    pub replay_buffer_mins: u64,
+    
+    /// Store of fleet-wide overrides (REMOTE_CONFIG_URL / REMOTE_CONFIG_TOKEN / REMOTE_CONFIG_POLL_SECS)
+    pub remote_config: Option<remote_config::RemoteConfigSource>,
+    
+    /// Book update surges at whale entries: journal, boost or suppress, None = off
+    /// (QUOTE_SURGE_MODE / QUOTE_SURGE_RATIO / QUOTE_SURGE_BOOST)
+    pub quote_surge: Option<quote_activity::SurgePolicy>,
+    
+    /// Loss the daily Monte Carlo report gives the chance of reaching, None = not reported (RISK_LOSS_LIMIT_USD)
+    pub risk_loss_limit_usd: Option<f64>,
+    
+    /// Skip market families with chronically wide spreads, thin books or no takers, None = off (QUALITY_*)
+    pub market_quality: Option<market_quality::QualityPolicy>,
+    
+    /// Built-in pre-trade compliance rules, None = none set (COMPLIANCE_*)
+    pub compliance: Option<compliance::ComplianceRules>,
+    
+    /// Cap on the premium still at risk across open positions, None = uncapped (MAX_CAPITAL_AT_RISK_USD)
+    pub max_capital_at_risk_usd: Option<f64>,
+    
+    /// Taker buy/sell imbalance from trade prints, journaled and optionally gating entries, None = off
+    /// (TAKER_FLOW_MODE / TAKER_FLOW_WINDOW_SECS / TAKER_FLOW_THRESHOLD / TAKER_FLOW_MIN_USD)
+    pub taker_flow: Option<taker_flow::FlowPolicy>,
+    
+    /// Cap on the first live entries, None = off (LIVE_WARMUP_TRADES / LIVE_WARMUP_MAX_USD)
+    pub live_warmup: Option<live_interlock::WarmupPolicy>,
+    
+    /// Live entry size ramp, None = full size from the start (SIZE_RAMP_*)
+    pub size_ramp: Option<size_ramp::RampPolicy>,
 }
 
 impl Config {
@@ -261,8 +459,10 @@ impl Config {
             anyhow::bail!("FUNDER_ADDRESS contains invalid characters. Must be hexadecimal (0-9, a-f, A-F).");
         }
         
-        // WebSocket URL from either provider
-        let wss_url = if let Ok(key) = env::var("ALCHEMY_API_KEY") {
+        // WebSocket URL from either provider, or given whole (WSS_URL, e.g. the mock exchange)
+        let wss_url = if let Some(url) = env::var("WSS_URL").ok().filter(|u| !u.trim().is_empty()) {
+            url.trim().to_string()
+        } else if let Ok(key) = env::var("ALCHEMY_API_KEY") {
             let key = key.trim();
             if key.is_empty() || key == "your_alchemy_api_key_here" {
                 anyhow::bail!(
@@ -302,6 +502,16 @@ impl Config {
             );
         };
         
+        // Chainstack is a second candidate when both providers are configured
+        let mut wss_urls = vec![wss_url.clone()];
+        if let Ok(key) = env::var("CHAINSTACK_API_KEY") {
+            let key = key.trim();
+            let url = format!("wss://polygon-mainnet.core.chainstack.com/{}", key);
+            if !key.is_empty() && key != "your_chainstack_api_key_here" && !wss_urls.contains(&url) {
+                wss_urls.push(url);
+            }
+        }
+        
         // Validate TARGET_WHALE_ADDRESS (used by TARGET_TOPIC_HEX lazy static)
         let target_whale = env::var("TARGET_WHALE_ADDRESS")
             .context("TARGET_WHALE_ADDRESS env var is required. Add it to your .env file.\n\
@@ -337,20 +547,312 @@ impl Config {
             .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
             .unwrap_or(false);
         
-        Ok(Self {
+        let shadow_trading = env::var("SHADOW_TRADING")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        
+        let adaptive_offset = env::var("ADAPTIVE_OFFSET")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        
+        let latency_mode = env::var("LATENCY_MODE")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        
+        let exit_book_feed = env::var("EXIT_BOOK_FEED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        
+        let lift_to_minimum = env::var("LIFT_TO_MINIMUM")
+            .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+            .unwrap_or(true);
+        
+        let strategy_tag = env::var("STRATEGY_TAG")
+            .map(|v| v.trim().to_string())
+            .unwrap_or_else(|_| strategy::DEFAULT_STRATEGY_TAG.to_string());
+        if !strategy::is_valid_tag(&strategy_tag) {
+            anyhow::bail!("STRATEGY_TAG '{}' is invalid. Use up to 32 letters, digits, '-' or '_'.", strategy_tag);
+        }
+        
+        let archive_daily = env::var("ARCHIVE_DAILY")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let archive = if archive_daily {
+            let s3 = match env::var("ARCHIVE_S3_BUCKET") {
+                Ok(bucket) if !bucket.trim().is_empty() => {
+                    let region = env::var("ARCHIVE_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+                    Some(archive::S3Target {
+                        endpoint: env::var("ARCHIVE_S3_ENDPOINT").unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", region)),
+                        bucket: bucket.trim().to_string(),
+                        region,
+                        access_key: env::var("ARCHIVE_S3_ACCESS_KEY").context("ARCHIVE_S3_BUCKET is set but ARCHIVE_S3_ACCESS_KEY is missing")?,
+                        secret_key: env::var("ARCHIVE_S3_SECRET_KEY").context("ARCHIVE_S3_BUCKET is set but ARCHIVE_S3_SECRET_KEY is missing")?,
+                    })
+                }
+                _ => None,
+            };
+            Some(archive::ArchiveConfig {
+                dir: env::var("ARCHIVE_DIR").unwrap_or_else(|_| "archive".to_string()).into(),
+                s3,
+            })
+        } else {
+            None
+        };
+        
+        let conflict = Some(conflict::ConflictPolicy::parse(
+            &env::var("CONFLICT_MODE").unwrap_or_else(|_| "hedge".to_string()),
+            &env::var("CONFLICT_MODE_BY_SLUG").unwrap_or_default(),
+        )?);
+        
+        let cluster = match env_parse("CLUSTER_MAX_ENTRIES", 0usize) {
+            0 => None,
+            max_entries => Some(clustering::ClusterLimit {
+                max_entries,
+                window_secs: env_parse("CLUSTER_WINDOW_SECS", 120).max(1),
+                slug_segments: env_parse("CLUSTER_SLUG_SEGMENTS", 1usize).max(1),
+            }),
+        };
+        
+        let remote_config = match env::var("REMOTE_CONFIG_URL") {
+            Ok(url) if !url.trim().is_empty() => Some(remote_config::RemoteConfigSource {
+                store: remote_config::RemoteStore::parse(&url)?,
+                token: env::var("REMOTE_CONFIG_TOKEN").ok().filter(|t| !t.trim().is_empty()),
+                poll: Duration::from_secs(env_parse("REMOTE_CONFIG_POLL_SECS", 30).max(1)),
+            }),
+            _ => None,
+        };
+        
+        let quote_surge = quote_activity::SurgePolicy::parse(
+            &env::var("QUOTE_SURGE_MODE").unwrap_or_default(),
+            env_parse("QUOTE_SURGE_RATIO", 3.0),
+            env_parse("QUOTE_SURGE_BOOST", 1.5),
+        )?;
+        
+        let size_ramp = Some(size_ramp::RampPolicy {
+            start: env_parse("SIZE_RAMP_START", 0.0_f64).min(1.0),
+            step: env_parse("SIZE_RAMP_STEP", 0.25_f64).max(0.01),
+            trades: env_parse("SIZE_RAMP_TRADES", 20_usize).max(1),
+            min_win_rate: env_parse("SIZE_RAMP_MIN_WIN_RATE", 0.5),
+            max_slippage: env_parse("SIZE_RAMP_MAX_SLIPPAGE", 0.01),
+            drawdown_usd: Some(env_parse("SIZE_RAMP_DRAWDOWN_USD", 0.0)).filter(|v: &f64| *v > 0.0),
+        }).filter(|r| r.start > 0.0 && r.start < 1.0);
+        
+        let taker_flow = taker_flow::FlowPolicy::parse(
+            &env::var("TAKER_FLOW_MODE").unwrap_or_default(),
+            env_parse("TAKER_FLOW_WINDOW_SECS", 60),
+            env_parse("TAKER_FLOW_THRESHOLD", 0.5),
+            env_parse("TAKER_FLOW_MIN_USD", 100.0),
+        )?;
+        
+        let market_quality = Some(market_quality::QualityPolicy {
+            max_spread: Some(env_parse("QUALITY_MAX_SPREAD", 0.0)).filter(|v: &f64| *v > 0.0),
+            min_depth_usd: Some(env_parse("QUALITY_MIN_DEPTH_USD", 0.0)).filter(|v: &f64| *v > 0.0),
+            min_trades_per_hour: Some(env_parse("QUALITY_MIN_TRADES_PER_HOUR", 0.0)).filter(|v: &f64| *v > 0.0),
+            window_secs: env_parse("QUALITY_WINDOW_HOURS", 24u64).max(1) * 3600,
+            min_samples: env_parse("QUALITY_MIN_SAMPLES", 12usize).max(1),
+            sample_secs: env_parse("QUALITY_SAMPLE_SECS", 300u64).max(30),
+            slug_segments: env_parse("QUALITY_SLUG_SEGMENTS", 2usize).max(1),
+            allow: market_quality::parse_families(&env::var("QUALITY_ALLOW").unwrap_or_default()),
+            block: market_quality::parse_families(&env::var("QUALITY_BLOCK").unwrap_or_default()),
+        }).filter(market_quality::QualityPolicy::is_active);
+        
+        let compliance = Some(compliance::ComplianceRules {
+            block_slugs: compliance::parse_list(&env::var("COMPLIANCE_BLOCK_SLUGS").unwrap_or_default()),
+            block_keywords: compliance::parse_list(&env::var("COMPLIANCE_BLOCK_KEYWORDS").unwrap_or_default().to_lowercase()),
+            max_order_usd: Some(env_parse("COMPLIANCE_MAX_ORDER_USD", 0.0)).filter(|v: &f64| *v > 0.0),
+            max_order_shares: Some(env_parse("COMPLIANCE_MAX_ORDER_SHARES", 0.0)).filter(|v: &f64| *v > 0.0),
+            block_unknown: env::var("COMPLIANCE_BLOCK_UNKNOWN").map(|v| v.eq_ignore_ascii_case("true") || v == "1").unwrap_or(false),
+        }).filter(compliance::ComplianceRules::is_active);
+        
+        // Both cutoff and deadline are needed to enable the schedule
+        let flatten = match (env::var("FLATTEN_ENTRY_CUTOFF"), env::var("FLATTEN_BY")) {
+            (Ok(cutoff), Ok(by)) if !cutoff.trim().is_empty() && !by.trim().is_empty() => Some(flatten::FlattenPolicy::parse(
+                &cutoff,
+                &by,
+                &env::var("FLATTEN_RESUME").unwrap_or_else(|_| "06:00".to_string()),
+                env_parse("FLATTEN_MAX_CONCESSION", 0.05),
+            )?),
+            _ => None,
+        };
+        
+        let sessions = match env::var("SESSIONS") {
+            Ok(spec) if !spec.trim().is_empty() => Some(session::SessionSchedule::parse(&spec, |var| env::var(var).ok())?),
+            _ => None,
+        };
+        
+        let remediation = remediation::RemediationPolicy::parse(
+            &env::var("AUTO_REMEDIATE").unwrap_or_default(),
+            env_parse("REMEDIATE_PAUSE_SECS", 900),
+        )?;
+        
+        let process_role = match env::var("PROCESS_ROLE") {
+            Ok(role) => feed_ipc::ProcessRole::parse(&role)
+                .ok_or_else(|| anyhow::anyhow!("PROCESS_ROLE must be all, feed or executor (got '{}')", role))?,
+            Err(_) => feed_ipc::ProcessRole::All,
+        };
+        if cfg!(not(unix)) && process_role != feed_ipc::ProcessRole::All {
+            anyhow::bail!("PROCESS_ROLE={} needs unix sockets", process_role.as_str());
+        }
+        
+        let tui = env::var("UI_MODE").map(|v| v.eq_ignore_ascii_case("tui")).unwrap_or(false)
+            || env::args().any(|a| a == "--tui");
+        
+        let cfg = Self {
             private_key,
             funder_address,
             wss_url,
+            wss_urls,
             enable_trading,
             mock_trading,
+            shadow_trading,
+            adaptive_offset,
+            maker_rebate_bps: env_parse("MAKER_REBATE_BPS", 0.0),
+            strategy_tag,
+            strategy_max_open_usd: env_parse("STRATEGY_MAX_OPEN_USD", 0.0),
+            tui,
+            log_max_mb: env_parse("LOG_MAX_MB", 100),
+            metadata_retention_hours: env_parse("METADATA_RETENTION_HOURS", 7 * 24),
             cb_large_trade_shares: env_parse("CB_LARGE_TRADE_SHARES", 1500.0),
             cb_consecutive_trigger: env_parse("CB_CONSECUTIVE_TRIGGER", 2u8),
             cb_sequence_window_secs: env_parse("CB_SEQUENCE_WINDOW_SECS", 30),
             cb_min_depth_usd: env_parse("CB_MIN_DEPTH_USD", 200.0),
             cb_trip_duration_secs: env_parse("CB_TRIP_DURATION_SECS", 120),
-        })
+            impact_max_depth_fraction: env_parse("IMPACT_MAX_DEPTH_FRACTION", 0.0),
+            impact_depth_levels: env_parse("IMPACT_DEPTH_LEVELS", 5),
+            slippage_budget: env_parse("SLIPPAGE_BUDGET", 0.0),
+            signal_max_age_ms: env_parse("SIGNAL_MAX_AGE_MS", 0),
+            submit_deadline_ms: env_parse("SUBMIT_DEADLINE_MS", 0),
+            latency_mode,
+            ack_budget_ms: env_parse("LATENCY_BUDGET_ACK_MS", 0),
+            book_age_budget_ms: env_parse("LATENCY_BUDGET_BOOK_AGE_MS", 0),
+            depth_snapshot_secs: env_parse("DEPTH_SNAPSHOT_SECS", 60),
+            flatten,
+            rr_min_ratio: env_parse("RR_MIN_RATIO", 0.0),
+            rr_take_profit_pct: env_parse("RR_TAKE_PROFIT_PCT", 0.10),
+            probe_shares: env_parse("PROBE_SHARES", 0.0),
+            archive,
+            trade_sync_secs: env_parse("TRADE_SYNC_SECS", 0),
+            exit_book_feed,
+            conflict,
+            cluster,
+            cash_reserve_pct: env_parse("CASH_RESERVE_PCT", 0.0),
+            cost_budget_per_share: env_parse("COST_BUDGET", 0.0),
+            cost_budget_entry_share: env_parse("COST_BUDGET_ENTRY_SHARE", 0.5),
+            min_order_usd: env_parse("MIN_ORDER_USD", 0.0),
+            min_order_shares: env_parse("MIN_ORDER_SHARES", 0.0),
+            lift_to_minimum,
+            size_jitter_pct: env_parse("SIZE_JITTER_PCT", 0.0),
+            price_jitter_ticks: env_parse("PRICE_JITTER_TICKS", 0),
+            remediation,
+            health_file: env::var("HEALTH_FILE").ok().filter(|f| !f.trim().is_empty()),
+            health_max_silence_secs: env_parse("HEALTH_MAX_SILENCE_SECS", 600),
+            sessions,
+            process_role,
+            feed_socket: env::var("FEED_SOCKET").ok().filter(|p| !p.trim().is_empty())
+                .unwrap_or_else(|| "pm_bot_feed.sock".to_string()),
+            settlement_check_secs: env_parse("SETTLEMENT_CHECK_SECS", pnl_attribution::SETTLEMENT_CHECK_INTERVAL.as_secs()).max(1),
+            settlement_lag_secs: env_parse("SETTLEMENT_LAG_SECS", lockup::DEFAULT_SETTLEMENT_LAG.as_secs()),
+            replay_buffer_mins: env_parse("REPLAY_BUFFER_MINS", 0),
+            remote_config,
+            quote_surge,
+            market_quality,
+            compliance,
+            risk_loss_limit_usd: Some(env_parse("RISK_LOSS_LIMIT_USD", 0.0)).filter(|l: &f64| *l > 0.0),
+            taker_flow,
+            size_ramp,
+            live_warmup: Some(live_interlock::WarmupPolicy {
+                trades: env_parse("LIVE_WARMUP_TRADES", 10),
+                max_usd: env_parse("LIVE_WARMUP_MAX_USD", 5.0),
+            }).filter(|w| w.trades > 0 && w.max_usd > 0.0),
+            max_capital_at_risk_usd: Some(env_parse("MAX_CAPITAL_AT_RISK_USD", 0.0)).filter(|l: &f64| *l > 0.0),
+        };
+        if let Some(schedule) = &cfg.sessions {
+            schedule.validate(&cfg)?;
+        }
+        Ok(cfg)
+    }
+    
+    /// Convert to RetentionConfig for the periodic retention sweep
+    pub fn retention_config(&self) -> retention::RetentionConfig {
+        retention::RetentionConfig {
+            log_max_bytes: self.log_max_mb * 1024 * 1024,
+            metadata_max_age: Duration::from_secs(self.metadata_retention_hours * 60 * 60),
+        }
+    }
+    
+    /// Per-tag exposure cap (None when STRATEGY_MAX_OPEN_USD is unset or 0)
+    pub fn strategy_cap(&self) -> Option<f64> {
+        (self.strategy_max_open_usd > 0.0).then_some(self.strategy_max_open_usd)
     }
     
+    /// Reward/risk gate (None when RR_MIN_RATIO is unset or 0)
+    pub fn rr_gate(&self) -> Option<reward_risk::RrGate> {
+        (self.rr_min_ratio > 0.0).then_some(reward_risk::RrGate {
+            min_ratio: self.rr_min_ratio,
+            take_profit_pct: self.rr_take_profit_pct.max(0.0),
+        })
+    }
+
+    /// Soft-entry probing (None when PROBE_SHARES is unset or 0)
+    pub fn probe_policy(&self) -> Option<probe::ProbePolicy> {
+        (self.probe_shares > 0.0).then_some(probe::ProbePolicy { shares: self.probe_shares })
+    }
+
+    /// Cash reserve fraction (None when CASH_RESERVE_PCT is unset or 0)
+    pub fn cash_reserve(&self) -> Option<f64> {
+        (self.cash_reserve_pct > 0.0).then_some(self.cash_reserve_pct.min(1.0))
+    }
+
+    /// Round-trip cost budget (None when COST_BUDGET is unset or 0)
+    pub fn cost_budget(&self) -> Option<cost_budget::CostBudget> {
+        (self.cost_budget_per_share > 0.0).then_some(cost_budget::CostBudget {
+            per_share: self.cost_budget_per_share,
+            entry_share: self.cost_budget_entry_share.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Health file and watchdog policy
+    pub fn health_policy(&self) -> healthcheck::HealthPolicy {
+        healthcheck::HealthPolicy {
+            file: self.health_file.clone(),
+            max_silence: Duration::from_secs(self.health_max_silence_secs.max(1)),
+        }
+    }
+
+    /// Per-stage budgets of the latency breakdown (0 = unchecked)
+    pub fn latency_budget(&self) -> latency_budget::LatencyBudget {
+        let budget = |ms: u64| (ms > 0).then(|| Duration::from_millis(ms));
+        latency_budget::LatencyBudget {
+            signal_to_submit: budget(self.submit_deadline_ms),
+            submit_to_ack: budget(self.ack_budget_ms),
+            book_age: budget(self.book_age_budget_ms),
+        }
+    }
+
+    /// Override one tunable by its env var name (used by time-boxed experiments)
+    pub fn apply_override(&mut self, key: &str, value: &str) -> Result<()> {
+        fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T> {
+            value.trim().parse().map_err(|_| anyhow::anyhow!("{}={} is not a valid value", key, value))
+        }
+        match key {
+            "CB_LARGE_TRADE_SHARES" => self.cb_large_trade_shares = parse(key, value)?,
+            "CB_CONSECUTIVE_TRIGGER" => self.cb_consecutive_trigger = parse(key, value)?,
+            "CB_SEQUENCE_WINDOW_SECS" => self.cb_sequence_window_secs = parse(key, value)?,
+            "CB_MIN_DEPTH_USD" => self.cb_min_depth_usd = parse(key, value)?,
+            "CB_TRIP_DURATION_SECS" => self.cb_trip_duration_secs = parse(key, value)?,
+            "IMPACT_MAX_DEPTH_FRACTION" => self.impact_max_depth_fraction = parse(key, value)?,
+            "IMPACT_DEPTH_LEVELS" => self.impact_depth_levels = parse(key, value)?,
+            "SLIPPAGE_BUDGET" => self.slippage_budget = parse(key, value)?,
+            "MIN_ORDER_USD" => self.min_order_usd = parse(key, value)?,
+            "MIN_ORDER_SHARES" => self.min_order_shares = parse(key, value)?,
+            "STRATEGY_MAX_OPEN_USD" => self.strategy_max_open_usd = parse(key, value)?,
+            "RR_MIN_RATIO" => self.rr_min_ratio = parse(key, value)?,
+            "RR_TAKE_PROFIT_PCT" => self.rr_take_profit_pct = parse(key, value)?,
+            _ => anyhow::bail!("{} cannot be overridden by an experiment", key),
+        }
+        Ok(())
+    }
+
     /// Convert to RiskGuardConfig for safety checks
     pub fn risk_guard_config(&self) -> risk_guard::RiskGuardConfig {
         risk_guard::RiskGuardConfig {
@@ -359,6 +861,21 @@ impl Config {
             sequence_window: Duration::from_secs(self.cb_sequence_window_secs),
             min_depth_beyond_usd: self.cb_min_depth_usd,
             trip_duration: Duration::from_secs(self.cb_trip_duration_secs),
+            max_depth_fraction: self.impact_max_depth_fraction.clamp(0.0, 1.0),
+            depth_levels: self.impact_depth_levels.max(1),
+            slippage_budget: self.slippage_budget.clamp(0.0, 0.5),
+            max_signal_age: Duration::from_millis(self.signal_max_age_ms),
+            submit_deadline: Duration::from_millis(self.submit_deadline_ms),
+            latency_mode: self.latency_mode,
+            order_minimums: signal_math::OrderMinimums {
+                min_usd: self.min_order_usd.max(0.0),
+                min_shares: self.min_order_shares.max(0.0),
+                lift: self.lift_to_minimum,
+            },
+            jitter: jitter::Jitter {
+                size_pct: self.size_jitter_pct.clamp(0.0, 0.5),
+                price_ticks: self.price_jitter_ticks,
+            },
         }
     }
 }
@@ -0,0 +1,83 @@
+//! Shadow live mode
+//! Runs the full live order path with real credentials (sizing, risk checks, signing, L2 auth
+//! headers) but stops right before the POST and records exactly what would have been sent
+
+use anyhow::Result;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/// Where would-be orders are appended (one JSON object per line)
+pub const SHADOW_ORDERS_FILE: &str = "shadow_orders.jsonl";
+
+/// Headers whose values are long-lived secrets and must not land on disk
+const SECRET_HEADERS: [&str; 2] = ["POLY_API_KEY", "POLY_PASSPHRASE"];
+
+#[derive(Debug, Serialize)]
+pub struct ShadowOrder {
+    /// Unix milliseconds when the order was built
+    pub ts_ms: u128,
+    /// Strategy tag of the instance that built the order
+    pub tag: String,
+    pub method: &'static str,
+    pub url: String,
+    /// Request headers with secrets redacted
+    pub headers: Vec<(String, String)>,
+    /// Exact request body (parsed so the journal stays readable)
+    pub body: Value,
+}
+
+impl ShadowOrder {
+    pub fn new(url: &str, headers: &HeaderMap, body: &str) -> Self {
+        let headers = headers.iter()
+            .map(|(name, value)| {
+                let name = name.as_str().to_ascii_uppercase();
+                let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                    "<redacted>".to_string()
+                } else {
+                    value.to_str().unwrap_or("<binary>").to_string()
+                };
+                (name, value)
+            })
+            .collect();
+        Self {
+            ts_ms: u128::from(crate::timestamp::unix_millis()),
+            tag: crate::strategy::strategy_ledger().tag(),
+            method: "POST",
+            url: url.to_string(),
+            headers,
+            body: serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.to_string())),
+        }
+    }
+}
+
+/// Append a would-be order to the shadow journal
+pub fn record_order(url: &str, headers: &HeaderMap, body: &str) -> Result<()> {
+    let line = serde_json::to_string(&ShadowOrder::new(url, headers, body))?;
+    let mut f = OpenOptions::new().append(true).create(true).open(SHADOW_ORDERS_FILE)?;
+    writeln!(f, "{}", line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_secrets_redacted_and_body_kept() {
+        let mut headers = HeaderMap::new();
+        headers.insert("POLY_ADDRESS", HeaderValue::from_static("0xabc"));
+        headers.insert("POLY_API_KEY", HeaderValue::from_static("key-123"));
+        headers.insert("POLY_PASSPHRASE", HeaderValue::from_static("pass"));
+
+        let order = ShadowOrder::new("https://clob.polymarket.com/order", &headers, r#"{"orderType":"FAK"}"#);
+        let get = |k: &str| order.headers.iter().find(|(n, _)| n == k).map(|(_, v)| v.as_str());
+        assert_eq!(get("POLY_ADDRESS"), Some("0xabc"));
+        assert_eq!(get("POLY_API_KEY"), Some("<redacted>"));
+        assert_eq!(get("POLY_PASSPHRASE"), Some("<redacted>"));
+        assert_eq!(order.body["orderType"], "FAK");
+    }
+}
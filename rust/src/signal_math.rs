@@ -0,0 +1,204 @@
+//! Pure copy-trade formulas
+//! Limit price, exchange-minimum floor, probabilistic sizing and the impact cap, free of I/O,
+//! globals and randomness (the sizing roll is passed in) so pm_bot, the engine and tests all
+//! share one definition
+
+use crate::models::SizeType;
+use crate::settings::{MIN_CASH_VALUE, MIN_SHARE_COUNT, SCALING_RATIO, USE_PROBABILISTIC_SIZING};
+
+/// Whale price moved `buffer` against us, clamped to the valid range (0.01 - 0.99)
+#[inline]
+pub fn limit_price(whale_price: f64, buffer: f64, is_buy: bool) -> f64 {
+    if is_buy {
+        (whale_price + buffer).min(0.99)
+    } else {
+        (whale_price - buffer).max(0.01)
+    }
+}
+
+/// Smallest order the exchange accepts at `price`, in shares
+#[inline]
+pub fn size_floor(price: f64) -> f64 {
+    (MIN_CASH_VALUE / price.max(0.0001)).max(MIN_SHARE_COUNT)
+}
+
+/// Round shares down to the 0.01 the exchange accepts
+#[inline]
+pub fn floor_cents(shares: f64) -> f64 {
+    (shares * 100.0).floor() / 100.0
+}
+
+/// Most shares (in 0.01 steps) costing at most `max_usd` at `price` for a whole number of
+/// cents, so the signed USDC amount is not rounded away from the limit price
+pub fn floor_cents_for_usd(max_usd: f64, price: f64) -> f64 {
+    let price_units = (price * 10_000.0).round() as i64;
+    if price_units <= 0 {
+        return 0.0;
+    }
+    // shares_cents x price_units is the cost in 1e-6 USD
+    let mut shares_cents = (max_usd * 1_000_000.0 / price_units as f64 + 1e-6).floor() as i64;
+    while shares_cents > 0 && (shares_cents * price_units) % 10_000 != 0 {
+        shares_cents -= 1;
+    }
+    shares_cents as f64 / 100.0
+}
+
+/// Order minimums on top of the exchange's (MIN_ORDER_USD / MIN_ORDER_SHARES / LIFT_TO_MINIMUM)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderMinimums {
+    /// Smallest order value in USD; the exchange's MIN_CASH_VALUE applies regardless
+    pub min_usd: f64,
+    pub min_shares: f64,
+    /// Copies below the minimum are lifted to it (probabilistically) instead of rejected
+    pub lift: bool,
+}
+
+impl OrderMinimums {
+    /// Exchange minimums only, small copies lifted
+    pub const EXCHANGE: Self = Self { min_usd: 0.0, min_shares: 0.0, lift: true };
+
+    /// Smallest acceptable order at `price`, in shares
+    pub fn floor(&self, price: f64) -> f64 {
+        size_floor(price).max(self.min_usd / price.max(0.0001)).max(self.min_shares)
+    }
+
+    /// Err(reason) when `shares` at `price` is below a minimum
+    pub fn check(&self, shares: f64, price: f64) -> Result<(), String> {
+        if shares < self.min_shares.max(MIN_SHARE_COUNT) - 1e-9 {
+            return Err(format!("{:.2} < {:.2} shares", shares, self.min_shares.max(MIN_SHARE_COUNT)));
+        }
+        let (usd, min_usd) = (shares * price, self.min_usd.max(MIN_CASH_VALUE));
+        if usd < min_usd - 1e-9 {
+            return Err(format!("${:.2} < ${:.2}", usd, min_usd));
+        }
+        Ok(())
+    }
+}
+
+impl Default for OrderMinimums {
+    fn default() -> Self {
+        Self::EXCHANGE
+    }
+}
+
+/// SCALING_RATIO of the whale, lifted to the order minimum. Below the minimum the copy is
+/// placed at the minimum with probability target/minimum (when probabilistic sizing is on), so
+/// the expected size still tracks the whale. Without `mins.lift` the copy keeps its scaled size
+/// and `OrderMinimums::check` rejects it. `roll` is uniform in [0, 1)
+pub fn safe_size(whale_shares: f64, price: f64, size_multiplier: f64, roll: f64, mins: &OrderMinimums) -> (f64, SizeType) {
+    let target_scaled = whale_shares * SCALING_RATIO * size_multiplier;
+    let required_floor = mins.floor(price);
+
+    if target_scaled >= required_floor || !mins.lift {
+        return (target_scaled, SizeType::Scaled);
+    }
+    if !USE_PROBABILISTIC_SIZING {
+        return (required_floor, SizeType::Scaled);
+    }
+
+    let probability = target_scaled / required_floor;
+    let pct = (probability * 100.0) as u8;
+    if roll < probability {
+        (required_floor, SizeType::ProbHit(pct))
+    } else {
+        (0.0, SizeType::ProbSkip(pct))
+    }
+}
+
+/// Entry size after the market impact cap: at most `fraction` of the visible depth
+#[inline]
+pub fn impact_capped(shares: f64, visible_depth: f64, fraction: f64) -> f64 {
+    shares.min(visible_depth.max(0.0) * fraction.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn limit_price_stays_in_range(whale in 0.01f64..0.99, buffer in 0.0f64..0.2, is_buy: bool) {
+            let p = limit_price(whale, buffer, is_buy);
+            prop_assert!((0.01..=0.99).contains(&p));
+            // Never better than the whale's own price
+            let ok = if is_buy { p >= whale.min(0.99) } else { p <= whale.max(0.01) };
+            prop_assert!(ok);
+        }
+
+        #[test]
+        fn limit_price_monotonic_in_buffer(whale in 0.01f64..0.99, a in 0.0f64..0.2, b in 0.0f64..0.2) {
+            let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+            prop_assert!(limit_price(whale, lo, true) <= limit_price(whale, hi, true));
+            prop_assert!(limit_price(whale, lo, false) >= limit_price(whale, hi, false));
+        }
+
+        #[test]
+        fn safe_size_is_zero_or_tradable(whale in 0.0f64..100_000.0, price in 0.01f64..0.99, mult in 0.5f64..2.0, roll in 0.0f64..1.0) {
+            let (shares, kind) = safe_size(whale, price, mult, roll, &OrderMinimums::EXCHANGE);
+            match kind {
+                SizeType::ProbSkip(_) => prop_assert_eq!(shares, 0.0),
+                _ => prop_assert!(shares >= size_floor(price) - 1e-9),
+            }
+            // Never more than the scaled whale, unless lifted to exactly the minimum
+            prop_assert!(shares <= (whale * SCALING_RATIO * mult).max(size_floor(price)) + 1e-9);
+        }
+
+        #[test]
+        fn safe_size_monotonic_in_whale(a in 0.0f64..100_000.0, b in 0.0f64..100_000.0, price in 0.01f64..0.99, roll in 0.0f64..1.0) {
+            let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+            let mins = OrderMinimums::EXCHANGE;
+            prop_assert!(safe_size(lo, price, 1.0, roll, &mins).0 <= safe_size(hi, price, 1.0, roll, &mins).0);
+        }
+
+        #[test]
+        fn impact_cap_never_exceeded(shares in 0.0f64..10_000.0, visible in 0.0f64..10_000.0, fraction in 0.0f64..1.0) {
+            let capped = impact_capped(shares, visible, fraction);
+            prop_assert!(capped <= shares && capped <= visible * fraction + 1e-9);
+        }
+
+        #[test]
+        fn floor_cents_rounds_down(x in 0.0f64..100_000.0) {
+            let f = floor_cents(x);
+            prop_assert!(f <= x && x - f < 0.01 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_probabilistic_sizing_below_minimum() {
+        // 10 whale shares × 0.02 = 0.2 shares vs a floor of 1.01 / 0.5 = 2.02
+        let mins = OrderMinimums::EXCHANGE;
+        let (shares, kind) = safe_size(10.0, 0.5, 1.0, 0.0, &mins);
+        assert_eq!(kind, SizeType::ProbHit(9));
+        assert!((shares - 2.02).abs() < 1e-9);
+        assert!(mins.check(shares, 0.5).is_ok());
+        assert_eq!(safe_size(10.0, 0.5, 1.0, 0.5, &mins), (0.0, SizeType::ProbSkip(9)));
+        assert_eq!(safe_size(5000.0, 0.5, 1.0, 0.99, &mins), (100.0, SizeType::Scaled));
+    }
+
+    #[test]
+    fn test_minimums_reject_instead_of_lifting() {
+        let mins = OrderMinimums { min_usd: 5.0, min_shares: 5.0, lift: false };
+        // 0.2 shares stays 0.2 and is rejected rather than inflated
+        let (shares, kind) = safe_size(10.0, 0.5, 1.0, 0.0, &mins);
+        assert_eq!((shares, kind), (10.0 * SCALING_RATIO, SizeType::Scaled));
+        assert_eq!(mins.check(shares, 0.5), Err("0.20 < 5.00 shares".to_string()));
+        // Enough shares at a low price can still be under the notional minimum
+        assert_eq!(mins.check(20.0, 0.05), Err("$1.00 < $5.00".to_string()));
+        assert!(mins.check(10.0, 0.5).is_ok());
+
+        // Lifting goes to the configured minimum, not just the exchange's
+        let lifted = OrderMinimums { lift: true, ..mins };
+        assert!((safe_size(10.0, 0.5, 1.0, 0.0, &lifted).0 - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_floor_cents_for_usd_costs_whole_cents() {
+        assert_eq!(floor_cents_for_usd(5.0, 0.50), 10.0);
+        // 9.80 x 0.51 = $4.998; the largest whole-cent cost at 0.51 is 9 shares
+        assert_eq!(floor_cents_for_usd(5.0, 0.51), 9.0);
+        assert_eq!(floor_cents_for_usd(5.0, 0.55), 9.0);
+        assert_eq!(floor_cents_for_usd(5.0, 0.25), 20.0);
+        assert_eq!(floor_cents_for_usd(0.001, 0.51), 0.0);
+    }
+}
@@ -0,0 +1,308 @@
+//! Live sizing ramp
+//! Scaling up a new live setup is done in steps: start at a fraction of the configured copy
+//! size and move up one step each time a window of SIZE_RAMP_TRADES closing trades meets the
+//! bar (win rate, average entry slippage against the whale's price). A window that misses the
+//! bar keeps the level; a drawdown of SIZE_RAMP_DRAWDOWN_USD from the realized peak sends it
+//! back to the start. Closing trades come from this tag's strategy ledger, so stop-loss and
+//! flatten exits count too. The level is kept in `size_ramp.json` and each change journaled
+
+use crate::strategy::{FillKind, StrategyFill, TagBook};
+use crate::timestamp;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Current level and window
+pub const SIZE_RAMP_FILE: &str = "size_ramp.json";
+
+/// One line per level change
+pub const SIZE_RAMP_LOG_FILE: &str = "size_ramp.jsonl";
+
+/// How often the ledger is re-read for new closing trades
+const RAMP_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RampPolicy {
+    /// Starting share of the configured size (0-1)
+    pub start: f64,
+    /// Added per window that meets the bar
+    pub step: f64,
+    /// Closing trades per window
+    pub trades: usize,
+    /// Share of closing trades with positive P&L a window needs
+    pub min_win_rate: f64,
+    /// Highest average entry slippage (fill minus whale price) a window may have
+    pub max_slippage: f64,
+    /// Realized drawdown from the peak that resets the level, None = never
+    pub drawdown_usd: Option<f64>,
+}
+
+// ============================================================================
+// Ramp
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RampState {
+    /// Current share of the configured size
+    pub level: f64,
+    /// Closing trades after this time (unix seconds) make up the current window
+    pub window_start: u64,
+    /// Entry slippage of the fills in the current window
+    pub slippage: Vec<f64>,
+    /// Realized P&L of the windows before the current one, since the last reset
+    pub banked_pnl: f64,
+    /// Highest realized P&L since the last reset
+    pub peak_pnl: f64,
+}
+
+impl RampState {
+    pub fn new(policy: &RampPolicy, now: u64) -> Self {
+        Self { level: policy.start, window_start: now, slippage: Vec::new(), banked_pnl: 0.0, peak_pnl: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum RampChange {
+    StepUp { from: f64, to: f64, win_rate: f64, slippage: f64 },
+    Hold { win_rate: f64, slippage: f64 },
+    Reset { from: f64, drawdown: f64 },
+}
+
+/// (ts, realized P&L) of `tag`'s closing trades after `since` (unix seconds), in ledger order
+pub fn closes_since(fills: &[StrategyFill], tag: &str, since: u64) -> Vec<(u64, f64)> {
+    let mut book = TagBook::default();
+    let mut out = Vec::new();
+    for fill in fills.iter().filter(|f| f.tag == tag) {
+        let before = book.realized_pnl;
+        book.apply(fill);
+        if fill.kind == FillKind::Trade && !fill.is_buy && fill.ts > since {
+            out.push((fill.ts, book.realized_pnl - before));
+        }
+    }
+    out
+}
+
+/// Apply the window's closing trades to `state`; Some when the window is full (every close
+/// in it counts) or a drawdown reset the level. The next window starts after the last close
+/// consumed, so closes written after the ledger was read land in it
+pub fn evaluate(policy: &RampPolicy, state: &mut RampState, closes: &[(u64, f64)]) -> Option<RampChange> {
+    // Drawdown anywhere in the running P&L since the last reset
+    let (mut pnl, mut peak) = (state.banked_pnl, state.peak_pnl);
+    for &(ts, c) in closes {
+        pnl += c;
+        peak = peak.max(pnl);
+        if let Some(limit) = policy.drawdown_usd && peak - pnl >= limit {
+            let from = state.level;
+            *state = RampState::new(policy, ts);
+            return Some(RampChange::Reset { from, drawdown: peak - pnl });
+        }
+    }
+    let &(last_ts, _) = closes.last().filter(|_| closes.len() >= policy.trades)?;
+    let win_rate = closes.iter().filter(|(_, p)| *p > 0.0).count() as f64 / closes.len() as f64;
+    let slippage = if state.slippage.is_empty() { 0.0 } else { state.slippage.iter().sum::<f64>() / state.slippage.len() as f64 };
+    let from = state.level;
+    if win_rate >= policy.min_win_rate && slippage <= policy.max_slippage {
+        state.level = (state.level + policy.step).min(1.0);
+    }
+    state.banked_pnl += closes.iter().map(|(_, p)| p).sum::<f64>();
+    state.peak_pnl = peak;
+    state.window_start = last_ts;
+    state.slippage.clear();
+    Some(if state.level > from {
+        RampChange::StepUp { from, to: state.level, win_rate, slippage }
+    } else {
+        RampChange::Hold { win_rate, slippage }
+    })
+}
+
+pub struct SizeRamp {
+    pub policy: RampPolicy,
+    path: Option<String>,
+    state: Mutex<RampState>,
+}
+
+impl SizeRamp {
+    /// State read from `path`, else a fresh ramp at the start level
+    pub fn new(policy: RampPolicy, path: Option<&str>, now: u64) -> Self {
+        let state = path
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_else(|| RampState::new(&policy, now));
+        Self { policy, path: path.map(String::from), state: Mutex::new(state) }
+    }
+
+    pub fn level(&self) -> f64 {
+        self.state.lock().map(|s| s.level).unwrap_or(self.policy.start)
+    }
+
+    pub fn window_start(&self) -> u64 {
+        self.state.lock().map(|s| s.window_start).unwrap_or(0)
+    }
+
+    /// An entry filled at `fill_price` copying a whale buy at `whale_price`
+    pub fn record_entry(&self, fill_price: f64, whale_price: f64) {
+        if let Ok(mut s) = self.state.lock() {
+            s.slippage.push(fill_price - whale_price);
+        }
+    }
+
+    /// Evaluate the closing trades since the window started and save the state
+    pub fn update(&self, closes: &[(u64, f64)]) -> Result<Option<RampChange>> {
+        let (change, state) = {
+            let Ok(mut s) = self.state.lock() else { return Ok(None) };
+            (evaluate(&self.policy, &mut s, closes), s.clone())
+        };
+        if let Some(path) = &self.path {
+            let tmp = format!("{}.tmp", path);
+            fs::write(&tmp, serde_json::to_string(&state)?)?;
+            fs::rename(&tmp, path)?;
+        }
+        Ok(change)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RampRecord {
+    ts: u64,
+    level: f64,
+    #[serde(flatten)]
+    change: RampChange,
+}
+
+fn journal(level: f64, change: RampChange) {
+    let Ok(line) = serde_json::to_string(&RampRecord { ts: timestamp::unix_secs(), level, change }) else { return };
+    match OpenOptions::new().append(true).create(true).open(SIZE_RAMP_LOG_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Size ramp journal write failed: {}", e),
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_SIZE_RAMP: OnceLock<SizeRamp> = OnceLock::new();
+
+/// Set once at startup, in live mode only
+pub fn init_size_ramp(policy: RampPolicy) {
+    let _ = GLOBAL_SIZE_RAMP.set(SizeRamp::new(policy, Some(SIZE_RAMP_FILE), timestamp::unix_secs()));
+}
+
+pub fn size_ramp() -> Option<&'static SizeRamp> {
+    GLOBAL_SIZE_RAMP.get()
+}
+
+/// Share of the configured size for entries (1 without a ramp)
+pub fn multiplier() -> f64 {
+    size_ramp().map_or(1.0, SizeRamp::level)
+}
+
+/// Re-read the ledger every `RAMP_CHECK_INTERVAL` and move the level
+pub fn spawn_ramp_task() -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RAMP_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(ramp) = size_ramp() else { return };
+            let (tag, since) = (crate::strategy::strategy_ledger().tag(), ramp.window_start());
+            let closes = tokio::task::spawn_blocking(move || {
+                closes_since(&crate::session::load_fills(crate::strategy::STRATEGY_LEDGER_FILE), &tag, since)
+            }).await.unwrap_or_default();
+            match ramp.update(&closes) {
+                Ok(Some(change)) => {
+                    let level = ramp.level();
+                    match change {
+                        RampChange::StepUp { from, to, win_rate, .. } => crate::console_println!(
+                            "📈 Size ramp {:.0}% -> {:.0}% (win rate {:.0}% over {} closes)", from * 100.0, to * 100.0, win_rate * 100.0, ramp.policy.trades
+                        ),
+                        RampChange::Reset { from, drawdown } => crate::console_println!(
+                            "📉 Size ramp reset {:.0}% -> {:.0}% after a ${:.2} drawdown", from * 100.0, level * 100.0, drawdown
+                        ),
+                        RampChange::Hold { .. } => {}
+                    }
+                    journal(level, change);
+                }
+                Ok(None) => {}
+                Err(e) => crate::console_eprintln!("⚠️ Size ramp write failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RampPolicy {
+        RampPolicy { start: 0.25, step: 0.25, trades: 4, min_win_rate: 0.5, max_slippage: 0.02, drawdown_usd: Some(10.0) }
+    }
+
+    /// Closes one second apart after `start`
+    fn closes(start: u64, pnl: &[f64]) -> Vec<(u64, f64)> {
+        pnl.iter().enumerate().map(|(i, p)| (start + 1 + i as u64, *p)).collect()
+    }
+
+    #[test]
+    fn test_ramp_steps_holds_and_resets() {
+        let p = policy();
+        let mut s = RampState::new(&p, 0);
+        assert_eq!(evaluate(&p, &mut s, &closes(0, &[1.0, 1.0])), None);
+
+        s.slippage = vec![0.01, 0.01];
+        assert!(matches!(evaluate(&p, &mut s, &closes(16, &[1.0, -1.0, 2.0, 1.0])), Some(RampChange::StepUp { .. })));
+        assert_eq!((s.level, s.window_start, s.banked_pnl, s.peak_pnl), (0.5, 20, 3.0, 3.0));
+
+        // Too much slippage: the window passes without a step
+        s.slippage = vec![0.05];
+        assert!(matches!(evaluate(&p, &mut s, &closes(26, &[1.0, 1.0, 1.0, 1.0])), Some(RampChange::Hold { .. })));
+        assert_eq!(s.level, 0.5);
+
+        // Peak 7, then down to -4: an $11 drawdown resets to the start
+        assert!(matches!(evaluate(&p, &mut s, &closes(38, &[-6.0, -5.0])), Some(RampChange::Reset { .. })));
+        assert_eq!(s, RampState::new(&p, 40));
+    }
+
+    #[test]
+    fn test_window_boundary_counts_each_close_once() {
+        let p = RampPolicy { trades: 2, drawdown_usd: None, ..policy() };
+        let fill = |ts, is_buy| StrategyFill {
+            ts, tag: "a".into(), token_id: "t".into(), is_buy, shares: 10.0, price: if is_buy { 0.40 } else { 0.50 },
+            kind: FillKind::Trade, session: None,
+        };
+        let mut ledger = vec![fill(1, true), fill(5, false), fill(5, true), fill(7, false)];
+        let mut s = RampState::new(&p, 0);
+        let window = closes_since(&ledger, "a", s.window_start);
+        assert!(evaluate(&p, &mut s, &window).is_some());
+        assert_eq!(s.window_start, 7);
+
+        // A close in the same second as the window start is not counted again; one written
+        // after the read is still ahead
+        ledger.extend([fill(7, true), fill(9, false)]);
+        assert_eq!(closes_since(&ledger, "a", s.window_start).iter().map(|c| c.0).collect::<Vec<_>>(), vec![9]);
+    }
+
+    #[test]
+    fn test_closes_since_uses_full_history_for_cost() {
+        let fill = |ts, is_buy, price, tag: &str| StrategyFill {
+            ts, tag: tag.into(), token_id: "t".into(), is_buy, shares: 10.0, price, kind: FillKind::Trade, session: None,
+        };
+        let fills = vec![
+            fill(1, true, 0.40, "a"),
+            fill(2, true, 0.40, "b"),
+            fill(5, false, 0.50, "a"),
+            fill(6, false, 0.30, "b"),
+        ];
+        let closes = closes_since(&fills, "a", 3);
+        assert_eq!(closes.len(), 1);
+        assert!((closes[0].1 - 1.0).abs() < 1e-9);
+        assert!(closes_since(&fills, "a", 5).is_empty());
+    }
+}
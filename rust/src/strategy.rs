@@ -0,0 +1,351 @@
+//! Strategy tagging for mixed deployments
+//! Several bot instances (e.g. one per followed whale) can share a funder wallet. Each instance
+//! runs under its own STRATEGY_TAG and appends its fills to a shared ledger, so exposure and
+//! realized P&L are attributed per tag and an optional per-tag exposure cap blocks new entries
+
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Append-only fill ledger shared by every instance running in this directory
+pub const STRATEGY_LEDGER_FILE: &str = "strategy_fills.jsonl";
+
+pub const DEFAULT_STRATEGY_TAG: &str = "default";
+
+/// Tags end up in file names and log lines, so keep them to a safe alphabet
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty() && tag.len() <= 32
+        && tag.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+// ============================================================================
+// Records
+// ============================================================================
+
+/// Ledger record kind: an exchange fill, or a manual override of the held size/price
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillKind {
+    #[default]
+    Trade,
+    Set,
+}
+
+impl FillKind {
+    fn is_trade(&self) -> bool {
+        *self == FillKind::Trade
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrategyFill {
+    /// Unix seconds
+    pub ts: u64,
+    pub tag: String,
+    pub token_id: String,
+    pub is_buy: bool,
+    pub shares: f64,
+    pub price: f64,
+    /// Older ledgers have no kind: every line is a trade
+    #[serde(default, skip_serializing_if = "FillKind::is_trade")]
+    pub kind: FillKind,
+    /// Intraday session in force when the fill was recorded (SESSIONS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+}
+
+/// Average-cost book for one tag
+#[derive(Debug, Clone, Default)]
+pub struct TagBook {
+    /// token_id -> (shares held, average entry price)
+    positions: FxHashMap<String, (f64, f64)>,
+    /// token_id -> premium paid for the position still open (every buy since it opened)
+    paid: FxHashMap<String, f64>,
+    pub realized_pnl: f64,
+    pub fills: usize,
+    pub volume_usd: f64,
+}
+
+impl TagBook {
+    pub(crate) fn apply(&mut self, fill: &StrategyFill) {
+        if fill.kind == FillKind::Set {
+            // Manual correction: replaces the held size and average, realizes nothing
+            if fill.shares > 1e-9 {
+                self.positions.insert(fill.token_id.clone(), (fill.shares, fill.price));
+                self.paid.insert(fill.token_id.clone(), fill.shares * fill.price);
+            } else {
+                self.positions.remove(&fill.token_id);
+                self.paid.remove(&fill.token_id);
+            }
+            return;
+        }
+        self.fills += 1;
+        self.volume_usd += fill.shares * fill.price;
+        let (held, avg) = self.positions.entry(fill.token_id.clone()).or_insert((0.0, 0.0));
+        if fill.is_buy {
+            let total = *held + fill.shares;
+            *avg = (*held * *avg + fill.shares * fill.price) / total;
+            *held = total;
+            *self.paid.entry(fill.token_id.clone()).or_default() += fill.shares * fill.price;
+        } else {
+            // Sells beyond what this tag holds (e.g. bought before tagging) realize nothing extra
+            let closed = fill.shares.min(*held);
+            self.realized_pnl += closed * (fill.price - *avg);
+            *held -= closed;
+        }
+        if *held <= 1e-9 {
+            self.positions.remove(&fill.token_id);
+            self.paid.remove(&fill.token_id);
+        }
+    }
+
+    /// Cost basis of everything still held
+    pub fn open_cost(&self) -> f64 {
+        self.positions.values().map(|(shares, avg)| shares * avg).sum()
+    }
+
+    pub fn open_positions(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// (shares, average entry) held in `token_id`
+    pub fn held(&self, token_id: &str) -> Option<(f64, f64)> {
+        self.positions.get(token_id).copied()
+    }
+
+    pub fn tokens(&self) -> Vec<String> {
+        self.positions.keys().cloned().collect()
+    }
+
+    /// Premium paid for the open position in `token_id`, partial exits not deducted
+    pub fn paid(&self, token_id: &str) -> f64 {
+        self.paid.get(token_id).copied().unwrap_or(0.0)
+    }
+}
+
+// ============================================================================
+// Ledger
+// ============================================================================
+
+pub struct StrategyLedger {
+    /// Swappable so a time-boxed experiment can attribute its fills to its own tag
+    tag: Mutex<String>,
+    /// Cap on this tag's open cost basis; None = uncapped
+    max_open_usd: Mutex<Option<f64>>,
+    path: Option<String>,
+    books: Mutex<FxHashMap<String, TagBook>>,
+}
+
+impl StrategyLedger {
+    /// Ledger for `tag` backed by `path` (None keeps it in memory only)
+    pub fn new(tag: &str, max_open_usd: Option<f64>, path: Option<&str>) -> Self {
+        Self {
+            tag: Mutex::new(tag.to_string()),
+            max_open_usd: Mutex::new(max_open_usd),
+            path: path.map(String::from),
+            books: Mutex::new(FxHashMap::default()),
+        }
+    }
+
+    pub fn tag(&self) -> String {
+        self.tag.lock().map(|t| t.clone()).unwrap_or_default()
+    }
+
+    pub fn max_open_usd(&self) -> Option<f64> {
+        self.max_open_usd.lock().ok().and_then(|m| *m)
+    }
+
+    /// Attribute fills from now on to `tag` under `max_open_usd`
+    pub fn retag(&self, tag: &str, max_open_usd: Option<f64>) {
+        if let Ok(mut t) = self.tag.lock() {
+            *t = tag.to_string();
+        }
+        if let Ok(mut m) = self.max_open_usd.lock() {
+            *m = max_open_usd;
+        }
+    }
+
+    /// Book of any tag seen in the ledger
+    pub fn book(&self, tag: &str) -> Option<TagBook> {
+        self.books.lock().ok()?.get(tag).cloned()
+    }
+
+    /// Replay the shared ledger (all tags); returns number of fills read
+    pub fn load(&self) -> usize {
+        let Some(path) = &self.path else { return 0 };
+        let Ok(data) = std::fs::read_to_string(path) else { return 0 };
+        let Ok(mut books) = self.books.lock() else { return 0 };
+        books.clear();
+        let mut n = 0;
+        for fill in data.lines().filter_map(|l| serde_json::from_str::<StrategyFill>(l).ok()) {
+            books.entry(fill.tag.clone()).or_default().apply(&fill);
+            n += 1;
+        }
+        n
+    }
+
+    /// Attribute a fill to this instance's tag
+    pub fn record(&self, token_id: &str, is_buy: bool, shares: f64, price: f64) {
+        if shares <= 0.0 {
+            return;
+        }
+        self.append(token_id, is_buy, shares, price, FillKind::Trade);
+    }
+
+    /// Overwrite this tag's held size and average price (0 shares drops the position)
+    pub fn record_set(&self, token_id: &str, shares: f64, price: f64) {
+        self.append(token_id, true, shares.max(0.0), price, FillKind::Set);
+    }
+
+    fn append(&self, token_id: &str, is_buy: bool, shares: f64, price: f64, kind: FillKind) {
+        let tag = self.tag();
+        let fill = StrategyFill {
+            ts: timestamp::unix_secs(),
+            tag: tag.clone(),
+            token_id: token_id.to_string(),
+            is_buy,
+            shares,
+            price,
+            kind,
+            session: crate::session::current_session(),
+        };
+        if let Ok(mut books) = self.books.lock() {
+            books.entry(tag).or_default().apply(&fill);
+        }
+        let Some(path) = &self.path else { return };
+        let Ok(line) = serde_json::to_string(&fill) else { return };
+        match OpenOptions::new().append(true).create(true).open(path) {
+            Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+            Err(e) => crate::console_eprintln!("⚠️ Strategy ledger write failed: {}", e),
+        }
+    }
+
+    /// Open cost basis attributed to this instance's tag
+    pub fn open_cost(&self) -> f64 {
+        self.books.lock()
+            .ok()
+            .and_then(|b| b.get(&self.tag()).map(TagBook::open_cost))
+            .unwrap_or(0.0)
+    }
+
+    /// (shares, average entry) this instance's tag holds in `token_id`
+    pub fn held(&self, token_id: &str) -> Option<(f64, f64)> {
+        self.books.lock().ok()?.get(&self.tag())?.held(token_id)
+    }
+
+    /// Tokens this instance's tag holds
+    pub fn held_tokens(&self) -> Vec<String> {
+        self.books.lock()
+            .ok()
+            .and_then(|b| b.get(&self.tag()).map(TagBook::tokens))
+            .unwrap_or_default()
+    }
+
+    /// Err(current open cost) if a new entry of `order_usd` would breach this tag's cap
+    pub fn check_entry(&self, order_usd: f64) -> Result<(), f64> {
+        let Some(max) = self.max_open_usd() else { return Ok(()) };
+        let open = self.open_cost();
+        if open + order_usd > max { Err(open) } else { Ok(()) }
+    }
+
+    /// Per-tag books, sorted by tag
+    pub fn snapshot(&self) -> Vec<(String, TagBook)> {
+        let mut out: Vec<_> = self.books.lock()
+            .map(|b| b.iter().map(|(t, book)| (t.clone(), book.clone())).collect())
+            .unwrap_or_default();
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+}
+
+/// Per-tag attribution table for `pm_bot strategies`
+pub fn render_report(books: &[(String, TagBook)]) -> String {
+    let mut out = format!(
+        "{:<16} {:>6} {:>12} {:>6} {:>12} {:>12}\n",
+        "TAG", "FILLS", "VOLUME", "OPEN", "OPEN_COST", "REALIZED"
+    );
+    for (tag, b) in books {
+        out.push_str(&format!(
+            "{:<16} {:>6} {:>12.2} {:>6} {:>12.2} {:>+12.2}\n",
+            tag, b.fills, b.volume_usd, b.open_positions(), b.open_cost(), b.realized_pnl
+        ));
+    }
+    out
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_LEDGER: OnceLock<StrategyLedger> = OnceLock::new();
+
+/// Initialize the global ledger for this instance's tag and replay the shared file
+pub fn init_strategy_ledger(tag: &str, max_open_usd: Option<f64>) {
+    let ledger = GLOBAL_LEDGER.get_or_init(|| StrategyLedger::new(tag, max_open_usd, Some(STRATEGY_LEDGER_FILE)));
+    ledger.load();
+}
+
+/// Get the global strategy ledger (untagged and uncapped if never initialized)
+pub fn strategy_ledger() -> &'static StrategyLedger {
+    GLOBAL_LEDGER.get_or_init(|| StrategyLedger::new(DEFAULT_STRATEGY_TAG, None, Some(STRATEGY_LEDGER_FILE)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_cost_pnl_and_cap() {
+        let l = StrategyLedger::new("whale_a", Some(100.0), None);
+        l.record("t1", true, 100.0, 0.40);
+        l.record("t1", true, 100.0, 0.50);
+        assert!((l.open_cost() - 90.0).abs() < 1e-9);
+        assert!(l.check_entry(10.0).is_ok());
+        assert_eq!(l.check_entry(10.01).unwrap_err(), l.open_cost());
+
+        l.record("t1", false, 100.0, 0.60);
+        let (tag, book) = &l.snapshot()[0];
+        assert_eq!(tag, "whale_a");
+        assert!((book.realized_pnl - 15.0).abs() < 1e-9);
+        assert!((book.open_cost() - 45.0).abs() < 1e-9);
+        assert!((book.paid("t1") - 90.0).abs() < 1e-9);
+        assert_eq!(book.fills, 3);
+    }
+
+    #[test]
+    fn test_shared_ledger_attributes_per_tag() {
+        let path = std::env::temp_dir().join(format!("pm_strategy_{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let a = StrategyLedger::new("a", None, Some(path));
+        let b = StrategyLedger::new("b", None, Some(path));
+        a.record("t1", true, 10.0, 0.5);
+        b.record("t2", true, 20.0, 0.25);
+        b.record("t2", false, 20.0, 0.35);
+
+        let reader = StrategyLedger::new("a", None, Some(path));
+        assert_eq!(reader.load(), 3);
+        let books = reader.snapshot();
+        assert_eq!(books.len(), 2);
+        assert!((books[0].1.open_cost() - 5.0).abs() < 1e-9);
+        assert!((books[1].1.realized_pnl - 2.0).abs() < 1e-9);
+        assert!(render_report(&books).contains("\nb "));
+        assert!(is_valid_tag("whale_a-2") && !is_valid_tag("a b") && !is_valid_tag(""));
+
+        // Retagged fills land in their own book
+        a.retag("exp-a", None);
+        a.record("t1", true, 4.0, 0.5);
+        assert_eq!(a.tag(), "exp-a");
+        assert!((a.book("exp-a").unwrap().open_cost() - 2.0).abs() < 1e-9);
+        assert!((a.book("a").unwrap().open_cost() - 5.0).abs() < 1e-9);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,380 @@
+//! In-process task supervision
+//! Background tasks are spawned through `supervise`, which restarts a task with exponential
+//! backoff when it panics, counts restarts (visible in the diagnostics dump) and raises an
+//! alert when a task keeps crashing. A component failing persistently (crashes, or feed
+//! reconnects reported through `report_failure`) is marked degraded: it is retried at a slow
+//! fixed rate instead of the normal backoff until its failure rate falls back
+
+use crate::diagnostics::diagnostics;
+use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+// =============================================================================
+// Configuration
+// =============================================================================
+
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    /// Delay before the first restart (doubles after each consecutive crash)
+    pub backoff_base: Duration,
+    pub backoff_max: Duration,
+    /// A task that ran this long before crashing starts again from `backoff_base`
+    pub healthy_after: Duration,
+    /// Alert once a task crashed this many times within `alert_window`
+    pub alert_after: usize,
+    pub alert_window: Duration,
+    /// Mark the component degraded once it failed this many times within `degrade_window`;
+    /// it recovers when the count drops below half of that
+    pub degrade_after: usize,
+    pub degrade_window: Duration,
+    /// Retry delay while degraded
+    pub degraded_retry: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            backoff_base: Duration::from_secs(1),
+            backoff_max: Duration::from_secs(60),
+            healthy_after: Duration::from_secs(60),
+            alert_after: 5,
+            alert_window: Duration::from_secs(10 * 60),
+            degrade_after: 50,
+            degrade_window: Duration::from_secs(60 * 60),
+            degraded_retry: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+// =============================================================================
+// Restart Registry
+// =============================================================================
+
+#[derive(Default)]
+pub struct RestartRegistry {
+    restarts: Mutex<FxHashMap<&'static str, Vec<Instant>>>,
+}
+
+impl RestartRegistry {
+    /// Record a crash; returns crashes of this task within `window` (including this one)
+    pub fn record(&self, task: &'static str, window: Duration) -> usize {
+        let now = Instant::now();
+        let Ok(mut restarts) = self.restarts.lock() else { return 0 };
+        let history = restarts.entry(task).or_default();
+        history.push(now);
+        // Keep only what the alert window needs; the total lives in the diagnostics heartbeat count
+        history.retain(|t| now.duration_since(*t) <= window);
+        history.len()
+    }
+
+    /// Crashes per task within the last alert window
+    pub fn recent(&self) -> Vec<(&'static str, usize)> {
+        let Ok(restarts) = self.restarts.lock() else { return Vec::new() };
+        let mut out: Vec<_> = restarts.iter().map(|(t, h)| (*t, h.len())).collect();
+        out.sort_unstable();
+        out
+    }
+
+    pub fn total_recent(&self) -> usize {
+        self.recent().iter().map(|(_, n)| n).sum()
+    }
+}
+
+static GLOBAL_RESTARTS: OnceLock<RestartRegistry> = OnceLock::new();
+
+/// Get the global restart registry
+pub fn restarts() -> &'static RestartRegistry {
+    GLOBAL_RESTARTS.get_or_init(|| {
+        diagnostics().register_gauge("task_restarts", || restarts().total_recent());
+        RestartRegistry::default()
+    })
+}
+
+// =============================================================================
+// Degraded Components
+// =============================================================================
+
+struct Health {
+    failures: VecDeque<Instant>,
+    window: Duration,
+    recover_below: usize,
+    degraded_since: Option<Instant>,
+}
+
+impl Health {
+    fn prune(&mut self, now: Instant) {
+        while self.failures.front().is_some_and(|t| now.duration_since(*t) > self.window) {
+            self.failures.pop_front();
+        }
+    }
+
+    /// Clear the degraded mark once failures fell back; returns true when it did
+    fn try_recover(&mut self, now: Instant) -> bool {
+        self.prune(now);
+        if self.degraded_since.is_some() && self.failures.len() < self.recover_below {
+            self.degraded_since = None;
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HealthChange {
+    Unchanged,
+    /// Just crossed `degrade_after` (failures in the window)
+    Degraded(usize),
+    Recovered,
+}
+
+#[derive(Default)]
+pub struct HealthRegistry {
+    components: Mutex<FxHashMap<&'static str, Health>>,
+}
+
+impl HealthRegistry {
+    pub fn record_failure(&self, component: &'static str, policy: &RestartPolicy, now: Instant) -> HealthChange {
+        let Ok(mut comps) = self.components.lock() else { return HealthChange::Unchanged };
+        let h = comps.entry(component).or_insert_with(|| Health {
+            failures: VecDeque::new(),
+            window: policy.degrade_window,
+            recover_below: policy.degrade_after.div_ceil(2),
+            degraded_since: None,
+        });
+        h.failures.push_back(now);
+        if h.try_recover(now) {
+            return HealthChange::Recovered;
+        }
+        if h.degraded_since.is_none() && h.failures.len() >= policy.degrade_after {
+            h.degraded_since = Some(now);
+            return HealthChange::Degraded(h.failures.len());
+        }
+        HealthChange::Unchanged
+    }
+
+    /// Whether `component` is degraded now, logging its recovery when the window cleared
+    pub fn is_degraded(&self, component: &'static str) -> bool {
+        let Ok(mut comps) = self.components.lock() else { return false };
+        let Some(h) = comps.get_mut(component) else { return false };
+        if h.try_recover(Instant::now()) {
+            crate::console_println!("✅ '{}' recovered, back to normal retries", component);
+        }
+        h.degraded_since.is_some()
+    }
+
+    /// (component, failures in window, degraded for), sorted by name
+    pub fn degraded(&self) -> Vec<(&'static str, usize, Duration)> {
+        let now = Instant::now();
+        let Ok(mut comps) = self.components.lock() else { return Vec::new() };
+        let mut out: Vec<_> = comps.iter_mut()
+            .filter_map(|(name, h)| {
+                h.prune(now);
+                Some((*name, h.failures.len(), now.duration_since(h.degraded_since?)))
+            })
+            .collect();
+        out.sort_unstable_by_key(|(name, _, _)| *name);
+        out
+    }
+
+    /// Dump line, empty while everything is healthy
+    pub fn report(&self) -> String {
+        let degraded = self.degraded();
+        if degraded.is_empty() {
+            return String::new();
+        }
+        let list: Vec<String> = degraded.iter()
+            .map(|(name, n, since)| format!("{} ({} failures/window, {}s)", name, n, since.as_secs()))
+            .collect();
+        let mut out = String::new();
+        let _ = writeln!(out, "  {:<14} {}", "DEGRADED", list.join(", "));
+        out
+    }
+}
+
+static GLOBAL_HEALTH: OnceLock<HealthRegistry> = OnceLock::new();
+
+/// Get the global degraded-component registry
+pub fn health() -> &'static HealthRegistry {
+    GLOBAL_HEALTH.get_or_init(|| {
+        diagnostics().register_gauge("degraded", || health().degraded().len());
+        HealthRegistry::default()
+    })
+}
+
+/// Record a failure of `component` (a crash, a dropped feed, a failed poll) and return how long
+/// to wait before retrying: `normal` while healthy, `policy.degraded_retry` once degraded
+pub fn report_failure(component: &'static str, policy: &RestartPolicy, normal: Duration) -> Duration {
+    match health().record_failure(component, policy, Instant::now()) {
+        HealthChange::Degraded(n) => {
+            diagnostics().heartbeat("supervisor", &format!("{} degraded", component));
+            crate::console_eprintln!(
+                "🚨 '{}' failed {} times in the last {}s: marked DEGRADED, retrying every {}s (see diagnostics dump)",
+                component, n, policy.degrade_window.as_secs(), policy.degraded_retry.as_secs()
+            );
+        }
+        HealthChange::Recovered => {
+            crate::console_println!("✅ '{}' recovered, back to normal retries", component);
+        }
+        HealthChange::Unchanged => {}
+    }
+    if health().is_degraded(component) { policy.degraded_retry.max(normal) } else { normal }
+}
+
+/// Record a crash of `task`, logging an alert if it keeps happening. Returns the restart delay
+pub fn report_crash(task: &'static str, policy: &RestartPolicy, backoff: Duration) -> Duration {
+    let recent = restarts().record(task, policy.alert_window);
+    diagnostics().end_op(task);
+    diagnostics().heartbeat("supervisor", &format!("{} crashed", task));
+    if recent >= policy.alert_after {
+        crate::console_eprintln!(
+            "🚨 Task '{}' crashed {} times in the last {}s, check the logs",
+            task, recent, policy.alert_window.as_secs()
+        );
+    }
+    report_failure(task, policy, backoff)
+}
+
+/// Next backoff delay after a crash
+pub fn next_backoff(current: Duration, ran_for: Duration, policy: &RestartPolicy) -> Duration {
+    if ran_for >= policy.healthy_after {
+        policy.backoff_base
+    } else {
+        (current * 2).min(policy.backoff_max)
+    }
+}
+
+// =============================================================================
+// Supervision
+// =============================================================================
+
+/// Run `spawn` and restart whatever it spawned each time the task panics.
+/// A task that returns normally is not restarted
+pub fn supervise<F>(name: &'static str, spawn: F) -> JoinHandle<()>
+where
+    F: Fn() -> JoinHandle<()> + Send + 'static,
+{
+    supervise_with(name, RestartPolicy::default(), spawn)
+}
+
+pub fn supervise_with<F>(name: &'static str, policy: RestartPolicy, spawn: F) -> JoinHandle<()>
+where
+    F: Fn() -> JoinHandle<()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut backoff = policy.backoff_base;
+        loop {
+            let started = Instant::now();
+            match spawn().await {
+                Ok(()) => {
+                    crate::console_println!("ℹ️ Task '{}' exited", name);
+                    return;
+                }
+                Err(e) if e.is_cancelled() => return,
+                Err(e) => {
+                    let ran_for = started.elapsed();
+                    backoff = if ran_for >= policy.healthy_after { policy.backoff_base } else { backoff };
+                    let delay = report_crash(name, &policy, backoff);
+                    crate::console_eprintln!(
+                        "💥 Task '{}' panicked after {:.1}s: {}. Restarting in {:.1}s",
+                        name, ran_for.as_secs_f64(), e, delay.as_secs_f64()
+                    );
+                    tokio::time::sleep(delay).await;
+                    backoff = next_backoff(backoff, ran_for, &policy);
+                }
+            }
+        }
+    })
+}
+
+/// Blocking counterpart of `supervise` for worker threads: reruns `body` on the current
+/// thread after each panic. Returns once `body` returns normally
+pub fn supervise_blocking<F>(name: &'static str, policy: RestartPolicy, mut body: F)
+where
+    F: FnMut(),
+{
+    let mut backoff = policy.backoff_base;
+    loop {
+        let started = Instant::now();
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(&mut body)) {
+            Ok(()) => return,
+            Err(_) => {
+                let ran_for = started.elapsed();
+                backoff = if ran_for >= policy.healthy_after { policy.backoff_base } else { backoff };
+                let delay = report_crash(name, &policy, backoff);
+                crate::console_eprintln!(
+                    "💥 Worker '{}' panicked after {:.1}s. Restarting in {:.1}s",
+                    name, ran_for.as_secs_f64(), delay.as_secs_f64()
+                );
+                std::thread::sleep(delay);
+                backoff = next_backoff(backoff, ran_for, &policy);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn fast_policy() -> RestartPolicy {
+        RestartPolicy {
+            backoff_base: Duration::from_millis(1),
+            backoff_max: Duration::from_millis(4),
+            healthy_after: Duration::from_secs(60),
+            alert_after: 2,
+            alert_window: Duration::from_secs(60),
+            degrade_after: 100,
+            degrade_window: Duration::from_secs(60),
+            degraded_retry: Duration::from_millis(4),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restarts_until_task_succeeds() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let r = runs.clone();
+        let handle = supervise_with("test_flaky", fast_policy(), move || {
+            let r = r.clone();
+            tokio::spawn(async move {
+                if r.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("boom");
+                }
+            })
+        });
+        handle.await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert!(restarts().recent().contains(&("test_flaky", 2)));
+    }
+
+    #[test]
+    fn test_degrades_and_recovers() {
+        let policy = RestartPolicy { degrade_after: 4, degrade_window: Duration::from_secs(60), ..fast_policy() };
+        let reg = HealthRegistry::default();
+        let t0 = Instant::now();
+        for i in 0..3 {
+            assert_eq!(reg.record_failure("feed", &policy, t0 + Duration::from_secs(i)), HealthChange::Unchanged);
+        }
+        assert_eq!(reg.record_failure("feed", &policy, t0 + Duration::from_secs(3)), HealthChange::Degraded(4));
+        assert_eq!(reg.record_failure("feed", &policy, t0 + Duration::from_secs(4)), HealthChange::Unchanged);
+        assert_eq!(reg.degraded().len(), 1);
+        assert!(reg.report().contains("feed (5 failures/window"));
+
+        // An hour later the old failures have left the window: one new failure is below half
+        assert_eq!(reg.record_failure("feed", &policy, t0 + Duration::from_secs(3600)), HealthChange::Recovered);
+        assert!(reg.report().is_empty());
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_resets() {
+        let p = RestartPolicy::default();
+        assert_eq!(next_backoff(Duration::from_secs(1), Duration::ZERO, &p), Duration::from_secs(2));
+        assert_eq!(next_backoff(Duration::from_secs(40), Duration::ZERO, &p), Duration::from_secs(60));
+        assert_eq!(next_backoff(Duration::from_secs(40), Duration::from_secs(120), &p), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,304 @@
+//! Taker flow from trade prints
+//! The market channel reports each trade on a subscribed token (`last_trade_price`) with the
+//! taker's side. Summing taker buys against taker sells over a short window gives the flow
+//! imbalance: +1 when every print was a taker buy, -1 when every print was a taker sell. At each
+//! whale entry the imbalance over three windows is journaled, and with TAKER_FLOW_MODE the
+//! configured window can confirm the entry (skip unless takers are buying) or suppress it
+//! (skip while takers are selling). Prints arrive on the quote activity subscription, which
+//! watches the token from the whale's first entry on, so a token's first entry has no flow yet
+
+use crate::timestamp;
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// One line per whale entry evaluated
+pub const TAKER_FLOW_FILE: &str = "taker_flow.jsonl";
+
+/// Windows journaled at each entry, shortest first
+pub const JOURNAL_WINDOWS: [Duration; 3] = [Duration::from_secs(30), Duration::from_secs(2 * 60), Duration::from_secs(10 * 60)];
+
+/// Prints older than the longest window a policy can ask for are dropped
+const MAX_WINDOW: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlowMode {
+    /// Journal the flow only
+    Record,
+    /// Skip entries unless the imbalance is at least the threshold
+    Confirm,
+    /// Skip entries while the imbalance is at or below minus the threshold
+    Suppress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlowPolicy {
+    pub mode: FlowMode,
+    pub window: Duration,
+    /// Imbalance (0-1) that confirms or suppresses
+    pub threshold: f64,
+    /// Less printed volume than this in the window decides nothing
+    pub min_usd: f64,
+}
+
+impl FlowPolicy {
+    /// TAKER_FLOW_MODE (off, record, confirm, suppress); None when off
+    pub fn parse(mode: &str, window_secs: u64, threshold: f64, min_usd: f64) -> Result<Option<Self>> {
+        let mode = match mode.trim().to_ascii_lowercase().as_str() {
+            "" | "off" => return Ok(None),
+            "record" => FlowMode::Record,
+            "confirm" => FlowMode::Confirm,
+            "suppress" => FlowMode::Suppress,
+            other => anyhow::bail!("TAKER_FLOW_MODE must be off, record, confirm or suppress (got '{}')", other),
+        };
+        if window_secs == 0 || window_secs > MAX_WINDOW.as_secs() {
+            anyhow::bail!("TAKER_FLOW_WINDOW_SECS must be 1-{} (got {})", MAX_WINDOW.as_secs(), window_secs);
+        }
+        if !(0.0..=1.0).contains(&threshold) {
+            anyhow::bail!("TAKER_FLOW_THRESHOLD must be between 0 and 1 (got {})", threshold);
+        }
+        Ok(Some(Self { mode, window: Duration::from_secs(window_secs), threshold, min_usd: min_usd.max(0.0) }))
+    }
+}
+
+// ============================================================================
+// Flow
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct Flow {
+    pub window_secs: u64,
+    pub buy_usd: f64,
+    pub sell_usd: f64,
+    pub prints: usize,
+}
+
+impl Flow {
+    pub fn volume_usd(&self) -> f64 {
+        self.buy_usd + self.sell_usd
+    }
+
+    /// (buy - sell) / (buy + sell); 0 without volume
+    pub fn imbalance(&self) -> f64 {
+        let v = self.volume_usd();
+        if v > 0.0 { (self.buy_usd - self.sell_usd) / v } else { 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Print {
+    ms: u64,
+    is_buy: bool,
+    usd: f64,
+}
+
+#[derive(Default)]
+pub struct TakerFlow {
+    prints: Mutex<FxHashMap<String, VecDeque<Print>>>,
+}
+
+impl TakerFlow {
+    pub fn record(&self, token_id: &str, is_buy: bool, usd: f64, now_ms: u64) {
+        let Ok(mut prints) = self.prints.lock() else { return };
+        let q = prints.entry(token_id.to_string()).or_default();
+        let keep = MAX_WINDOW.as_millis() as u64;
+        while q.front().is_some_and(|p| p.ms + keep < now_ms) {
+            q.pop_front();
+        }
+        q.push_back(Print { ms: now_ms, is_buy, usd });
+    }
+
+    /// Taker volume by side over the `window` up to `now_ms`
+    pub fn flow(&self, token_id: &str, window: Duration, now_ms: u64) -> Flow {
+        let mut f = Flow { window_secs: window.as_secs(), ..Default::default() };
+        let Ok(prints) = self.prints.lock() else { return f };
+        let start = now_ms.saturating_sub(window.as_millis() as u64);
+        for p in prints.get(token_id).into_iter().flatten().filter(|p| p.ms > start && p.ms <= now_ms) {
+            if p.is_buy { f.buy_usd += p.usd } else { f.sell_usd += p.usd }
+            f.prints += 1;
+        }
+        f
+    }
+
+    /// Drop tokens no longer subscribed
+    pub fn retain(&self, keep: impl Fn(&str) -> bool) {
+        if let Ok(mut prints) = self.prints.lock() {
+            prints.retain(|t, _| keep(t));
+        }
+    }
+}
+
+/// (token, taker is buying, USD) of each trade print in a market-channel message
+pub fn message_prints(msg: &Value) -> Vec<(String, bool, f64)> {
+    let events = msg.as_array().map(Vec::as_slice).unwrap_or(std::slice::from_ref(msg));
+    events.iter()
+        .filter(|e| e["event_type"].as_str() == Some("last_trade_price"))
+        .filter_map(|e| {
+            let token = e["asset_id"].as_str()?;
+            let num = |k: &str| e[k].as_str().and_then(|s| s.parse::<f64>().ok()).or_else(|| e[k].as_f64());
+            let usd = num("price")? * num("size")?;
+            let is_buy = match e["side"].as_str()? {
+                s if s.eq_ignore_ascii_case("BUY") => true,
+                s if s.eq_ignore_ascii_case("SELL") => false,
+                _ => return None,
+            };
+            (usd > 0.0).then(|| (token.to_string(), is_buy, usd))
+        })
+        .collect()
+}
+
+/// Ok or Err(reason) for an entry given the flow over the policy's window
+pub fn decide(policy: &FlowPolicy, flow: &Flow) -> Result<(), String> {
+    if flow.volume_usd() < policy.min_usd || flow.volume_usd() <= 0.0 {
+        return Ok(());
+    }
+    let imbalance = flow.imbalance();
+    let refused = match policy.mode {
+        FlowMode::Record => false,
+        FlowMode::Confirm => imbalance < policy.threshold,
+        FlowMode::Suppress => imbalance <= -policy.threshold,
+    };
+    if refused {
+        return Err(format!("imbalance {:+.2} over {}s, ${:.0} printed", imbalance, flow.window_secs, flow.volume_usd()));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Journal
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+struct FlowRecord<'a> {
+    ts: u64,
+    token_id: &'a str,
+    windows: Vec<Flow>,
+    /// Imbalance of each window, same order
+    imbalance: Vec<f64>,
+    /// "none", "confirm" or "suppress"
+    action: &'static str,
+}
+
+fn journal(token_id: &str, windows: Vec<Flow>, action: &'static str) {
+    let rec = FlowRecord {
+        ts: timestamp::unix_secs(),
+        token_id,
+        imbalance: windows.iter().map(Flow::imbalance).collect(),
+        windows,
+        action,
+    };
+    let Ok(line) = serde_json::to_string(&rec) else { return };
+    match OpenOptions::new().append(true).create(true).open(TAKER_FLOW_FILE) {
+        Ok(mut f) => { let _ = writeln!(f, "{}", line); }
+        Err(e) => crate::console_eprintln!("⚠️ Taker flow journal write failed: {}", e),
+    }
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_TAKER_FLOW: OnceLock<TakerFlow> = OnceLock::new();
+static GLOBAL_FLOW_POLICY: OnceLock<Option<FlowPolicy>> = OnceLock::new();
+
+pub fn taker_flow() -> &'static TakerFlow {
+    GLOBAL_TAKER_FLOW.get_or_init(TakerFlow::default)
+}
+
+/// Set once at startup (None = taker flow is not tracked)
+pub fn init_flow_policy(policy: Option<FlowPolicy>) {
+    let _ = GLOBAL_FLOW_POLICY.set(policy);
+}
+
+pub fn flow_policy() -> Option<&'static FlowPolicy> {
+    GLOBAL_FLOW_POLICY.get().and_then(Option::as_ref)
+}
+
+/// Record the trade prints of a market-channel message
+pub fn record_message(msg: &Value, now_ms: u64) {
+    if flow_policy().is_none() {
+        return;
+    }
+    for (token, is_buy, usd) in message_prints(msg) {
+        taker_flow().record(&token, is_buy, usd, now_ms);
+    }
+}
+
+/// Whale entry into `token_id`: watch the token, journal its taker flow and return
+/// Err(reason) when the policy skips the entry
+pub fn evaluate_entry(token_id: &str) -> Result<(), String> {
+    let Some(policy) = flow_policy() else { return Ok(()) };
+    let now = timestamp::unix_millis();
+    crate::quote_activity::quote_activity().watch(token_id, now);
+    let windows: Vec<Flow> = JOURNAL_WINDOWS.iter().map(|w| taker_flow().flow(token_id, *w, now)).collect();
+    let decision = decide(policy, &taker_flow().flow(token_id, policy.window, now));
+    let action = match (policy.mode, &decision) {
+        (FlowMode::Confirm, Err(_)) => "confirm",
+        (FlowMode::Suppress, Err(_)) => "suppress",
+        _ => "none",
+    };
+    journal(token_id, windows, action);
+    decision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_flow_windows_and_imbalance() {
+        let tf = TakerFlow::default();
+        let now = 10_000_000;
+        tf.record("t", true, 300.0, now - 100_000);
+        tf.record("t", false, 50.0, now - 20_000);
+        tf.record("t", true, 150.0, now - 5_000);
+        let short = tf.flow("t", Duration::from_secs(30), now);
+        assert_eq!((short.buy_usd, short.sell_usd, short.prints), (150.0, 50.0, 2));
+        assert!((short.imbalance() - 0.5).abs() < 1e-9);
+        let long = tf.flow("t", Duration::from_secs(120), now);
+        assert!((long.imbalance() - 0.8).abs() < 1e-9);
+        assert_eq!(tf.flow("other", Duration::from_secs(30), now).imbalance(), 0.0);
+    }
+
+    #[test]
+    fn test_decide_modes() {
+        let selling = Flow { window_secs: 60, buy_usd: 100.0, sell_usd: 900.0, prints: 5 };
+        let buying = Flow { window_secs: 60, buy_usd: 900.0, sell_usd: 100.0, prints: 5 };
+        let suppress = FlowPolicy::parse("suppress", 60, 0.5, 200.0).unwrap().unwrap();
+        assert!(decide(&suppress, &selling).unwrap_err().contains("-0.80"));
+        assert!(decide(&suppress, &buying).is_ok());
+        let confirm = FlowPolicy { mode: FlowMode::Confirm, ..suppress };
+        assert!(decide(&confirm, &buying).is_ok());
+        assert!(decide(&confirm, &selling).is_err());
+        // Too little printed volume decides nothing
+        assert!(decide(&confirm, &Flow { buy_usd: 10.0, sell_usd: 90.0, ..selling }).is_ok());
+        let record = FlowPolicy { mode: FlowMode::Record, ..suppress };
+        assert!(decide(&record, &selling).is_ok());
+
+        assert_eq!(FlowPolicy::parse("off", 60, 0.5, 0.0).unwrap(), None);
+        assert!(FlowPolicy::parse("confirm", 0, 0.5, 0.0).is_err());
+        assert!(FlowPolicy::parse("confirm", 60, 1.5, 0.0).is_err());
+        assert!(FlowPolicy::parse("follow", 60, 0.5, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_message_prints() {
+        let msg = json!([
+            { "event_type": "last_trade_price", "asset_id": "a", "price": "0.40", "side": "BUY", "size": "100" },
+            { "event_type": "price_change", "asset_id": "a", "price_changes": [] },
+            { "event_type": "last_trade_price", "asset_id": "b", "price": "0.5", "side": "SELL", "size": "20" }
+        ]);
+        assert_eq!(message_prints(&msg), vec![("a".to_string(), true, 40.0), ("b".to_string(), false, 10.0)]);
+    }
+}
@@ -0,0 +1,122 @@
+//! UTC timestamps
+//! One type for wall-clock times: nanoseconds since the Unix epoch, UTC. Times that are
+//! journaled, sent to another process or compared across runs go through it instead of each
+//! module reading SystemTime or chrono its own way. Intervals measured within one process
+//! (deadlines, round trips) stay on Instant, which cannot jump with the wall clock
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    /// The epoch; also stands for "unknown" in records that predate a timestamp field
+    pub const ZERO: Self = Self(0);
+
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    pub const fn from_nanos(nanos: u64) -> Self {
+        Self(nanos)
+    }
+
+    pub const fn from_millis(ms: u64) -> Self {
+        Self(ms.saturating_mul(1_000_000))
+    }
+
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(secs.saturating_mul(1_000_000_000))
+    }
+
+    pub const fn as_nanos(self) -> u64 {
+        self.0
+    }
+
+    pub const fn as_millis(self) -> u64 {
+        self.0 / 1_000_000
+    }
+
+    pub const fn as_secs(self) -> u64 {
+        self.0 / 1_000_000_000
+    }
+
+    pub const fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Time from `earlier` to `self`; zero when `earlier` is not earlier
+    pub fn since(self, earlier: Self) -> Duration {
+        Duration::from_nanos(self.0.saturating_sub(earlier.0))
+    }
+
+    pub fn to_datetime(self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.as_secs() as i64, (self.0 % 1_000_000_000) as u32).unwrap_or_default()
+    }
+
+    /// `self`, or now when it is unknown (zero)
+    pub fn or_now(self) -> Self {
+        if self.is_zero() { Self::now() } else { self }
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    /// Times before the epoch (a badly set clock) clamp to it
+    fn from(t: SystemTime) -> Self {
+        Self(t.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0))
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(dt: DateTime<Utc>) -> Self {
+        Self(dt.timestamp_nanos_opt().unwrap_or(0).max(0) as u64)
+    }
+}
+
+impl std::ops::Add<Duration> for Timestamp {
+    type Output = Self;
+
+    fn add(self, d: Duration) -> Self {
+        Self(self.0.saturating_add(d.as_nanos() as u64))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    /// RFC 3339 with nanoseconds ("2026-01-02T20:00:00.123456789Z")
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_datetime().format("%Y-%m-%dT%H:%M:%S%.9fZ"))
+    }
+}
+
+/// Unix seconds now
+pub fn unix_secs() -> u64 {
+    Timestamp::now().as_secs()
+}
+
+/// Unix milliseconds now
+pub fn unix_millis() -> u64 {
+    Timestamp::now().as_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let t = Timestamp::from_nanos(1_767_384_000_123_456_789);
+        assert_eq!(t.as_secs(), 1_767_384_000);
+        assert_eq!(t.as_millis(), 1_767_384_000_123);
+        assert_eq!(t.to_string(), "2026-01-02T20:00:00.123456789Z");
+        assert_eq!(Timestamp::from(t.to_datetime()), t);
+        assert_eq!(Timestamp::from(UNIX_EPOCH + Duration::from_nanos(t.as_nanos())), t);
+        assert_eq!((t + Duration::from_millis(5)).since(t), Duration::from_millis(5));
+        assert_eq!(t.since(t + Duration::from_secs(1)), Duration::ZERO);
+        assert_eq!(serde_json::to_string(&Timestamp::from_secs(2)).unwrap(), "2000000000");
+        assert!(!Timestamp::ZERO.or_now().is_zero());
+    }
+}
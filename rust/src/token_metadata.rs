@@ -0,0 +1,393 @@
+//! Token-level metadata cache
+//! Outcome labels, market question, tick size, min order size and neg-risk flag per CLOB token,
+//! persisted to disk and refreshed lazily once an entry is older than its TTL
+
+use crate::fixtures::{JsonSource, LiveSource};
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Cache file path
+const TOKEN_METADATA_CACHE_PATH: &str = ".token_metadata_cache.json";
+
+/// Entries older than this are refetched on next access (tick size can change near 0/1)
+pub const TOKEN_METADATA_TTL_SECS: u64 = 6 * 60 * 60; // 6 hours
+
+/// Tick size assumed when a token has no metadata yet
+pub const DEFAULT_TICK_SIZE: &str = "0.01";
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(2);
+
+// ============================================================================
+// Metadata
+// ============================================================================
+
+/// Static-ish description of a single outcome token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    pub token_id: String,
+    /// Outcome label ("Yes", "No", team name...)
+    pub outcome: String,
+    /// Market question
+    pub question: String,
+    pub slug: String,
+    pub condition_id: String,
+    /// Tick size as the CLOB spells it ("0.1", "0.01", "0.001", "0.0001")
+    pub tick_size: String,
+    /// Minimum order size in shares
+    pub min_order_size: f64,
+    pub neg_risk: bool,
+    /// Unix seconds when the market is scheduled to end (0 = unknown)
+    #[serde(default)]
+    pub end_ts: u64,
+    /// Unix seconds when this entry was fetched
+    pub fetched_at: u64,
+}
+
+impl TokenMetadata {
+    /// Whether this entry is past its TTL and should be refetched
+    #[inline]
+    pub fn is_stale(&self, now: u64, ttl_secs: u64) -> bool {
+        now.saturating_sub(self.fetched_at) >= ttl_secs
+    }
+
+    /// Short human-readable label for logs: "Outcome | question"
+    pub fn label(&self) -> String {
+        if self.question.is_empty() {
+            self.outcome.clone()
+        } else {
+            format!("{} | {}", self.outcome, self.question)
+        }
+    }
+}
+
+/// Map a numeric tick size to the string form used by order rounding
+pub fn tick_size_str(tick: f64) -> Option<&'static str> {
+    const TICKS: [(f64, &str); 4] = [(0.1, "0.1"), (0.01, "0.01"), (0.001, "0.001"), (0.0001, "0.0001")];
+    TICKS.iter()
+        .find(|(t, _)| (tick - t).abs() < 1e-9)
+        .map(|(_, s)| *s)
+}
+
+/// Gamma encodes list fields as JSON strings ("[\"Yes\", \"No\"]"); accept both forms
+fn string_list(v: &Value) -> Vec<String> {
+    match v {
+        Value::String(s) => serde_json::from_str::<Vec<String>>(s).unwrap_or_default(),
+        Value::Array(arr) => arr.iter().filter_map(|x| x.as_str().map(String::from)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Unix seconds of an RFC 3339 date field ("2026-01-02T20:00:00Z"), 0 when absent
+fn date_field(v: &Value) -> u64 {
+    v.as_str()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map_or(0, |d| d.timestamp().max(0) as u64)
+}
+
+fn number_field(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Parse one Gamma market object into metadata for each of its outcome tokens
+pub fn parse_gamma_market(market: &Value, fetched_at: u64) -> Vec<TokenMetadata> {
+    let token_ids = string_list(&market["clobTokenIds"]);
+    let outcomes = string_list(&market["outcomes"]);
+    let tick_size = number_field(&market["orderPriceMinTickSize"])
+        .and_then(tick_size_str)
+        .unwrap_or(DEFAULT_TICK_SIZE);
+    let min_order_size = number_field(&market["orderMinSize"]).unwrap_or(0.0);
+    let text = |key: &str| market[key].as_str().unwrap_or_default().to_string();
+
+    token_ids.into_iter()
+        .enumerate()
+        .map(|(i, token_id)| TokenMetadata {
+            token_id,
+            outcome: outcomes.get(i).cloned().unwrap_or_default(),
+            question: text("question"),
+            slug: text("slug"),
+            condition_id: text("conditionId"),
+            tick_size: tick_size.to_string(),
+            min_order_size,
+            neg_risk: market["negRisk"].as_bool().unwrap_or(false),
+            end_ts: date_field(&market["endDate"]),
+            fetched_at,
+        })
+        .collect()
+}
+
+/// Payout per share of `token_id` once its market is closed (1 winner, 0 loser)
+pub fn settlement_price(market: &Value, token_id: &str) -> Option<f64> {
+    if !market["closed"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    let i = string_list(&market["clobTokenIds"]).iter().position(|t| t == token_id)?;
+    string_list(&market["outcomePrices"]).get(i)?.parse().ok()
+}
+
+// ============================================================================
+// Cache
+// ============================================================================
+
+pub struct TokenMetadataCache {
+    entries: RwLock<FxHashMap<String, TokenMetadata>>,
+    ttl_secs: u64,
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
+    pub fetches: AtomicU64,
+}
+
+impl TokenMetadataCache {
+    pub fn new(ttl_secs: u64) -> Self {
+        Self {
+            entries: RwLock::new(FxHashMap::default()),
+            ttl_secs,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            fetches: AtomicU64::new(0),
+        }
+    }
+
+    /// Load persisted entries from disk (returns number loaded)
+    pub fn load(&self) -> usize {
+        let Ok(data) = std::fs::read_to_string(TOKEN_METADATA_CACHE_PATH) else { return 0 };
+        let Ok(map) = serde_json::from_str::<HashMap<String, TokenMetadata>>(&data) else { return 0 };
+        let count = map.len();
+        if let Ok(mut cache) = self.entries.write() {
+            cache.extend(map);
+        }
+        count
+    }
+
+    /// Write entries to disk on a background thread
+    pub fn persist(&self) {
+        let Ok(cache) = self.entries.read() else { return };
+        let Ok(data) = serde_json::to_string(&*cache) else { return };
+        std::thread::spawn(move || { let _ = std::fs::write(TOKEN_METADATA_CACHE_PATH, data); });
+    }
+
+    /// Get metadata for a token (stale entries are still returned; use `needs_fetch` to refresh)
+    #[inline]
+    pub fn get(&self, token_id: &str) -> Option<TokenMetadata> {
+        let found = self.entries.read().ok()?.get(token_id).cloned();
+        if found.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        found
+    }
+
+    /// True if the token is unknown or its entry is past the TTL
+    pub fn needs_fetch(&self, token_id: &str) -> bool {
+        let now = timestamp::unix_secs();
+        self.entries.read()
+            .map(|c| c.get(token_id).is_none_or(|m| m.is_stale(now, self.ttl_secs)))
+            .unwrap_or(true)
+    }
+
+    /// Insert entries (all tokens of a market arrive together)
+    pub fn insert_all(&self, items: Vec<TokenMetadata>) {
+        if let Ok(mut cache) = self.entries.write() {
+            for m in items {
+                cache.insert(m.token_id.clone(), m);
+            }
+        }
+    }
+
+    /// Drop entries not refreshed for `max_age_secs` (returns number removed)
+    pub fn evict_older_than(&self, max_age_secs: u64) -> usize {
+        let now = timestamp::unix_secs();
+        let Ok(mut cache) = self.entries.write() else { return 0 };
+        let before = cache.len();
+        cache.retain(|_, m| !m.is_stale(now, max_age_secs));
+        before - cache.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|c| c.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_stats_summary(&self) -> String {
+        format!(
+            "Token metadata: entries={}, hits={}, misses={}, fetches={}",
+            self.len(),
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+            self.fetches.load(Ordering::Relaxed),
+        )
+    }
+}
+
+// ============================================================================
+// Fetching
+// ============================================================================
+
+/// Gamma lookup of the market holding `token_id` (a one-element array; one request returns
+/// every token of the market)
+pub fn gamma_market_url(token_id: &str) -> String {
+    format!("{}/markets?clob_token_ids={}", crate::settings::gamma_api_base(), token_id)
+}
+
+/// Fetch metadata for a token (and its sibling outcomes) from the Gamma API and cache it
+pub async fn fetch_token_metadata(token_id: &str, client: &reqwest::Client) -> Option<TokenMetadata> {
+    fetch_token_metadata_from(token_id, &LiveSource { client: client.clone(), timeout: FETCH_TIMEOUT }).await
+}
+
+pub async fn fetch_token_metadata_from(token_id: &str, source: &dyn JsonSource) -> Option<TokenMetadata> {
+    let cache = global_token_metadata();
+    let val = source.get_json(&gamma_market_url(token_id)).await?;
+
+    let items = parse_gamma_market(val.get(0)?, timestamp::unix_secs());
+    let found = items.iter().find(|m| m.token_id == token_id).cloned();
+    cache.fetches.fetch_add(1, Ordering::Relaxed);
+    cache.insert_all(items);
+    cache.persist();
+    found
+}
+
+/// Payout of a resolved token (None while its market is open or on fetch errors)
+pub async fn fetch_settlement(token_id: &str, client: &reqwest::Client) -> Option<f64> {
+    fetch_settlement_from(token_id, &LiveSource { client: client.clone(), timeout: FETCH_TIMEOUT }).await
+}
+
+pub async fn fetch_settlement_from(token_id: &str, source: &dyn JsonSource) -> Option<f64> {
+    let val = source.get_json(&gamma_market_url(token_id)).await?;
+    settlement_price(val.get(0)?, token_id)
+}
+
+/// Refresh metadata in the background if the token is unknown or stale
+pub fn spawn_refresh_if_needed(token_id: &str, client: &reqwest::Client) {
+    if !global_token_metadata().needs_fetch(token_id) {
+        return;
+    }
+    let token_id = token_id.to_string();
+    let client = client.clone();
+    tokio::spawn(async move {
+        if fetch_token_metadata(&token_id, &client).await.is_none() {
+            crate::console_eprintln!("⚠️ Token metadata fetch failed for {}", token_id);
+        }
+    });
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_TOKEN_METADATA: OnceLock<TokenMetadataCache> = OnceLock::new();
+
+/// Get the global token metadata cache
+pub fn global_token_metadata() -> &'static TokenMetadataCache {
+    GLOBAL_TOKEN_METADATA.get_or_init(|| TokenMetadataCache::new(TOKEN_METADATA_TTL_SECS))
+}
+
+/// Load persisted metadata (call once at startup)
+pub fn init_token_metadata() -> usize {
+    let count = global_token_metadata().load();
+    crate::console_println!("🏷️ Loaded token metadata for {} tokens", count);
+    count
+}
+
+/// Get metadata for a token (convenience function)
+#[inline]
+pub fn get(token_id: &str) -> Option<TokenMetadata> {
+    global_token_metadata().get(token_id)
+}
+
+/// Get tick size for a token, falling back to the default tick
+#[inline]
+pub fn tick_size(token_id: &str) -> String {
+    get(token_id).map(|m| m.tick_size).unwrap_or_else(|| DEFAULT_TICK_SIZE.to_string())
+}
+
+/// Get a display label for a token if known (convenience function)
+#[inline]
+pub fn label(token_id: &str) -> Option<String> {
+    get(token_id).map(|m| m.label())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_market() -> Value {
+        serde_json::json!({
+            "question": "Will Nadal win?",
+            "slug": "nadal-vs-federer",
+            "conditionId": "0xabc",
+            "clobTokenIds": "[\"111\", \"222\"]",
+            "outcomes": "[\"Yes\", \"No\"]",
+            "orderPriceMinTickSize": 0.001,
+            "orderMinSize": 5,
+            "negRisk": true,
+            "endDate": "2026-01-02T20:00:00Z"
+        })
+    }
+
+    #[test]
+    fn test_parse_gamma_market() {
+        let items = parse_gamma_market(&sample_market(), 100);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].token_id, "111");
+        assert_eq!(items[0].outcome, "Yes");
+        assert_eq!(items[1].outcome, "No");
+        assert_eq!(items[1].tick_size, "0.001");
+        assert_eq!(items[1].min_order_size, 5.0);
+        assert!(items[1].neg_risk);
+        assert_eq!(items[0].end_ts, 1_767_384_000);
+        assert_eq!(items[0].label(), "Yes | Will Nadal win?");
+    }
+
+    #[test]
+    fn test_settlement_price_only_when_closed() {
+        let mut market = sample_market();
+        market["outcomePrices"] = serde_json::json!("[\"0\", \"1\"]");
+        assert_eq!(settlement_price(&market, "222"), None);
+        market["closed"] = serde_json::json!(true);
+        assert_eq!(settlement_price(&market, "111"), Some(0.0));
+        assert_eq!(settlement_price(&market, "222"), Some(1.0));
+        assert_eq!(settlement_price(&market, "333"), None);
+    }
+
+    #[test]
+    fn test_tick_size_str() {
+        assert_eq!(tick_size_str(0.01), Some("0.01"));
+        assert_eq!(tick_size_str(0.0001), Some("0.0001"));
+        assert_eq!(tick_size_str(0.05), None);
+    }
+
+    #[test]
+    fn test_staleness_and_refetch() {
+        let cache = TokenMetadataCache::new(60);
+        assert!(cache.needs_fetch("111"));
+
+        cache.insert_all(parse_gamma_market(&sample_market(), timestamp::unix_secs()));
+        assert!(!cache.needs_fetch("111"));
+        assert_eq!(cache.get("222").map(|m| m.outcome), Some("No".to_string()));
+
+        let old = parse_gamma_market(&sample_market(), timestamp::unix_secs() - 120);
+        cache.insert_all(old);
+        assert!(cache.needs_fetch("111"));
+
+        assert_eq!(cache.evict_older_than(3600), 0);
+        assert_eq!(cache.evict_older_than(60), 2);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+//! Account trade-history sync
+//! Polls the authenticated trade history for fills on this account and merges those the bot
+//! did not send (manual UI trades, another client) into the position tracker and this tag's
+//! strategy ledger, so exposure and the stop-loss always match the account. Own orders are
+//! recognised by the order hash, which every order signed in this process registers
+
+use crate::display;
+use crate::position_tracker::PositionTracker;
+use crate::strategy::{strategy_ledger, StrategyLedger};
+use crate::{PreparedCreds, RustClobClient};
+use crate::timestamp;
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// One line per external fill merged into the tracker
+pub const EXTERNAL_FILLS_FILE: &str = "external_fills.jsonl";
+
+/// Re-query this far back each poll; trades already seen are skipped by id
+const SYNC_SLACK_SECS: u64 = 120;
+
+/// Own order hashes and seen trade ids kept for dedup
+const REMEMBER: usize = 20_000;
+
+// ============================================================================
+// Fills
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountFill {
+    pub trade_id: String,
+    pub order_id: String,
+    pub token_id: String,
+    pub is_buy: bool,
+    pub shares: f64,
+    pub price: f64,
+    pub ts: u64,
+}
+
+fn num(v: &Value) -> Option<f64> {
+    v.as_str().and_then(|s| s.parse().ok()).or_else(|| v.as_f64())
+}
+
+/// This account's side of every trade in a `/data/trades` response. As taker the trade's own
+/// fields apply; as maker, each maker order placed by `funder` is a fill of its own
+pub fn parse_fills(trades: &Value, funder: &str) -> Vec<AccountFill> {
+    let Some(list) = trades["data"].as_array().or_else(|| trades.as_array()) else { return Vec::new() };
+    let mut fills = Vec::new();
+    for t in list {
+        let trade_id = t["id"].as_str().unwrap_or_default();
+        let ts = t["match_time"].as_str().and_then(|s| s.parse::<u64>().ok())
+            .or_else(|| t["match_time"].as_u64())
+            .unwrap_or(0);
+        let taker_buy = t["side"].as_str().is_some_and(|s| s.eq_ignore_ascii_case("BUY"));
+
+        if t["trader_side"].as_str().is_some_and(|s| s.eq_ignore_ascii_case("MAKER")) {
+            let makers = t["maker_orders"].as_array().map(Vec::as_slice).unwrap_or_default();
+            for m in makers.iter().filter(|m| m["maker_address"].as_str().is_some_and(|a| a.eq_ignore_ascii_case(funder))) {
+                let (Some(shares), Some(price)) = (num(&m["matched_amount"]), num(&m["price"])) else { continue };
+                let order_id = m["order_id"].as_str().unwrap_or_default();
+                fills.push(AccountFill {
+                    trade_id: format!("{}:{}", trade_id, order_id),
+                    order_id: order_id.to_lowercase(),
+                    token_id: m["asset_id"].as_str().or_else(|| t["asset_id"].as_str()).unwrap_or_default().to_string(),
+                    // A maker takes the other side of the taker
+                    is_buy: m["side"].as_str().map_or(!taker_buy, |s| s.eq_ignore_ascii_case("BUY")),
+                    shares,
+                    price,
+                    ts,
+                });
+            }
+        } else {
+            let (Some(shares), Some(price)) = (num(&t["size"]), num(&t["price"])) else { continue };
+            fills.push(AccountFill {
+                trade_id: trade_id.to_string(),
+                order_id: t["taker_order_id"].as_str().unwrap_or_default().to_lowercase(),
+                token_id: t["asset_id"].as_str().unwrap_or_default().to_string(),
+                is_buy: taker_buy,
+                shares,
+                price,
+                ts,
+            });
+        }
+    }
+    fills
+}
+
+// ============================================================================
+// Dedup Sets
+// ============================================================================
+
+/// Bounded FIFO set of ids
+#[derive(Default)]
+pub struct RecentIds {
+    inner: Mutex<(FxHashSet<String>, VecDeque<String>)>,
+}
+
+impl RecentIds {
+    /// False if the id was already present
+    pub fn insert(&self, id: &str) -> bool {
+        let Ok(mut guard) = self.inner.lock() else { return true };
+        let (set, order) = &mut *guard;
+        if !set.insert(id.to_string()) {
+            return false;
+        }
+        order.push_back(id.to_string());
+        if order.len() > REMEMBER
+            && let Some(old) = order.pop_front() {
+                set.remove(&old);
+            }
+        true
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.inner.lock().is_ok_and(|g| g.0.contains(id))
+    }
+}
+
+/// Called for every order signed in this process, with its EIP-712 hash (the exchange order id)
+pub fn register_own_order(order_hash: &str) {
+    own_orders().insert(&order_hash.to_lowercase());
+}
+
+// ============================================================================
+// Sync Task
+// ============================================================================
+
+/// Apply one fill if it is new and not ours. Returns true when it was merged
+pub async fn merge(fill: &AccountFill, tracker: &PositionTracker, ledger: &StrategyLedger, seen: &RecentIds) -> bool {
+    if !seen.insert(&fill.trade_id) || own_orders().contains(&fill.order_id) || fill.shares <= 0.0 {
+        return false;
+    }
+    if fill.is_buy {
+        tracker.add_position(fill.token_id.clone(), fill.price, fill.shares).await;
+    } else {
+        tracker.reduce_position(&fill.token_id, fill.shares).await;
+    }
+    ledger.record(&fill.token_id, fill.is_buy, fill.shares, fill.price);
+    true
+}
+
+fn journal(fill: &AccountFill, path: &str) -> std::io::Result<()> {
+    let line = serde_json::to_string(fill).map_err(std::io::Error::other)?;
+    writeln!(OpenOptions::new().append(true).create(true).open(path)?, "{}", line)
+}
+
+fn fetch(client: &RustClobClient, creds: &PreparedCreds, funder: &str, after: u64) -> Option<Value> {
+    let query = format!("maker_address={}&after={}", funder, after);
+    client.get_l2("/data/trades", &query, creds)
+        .ok()
+        .filter(|r| r.status().is_success())
+        .and_then(|r| r.json::<Value>().ok())
+}
+
+/// Poll trade history every `interval`, starting from now
+pub fn spawn_trade_sync_task(
+    tracker: Arc<PositionTracker>,
+    client: Arc<RustClobClient>,
+    funder: String,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let seen = RecentIds::default();
+        let mut cursor = timestamp::unix_secs();
+        loop {
+            tokio::time::sleep(interval).await;
+            let (c, pc, f, after) = (Arc::clone(&client), crate::credentials::current(), funder.clone(), cursor.saturating_sub(SYNC_SLACK_SECS));
+            let trades = tokio::task::spawn_blocking(move || fetch(&c, &pc, &f, after)).await.ok().flatten();
+            let Some(trades) = trades else {
+                crate::console_eprintln!("⚠️ Trade history sync failed, retrying in {}s", interval.as_secs());
+                continue;
+            };
+            for fill in parse_fills(&trades, &funder) {
+                cursor = cursor.max(fill.ts);
+                if merge(&fill, &tracker, strategy_ledger(), &seen).await {
+                    if let Err(e) = journal(&fill, EXTERNAL_FILLS_FILE) {
+                        crate::console_eprintln!("⚠️ External fill journal write failed: {}", e);
+                    }
+                    crate::console_println!(
+                        "🔁 External fill {} {} @ {} on {} merged into positions",
+                        if fill.is_buy { "BUY" } else { "SELL" }, display::shares(fill.shares), display::price(&fill.token_id, fill.price), fill.token_id
+                    );
+                }
+            }
+        }
+    })
+}
+
+// ============================================================================
+// Global Instance
+// ============================================================================
+
+static GLOBAL_OWN_ORDERS: OnceLock<RecentIds> = OnceLock::new();
+
+pub fn own_orders() -> &'static RecentIds {
+    GLOBAL_OWN_ORDERS.get_or_init(RecentIds::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_taker_and_maker_fills() {
+        let trades = json!({ "data": [
+            { "id": "t1", "trader_side": "TAKER", "taker_order_id": "0xAB", "asset_id": "111",
+              "side": "BUY", "size": "10", "price": "0.42", "match_time": "1700000000", "maker_orders": [] },
+            { "id": "t2", "trader_side": "MAKER", "asset_id": "111", "side": "BUY", "size": "30", "price": "0.40",
+              "match_time": "1700000100", "maker_orders": [
+                { "order_id": "0x01", "maker_address": "0xOTHER", "matched_amount": "20", "price": "0.40", "asset_id": "111" },
+                { "order_id": "0x02", "maker_address": "0xfunder", "matched_amount": "10", "price": "0.40", "asset_id": "111" }
+            ] },
+        ]});
+        let fills = parse_fills(&trades, "0xFUNDER");
+        assert_eq!(fills.len(), 2);
+        assert_eq!((fills[0].order_id.as_str(), fills[0].is_buy, fills[0].shares), ("0xab", true, 10.0));
+        // Our maker order sold into the taker's buy
+        assert_eq!((fills[1].trade_id.as_str(), fills[1].is_buy, fills[1].shares, fills[1].ts), ("t2:0x02", false, 10.0, 1700000100));
+    }
+
+    #[tokio::test]
+    async fn test_merge_skips_own_and_repeated_fills() {
+        let tracker = PositionTracker::new();
+        let ledger = StrategyLedger::new("default", None, None);
+        let seen = RecentIds::default();
+        let fill = |trade: &str, order: &str| AccountFill {
+            trade_id: trade.into(), order_id: order.into(), token_id: "sync-test".into(),
+            is_buy: true, shares: 10.0, price: 0.5, ts: 0,
+        };
+        register_own_order("0xOWN");
+        assert!(!merge(&fill("a", "0xown"), &tracker, &ledger, &seen).await);
+        assert!(tracker.get_position("sync-test").await.is_none());
+        assert!(merge(&fill("b", "0xmanual"), &tracker, &ledger, &seen).await);
+        assert!(!merge(&fill("b", "0xmanual"), &tracker, &ledger, &seen).await);
+        assert_eq!(tracker.get_position("sync-test").await.map(|p| p.shares), Some(10.0));
+        assert_eq!(ledger.held("sync-test"), Some((10.0, 0.5)));
+    }
+}
@@ -0,0 +1,169 @@
+//! Terminal UI blotter (cargo feature `tui`)
+//! Panels: open positions (with the settlement lockup), working orders, fills tape, book prices and the signal log.
+//! Press `q` to quit.
+
+use crate::asset_state::asset_states;
+use crate::blotter::{self, blotter};
+use crate::display;
+use crate::position_tracker::PositionTracker;
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::{self, Event, KeyCode};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table};
+use ratatui::Frame;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Redraw cadence
+const FRAME_INTERVAL: Duration = Duration::from_millis(250);
+
+struct PositionView {
+    token_id: String,
+    entry_price: f64,
+    shares: f64,
+    age_secs: u64,
+}
+
+/// Take over the terminal and render the blotter until the user presses `q`
+pub fn spawn_tui(tracker: Arc<PositionTracker>) -> tokio::task::JoinHandle<()> {
+    blotter::set_tui_active(true);
+    tokio::spawn(async move {
+        let mut terminal = ratatui::init();
+        let mut interval = tokio::time::interval(FRAME_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let positions: Vec<PositionView> = tracker.get_all_positions().await
+                .into_iter()
+                .map(|p| PositionView {
+                    age_secs: p.age_secs(),
+                    token_id: p.token_id,
+                    entry_price: p.entry_price,
+                    shares: p.shares,
+                })
+                .collect();
+
+            if let Err(e) = terminal.draw(|f| render(f, &positions)) {
+                ratatui::restore();
+                blotter::set_tui_active(false);
+                eprintln!("⚠️ TUI draw failed, falling back to headless output: {}", e);
+                return;
+            }
+
+            while event::poll(Duration::ZERO).unwrap_or(false) {
+                if let Ok(Event::Key(key)) = event::read()
+                    && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        ratatui::restore();
+                        blotter::set_tui_active(false);
+                        println!("👋 Quit from TUI");
+                        std::process::exit(0);
+                    }
+            }
+        }
+    })
+}
+
+fn short_token(token_id: &str) -> String {
+    if token_id.len() > 14 {
+        format!("{}…{}", &token_id[..6], &token_id[token_id.len() - 6..])
+    } else {
+        token_id.to_string()
+    }
+}
+
+fn clock(at: SystemTime) -> String {
+    DateTime::<Local>::from(at).format("%H:%M:%S").to_string()
+}
+
+fn panel(title: &str) -> Block<'_> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+fn render(f: &mut Frame, positions: &[PositionView]) {
+    let header_style = Style::default().add_modifier(Modifier::BOLD).fg(Color::Yellow);
+    let [top, middle, bottom] = Layout::vertical([
+        Constraint::Percentage(30),
+        Constraint::Percentage(30),
+        Constraint::Percentage(40),
+    ]).areas(f.area());
+    let [positions_area, working_area] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(top);
+    let [fills_area, prices_area] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(middle);
+
+    // Positions, titled with the worst case and the capital waiting on settlement
+    let lockup = crate::lockup::current_forecast();
+    let at_risk = crate::capital_at_risk::current().at_risk_usd;
+    let positions_title = if lockup.locked_usd > 0.0 {
+        let limit = crate::capital_at_risk::limit().map(|max| format!("/{}", display::usd(max))).unwrap_or_default();
+        format!(
+            " Positions: {}{} at risk, {} decided, {} back in 24h ",
+            display::usd(at_risk), limit, display::usd(lockup.decided_usd), display::usd(lockup.within_day_usd)
+        )
+    } else {
+        " Positions ".to_string()
+    };
+    let rows = positions.iter().map(|p| Row::new(vec![
+        short_token(&p.token_id),
+        display::avg_price(&p.token_id, p.entry_price),
+        display::shares(p.shares),
+        display::usd(p.entry_price * p.shares),
+        format!("{}s", p.age_secs),
+    ]));
+    let table = Table::new(rows, [Constraint::Length(16), Constraint::Length(8), Constraint::Length(10), Constraint::Length(10), Constraint::Length(8)])
+        .header(Row::new(vec!["token", "entry", "shares", "cost", "age"]).style(header_style))
+        .block(panel(&positions_title));
+    f.render_widget(table, positions_area);
+
+    // Working orders (tokens mid-entry, mid-exit or settling)
+    let rows = asset_states().snapshot().into_iter().map(|(token, phase, age)| Row::new(vec![
+        short_token(&token),
+        phase.as_str().to_string(),
+        format!("{:.1}s", age.as_secs_f64()),
+    ]));
+    let table = Table::new(rows, [Constraint::Length(16), Constraint::Length(10), Constraint::Length(8)])
+        .header(Row::new(vec!["token", "state", "age"]).style(header_style))
+        .block(panel(" Working "));
+    f.render_widget(table, working_area);
+
+    // Fills tape
+    let tape_rows = fills_area.height.saturating_sub(3) as usize;
+    let rows = blotter().recent_fills(tape_rows).into_iter().map(|fill| {
+        let color = if fill.side == "BUY" { Color::Green } else { Color::Red };
+        Row::new(vec![
+            clock(fill.at),
+            fill.side.to_string(),
+            short_token(&fill.token_id),
+            display::shares(fill.shares),
+            display::avg_price(&fill.token_id, fill.price),
+            format!("{:.0}", fill.whale_shares),
+        ]).style(Style::default().fg(color))
+    });
+    let table = Table::new(rows, [Constraint::Length(9), Constraint::Length(5), Constraint::Length(16), Constraint::Length(9), Constraint::Length(8), Constraint::Length(8)])
+        .header(Row::new(vec!["time", "side", "token", "shares", "price", "whale"]).style(header_style))
+        .block(panel(" Fills "));
+    f.render_widget(table, fills_area);
+
+    // Book prices
+    let price_rows = prices_area.height.saturating_sub(3) as usize;
+    let rows = blotter().prices(price_rows).into_iter().map(|(token, p)| Row::new(vec![
+        short_token(&token),
+        format!("{} @ {}", p.best_price, p.best_size),
+        p.second_price,
+        clock(p.updated_at),
+    ]));
+    let table = Table::new(rows, [Constraint::Length(16), Constraint::Length(18), Constraint::Length(8), Constraint::Length(9)])
+        .header(Row::new(vec!["token", "best", "2nd", "updated"]).style(header_style))
+        .block(panel(" Prices "));
+    f.render_widget(table, prices_area);
+
+    // Signal log
+    let log_rows = bottom.height.saturating_sub(2) as usize;
+    let lines: Vec<ratatui::text::Line> = blotter().recent_log(log_rows).into_iter()
+        .map(|l| {
+            let style = if l.is_error { Style::default().fg(Color::Red) } else { Style::default() };
+            ratatui::text::Line::styled(format!("{} {}", clock(l.at), l.text), style)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines).block(panel(" Log (q to quit) ")), bottom);
+}
@@ -0,0 +1,261 @@
+//! Post-trade what-if for skipped whale trades
+//! Every whale trade one of our filters rejected is re-priced after a fixed horizon, so the
+//! opportunity cost (or the loss avoided) of each filter can be measured instead of guessed
+
+use crate::position_tracker::PriceFetcher;
+use crate::timestamp;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Resolved what-ifs (one JSON object per line)
+pub const WHAT_IF_FILE: &str = "what_if.jsonl";
+
+/// How long after the skipped trade it is marked to market
+pub const WHAT_IF_HORIZON: Duration = Duration::from_secs(15 * 60);
+
+/// How often pending what-ifs are checked
+const WHAT_IF_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Bound on pending what-ifs (oldest dropped first during whale bursts)
+const MAX_PENDING: usize = 2_000;
+
+/// Statuses that mean a filter decided against the trade. Others (disabled, mock,
+/// busy, duplicate intent) are mechanics, not filters
+const FILTER_PREFIXES: [&str; 15] = [
+    "SKIPPED_SMALL",
+    "RISK_BLOCKED",
+    "SKIPPED_PROBABILITY",
+    "SKIPPED_MIN_SIZE",
+    "SKIPPED_DUST",
+    "SKIPPED_STRATEGY_CAP",
+    "SKIPPED_DEPTH_CAP",
+    "SKIPPED_RR",
+    "SKIPPED_CONFLICT",
+    "SKIPPED_CLUSTER",
+    "SKIPPED_QUALITY",
+    "SKIPPED_QUOTE_SURGE",
+    "SKIPPED_TAKER_FLOW",
+    "SKIPPED_CASH_RESERVE",
+    "SKIPPED_CAPITAL_AT_RISK",
+];
+
+/// Filter name from an order status ("RISK_BLOCKED:THIN_BOOK", "SKIPPED_SMALL", ...)
+pub fn filter_name(status: &str) -> Option<&str> {
+    if !FILTER_PREFIXES.iter().any(|p| status.starts_with(p)) {
+        return None;
+    }
+    let end = status.find([' ', '(']).unwrap_or(status.len());
+    Some(status[..end].trim_end())
+}
+
+// ============================================================================
+// Records
+// ============================================================================
+
+#[derive(Debug, Clone)]
+struct Pending {
+    at: Instant,
+    ts: u64,
+    filter: String,
+    token_id: String,
+    is_buy: bool,
+    entry_price: f64,
+    shares: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WhatIf {
+    /// Unix seconds of the skipped whale trade
+    pub ts: u64,
+    pub filter: String,
+    pub token_id: String,
+    pub side: &'static str,
+    pub entry_price: f64,
+    pub mark_price: f64,
+    pub shares: f64,
+    /// What the copy would have made (negative = loss the filter avoided)
+    pub pnl: f64,
+}
+
+/// Hypothetical P&L of a copy entered at `entry` and marked at `mark`
+pub fn what_if_pnl(is_buy: bool, entry: f64, mark: f64, shares: f64) -> f64 {
+    if is_buy { (mark - entry) * shares } else { (entry - mark) * shares }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilterOutcome {
+    pub resolved: u64,
+    /// Skips the copy would have profited from
+    pub missed_wins: u64,
+    pub pnl: f64,
+}
+
+// ============================================================================
+// Tracker
+// ============================================================================
+
+#[derive(Default)]
+pub struct WhatIfTracker {
+    pending: Mutex<VecDeque<Pending>>,
+    by_filter: Mutex<FxHashMap<String, FilterOutcome>>,
+}
+
+impl WhatIfTracker {
+    /// Remember a rejected trade if `status` names a filter
+    pub fn record_rejection(&self, status: &str, token_id: &str, is_buy: bool, entry_price: f64, shares: f64) {
+        let Some(filter) = filter_name(status) else { return };
+        let Ok(mut pending) = self.pending.lock() else { return };
+        if pending.len() >= MAX_PENDING {
+            pending.pop_front();
+        }
+        pending.push_back(Pending {
+            at: Instant::now(),
+            ts: timestamp::unix_secs(),
+            filter: filter.to_string(),
+            token_id: token_id.to_string(),
+            is_buy,
+            entry_price,
+            shares,
+        });
+    }
+
+    fn take_due(&self, horizon: Duration) -> Vec<Pending> {
+        let Ok(mut pending) = self.pending.lock() else { return Vec::new() };
+        let mut due = Vec::new();
+        while pending.front().is_some_and(|p| p.at.elapsed() >= horizon) {
+            due.extend(pending.pop_front());
+        }
+        due
+    }
+
+    fn resolve(&self, p: Pending, mark_price: f64) -> WhatIf {
+        let pnl = what_if_pnl(p.is_buy, p.entry_price, mark_price, p.shares);
+        if let Ok(mut by_filter) = self.by_filter.lock() {
+            let o = by_filter.entry(p.filter.clone()).or_default();
+            o.resolved += 1;
+            o.pnl += pnl;
+            if pnl > 0.0 {
+                o.missed_wins += 1;
+            }
+        }
+        WhatIf {
+            ts: p.ts,
+            filter: p.filter,
+            token_id: p.token_id,
+            side: if p.is_buy { "BUY" } else { "SELL" },
+            entry_price: p.entry_price,
+            mark_price,
+            shares: p.shares,
+            pnl,
+        }
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.lock().map(|p| p.len()).unwrap_or(0)
+    }
+
+    /// Per-filter opportunity cost lines for the diagnostics dump
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let Ok(by_filter) = self.by_filter.lock() else { return out };
+        let mut rows: Vec<_> = by_filter.iter().collect();
+        rows.sort_by(|a, b| a.0.cmp(b.0));
+        for (filter, o) in rows {
+            let _ = writeln!(
+                out, "  what-if {:<28} skipped={} would_win={} pnl={:+.2}",
+                filter, o.resolved, o.missed_wins, o.pnl
+            );
+        }
+        out
+    }
+
+    /// Mark every due rejection to market; unpriceable ones are dropped
+    pub async fn resolve_due(&self, fetcher: &dyn PriceFetcher, horizon: Duration) -> Vec<WhatIf> {
+        let mut resolved = Vec::new();
+        for p in self.take_due(horizon) {
+            if let Some(mark) = fetcher.get_current_price(&p.token_id).await {
+                resolved.push(self.resolve(p, mark));
+            }
+        }
+        resolved
+    }
+}
+
+static GLOBAL_WHAT_IF: OnceLock<WhatIfTracker> = OnceLock::new();
+
+/// Get the global what-if tracker
+pub fn what_if() -> &'static WhatIfTracker {
+    GLOBAL_WHAT_IF.get_or_init(WhatIfTracker::default)
+}
+
+/// Spawn the task that marks skipped trades to market and appends them to `WHAT_IF_FILE`
+pub fn spawn_what_if_task(fetcher: Arc<dyn PriceFetcher>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(WHAT_IF_CHECK_INTERVAL).await;
+            let resolved = what_if().resolve_due(fetcher.as_ref(), WHAT_IF_HORIZON).await;
+            if resolved.is_empty() {
+                continue;
+            }
+            let mut lines = String::new();
+            for w in &resolved {
+                if let Ok(line) = serde_json::to_string(w) {
+                    lines.push_str(&line);
+                    lines.push('\n');
+                }
+            }
+            let written = OpenOptions::new().append(true).create(true).open(WHAT_IF_FILE)
+                .and_then(|mut f| f.write_all(lines.as_bytes()));
+            if let Err(e) = written {
+                crate::console_eprintln!("⚠️ What-if journal write failed: {}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedPrice(f64);
+
+    #[async_trait::async_trait]
+    impl PriceFetcher for FixedPrice {
+        async fn get_current_price(&self, _token_id: &str) -> Option<f64> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn test_filter_name() {
+        assert_eq!(filter_name("SKIPPED_SMALL (<10 shares)"), Some("SKIPPED_SMALL"));
+        assert_eq!(filter_name("RISK_BLOCKED:THIN_BOOK"), Some("RISK_BLOCKED:THIN_BOOK"));
+        assert_eq!(filter_name("SKIPPED_BUSY (EXITING)"), None);
+        assert_eq!(filter_name("200 OK [SCALED]"), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolves_and_aggregates_per_filter() {
+        let t = WhatIfTracker::default();
+        t.record_rejection("SKIPPED_SMALL (<10 shares)", "a", true, 0.40, 10.0);
+        t.record_rejection("SKIPPED_SMALL (<10 shares)", "b", false, 0.40, 10.0);
+        t.record_rejection("MOCK_ONLY", "c", true, 0.40, 10.0);
+        assert_eq!(t.pending_len(), 2);
+
+        let resolved = t.resolve_due(&FixedPrice(0.50), Duration::ZERO).await;
+        assert_eq!(resolved.len(), 2);
+        assert!((resolved[0].pnl - 1.0).abs() < 1e-9);
+        assert!((resolved[1].pnl + 1.0).abs() < 1e-9);
+        assert!(t.report().contains("skipped=2 would_win=1 pnl=+0.00"));
+    }
+}
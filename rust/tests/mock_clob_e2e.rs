@@ -0,0 +1,96 @@
+//! End-to-end run of the pm_bot binary against the mock exchange: whale fill on the log
+//! feed, copy order matched on the mock book, market resolved, settlement attributed.
+//! Spawns the bot, so it only runs on request: cargo test --test mock_clob_e2e -- --ignored
+
+use pm_whale_follower::mock_clob::{self, MockBook, MockClob, MockMarket};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+const WHALE: &str = "204f72f35326db932158cba6adff0b9a1da95e14";
+const YES: &str = "1001";
+const NO: &str = "1002";
+
+/// Well-known development key; nothing is signed for a real exchange
+const PRIVATE_KEY: &str = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+const FUNDER: &str = "70997970C51812dc3A010C7d01b50e0d17dc79C8";
+
+/// Kills the bot when the test ends, passed or not
+struct Bot(Child);
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+async fn wait_for(what: &str, timeout: Duration, done: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !done() {
+        assert!(start.elapsed() < timeout, "timed out waiting for {}", what);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+fn spawn_bot(dir: &Path, env: &[(&'static str, String)]) -> Bot {
+    let mut vars: Vec<(&str, String)> = env.to_vec();
+    vars.extend([
+        ("PRIVATE_KEY", PRIVATE_KEY.to_string()),
+        ("FUNDER_ADDRESS", FUNDER.to_string()),
+        ("TARGET_WHALE_ADDRESS", WHALE.to_string()),
+        ("ENABLE_TRADING", "true".to_string()),
+        ("MOCK_TRADING", "false".to_string()),
+        ("SHADOW_TRADING", "false".to_string()),
+        ("TRADE_SYNC_SECS", "0".to_string()),
+        ("SETTLEMENT_CHECK_SECS", "1".to_string()),
+        ("LIVE_CONFIRM", "i-understand-live-risk".to_string()),
+    ]);
+    // The bot refuses to start without a .env; the same values are also passed directly so an
+    // inherited environment cannot override them
+    let dotenv: String = vars.iter().map(|(k, v)| format!("{}={}\n", k, v)).collect();
+    std::fs::write(dir.join(".env"), dotenv).unwrap();
+    let child = Command::new(env!("CARGO_BIN_EXE_pm_bot"))
+        .current_dir(dir)
+        .envs(vars)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    Bot(child)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "spawns pm_bot; run with --ignored"]
+async fn test_whale_fill_to_settlement() {
+    let mock = MockClob::new(WHALE, 1_000.0);
+    mock.add_market(MockMarket::binary("mock-e2e-market", YES, NO));
+    mock.set_book(YES, MockBook::new(vec![(0.48, 2_000.0)], vec![(0.50, 2_000.0), (0.51, 2_000.0), (0.52, 2_000.0)]));
+    mock.set_book(NO, MockBook::new(vec![(0.48, 2_000.0)], vec![(0.52, 2_000.0)]));
+    let endpoints = mock_clob::start(mock.clone(), "127.0.0.1:0", "127.0.0.1:0").await.unwrap();
+
+    let dir = std::env::temp_dir().join(format!("pm_bot_e2e_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let _bot = spawn_bot(&dir, &endpoints.bot_env());
+
+    wait_for("the bot's log subscription", Duration::from_secs(30), || mock.feed_subscribers() > 0).await;
+    mock.whale_fill(YES, true, 5_000.0, 0.50);
+
+    wait_for("a filled copy order", Duration::from_secs(15), || mock.orders().iter().any(|o| o.filled > 0.0)).await;
+    let orders = mock.orders();
+    let copy = orders.iter().find(|o| o.filled > 0.0).unwrap();
+    assert_eq!(copy.token_id, YES);
+    assert!(copy.is_buy);
+    assert!(copy.price >= 0.50 && copy.price <= 0.52, "{:?}", copy);
+    assert_eq!(orders.iter().filter(|o| o.filled > 0.0).count(), 1, "copied once: {:?}", orders);
+    let held = mock.position(YES);
+    assert!((1_000.0 - mock.balance() - held * 0.50).abs() < 1e-6, "bought {} at 0.50", held);
+
+    mock.resolve(YES);
+    let attribution = dir.join("pnl_attribution.jsonl");
+    wait_for("the settlement line", Duration::from_secs(15), || attribution.exists()).await;
+    let line = std::fs::read_to_string(&attribution).unwrap();
+    assert!(line.contains(YES), "{}", line);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}